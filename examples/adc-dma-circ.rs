@@ -36,10 +36,10 @@ fn main() -> ! {
     // Configure pa0 as an analog input
     let adc_ch0 = gpioa.pa0.into_analog(&mut gpioa.crl);
 
-    let adc_dma = adc1.with_dma(adc_ch0, dma_ch1);
     let buf = singleton!(: [[u16; 8]; 2] = [[0; 8]; 2]).unwrap();
 
-    let mut circ_buffer = adc_dma.circ_read(buf);
+    // The ADC converts continuously, the DMA fills both halves of `buf` in turn
+    let mut circ_buffer = adc1.into_continuous_dma(adc_ch0, dma_ch1, buf);
 
     while circ_buffer.readable_half().unwrap() != Half::First {}
 
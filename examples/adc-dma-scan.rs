@@ -0,0 +1,49 @@
+//! ADC scan mode: three channels converted in one sequence and stored by DMA
+
+#![allow(clippy::empty_loop)]
+#![no_main]
+#![no_std]
+
+use panic_halt as _;
+
+use cortex_m::{asm, singleton};
+
+use cortex_m_rt::entry;
+use stm32f1xx_hal::{adc, pac, prelude::*};
+
+#[entry]
+fn main() -> ! {
+    // Acquire peripherals
+    let p = pac::Peripherals::take().unwrap();
+    let mut flash = p.FLASH.constrain();
+    let rcc = p.RCC.constrain();
+
+    let clocks = rcc.cfgr.adcclk(2.MHz()).freeze(&mut flash.acr);
+
+    let dma_ch1 = p.DMA1.split().1;
+
+    // Setup ADC
+    let adc1 = adc::Adc::adc1(p.ADC1, &clocks);
+
+    // Setup GPIOA
+    let mut gpioa = p.GPIOA.split();
+
+    let pins = (
+        gpioa.pa0.into_analog(&mut gpioa.crl),
+        gpioa.pa1.into_analog(&mut gpioa.crl),
+        gpioa.pa2.into_analog(&mut gpioa.crl),
+    );
+
+    // PA0, PA2 and PA1, in this order
+    let adc_dma = adc1.with_scan_dma_channels(pins, &[0, 2, 1], dma_ch1);
+
+    // One sample per channel of the sequence
+    let buf = singleton!(: [u16; 3] = [0; 3]).unwrap();
+
+    let (_buf, adc_dma) = adc_dma.read(buf).wait();
+    asm::bkpt();
+
+    let (_adc1, _pins, _dma_ch1) = adc_dma.split();
+
+    loop {}
+}
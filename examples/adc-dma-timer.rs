@@ -0,0 +1,55 @@
+//! ADC sampling at a fixed rate: TIM3 TRGO starts every conversion, DMA stores the results
+
+#![allow(clippy::empty_loop)]
+#![no_main]
+#![no_std]
+
+use panic_halt as _;
+
+use cortex_m::{asm, singleton};
+
+use cortex_m_rt::entry;
+use stm32f1xx_hal::{
+    adc,
+    pac::{self, adc1::cr2::EXTSEL, tim2::cr2::MMS},
+    prelude::*,
+};
+
+#[entry]
+fn main() -> ! {
+    // Acquire peripherals
+    let p = pac::Peripherals::take().unwrap();
+    let mut flash = p.FLASH.constrain();
+    let rcc = p.RCC.constrain();
+
+    let clocks = rcc.cfgr.adcclk(2.MHz()).freeze(&mut flash.acr);
+
+    let dma_ch1 = p.DMA1.split().1;
+
+    // Setup ADC
+    let adc1 = adc::Adc::adc1(p.ADC1, &clocks);
+
+    // Setup GPIOA
+    let mut gpioa = p.GPIOA.split();
+
+    // Configure pa0 as an analog input
+    let adc_ch0 = gpioa.pa0.into_analog(&mut gpioa.crl);
+
+    // TIM3 generates TRGO on every update event, 1000 times per second
+    let mut timer = p.TIM3.counter_hz(&clocks);
+    timer.set_master_mode(MMS::Update);
+
+    let adc_dma = adc1.with_triggered_dma(adc_ch0, &[0], EXTSEL::Tim3trgo, dma_ch1);
+    let buf = singleton!(: [u16; 100] = [0; 100]).unwrap();
+
+    let transfer = adc_dma.read(buf);
+    timer.start(1.kHz()).unwrap();
+
+    // 100 samples taken 1 ms apart
+    let (_buf, adc_dma) = transfer.wait();
+    asm::bkpt();
+
+    let (_adc1, _adc_ch0, _dma_ch1) = adc_dma.split();
+
+    loop {}
+}
@@ -0,0 +1,42 @@
+//! Converts an injected sequence of three channels and reads every rank
+
+#![deny(unsafe_code)]
+#![no_main]
+#![no_std]
+
+use panic_semihosting as _;
+
+use cortex_m_rt::entry;
+use stm32f1xx_hal::{adc, pac, pac::adc1::cr2::JEXTSEL, prelude::*};
+
+use cortex_m_semihosting::hprintln;
+
+#[entry]
+fn main() -> ! {
+    let p = pac::Peripherals::take().unwrap();
+    let mut flash = p.FLASH.constrain();
+    let rcc = p.RCC.constrain();
+    let clocks = rcc.cfgr.adcclk(2.MHz()).freeze(&mut flash.acr);
+
+    let mut adc1 = adc::Adc::adc1(p.ADC1, &clocks);
+
+    let mut gpioa = p.GPIOA.split();
+    let _pa0 = gpioa.pa0.into_analog(&mut gpioa.crl);
+    let _pa1 = gpioa.pa1.into_analog(&mut gpioa.crl);
+    let _pa2 = gpioa.pa2.into_analog(&mut gpioa.crl);
+
+    // More than one channel, so the ADC converts them in scan mode
+    adc1.set_injected_sequence(&[0, 1, 2]);
+    adc1.set_injected_trigger(JEXTSEL::Jswstart);
+
+    loop {
+        adc1.start_injected();
+        while !adc1.is_injected_done() {}
+        adc1.clear_injected_done();
+
+        let pa0 = adc1.read_injected(0);
+        let pa1 = adc1.read_injected(1);
+        let pa2 = adc1.read_injected(2);
+        hprintln!("PA0: {}, PA1: {}, PA2: {}", pa0, pa1, pa2);
+    }
+}
@@ -26,6 +26,7 @@ fn main() -> ! {
 
     // Setup ADC
     let mut adc1 = adc::Adc::adc1(p.ADC1, &clocks);
+    hprintln!("adc1 calibration: {}", adc1.calibration_value());
 
     #[cfg(any(feature = "stm32f103", feature = "connectivity"))]
     let mut adc2 = adc::Adc::adc2(p.ADC2, &clocks);
@@ -43,7 +43,8 @@ fn main() -> ! {
     // Read temperature sensor
     loop {
         let temp = adc.read_temp();
+        let vdda = adc.read_vdda_mv();
 
-        hprintln!("temp: {}", temp);
+        hprintln!("temp: {}, VDDA: {} mV", temp, vdda);
     }
 }
@@ -0,0 +1,30 @@
+//! Counts the resets in a backup data register
+
+#![deny(unsafe_code)]
+#![no_std]
+#![no_main]
+
+use panic_semihosting as _;
+
+use cortex_m_semihosting::hprintln;
+
+use cortex_m_rt::entry;
+use stm32f1xx_hal::{pac, prelude::*};
+
+#[entry]
+fn main() -> ! {
+    let p = pac::Peripherals::take().unwrap();
+
+    let mut pwr = p.PWR;
+    let rcc = p.RCC.constrain();
+    let backup_domain = rcc.bkp.constrain(p.BKP, &mut pwr);
+
+    // DR1 keeps its value across resets and, with a battery on VBAT, power loss
+    let boots = backup_domain.read_data_register(0).wrapping_add(1);
+    backup_domain.write_data_register(0, boots);
+    hprintln!("boot #{}", boots);
+
+    loop {
+        cortex_m::asm::wfi();
+    }
+}
@@ -0,0 +1,63 @@
+//! Running light on a row of LEDs connected to PB12 - PB15
+//!
+//! The pins keep their port in the type, so they can be stored in an array
+//! without erasing them completely.
+
+#![deny(unsafe_code)]
+#![no_std]
+#![no_main]
+
+use panic_halt as _;
+
+use nb::block;
+
+use cortex_m_rt::entry;
+use stm32f1xx_hal::{
+    gpio::{Output, PEPin},
+    pac,
+    prelude::*,
+    timer::Timer,
+};
+
+#[entry]
+fn main() -> ! {
+    let cp = cortex_m::Peripherals::take().unwrap();
+    let dp = pac::Peripherals::take().unwrap();
+
+    let mut flash = dp.FLASH.constrain();
+    let rcc = dp.RCC.constrain();
+
+    let clocks = rcc.cfgr.freeze(&mut flash.acr);
+
+    let mut gpiob = dp.GPIOB.split();
+
+    let mut timer = Timer::syst(cp.SYST, &clocks).counter_hz();
+    timer.start(4.Hz()).unwrap();
+
+    let mut leds: [PEPin<'B', Output>; 4] = [
+        gpiob
+            .pb12
+            .into_push_pull_output(&mut gpiob.crh)
+            .erase_number(),
+        gpiob
+            .pb13
+            .into_push_pull_output(&mut gpiob.crh)
+            .erase_number(),
+        gpiob
+            .pb14
+            .into_push_pull_output(&mut gpiob.crh)
+            .erase_number(),
+        gpiob
+            .pb15
+            .into_push_pull_output(&mut gpiob.crh)
+            .erase_number(),
+    ];
+
+    loop {
+        for led in leds.iter_mut() {
+            led.set_high();
+            block!(timer.wait()).unwrap();
+            led.set_low();
+        }
+    }
+}
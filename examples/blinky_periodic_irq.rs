@@ -0,0 +1,56 @@
+//! Blinks the LED on PC13 from the periodic update interrupt of TIM3
+
+#![no_main]
+#![no_std]
+
+use panic_halt as _;
+
+use core::cell::RefCell;
+use cortex_m::{asm::wfi, interrupt::Mutex};
+use cortex_m_rt::entry;
+use stm32f1xx_hal::{
+    gpio::{Output, PC13},
+    pac::{self, interrupt, Interrupt, TIM3},
+    prelude::*,
+    timer::{Event, Timer},
+};
+
+type Blinky = (PC13<Output>, Timer<TIM3>);
+
+static G_BLINKY: Mutex<RefCell<Option<Blinky>>> = Mutex::new(RefCell::new(None));
+
+#[interrupt]
+fn TIM3() {
+    cortex_m::interrupt::free(|cs| {
+        if let Some((led, timer)) = G_BLINKY.borrow(cs).borrow_mut().as_mut() {
+            timer.clear_interrupt(Event::Update);
+            led.toggle();
+        }
+    });
+}
+
+#[entry]
+fn main() -> ! {
+    let dp = pac::Peripherals::take().unwrap();
+
+    let mut flash = dp.FLASH.constrain();
+    let rcc = dp.RCC.constrain();
+
+    let clocks = rcc.cfgr.freeze(&mut flash.acr);
+
+    let mut gpioc = dp.GPIOC.split();
+    let led = gpioc.pc13.into_push_pull_output(&mut gpioc.crh);
+
+    // Toggle the LED twice per second
+    let mut timer = Timer::new(dp.TIM3, &clocks);
+    timer.start_periodic(2.Hz()).unwrap();
+
+    cortex_m::interrupt::free(|cs| *G_BLINKY.borrow(cs).borrow_mut() = Some((led, timer)));
+    unsafe {
+        cortex_m::peripheral::NVIC::unmask(Interrupt::TIM3);
+    }
+
+    loop {
+        wfi();
+    }
+}
@@ -66,7 +66,7 @@ fn RTCALARM() {
         cortex_m::interrupt::free(|cs| G_EXTI.borrow(cs).replace(None).unwrap())
     });
 
-    exti.pr().write(|w| w.pr17().clear_bit_by_one());
+    rtc.clear_alarm_exti(exti);
     rtc.set_alarm(rtc.current_time() + TOGGLE_INTERVAL_SECONDS);
 
     let _ = led.toggle();
@@ -87,13 +87,6 @@ fn main() -> ! {
 
     cortex_m::interrupt::free(|cs| *G_LED.borrow(cs).borrow_mut() = Some(led));
 
-    // Set up the EXTI (see notes in section 18.4.2 of reference manual)
-    let exti = dp.EXTI;
-    exti.ftsr().write(|w| w.tr17().set_bit());
-    exti.imr().write(|w| w.mr17().set_bit());
-
-    cortex_m::interrupt::free(|cs| *G_EXTI.borrow(cs).borrow_mut() = Some(exti));
-
     // Set up the RTC
     // Enable writes to the backup domain
     let mut backup_domain = rcc.bkp.constrain(dp.BKP, &mut pwr);
@@ -103,6 +96,12 @@ fn main() -> ! {
     rtc.set_alarm(TOGGLE_INTERVAL_SECONDS);
     rtc.listen_alarm();
 
+    // Set up the EXTI (see notes in section 18.4.2 of reference manual)
+    let mut exti = dp.EXTI;
+    rtc.listen_alarm_exti(&mut exti);
+
+    cortex_m::interrupt::free(|cs| *G_EXTI.borrow(cs).borrow_mut() = Some(exti));
+
     cortex_m::interrupt::free(|cs| *G_RTC.borrow(cs).borrow_mut() = Some(rtc));
 
     // Enable RTCALARM IRQ
@@ -0,0 +1,46 @@
+//! Sends and receives a CAN frame in self-test mode.
+//! Does not require any transceiver or pins.
+
+#![allow(clippy::empty_loop)]
+#![no_main]
+#![no_std]
+
+use bxcan::{filter::Mask32, Fifo, Frame, StandardId};
+use panic_halt as _;
+
+use cortex_m_rt::entry;
+use nb::block;
+use stm32f1xx_hal::{can::Can, gpio::Floating, pac, prelude::*};
+
+#[entry]
+fn main() -> ! {
+    let dp = pac::Peripherals::take().unwrap();
+
+    let mut flash = dp.FLASH.constrain();
+    let rcc = dp.RCC.constrain();
+
+    let clocks = rcc.cfgr.use_hse(8.MHz()).freeze(&mut flash.acr);
+
+    let can = Can::<_, Floating>::new_loopback(
+        dp.CAN1,
+        #[cfg(not(feature = "connectivity"))]
+        dp.USB,
+    );
+
+    let timing = Can::<pac::CAN1>::btr_for_bitrate(&clocks, 500.kHz()).unwrap();
+    let mut can = can.into_self_test(timing.btr()).unwrap();
+
+    can.modify_filters()
+        .enable_bank(0, Fifo::Fifo0, Mask32::accept_all());
+
+    let frame_tx = Frame::new_data(StandardId::new(0x123).unwrap(), [1, 2, 3, 4]);
+    block!(can.transmit(&frame_tx)).unwrap();
+    let frame_rx = block!(can.receive()).unwrap();
+    assert_eq!(frame_tx, frame_rx);
+
+    let mut gpioc = dp.GPIOC.split();
+    let mut led = gpioc.pc13.into_push_pull_output(&mut gpioc.crh);
+    led.set_low();
+
+    loop {}
+}
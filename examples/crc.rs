@@ -23,5 +23,11 @@ fn main() -> ! {
     let val = crc.read();
     hprintln!("found={:08x}, expected={:08x}", val, 0xdf8a8a2b_u32);
 
+    crc.reset();
+    crc.feed_bytes(b"123456789");
+
+    let val = crc.result();
+    hprintln!("found={:08x}, expected={:08x}", val, 0x0376e6e7_u32);
+
     loop {}
 }
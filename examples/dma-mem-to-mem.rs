@@ -0,0 +1,31 @@
+//! Copies a buffer by DMA
+
+#![deny(unsafe_code)]
+#![allow(clippy::empty_loop)]
+#![no_std]
+#![no_main]
+
+use panic_halt as _;
+
+use cortex_m::singleton;
+use cortex_m_rt::entry;
+use cortex_m_semihosting::hprintln;
+use stm32f1xx_hal::{pac, prelude::*};
+
+#[entry]
+fn main() -> ! {
+    let dp = pac::Peripherals::take().unwrap();
+
+    let channels = dp.DMA1.split();
+
+    let src = singleton!(: [u8; 1024] = [0x55; 1024]).unwrap();
+    let dst = singleton!(: [u8; 1024] = [0; 1024]).unwrap();
+
+    // Whole words are copied if both buffers happen to be word aligned
+    let transfer = channels.1.mem_to_mem(&*src, dst);
+    let ((src, dst), _payload) = transfer.wait();
+
+    hprintln!("equal: {}", src == dst);
+
+    loop {}
+}
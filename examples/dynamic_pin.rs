@@ -0,0 +1,55 @@
+//! Toggles pins of different ports, stored in one array of `DynamicPin`s.
+//!
+//! Reads back the output state after every toggle, which has to match the last written value.
+
+#![deny(unsafe_code)]
+#![no_std]
+#![no_main]
+
+use panic_semihosting as _;
+
+use nb::block;
+
+use cortex_m_rt::entry;
+use cortex_m_semihosting::hprintln;
+use embedded_hal::digital::{OutputPin, StatefulOutputPin};
+use stm32f1xx_hal::{gpio::DynamicPin, pac, prelude::*};
+
+#[entry]
+fn main() -> ! {
+    let cp = cortex_m::Peripherals::take().unwrap();
+    let dp = pac::Peripherals::take().unwrap();
+
+    let mut flash = dp.FLASH.constrain();
+    let rcc = dp.RCC.constrain();
+
+    let clocks = rcc.cfgr.freeze(&mut flash.acr);
+
+    let mut gpioa = dp.GPIOA.split();
+    let mut gpioc = dp.GPIOC.split();
+
+    let mut led = gpioc.pc13.into_dynamic(&mut gpioc.crh).erase_dynamic();
+    led.make_push_pull_output(&mut gpioc.crh);
+    let mut pa5 = gpioa.pa5.into_dynamic(&mut gpioa.crl).erase_dynamic();
+    pa5.make_open_drain_output(&mut gpioa.crl);
+
+    let mut pins: [DynamicPin; 2] = [led, pa5];
+    for pin in &mut pins {
+        OutputPin::set_low(pin).unwrap();
+    }
+
+    let mut timer = cp.SYST.counter_hz(&clocks);
+    timer.start(1.Hz()).unwrap();
+
+    let mut high = false;
+    loop {
+        block!(timer.wait()).unwrap();
+        high = !high;
+        for pin in &mut pins {
+            StatefulOutputPin::toggle(pin).unwrap();
+            assert_eq!(StatefulOutputPin::is_set_high(pin).unwrap(), high);
+            assert_eq!(StatefulOutputPin::is_set_low(pin).unwrap(), !high);
+        }
+        hprintln!("pins set {}", if high { "high" } else { "low" });
+    }
+}
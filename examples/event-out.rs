@@ -0,0 +1,62 @@
+//! Synchronizes two MCUs using the Cortex EVENTOUT signal
+//!
+//! Flash this example with `LEADER = true` on one board and with `LEADER = false` on another.
+//! Connect PA0 of both boards. The leader pulses PA0 on every `SEV` instruction, which wakes up
+//! the follower from `WFE` through the EXTI event line 0. Both toggle their LEDs in sync.
+
+#![deny(unsafe_code)]
+#![no_main]
+#![no_std]
+
+use panic_halt as _;
+
+use cortex_m::asm;
+use cortex_m_rt::entry;
+use stm32f1xx_hal::{
+    afio::Port,
+    gpio::{Edge, ExtiPin},
+    pac,
+    prelude::*,
+};
+
+const LEADER: bool = true;
+
+#[entry]
+fn main() -> ! {
+    let cp = cortex_m::Peripherals::take().unwrap();
+    let dp = pac::Peripherals::take().unwrap();
+
+    let mut flash = dp.FLASH.constrain();
+    let rcc = dp.RCC.constrain();
+    let clocks = rcc.cfgr.freeze(&mut flash.acr);
+
+    let mut afio = dp.AFIO.constrain();
+    let mut gpioa = dp.GPIOA.split();
+    let mut gpioc = dp.GPIOC.split();
+    let mut led = gpioc.pc13.into_push_pull_output(&mut gpioc.crh);
+
+    if LEADER {
+        let _pa0 = gpioa.pa0.into_alternate_push_pull(&mut gpioa.crl);
+        afio.evcr.set_event_output(Port::A, 0);
+        afio.evcr.enable();
+
+        let mut delay = cp.SYST.delay(&clocks);
+        loop {
+            delay.delay_ms(500_u16);
+            led.toggle();
+            // Pulse EVENTOUT
+            asm::sev();
+        }
+    } else {
+        let mut exti = dp.EXTI;
+        let mut pa0 = gpioa.pa0.into_floating_input(&mut gpioa.crl);
+        pa0.make_interrupt_source(&mut afio);
+        pa0.trigger_on_edge(&mut exti, Edge::Rising);
+        pa0.enable_event(&mut exti);
+
+        loop {
+            asm::wfe();
+            led.toggle();
+        }
+    }
+}
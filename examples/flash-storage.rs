@@ -0,0 +1,41 @@
+//! Stores a value in the last flash page and reads it back
+
+#![deny(unsafe_code)]
+#![no_std]
+#![no_main]
+
+use panic_semihosting as _;
+
+use cortex_m_semihosting::hprintln;
+
+use cortex_m_rt::entry;
+use stm32f1xx_hal::{
+    flash::{FlashSize, SectorSize},
+    pac,
+    prelude::*,
+};
+
+// Last 1K page of a 64K device, keep it out of the program in memory.x
+const CONFIG_OFFSET: u32 = 63 * 1024;
+
+#[entry]
+fn main() -> ! {
+    let p = pac::Peripherals::take().unwrap();
+
+    let mut flash = p.FLASH.constrain();
+    let mut writer = flash.writer(SectorSize::Sz1K, FlashSize::Sz64K);
+
+    let stored = writer.read(CONFIG_OFFSET, 4).unwrap();
+    let boots = u32::from_le_bytes([stored[0], stored[1], stored[2], stored[3]]);
+    // An erased page reads as 0xFFFF_FFFF
+    let boots = if boots == u32::MAX { 1 } else { boots + 1 };
+    hprintln!("boot #{}", boots);
+
+    assert!(!writer.is_page_write_protected(CONFIG_OFFSET / 1024));
+    writer.page_erase(CONFIG_OFFSET).unwrap();
+    writer.write(CONFIG_OFFSET, &boots.to_le_bytes()).unwrap();
+
+    loop {
+        cortex_m::asm::wfi();
+    }
+}
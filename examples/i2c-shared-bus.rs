@@ -0,0 +1,66 @@
+//! Two I2C sensors with different addresses on a shared I2C1 bus (PB6, PB7).
+//!
+//! `BlockingI2c` implements the `I2c` trait of `embedded-hal` 1.0, so the `RefCellDevice`
+//! wrapper of the `embedded-hal-bus` crate can hand out one `I2c` per driver.
+
+#![deny(unsafe_code)]
+#![no_std]
+#![no_main]
+
+use core::cell::RefCell;
+
+use cortex_m_rt::entry;
+use cortex_m_semihosting::hprintln;
+use embedded_hal::i2c::I2c;
+use embedded_hal_bus::i2c::RefCellDevice;
+use panic_semihosting as _;
+use stm32f1xx_hal::{
+    i2c::{BlockingI2c, Mode},
+    pac,
+    prelude::*,
+};
+
+/// Reads the identification register of a BMP280 or similar sensor
+fn chip_id<I: I2c>(i2c: &mut I, address: u8) -> Result<u8, I::Error> {
+    let mut id = [0];
+    i2c.write_read(address, &[0xd0], &mut id)?;
+    Ok(id[0])
+}
+
+#[entry]
+fn main() -> ! {
+    let dp = pac::Peripherals::take().unwrap();
+
+    let mut flash = dp.FLASH.constrain();
+    let rcc = dp.RCC.constrain();
+
+    let clocks = rcc.cfgr.freeze(&mut flash.acr);
+
+    let mut afio = dp.AFIO.constrain();
+    let mut gpiob = dp.GPIOB.split();
+
+    let scl = gpiob.pb6.into_alternate_open_drain(&mut gpiob.crl);
+    let sda = gpiob.pb7.into_alternate_open_drain(&mut gpiob.crl);
+
+    let i2c = BlockingI2c::new(
+        dp.I2C1,
+        (scl, sda, &mut afio.mapr),
+        Mode::standard(100.kHz()),
+        &clocks,
+        1000,
+        10,
+        1000,
+        1000,
+    );
+
+    let bus = RefCell::new(i2c);
+    let mut sensor1 = RefCellDevice::new(&bus);
+    let mut sensor2 = RefCellDevice::new(&bus);
+
+    hprintln!("sensor 1: {:?}", chip_id(&mut sensor1, 0x76));
+    hprintln!("sensor 2: {:?}", chip_id(&mut sensor2, 0x77));
+
+    loop {
+        cortex_m::asm::wfi();
+    }
+}
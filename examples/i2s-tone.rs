@@ -0,0 +1,48 @@
+//! Plays a 1 kHz square wave on an I2S DAC connected to SPI2
+//!
+//! PB12 is WS, PB13 CK and PB15 SD. With a 64 MHz system clock the 16 kHz sample rate
+//! is exact.
+
+#![deny(unsafe_code)]
+#![no_std]
+#![no_main]
+
+use panic_halt as _;
+
+use cortex_m_rt::entry;
+use stm32f1xx_hal::{
+    i2s::{I2s, I2sConfig, I2sMode},
+    pac,
+    prelude::*,
+};
+
+#[entry]
+fn main() -> ! {
+    let dp = pac::Peripherals::take().unwrap();
+
+    let mut flash = dp.FLASH.constrain();
+    let rcc = dp.RCC.constrain();
+    let clocks = rcc
+        .cfgr
+        .use_hse(8.MHz())
+        .sysclk(64.MHz())
+        .freeze(&mut flash.acr);
+
+    let mut gpiob = dp.GPIOB.split();
+    let pins = (
+        gpiob.pb12.into_alternate_push_pull(&mut gpiob.crh),
+        gpiob.pb13.into_alternate_push_pull(&mut gpiob.crh),
+        gpiob.pb15.into_alternate_push_pull(&mut gpiob.crh),
+    );
+
+    let config = I2sConfig::new(I2sMode::MasterTx, 16.kHz());
+    let mut i2s = I2s::new(dp.SPI2, pins, config, &clocks);
+
+    // 16 samples per period at 16 kHz
+    let mut phase = 0u8;
+    loop {
+        let sample = if phase < 8 { 0x2000 } else { 0xe000 };
+        i2s.write_frame(sample, sample).unwrap();
+        phase = (phase + 1) % 16;
+    }
+}
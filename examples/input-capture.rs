@@ -0,0 +1,59 @@
+//! Measures the frequency of a tachometer signal on PA0 with input capture
+
+#![deny(unsafe_code)]
+#![no_main]
+#![no_std]
+
+use panic_semihosting as _;
+
+use cortex_m_semihosting::hprintln;
+
+use cortex_m_rt::entry;
+use stm32f1xx_hal::{
+    pac,
+    prelude::*,
+    timer::{
+        capture::{CaptureConfig, Edge},
+        Tim2NoRemap, Timer,
+    },
+};
+
+#[entry]
+fn main() -> ! {
+    let p = pac::Peripherals::take().unwrap();
+
+    let mut flash = p.FLASH.constrain();
+    let rcc = p.RCC.constrain();
+
+    let clocks = rcc.cfgr.freeze(&mut flash.acr);
+
+    let mut afio = p.AFIO.constrain();
+    let mut gpioa = p.GPIOA.split();
+
+    let pin = gpioa.pa0.into_pull_up_input(&mut gpioa.crl);
+
+    // 1 µs resolution, filter out short glitches of the open collector output
+    let mut capture = Timer::new(p.TIM2, &clocks).input_capture::<Tim2NoRemap, _, _, 0>(
+        pin,
+        &mut afio.mapr,
+        1.MHz(),
+        CaptureConfig::default().edge(Edge::Falling).filter(0b0011),
+    );
+    let tick = capture.tick_frequency().raw() as u64;
+
+    let mut previous = None;
+    loop {
+        match capture.read() {
+            Ok(c) => {
+                if let Some(p) = previous {
+                    if let Some(mhz) = (tick * 1000).checked_div(c.ticks_since(&p)) {
+                        hprintln!("{} mHz", mhz);
+                    }
+                }
+                previous = Some(c);
+            }
+            Err(nb::Error::Other(_)) => previous = None,
+            Err(nb::Error::WouldBlock) => {}
+        }
+    }
+}
@@ -0,0 +1,36 @@
+//! Outputs half of the PLL clock on the MCO pin (PA8)
+//!
+//! With the clock configuration below this is a 36 MHz signal which can be checked with an
+//! oscilloscope or a frequency counter.
+
+#![deny(unsafe_code)]
+#![no_std]
+#![no_main]
+
+use panic_halt as _;
+
+use cortex_m_rt::entry;
+use stm32f1xx_hal::{pac, prelude::*, rcc::McoSource};
+
+#[entry]
+fn main() -> ! {
+    let dp = pac::Peripherals::take().unwrap();
+
+    let mut flash = dp.FLASH.constrain();
+    let rcc = dp.RCC.constrain();
+
+    let _clocks = rcc
+        .cfgr
+        .use_hse(8.MHz())
+        .sysclk(72.MHz())
+        .mco(McoSource::PllDiv2)
+        .freeze(&mut flash.acr);
+
+    // MCO is the alternate function of PA8, the pin has to be able to drive 50 MHz
+    let mut gpioa = dp.GPIOA.split();
+    let _mco = gpioa.pa8.into_alternate_push_pull(&mut gpioa.crh);
+
+    loop {
+        cortex_m::asm::wfi();
+    }
+}
@@ -44,7 +44,7 @@ fn main() -> ! {
     let spi = Spi::new(
         dp.SPI1,
         (sck, miso, mosi, &mut afio.mapr),
-        mpu9250::MODE.into(),
+        mpu9250::MODE,
         1.MHz(),
         &clocks,
     );
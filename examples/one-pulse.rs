@@ -0,0 +1,48 @@
+//! Generates a 10 µs pulse on PA0 2 ms after each falling edge on PA1 (TIM2 channel 2)
+
+#![deny(unsafe_code)]
+#![no_main]
+#![no_std]
+
+use panic_halt as _;
+
+use cortex_m_rt::entry;
+use stm32f1xx_hal::{
+    pac,
+    prelude::*,
+    timer::{capture::Edge, Tim2NoRemap, Timer},
+};
+
+#[entry]
+fn main() -> ! {
+    let p = pac::Peripherals::take().unwrap();
+
+    let mut flash = p.FLASH.constrain();
+    let rcc = p.RCC.constrain();
+
+    let clocks = rcc.cfgr.freeze(&mut flash.acr);
+
+    let mut afio = p.AFIO.constrain();
+    let mut gpioa = p.GPIOA.split();
+
+    let out = gpioa.pa0.into_alternate_push_pull(&mut gpioa.crl);
+    let _trigger = gpioa.pa1.into_pull_up_input(&mut gpioa.crl);
+
+    let mut pulse = Timer::new(p.TIM2, &clocks).one_pulse::<Tim2NoRemap, _, _>(
+        out,
+        &mut afio.mapr,
+        2.millis(),
+        10.micros(),
+    );
+
+    // A first pulse fired by software
+    pulse.trigger();
+    nb::block!(pulse.wait()).unwrap();
+
+    // Then one pulse for each falling edge on PA1
+    pulse.enable_external_trigger(Edge::Falling);
+
+    loop {
+        nb::block!(pulse.wait()).unwrap();
+    }
+}
@@ -0,0 +1,80 @@
+//! Reads the temperature of all DS18B20 1-Wire sensors on PB9.
+//!
+//! The bus is driven by an open-drain pin that is released as a pulled up input, so no
+//! external pull-up resistor is needed for short wires.
+
+#![deny(unsafe_code)]
+#![no_main]
+#![no_std]
+
+use panic_semihosting as _;
+
+use cortex_m_rt::entry;
+use cortex_m_semihosting::hprintln;
+use stm32f1xx_hal::{onewire::OneWire, pac, prelude::*, time::MonoTimer};
+
+/// Convert T, starts a temperature conversion
+const CONVERT_T: u8 = 0x44;
+/// Read Scratchpad, reads the temperature, the configuration and a CRC
+const READ_SCRATCHPAD: u8 = 0xbe;
+/// Family code of the DS18B20
+const DS18B20: u8 = 0x28;
+
+#[entry]
+fn main() -> ! {
+    let cp = cortex_m::Peripherals::take().unwrap();
+    let dp = pac::Peripherals::take().unwrap();
+
+    let mut flash = dp.FLASH.constrain();
+    let rcc = dp.RCC.constrain();
+
+    let clocks = rcc.cfgr.freeze(&mut flash.acr);
+
+    let mut gpiob = dp.GPIOB.split();
+    let pin = gpiob
+        .pb9
+        .into_open_drain_pull_up(&mut gpiob.crh)
+        .with_cr(&mut gpiob.crh);
+
+    let mut bus = OneWire::new(pin, MonoTimer::new(cp.DWT, cp.DCB, &clocks));
+    let mut delay = dp.TIM2.delay_ms(&clocks);
+
+    loop {
+        // Start the conversion on all sensors at once
+        if bus.skip_rom().is_err() {
+            hprintln!("no device");
+            delay.delay(1.secs());
+            continue;
+        }
+        bus.write_byte(CONVERT_T);
+        // The conversion is finished when the sensors release the bus
+        while !bus.read_bit() {}
+
+        let mut roms = [[0; 8]; 4];
+        let mut count = 0;
+        for rom in bus.search_rom() {
+            match rom {
+                Ok(rom) if rom[0] == DS18B20 && count < roms.len() => {
+                    roms[count] = rom;
+                    count += 1;
+                }
+                Ok(_) => {}
+                Err(e) => hprintln!("search failed: {:?}", e),
+            }
+        }
+
+        for rom in &roms[..count] {
+            bus.match_rom(rom).unwrap();
+            bus.write_byte(READ_SCRATCHPAD);
+            let mut scratchpad = [0; 9];
+            bus.read_bytes(&mut scratchpad);
+            if stm32f1xx_hal::onewire::crc8(&scratchpad) != 0 {
+                hprintln!("{:02x?}: CRC mismatch", rom);
+                continue;
+            }
+            let raw = i16::from_le_bytes([scratchpad[0], scratchpad[1]]);
+            hprintln!("{:02x?}: {} mC", rom, raw as i32 * 625 / 10);
+        }
+        delay.delay(1.secs());
+    }
+}
@@ -0,0 +1,64 @@
+//! Complementary PWM outputs with dead-time and break input on TIM1, e.g. for a half-bridge
+
+#![deny(unsafe_code)]
+#![no_main]
+#![no_std]
+
+use panic_halt as _;
+
+use cortex_m_rt::entry;
+use stm32f1xx_hal::{
+    pac,
+    prelude::*,
+    timer::{BreakPolarity, Channel, Tim1NoRemap, Timer},
+};
+
+#[entry]
+fn main() -> ! {
+    let p = pac::Peripherals::take().unwrap();
+
+    let mut flash = p.FLASH.constrain();
+    let rcc = p.RCC.constrain();
+
+    let clocks = rcc.cfgr.sysclk(72.MHz()).freeze(&mut flash.acr);
+
+    let mut afio = p.AFIO.constrain();
+    let mut gpioa = p.GPIOA.split();
+    let mut gpiob = p.GPIOB.split();
+
+    // CH1 and CH2
+    let c1 = gpioa.pa8.into_alternate_push_pull(&mut gpioa.crh);
+    let c2 = gpioa.pa9.into_alternate_push_pull(&mut gpioa.crh);
+    // CH1N, CH2N and BKIN are not part of the pin tuple, configure them manually
+    let _c1n = gpiob.pb13.into_alternate_push_pull(&mut gpiob.crh);
+    let _c2n = gpiob.pb14.into_alternate_push_pull(&mut gpiob.crh);
+    let bkin = gpiob.pb12.into_pull_up_input(&mut gpiob.crh);
+
+    let mut pwm =
+        Timer::new(p.TIM1, &clocks).pwm_hz::<Tim1NoRemap, _, _>((c1, c2), &mut afio.mapr, 20.kHz());
+
+    // 500 ns between switching off one transistor of the bridge and switching on the other one
+    pwm.set_dead_time_ns(500);
+    // Switch all outputs off when BKIN is pulled low
+    pwm.enable_break(BreakPolarity::ActiveLow);
+
+    let max = pwm.get_max_duty();
+    pwm.set_duty(Channel::C1, max / 4);
+    pwm.set_duty(Channel::C2, max / 2);
+
+    pwm.enable(Channel::C1);
+    pwm.enable_complementary(Channel::C1);
+    pwm.enable(Channel::C2);
+    pwm.enable_complementary(Channel::C2);
+    pwm.enable_outputs();
+
+    loop {
+        if pwm.is_break_pending() {
+            // Outputs stay off until the fault is gone and they are enabled again
+            pwm.clear_break();
+            if bkin.is_high() {
+                pwm.enable_outputs();
+            }
+        }
+    }
+}
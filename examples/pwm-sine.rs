@@ -0,0 +1,60 @@
+//! Outputs a 500 Hz sine wave on PA0 by streaming duty cycles to TIM2 CH1 with DMA
+//!
+//! Connect an RC low-pass filter (e.g. 1 kΩ and 100 nF) to PA0 to get the analog signal.
+
+#![deny(unsafe_code)]
+#![no_main]
+#![no_std]
+
+use panic_halt as _;
+
+use cortex_m::singleton;
+use cortex_m_rt::entry;
+use stm32f1xx_hal::{
+    pac,
+    prelude::*,
+    timer::{Tim2NoRemap, WaveformGenerator},
+};
+
+/// One period of a sine from 0 to 1000
+const SINE: [u16; 64] = [
+    500, 549, 598, 645, 691, 736, 778, 817, 854, 887, 916, 941, 962, 978, 990, 998, 1000, 998, 990,
+    978, 962, 941, 916, 887, 854, 817, 778, 736, 691, 645, 598, 549, 500, 451, 402, 355, 309, 264,
+    222, 183, 146, 113, 84, 59, 38, 22, 10, 2, 0, 2, 10, 22, 38, 59, 84, 113, 146, 183, 222, 264,
+    309, 355, 402, 451,
+];
+
+#[entry]
+fn main() -> ! {
+    let p = pac::Peripherals::take().unwrap();
+
+    let mut flash = p.FLASH.constrain();
+    let rcc = p.RCC.constrain();
+    let clocks = rcc.cfgr.sysclk(72.MHz()).freeze(&mut flash.acr);
+
+    let mut afio = p.AFIO.constrain();
+    let mut gpioa = p.GPIOA.split();
+    let pa0 = gpioa.pa0.into_alternate_push_pull(&mut gpioa.crl);
+
+    // 64 samples per period at 32 kHz give 500 Hz
+    let c1 = p
+        .TIM2
+        .pwm_hz::<Tim2NoRemap, _, _>(pa0, &mut afio.mapr, 32.kHz(), &clocks)
+        .split();
+
+    // Scale the table to the duty cycle range of the timer
+    let max = u32::from(c1.get_max_duty());
+    let table = singleton!(: [u16; 64] = [0; 64]).unwrap();
+    for (sample, &s) in table.iter_mut().zip(SINE.iter()) {
+        *sample = (u32::from(s) * max / 1000) as u16;
+    }
+
+    // TIM2 update requests are served by DMA1 channel 2
+    let dma1 = p.DMA1.split();
+    let mut wave = WaveformGenerator::new(c1, dma1.2, table, &clocks);
+    wave.start();
+
+    loop {
+        cortex_m::asm::wfi();
+    }
+}
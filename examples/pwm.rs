@@ -89,6 +89,11 @@ fn main() -> ! {
 
     asm::bkpt();
 
+    // The same duty cycle relative to the period, independent of the auto reload value
+    pwm.set_duty_percent(Channel::C3, 25);
+
+    asm::bkpt();
+
     // Use the Pwm object to set C3 to be zero
     pwm.set_duty(Channel::C3, 0);
 
@@ -107,6 +112,11 @@ fn main() -> ! {
 
     asm::bkpt();
 
+    // Use the PwmChannel object to set C3 to a tenth of the period
+    pwm_channel.set_duty_fraction(0.1);
+
+    asm::bkpt();
+
     // Use the PwmChannel object to set C3 to be zero
     pwm_channel.set_duty(0);
 
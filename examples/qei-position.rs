@@ -0,0 +1,43 @@
+//! Tracking an encoder position beyond the 16 bit counter range
+
+#![deny(unsafe_code)]
+#![no_main]
+#![no_std]
+
+use panic_semihosting as _;
+
+use cortex_m_semihosting::hprintln;
+
+use cortex_m_rt::entry;
+use stm32f1xx_hal::{pac, prelude::*, qei::QeiOptions, timer::Timer};
+
+#[entry]
+fn main() -> ! {
+    let dp = pac::Peripherals::take().unwrap();
+    let cp = cortex_m::Peripherals::take().unwrap();
+
+    let mut flash = dp.FLASH.constrain();
+    let rcc = dp.RCC.constrain();
+
+    let clocks = rcc.cfgr.freeze(&mut flash.acr);
+
+    let mut afio = dp.AFIO.constrain();
+    let gpioa = dp.GPIOA.split();
+
+    let c1 = gpioa.pa6;
+    let c2 = gpioa.pa7;
+
+    let mut qei = Timer::new(dp.TIM3, &clocks).qei((c1, c2), &mut afio.mapr, QeiOptions::default());
+    let mut delay = cp.SYST.delay(&clocks);
+
+    loop {
+        // Polling is fine as long as the encoder moves less than 32768 counts in between,
+        // otherwise call `handle_overflow` from the TIM3 interrupt after `listen_overflow`
+        for _ in 0..100 {
+            qei.handle_overflow();
+            delay.delay_ms(10_u16);
+        }
+
+        hprintln!("{} {:?}", qei.position(), qei.direction());
+    }
+}
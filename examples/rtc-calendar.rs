@@ -0,0 +1,54 @@
+//! Outputs the current date and time to hstdout using the real time clock
+
+#![deny(unsafe_code)]
+#![no_std]
+#![no_main]
+
+use panic_semihosting as _;
+
+use cortex_m_semihosting::hprintln;
+
+use cortex_m_rt::entry;
+use stm32f1xx_hal::rtc::RestoredOrNewRtc::{New, Restored};
+use stm32f1xx_hal::{
+    pac,
+    prelude::*,
+    rtc::{DateTime, Rtc},
+};
+
+#[entry]
+fn main() -> ! {
+    let p = pac::Peripherals::take().unwrap();
+
+    let mut pwr = p.PWR;
+    let rcc = p.RCC.constrain();
+    let mut backup_domain = rcc.bkp.constrain(p.BKP, &mut pwr);
+
+    // Count the seconds since 2000-01-01 instead of the Unix epoch
+    let epoch = DateTime::new(2000, 1, 1, 0, 0, 0);
+    let rtc = match Rtc::restore_or_new(p.RTC, &mut backup_domain) {
+        Restored(mut rtc) => {
+            rtc.set_epoch(epoch);
+            rtc
+        }
+        New(mut rtc) => {
+            // The counter runs at 1 Hz, set the clock once after the RTC was initialized
+            rtc.set_epoch(epoch);
+            rtc.set_date_time(DateTime::new(2024, 2, 29, 12, 0, 0));
+            rtc
+        }
+    };
+
+    loop {
+        let now = rtc.now();
+        hprintln!(
+            "{}-{:02}-{:02} {:02}:{:02}:{:02}",
+            now.year,
+            now.month,
+            now.day,
+            now.hour,
+            now.minute,
+            now.second
+        );
+    }
+}
@@ -0,0 +1,67 @@
+//! Toggles an LED every second from the RTC second interrupt
+//!
+//! This assumes that a LED is connected to pc13 as is the case on the blue pill board.
+
+#![no_std]
+#![no_main]
+
+use panic_halt as _;
+
+use stm32f1xx_hal as hal;
+
+use crate::hal::{
+    gpio::{gpioc, Output, PushPull},
+    pac::{interrupt, Interrupt, Peripherals},
+    prelude::*,
+    rtc::Rtc,
+};
+
+use core::cell::RefCell;
+use cortex_m::{asm::wfi, interrupt::Mutex};
+use cortex_m_rt::entry;
+
+type LedPin = gpioc::PC13<Output<PushPull>>;
+
+static G_LED: Mutex<RefCell<Option<LedPin>>> = Mutex::new(RefCell::new(None));
+static G_RTC: Mutex<RefCell<Option<Rtc>>> = Mutex::new(RefCell::new(None));
+
+#[interrupt]
+fn RTC() {
+    static mut LED: Option<LedPin> = None;
+    static mut RTC: Option<Rtc> = None;
+
+    let led = LED.get_or_insert_with(|| {
+        cortex_m::interrupt::free(|cs| G_LED.borrow(cs).replace(None).unwrap())
+    });
+    let rtc = RTC.get_or_insert_with(|| {
+        cortex_m::interrupt::free(|cs| G_RTC.borrow(cs).replace(None).unwrap())
+    });
+
+    rtc.clear_second_flag();
+    led.toggle();
+}
+
+#[entry]
+fn main() -> ! {
+    let dp = Peripherals::take().unwrap();
+
+    let mut pwr = dp.PWR;
+    let rcc = dp.RCC.constrain();
+
+    let mut gpioc = dp.GPIOC.split();
+    let led = gpioc.pc13.into_push_pull_output(&mut gpioc.crh);
+    cortex_m::interrupt::free(|cs| *G_LED.borrow(cs).borrow_mut() = Some(led));
+
+    // The RTC runs at 1 Hz from the LSE
+    let mut backup_domain = rcc.bkp.constrain(dp.BKP, &mut pwr);
+    let mut rtc = Rtc::new(dp.RTC, &mut backup_domain);
+    rtc.listen_seconds();
+    cortex_m::interrupt::free(|cs| *G_RTC.borrow(cs).borrow_mut() = Some(rtc));
+
+    unsafe { cortex_m::peripheral::NVIC::unmask(Interrupt::RTC) };
+
+    loop {
+        // The second interrupt wakes the device from Sleep mode
+        wfi();
+    }
+}
@@ -23,6 +23,8 @@ fn main() -> ! {
 
     // Initializes rtc every startup, use only if you don't have a battery.
     // let rtc = Rtc::new(p.RTC, &mut backup_domain);
+    // Or, to detect a missing or broken crystal:
+    // let rtc = Rtc::new_lse(p.RTC, &mut backup_domain, &clocks).unwrap();
 
     // Restores Rtc: that happens in case it was already running, a battery is connected,
     // and it was already initialized before.
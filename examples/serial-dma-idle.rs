@@ -0,0 +1,57 @@
+//! Serial interface variable-length frame reception using circular DMA and idle line detection
+
+#![deny(unsafe_code)]
+#![no_std]
+#![no_main]
+
+use panic_halt as _;
+
+use cortex_m::singleton;
+
+use cortex_m_rt::entry;
+use stm32f1xx_hal::{
+    pac,
+    prelude::*,
+    serial::{Config, Serial},
+};
+
+#[entry]
+fn main() -> ! {
+    let p = pac::Peripherals::take().unwrap();
+
+    let mut flash = p.FLASH.constrain();
+    let rcc = p.RCC.constrain();
+
+    let clocks = rcc.cfgr.freeze(&mut flash.acr);
+
+    let mut afio = p.AFIO.constrain();
+    let channels = p.DMA1.split();
+
+    let mut gpioa = p.GPIOA.split();
+
+    // USART1
+    let tx = gpioa.pa9.into_alternate_push_pull(&mut gpioa.crh);
+    let rx = gpioa.pa10;
+
+    let serial = Serial::new(
+        p.USART1,
+        (tx, rx, &mut afio.mapr),
+        Config::default().baudrate(9_600.bps()),
+        &clocks,
+    );
+
+    let (mut tx, rx) = serial.split();
+    let rx = rx.with_dma(channels.5);
+    let buf = singleton!(: [u8; 64] = [0; 64]).unwrap();
+
+    let mut frames = rx.frame_read(buf);
+
+    loop {
+        // Echo every received frame back once the line went idle
+        if let Ok(frame) = frames.read_frame() {
+            for &byte in frame.iter() {
+                nb::block!(tx.write_u8(byte)).ok();
+            }
+        }
+    }
+}
@@ -0,0 +1,75 @@
+//! Pings a Dynamixel AX-12 servo over a single-wire half-duplex USART link
+//!
+//! The data line of the servo is connected to PA9 (USART1 TX). The servo
+//! defaults to ID 1 and 1 Mbps.
+
+#![deny(unsafe_code)]
+#![no_std]
+#![no_main]
+
+use panic_halt as _;
+
+use cortex_m::asm;
+use cortex_m_rt::entry;
+use nb::block;
+use stm32f1xx_hal::{
+    pac,
+    prelude::*,
+    serial::{Config, Serial},
+};
+
+const SERVO_ID: u8 = 1;
+const INSTRUCTION_PING: u8 = 0x01;
+
+#[entry]
+fn main() -> ! {
+    let p = pac::Peripherals::take().unwrap();
+
+    let mut flash = p.FLASH.constrain();
+    let rcc = p.RCC.constrain();
+
+    let clocks = rcc
+        .cfgr
+        .use_hse(8.MHz())
+        .sysclk(72.MHz())
+        .pclk1(36.MHz())
+        .freeze(&mut flash.acr);
+
+    let mut afio = p.AFIO.constrain();
+    let mut gpioa = p.GPIOA.split();
+
+    // The servo pulls the data line up, so TX is driven as open-drain
+    let tx = gpioa.pa9.into_alternate_open_drain(&mut gpioa.crh);
+
+    let serial = Serial::new(
+        p.USART1,
+        (tx, &mut afio.mapr),
+        Config::default().baudrate(1_000_000.bps()),
+        &clocks,
+    );
+    let (mut servo, _rx_pin) = serial.into_half_duplex();
+
+    // Ping packet: header, ID, length, instruction, checksum
+    let length = 2;
+    let checksum = !(SERVO_ID.wrapping_add(length).wrapping_add(INSTRUCTION_PING));
+    servo
+        .bwrite_all(&[0xff, 0xff, SERVO_ID, length, INSTRUCTION_PING, checksum])
+        .unwrap();
+
+    // Turn the line around as soon as the last byte is sent, the servo answers
+    // after its return delay time
+    block!(servo.flush_then_listen()).unwrap();
+
+    // Status packet: header, ID, length, error, checksum
+    let mut status = [0u8; 6];
+    for byte in status.iter_mut() {
+        *byte = block!(servo.read()).unwrap();
+    }
+
+    let _servo_error = status[4];
+    asm::bkpt();
+
+    loop {
+        asm::wfi();
+    }
+}
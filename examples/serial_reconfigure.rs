@@ -84,6 +84,9 @@ fn main() -> ! {
     // This may block for a while if the transmission is still in progress.
     block!(serial.reconfigure(Config::default().baudrate(115_200.bps()), &clocks)).unwrap();
 
+    // The actual baud rate differs from the requested one by the rounding of the divider
+    assert!(serial.baud_rate_error(115_200.bps(), &clocks).abs() < 10_000);
+
     // Let's see if it works.'
     let sent = b'Y';
     block!(serial.tx.write_u8(sent)).unwrap();
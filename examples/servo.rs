@@ -0,0 +1,49 @@
+//! Sweeps a hobby servo connected to PA0 back and forth
+
+#![deny(unsafe_code)]
+#![no_main]
+#![no_std]
+
+use panic_halt as _;
+
+use cortex_m_rt::entry;
+use stm32f1xx_hal::{
+    pac,
+    prelude::*,
+    timer::{Servo, ServoConfig, Tim2NoRemap},
+};
+
+#[entry]
+fn main() -> ! {
+    let p = pac::Peripherals::take().unwrap();
+    let cp = cortex_m::Peripherals::take().unwrap();
+
+    let mut flash = p.FLASH.constrain();
+    let rcc = p.RCC.constrain();
+
+    let clocks = rcc.cfgr.freeze(&mut flash.acr);
+
+    let mut afio = p.AFIO.constrain();
+    let mut gpioa = p.GPIOA.split();
+
+    let c1 = gpioa.pa0.into_alternate_push_pull(&mut gpioa.crl);
+    let pwm = p
+        .TIM2
+        .pwm_hz::<Tim2NoRemap, _, _>(c1, &mut afio.mapr, 50.Hz(), &clocks);
+
+    // This servo needs 0.6 ms - 2.4 ms for its full range and is centered 20 µs late
+    let config = ServoConfig::default().pulse_range(600, 2400).offset_us(-20);
+    let mut servo = Servo::with_config(pwm.split(), config);
+    servo.enable();
+
+    let mut delay = cp.SYST.delay(&clocks);
+
+    loop {
+        for deg in (0..=180).step_by(10) {
+            servo.set_angle(deg);
+            delay.delay_ms(100_u32);
+        }
+        servo.set_pulse_us(1500);
+        delay.delay_ms(1_000_u32);
+    }
+}
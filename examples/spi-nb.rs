@@ -0,0 +1,88 @@
+//! Non-blocking SPI loopback test
+//!
+//! Connect MOSI (PA7) to MISO (PA6). Words are sent and read back with the
+//! `embedded-hal-nb` `FullDuplex` operations, polled from a small state machine
+//! instead of busy-waiting on each word. Every word written must be read back.
+
+#![allow(clippy::empty_loop)]
+#![no_std]
+#![no_main]
+
+use cortex_m::asm;
+use cortex_m_rt::entry;
+use panic_halt as _;
+
+use embedded_hal_nb::spi::FullDuplex;
+use stm32f1xx_hal::{
+    pac,
+    prelude::*,
+    spi::{Mode, Phase, Polarity},
+};
+
+const MODE: Mode = Mode {
+    polarity: Polarity::IdleLow,
+    phase: Phase::CaptureOnFirstTransition,
+};
+
+enum State {
+    Send(u8),
+    Receive(u8),
+}
+
+#[entry]
+fn main() -> ! {
+    let dp = pac::Peripherals::take().unwrap();
+
+    let mut flash = dp.FLASH.constrain();
+    let rcc = dp.RCC.constrain();
+    let clocks = rcc.cfgr.freeze(&mut flash.acr);
+
+    let mut afio = dp.AFIO.constrain();
+    let gpioa = dp.GPIOA.split();
+
+    let mut spi = dp.SPI1.spi(
+        (gpioa.pa5, gpioa.pa6, gpioa.pa7, &mut afio.mapr),
+        MODE,
+        1.MHz(),
+        &clocks,
+    );
+
+    let mut state = State::Send(0);
+    let mut lost = 0u32;
+    let mut count = 0u32;
+
+    loop {
+        state = match state {
+            State::Send(word) => match FullDuplex::write(&mut spi, word) {
+                Ok(()) => State::Receive(word),
+                Err(nb::Error::WouldBlock) => State::Send(word),
+                Err(nb::Error::Other(_)) => {
+                    lost += 1;
+                    State::Send(word)
+                }
+            },
+            State::Receive(word) => match FullDuplex::read(&mut spi) {
+                Ok(received) => {
+                    if received != word {
+                        lost += 1;
+                    }
+                    count += 1;
+                    State::Send(word.wrapping_add(1))
+                }
+                Err(nb::Error::WouldBlock) => State::Receive(word),
+                Err(nb::Error::Other(_)) => {
+                    lost += 1;
+                    State::Send(word.wrapping_add(1))
+                }
+            },
+        };
+
+        // other work can run here between the non-blocking operations
+
+        if count == 10_000 {
+            assert_eq!(lost, 0, "words were lost");
+            asm::bkpt();
+            count = 0;
+        }
+    }
+}
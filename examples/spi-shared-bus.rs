@@ -0,0 +1,67 @@
+//! Two SPI sensors with their own chip selects (PA3, PA4) on a shared SPI1 bus.
+//!
+//! `Spi` implements the `SpiBus` trait of `embedded-hal` 1.0, so the `RefCellDevice` and
+//! `CriticalSectionDevice` wrappers of the `embedded-hal-bus` crate can turn it into one
+//! `SpiDevice` per chip select.
+
+#![deny(unsafe_code)]
+#![no_std]
+#![no_main]
+
+use core::cell::RefCell;
+
+use cortex_m_rt::entry;
+use cortex_m_semihosting::hprintln;
+use embedded_hal::spi::{Operation, SpiDevice, MODE_3};
+use embedded_hal_bus::spi::RefCellDevice;
+use panic_semihosting as _;
+use stm32f1xx_hal::{gpio::PinState, pac, prelude::*, spi::Spi};
+
+/// Reads the identification register of a LIS3DH or similar sensor
+fn who_am_i<D: SpiDevice>(device: &mut D) -> Result<u8, D::Error> {
+    let mut id = [0];
+    device.transaction(&mut [Operation::Write(&[0x8f]), Operation::Read(&mut id)])?;
+    Ok(id[0])
+}
+
+#[entry]
+fn main() -> ! {
+    let dp = pac::Peripherals::take().unwrap();
+
+    let mut flash = dp.FLASH.constrain();
+    let rcc = dp.RCC.constrain();
+
+    let clocks = rcc.cfgr.freeze(&mut flash.acr);
+
+    let mut afio = dp.AFIO.constrain();
+    let mut gpioa = dp.GPIOA.split();
+
+    let sck = gpioa.pa5.into_alternate_push_pull(&mut gpioa.crl);
+    let miso = gpioa.pa6;
+    let mosi = gpioa.pa7.into_alternate_push_pull(&mut gpioa.crl);
+    let cs1 = gpioa
+        .pa3
+        .into_push_pull_output_with_state(&mut gpioa.crl, PinState::High);
+    let cs2 = gpioa
+        .pa4
+        .into_push_pull_output_with_state(&mut gpioa.crl, PinState::High);
+
+    let spi = Spi::new(
+        dp.SPI1,
+        (sck, miso, mosi, &mut afio.mapr),
+        MODE_3,
+        1.MHz(),
+        &clocks,
+    );
+
+    let bus = RefCell::new(spi);
+    let mut sensor1 = RefCellDevice::new_no_delay(&bus, cs1).unwrap();
+    let mut sensor2 = RefCellDevice::new_no_delay(&bus, cs2).unwrap();
+
+    hprintln!("sensor 1: {:?}", who_am_i(&mut sensor1));
+    hprintln!("sensor 2: {:?}", who_am_i(&mut sensor2));
+
+    loop {
+        cortex_m::asm::wfi();
+    }
+}
@@ -0,0 +1,68 @@
+//! SPI slave answering requests of an external master (another MCU)
+//!
+//! The master selects this board with NSS, sends a command byte and reads the answer in the
+//! following frame. The answer to a command is the command incremented by one.
+//!
+//! PB12 <-NSS-  master
+//! PB13 <-SCK-  master
+//! PB14 -MISO-> master
+//! PB15 <-MOSI- master
+
+#![deny(unsafe_code)]
+#![no_std]
+#![no_main]
+
+use panic_halt as _;
+
+use cortex_m_rt::entry;
+use stm32f1xx_hal::{
+    pac,
+    prelude::*,
+    spi::{Error, Mode, Phase, Polarity, SlaveSelect},
+};
+
+const MODE: Mode = Mode {
+    polarity: Polarity::IdleLow,
+    phase: Phase::CaptureOnFirstTransition,
+};
+
+/// Sent while the master clocks in a command
+const IDLE: u8 = 0xff;
+
+#[entry]
+fn main() -> ! {
+    let dp = pac::Peripherals::take().unwrap();
+
+    let mut flash = dp.FLASH.constrain();
+    let rcc = dp.RCC.constrain();
+    let _clocks = rcc.cfgr.freeze(&mut flash.acr);
+
+    let mut gpiob = dp.GPIOB.split();
+
+    // NSS is an alternate function input
+    let _nss = gpiob.pb12.into_floating_input(&mut gpiob.crh);
+    let sck = gpiob.pb13;
+    let miso = gpiob.pb14;
+    let mosi = gpiob.pb15;
+
+    let mut spi = dp.SPI2.spi_slave((sck, miso, mosi), MODE);
+    spi.slave_select(SlaveSelect::Hardware);
+
+    // The first frame of the master is answered with whatever is in the transmit buffer
+    spi.preload(IDLE);
+
+    loop {
+        match spi.read_nonblocking() {
+            Ok(cmd) => {
+                // The answer goes out with the next frame clocked by the master
+                let _ = nb::block!(spi.write_nonblocking(cmd.wrapping_add(1)));
+            }
+            Err(nb::Error::WouldBlock) => {}
+            Err(nb::Error::Other(Error::Overrun)) => {
+                // A command was missed, resynchronize with the master
+                spi.preload(IDLE);
+            }
+            Err(nb::Error::Other(_)) => {}
+        }
+    }
+}
@@ -0,0 +1,36 @@
+//! Enters Standby mode and wakes up by a button connecting PA0 (WKUP) to VDD
+//!
+//! The LED on pc13 lights up for a second after each wakeup.
+
+#![no_std]
+#![no_main]
+
+use panic_halt as _;
+
+use cortex_m_rt::entry;
+use stm32f1xx_hal::{pac, prelude::*, timer::Timer};
+
+#[entry]
+fn main() -> ! {
+    let mut cp = cortex_m::Peripherals::take().unwrap();
+    let dp = pac::Peripherals::take().unwrap();
+
+    let mut flash = dp.FLASH.constrain();
+    let rcc = dp.RCC.constrain();
+    let mut pwr = dp.PWR.constrain();
+    let clocks = rcc.cfgr.freeze(&mut flash.acr);
+
+    let mut gpioc = dp.GPIOC.split();
+    let mut led = gpioc.pc13.into_push_pull_output(&mut gpioc.crh);
+
+    if pwr.was_standby_wakeup() {
+        led.set_low();
+        let mut delay = Timer::syst(cp.SYST, &clocks).delay();
+        delay.delay_ms(1000u32);
+        led.set_high();
+    }
+    pwr.clear_wakeup_flag();
+
+    pwr.enable_wakeup_pin();
+    pwr.enter_standby(&mut cp.SCB);
+}
@@ -0,0 +1,58 @@
+//! Wakes up from Stop mode every 5 seconds by the RTC alarm and toggles an LED
+//!
+//! This assumes that a LED is connected to pc13 as is the case on the blue pill board.
+
+#![no_std]
+#![no_main]
+
+use panic_halt as _;
+
+use cortex_m_rt::entry;
+use stm32f1xx_hal::{
+    pac::{self, interrupt, Interrupt},
+    prelude::*,
+    pwr::StopMode,
+    rtc::Rtc,
+};
+
+const WAKEUP_INTERVAL_SECONDS: u32 = 5;
+
+#[interrupt]
+fn RTCALARM() {
+    // The flags are cleared in the main loop before entering Stop mode again
+}
+
+#[entry]
+fn main() -> ! {
+    let mut cp = cortex_m::Peripherals::take().unwrap();
+    let mut dp = pac::Peripherals::take().unwrap();
+
+    let mut flash = dp.FLASH.constrain();
+    let rcc = dp.RCC.constrain();
+    let mut pwr = dp.PWR.constrain();
+
+    // The HSE and PLL are restarted after each wakeup
+    let _clocks = rcc
+        .cfgr
+        .use_hse(8.MHz())
+        .sysclk(72.MHz())
+        .freeze(&mut flash.acr);
+
+    let mut gpioc = dp.GPIOC.split();
+    let mut led = gpioc.pc13.into_push_pull_output(&mut gpioc.crh);
+
+    let mut backup_domain = rcc.bkp.constrain(dp.BKP, &mut pwr);
+    let mut rtc = Rtc::new(dp.RTC, &mut backup_domain);
+    rtc.listen_alarm();
+    rtc.listen_alarm_exti(&mut dp.EXTI);
+
+    unsafe { cortex_m::peripheral::NVIC::unmask(Interrupt::RTCALARM) };
+
+    loop {
+        rtc.clear_alarm_exti(&mut dp.EXTI);
+        rtc.set_alarm(rtc.current_time() + WAKEUP_INTERVAL_SECONDS);
+        pwr.enter_stop(&mut cp.SCB, StopMode::LowPowerRegulator)
+            .unwrap();
+        led.toggle();
+    }
+}
@@ -0,0 +1,55 @@
+//! 32 bit microsecond counter from TIM2 (master) and TIM3 (slave), with input capture on PA0
+
+#![deny(unsafe_code)]
+#![no_main]
+#![no_std]
+
+use panic_semihosting as _;
+
+use cortex_m_rt::entry;
+use cortex_m_semihosting::hprintln;
+use stm32f1xx_hal::{
+    pac,
+    prelude::*,
+    timer::{capture::Edge, Tim2NoRemap, Timer},
+};
+
+#[entry]
+fn main() -> ! {
+    let dp = pac::Peripherals::take().unwrap();
+    let cp = cortex_m::Peripherals::take().unwrap();
+
+    let mut flash = dp.FLASH.constrain();
+    let rcc = dp.RCC.constrain();
+
+    let clocks = rcc.cfgr.freeze(&mut flash.acr);
+
+    let counter = Timer::new(dp.TIM2, &clocks).cascade(Timer::new(dp.TIM3, &clocks), 1.MHz());
+    let mut delay = cp.SYST.delay(&clocks);
+
+    // Unlike a single 16 bit timer this does not wrap after 65 ms
+    let start = counter.count();
+    delay.delay_ms(500_u16);
+    hprintln!("{} µs", counter.count().wrapping_sub(start));
+
+    // Time between rising edges on PA0 (TIM2 channel 1)
+    let mut afio = dp.AFIO.constrain();
+    let mut gpioa = dp.GPIOA.split();
+    let pin = gpioa.pa0.into_pull_down_input(&mut gpioa.crl);
+    let mut capture =
+        counter.input_capture::<Tim2NoRemap, _, _, 0>(pin, &mut afio.mapr, Edge::Rising);
+
+    let mut previous = None;
+    loop {
+        match capture.read() {
+            Ok(now) => {
+                if let Some(previous) = previous {
+                    hprintln!("period: {} µs", now.wrapping_sub(previous));
+                }
+                previous = Some(now);
+            }
+            Err(nb::Error::Other(_)) => previous = None,
+            Err(nb::Error::WouldBlock) => {}
+        }
+    }
+}
@@ -0,0 +1,59 @@
+//! Free running microsecond timestamps from TIM3
+
+#![no_main]
+#![no_std]
+
+use panic_semihosting as _;
+
+use core::cell::RefCell;
+use cortex_m::interrupt::Mutex;
+use cortex_m_rt::entry;
+use cortex_m_semihosting::hprintln;
+use stm32f1xx_hal::{
+    pac::{self, interrupt, Interrupt, TIM3},
+    prelude::*,
+    timer::{Event, MonoTimerExt, MonoTimerUs},
+};
+
+static G_MONO: Mutex<RefCell<Option<MonoTimerUs<TIM3>>>> = Mutex::new(RefCell::new(None));
+
+// Counts the overflows of the 16 bit counter
+#[interrupt]
+fn TIM3() {
+    cortex_m::interrupt::free(|cs| {
+        if let Some(mono) = G_MONO.borrow(cs).borrow_mut().as_mut() {
+            mono.handle_overflow();
+        }
+    });
+}
+
+fn now_us() -> u64 {
+    cortex_m::interrupt::free(|cs| G_MONO.borrow(cs).borrow().as_ref().unwrap().now().ticks())
+}
+
+#[entry]
+fn main() -> ! {
+    let dp = pac::Peripherals::take().unwrap();
+    let cp = cortex_m::Peripherals::take().unwrap();
+
+    let mut flash = dp.FLASH.constrain();
+    let rcc = dp.RCC.constrain();
+
+    let clocks = rcc.cfgr.freeze(&mut flash.acr);
+
+    let mut mono = dp.TIM3.monotonic_us(&clocks);
+    mono.listen(Event::Update);
+    cortex_m::interrupt::free(|cs| *G_MONO.borrow(cs).borrow_mut() = Some(mono));
+
+    unsafe {
+        cortex_m::peripheral::NVIC::unmask(Interrupt::TIM3);
+    }
+
+    let mut delay = cp.SYST.delay(&clocks);
+
+    loop {
+        let start = now_us();
+        delay.delay_ms(250_u16);
+        hprintln!("250 ms took {} µs", now_us() - start);
+    }
+}
@@ -32,8 +32,6 @@ fn main() -> ! {
         .pclk1(24.MHz())
         .freeze(&mut flash.acr);
 
-    assert!(clocks.usbclk_valid());
-
     // Configure the on-board LED (PC13, green)
     let mut gpioc = dp.GPIOC.split();
     let mut led = gpioc.pc13.into_push_pull_output(&mut gpioc.crh);
@@ -49,11 +47,12 @@ fn main() -> ! {
     usb_dp.set_low();
     delay(clocks.sysclk().raw() / 100);
 
-    let usb = Peripheral {
-        usb: dp.USB,
-        pin_dm: gpioa.pa11,
-        pin_dp: usb_dp.into_floating_input(&mut gpioa.crh),
-    };
+    let usb = Peripheral::new(
+        dp.USB,
+        gpioa.pa11,
+        usb_dp.into_floating_input(&mut gpioa.crh),
+        &clocks,
+    );
     let usb_bus = UsbBus::new(usb);
 
     let mut serial = SerialPort::new(&usb_bus);
@@ -31,8 +31,6 @@ fn main() -> ! {
         .pclk1(24.MHz())
         .freeze(&mut flash.acr);
 
-    assert!(clocks.usbclk_valid());
-
     let mut gpioa = dp.GPIOA.split();
 
     // BluePill board has a pull-up resistor on the D+ line.
@@ -46,11 +44,7 @@ fn main() -> ! {
     let usb_dm = gpioa.pa11;
     let usb_dp = usb_dp.into_floating_input(&mut gpioa.crh);
 
-    let usb = Peripheral {
-        usb: dp.USB,
-        pin_dm: usb_dm,
-        pin_dp: usb_dp,
-    };
+    let usb = Peripheral::new(dp.USB, usb_dm, usb_dp, &clocks);
 
     // Unsafe to allow access to static variables
     unsafe {
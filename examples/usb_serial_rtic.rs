@@ -36,8 +36,6 @@ mod app {
             .pclk1(24.MHz())
             .freeze(&mut flash.acr);
 
-        assert!(clocks.usbclk_valid());
-
         let mut gpioa = cx.device.GPIOA.split();
 
         // BluePill board has a pull-up resistor on the D+ line.
@@ -51,11 +49,7 @@ mod app {
         let usb_dm = gpioa.pa11;
         let usb_dp = usb_dp.into_floating_input(&mut gpioa.crh);
 
-        let usb = Peripheral {
-            usb: cx.device.USB,
-            pin_dm: usb_dm,
-            pin_dp: usb_dp,
-        };
+        let usb = Peripheral::new(cx.device.USB, usb_dm, usb_dp, &clocks);
 
         unsafe {
             USB_BUS.replace(UsbBus::new(usb));
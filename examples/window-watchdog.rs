@@ -0,0 +1,43 @@
+//! Feeds the window watchdog inside its window and saves state on early wakeup
+
+#![no_std]
+#![no_main]
+
+use panic_halt as _;
+
+use cortex_m::asm::delay;
+use cortex_m_rt::entry;
+use fugit::ExtU32;
+use stm32f1xx_hal::{pac, pac::interrupt, prelude::*, watchdog::WindowWatchdog};
+
+#[entry]
+fn main() -> ! {
+    let dp = pac::Peripherals::take().unwrap();
+
+    let mut flash = dp.FLASH.constrain();
+    let rcc = dp.RCC.constrain();
+    let clocks = rcc
+        .cfgr
+        .sysclk(72.MHz())
+        .pclk1(36.MHz())
+        .freeze(&mut flash.acr);
+
+    let mut wwdg = WindowWatchdog::new(dp.WWDG);
+    wwdg.stop_on_debug(&dp.DBGMCU, true);
+    wwdg.listen();
+    unsafe { cortex_m::peripheral::NVIC::unmask(pac::Interrupt::WWDG) };
+
+    // Feeding is allowed between 10 ms and 40 ms after the previous feed
+    wwdg.start(&clocks, 10.millis(), 40.millis());
+
+    loop {
+        // 20 ms at 72 MHz
+        delay(72_000 * 20);
+        wwdg.feed();
+    }
+}
+
+#[interrupt]
+fn WWDG() {
+    // Save state here, the MCU is reset after one more watchdog tick
+}
@@ -1,4 +1,32 @@
 //! # API for the Analog to Digital converter
+//!
+//! ## External trigger
+//!
+//! Conversions of the regular sequence can be started by a timer event instead of software,
+//! see `Adc::set_external_trigger` and `Adc::with_triggered_dma`. The `EXTSEL` encoding
+//! differs between the ADCs:
+//!
+//! | `EXTSEL` | ADC1 and ADC2                | ADC3 (high and XL-density) |
+//! |----------|------------------------------|----------------------------|
+//! | `0b000`  | TIM1 CC1                     | TIM3 CC1                   |
+//! | `0b001`  | TIM1 CC2                     | TIM2 CC3                   |
+//! | `0b010`  | TIM1 CC3                     | TIM1 CC3                   |
+//! | `0b011`  | TIM2 CC2                     | TIM8 CC1                   |
+//! | `0b100`  | TIM3 TRGO                    | TIM8 TRGO                  |
+//! | `0b101`  | TIM4 CC4                     | TIM5 CC1                   |
+//! | `0b110`  | EXTI line 11 / TIM8 TRGO     | TIM5 CC3                   |
+//! | `0b111`  | SWSTART                      | SWSTART                    |
+//!
+//! TIM8 TRGO replaces EXTI line 11 for ADC1 and ADC2 on high and XL-density devices when
+//! `ADC12_ETRGREG_REMAP` is set in `AFIO_MAPR2`. A timer drives TRGO on its update event
+//! after `set_master_mode(MMS::Update)`.
+//!
+//! ## Analog watchdog
+//!
+//! `Adc::configure_watchdog` monitors the regular conversions of one channel in hardware,
+//! e.g. for undervoltage or overcurrent trips. A result outside the thresholds sets the
+//! watchdog flag and raises the `ADC1_2` (or `ADC3`) interrupt, the handler checks
+//! `is_watchdog_triggered` and calls `clear_watchdog_flag`.
 
 use core::marker::PhantomData;
 use embedded_hal_02::adc::{Channel, OneShot};
@@ -20,14 +48,18 @@ use crate::pac::{self, RCC};
 pub struct Continuous;
 /// Scan mode
 pub struct Scan;
+/// Conversions started by an external trigger
+pub struct Triggered;
 
 /// ADC configuration
 pub struct Adc<ADC> {
     rb: ADC,
     sample_time: SampleTime,
+    channel_sample_times: [Option<SampleTime>; MAX_CHANNEL as usize + 1],
     align: Align,
     sysclk: Hertz,
     adcclk: Hertz,
+    calibration: u16,
 }
 
 /// ADC sampling time
@@ -170,6 +202,28 @@ adc_pins!(pac::ADC3,
     gpio::PC3<Analog> => 13,
 );
 
+/// Highest ADC channel number (channel 16 and 17 are internal to ADC1)
+pub const MAX_CHANNEL: u8 = 17;
+
+/// Maximum number of conversions in the regular sequence
+pub const MAX_SEQUENCE_LEN: usize = 16;
+
+/// Maximum number of conversions in the injected sequence
+pub const MAX_INJECTED_LEN: usize = 4;
+
+/// `JSQR` value for the injected sequence `channels`
+///
+/// A sequence shorter than 4 occupies the last JSQx slots.
+fn jsqr_bits(channels: &[u8]) -> u32 {
+    let len = channels.len();
+    let first = MAX_INJECTED_LEN - len;
+    channels
+        .iter()
+        .enumerate()
+        .fold(0u32, |s, (i, c)| s | ((*c as u32) << ((first + i) * 5)))
+        | (((len - 1) as u32) << 20)
+}
+
 /// Stored ADC config can be restored using the `Adc::restore_cfg` method
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Default)]
 pub struct StoredConfig(SampleTime, Align);
@@ -189,9 +243,11 @@ macro_rules! adc_hal {
                     let mut s = Self {
                         rb: adc,
                         sample_time: SampleTime::default(),
+                        channel_sample_times: [None; MAX_CHANNEL as usize + 1],
                         align: Align::default(),
                         sysclk: clocks.sysclk(),
                         adcclk: clocks.adcclk(),
+                        calibration: 0,
                     };
                     s.enable_clock();
                     s.power_down();
@@ -213,6 +269,35 @@ macro_rules! adc_hal {
                     s
                 }
 
+                /// Runs the self-calibration again and returns the new calibration value.
+                ///
+                /// The calibration is done once by the constructor. It corrects the offset
+                /// of the internal capacitor bank and changes with temperature and supply
+                /// voltage, so it can be repeated after large changes of these, e.g. before a
+                /// precision measurement. The ADC has been powered on for more than the two
+                /// ADC clock cycles required before a calibration since the constructor.
+                ///
+                /// Busy waits for the `RSTCAL` and `CAL` bits, which takes about 83 ADC
+                /// clock cycles. No conversion may be running.
+                pub fn calibrate(&mut self) -> u16 {
+                    /* reset calibration */
+                    self.rb.cr2().modify(|_, w| w.rstcal().set_bit());
+                    while self.rb.cr2().read().rstcal().bit_is_set() {}
+
+                    /* calibrate */
+                    self.rb.cr2().modify(|_, w| w.cal().set_bit());
+                    while self.rb.cr2().read().cal().bit_is_set() {}
+
+                    // The calibration code is left in DR until the next conversion
+                    self.calibration = self.rb.dr().read().data().bits();
+                    self.calibration
+                }
+
+                /// Calibration value of the last calibration, read from `DR` after it finished
+                pub fn calibration_value(&self) -> u16 {
+                    self.calibration
+                }
+
                 /// Save current ADC config
                 pub fn save_cfg(&mut self) -> StoredConfig {
                     StoredConfig(self.sample_time, self.align)
@@ -221,42 +306,226 @@ macro_rules! adc_hal {
                 /// Restore saved ADC config
                 pub fn restore_cfg(&mut self, cfg: StoredConfig) {
                     self.sample_time = cfg.0;
-                    self.align = cfg.1;
+                    self.set_align(cfg.1);
                 }
 
                 /// Reset the ADC config to default, return existing config
                 pub fn default_cfg(&mut self) -> StoredConfig {
                     let cfg = self.save_cfg();
                     self.sample_time = SampleTime::default();
-                    self.align = Align::default();
+                    self.set_align(Align::default());
                     cfg
                 }
 
                 /// Set ADC sampling time
                 ///
                 /// Options can be found in [SampleTime](crate::adc::SampleTime).
+                /// Used for all channels without a sample time of their own, see
+                /// [`set_channel_sample_time`](Self::set_channel_sample_time).
                 pub fn set_sample_time(&mut self, t_samp: SampleTime) {
                     self.sample_time = t_samp;
                 }
 
+                /// Set the sampling time of a single channel
+                ///
+                /// Sources with a high impedance need a longer sampling time to charge the
+                /// sampling capacitor. The temperature sensor and Vrefint (channel 16 and 17)
+                /// need at least 17.1 us, e.g. `SampleTime::T_239` at 14 MHz ADC clock.
+                ///
+                /// Panics if `chan` is above [`MAX_CHANNEL`].
+                pub fn set_channel_sample_time(&mut self, chan: u8, sample_time: SampleTime) {
+                    assert!(chan <= MAX_CHANNEL, "invalid ADC channel");
+                    self.channel_sample_times[chan as usize] = Some(sample_time);
+                    self.write_sample_time(chan, sample_time);
+                }
+
+                /// Makes a channel use the sampling time set by
+                /// [`set_sample_time`](Self::set_sample_time) again
+                pub fn clear_channel_sample_time(&mut self, chan: u8) {
+                    assert!(chan <= MAX_CHANNEL, "invalid ADC channel");
+                    self.channel_sample_times[chan as usize] = None;
+                }
+
+                /// Returns the sampling time used for a channel
+                pub fn channel_sample_time(&self, chan: u8) -> SampleTime {
+                    self.channel_sample_times
+                        .get(chan as usize)
+                        .copied()
+                        .flatten()
+                        .unwrap_or(self.sample_time)
+                }
+
                 /// Set the Adc result alignment
                 ///
-                /// Options can be found in [Align](crate::adc::Align).
+                /// Options can be found in [Align](crate::adc::Align). The 12 bit results of
+                /// regular conversions, returned by `read` and the DMA transfers, are in bits
+                /// 11..0 with right alignment and in bits 15..4 with left alignment, so they
+                /// can be treated like 16 bit values. Injected results are signed, with left
+                /// alignment the sign is in bit 15 and the data in bits 14..3, see
+                /// `read_injected`.
+                ///
+                /// Must not be called during a conversion.
                 pub fn set_align(&mut self, align: Align) {
                     self.align = align;
+                    // Writing CR2 without a change with ADON set starts a conversion
+                    if self.rb.cr2().read().align().bit() != bool::from(align) {
+                        self.rb.cr2().modify(|_, w| w.align().bit(align.into()));
+                    }
                 }
 
                 /// Returns the largest possible sample value for the current settings
                 pub fn max_sample(&self) -> u16 {
                     match self.align {
-                        Align::Left => u16::MAX,
+                        Align::Left => ((1 << 12) - 1) << 4,
                         Align::Right => (1 << 12) - 1,
                     }
                 }
 
+                /// Selects the event starting a conversion of the regular sequence
+                ///
+                /// See the [module documentation](crate::adc#external-trigger) for the
+                /// available events of each ADC.
                 #[inline(always)]
                 pub fn set_external_trigger(&mut self, trigger: crate::pac::$adc::cr2::EXTSEL) {
-                    self.rb.cr2().modify(|_, w| w.extsel().variant(trigger))
+                    self.rb.cr2().modify(|_, w| {
+                        w.extsel().variant(trigger);
+                        w.exttrig().set_bit()
+                    })
+                }
+
+                /// Configures the injected sequence
+                ///
+                /// Up to 4 `channels` are converted in order, each with the current sample
+                /// time, when the injected trigger occurs. The result of the n-th channel is
+                /// read with `read_injected(n)`. Scan mode is enabled for sequences of more
+                /// than one channel and kept while such a sequence is configured.
+                ///
+                /// Panics if the sequence is empty, longer than 4 or contains a channel above
+                /// [`MAX_CHANNEL`].
+                pub fn set_injected_sequence(&mut self, channels: &[u8]) {
+                    let len = channels.len();
+                    assert!(
+                        (1..=MAX_INJECTED_LEN).contains(&len),
+                        "injected sequence must have 1 to 4 channels"
+                    );
+                    for chan in channels {
+                        self.write_sample_time(*chan, self.channel_sample_time(*chan));
+                    }
+                    let bits = jsqr_bits(channels);
+                    self.rb.jsqr().write(|w| unsafe { w.bits(bits) });
+                    // all channels of the injected sequence are only converted in scan mode
+                    self.rb.cr1().modify(|_, w| w.scan().bit(len > 1));
+                }
+
+                /// Scan mode is needed for an injected sequence with more than one channel
+                fn injected_scan(&self) -> bool {
+                    self.rb.jsqr().read().jl().bits() > 0
+                }
+
+                /// Sets the offset subtracted from the result of the `rank`-th injected channel
+                ///
+                /// `rank` counts from 0, `offset` is a 12 bit raw value. The result read by
+                /// `read_injected` can become negative.
+                pub fn set_injected_offset(&mut self, rank: usize, offset: u16) {
+                    let offset = (offset & 0xfff) as u32;
+                    match rank {
+                        0 => self.rb.jofr1().write(|w| unsafe { w.bits(offset) }),
+                        1 => self.rb.jofr2().write(|w| unsafe { w.bits(offset) }),
+                        2 => self.rb.jofr3().write(|w| unsafe { w.bits(offset) }),
+                        3 => self.rb.jofr4().write(|w| unsafe { w.bits(offset) }),
+                        _ => panic!("invalid injected rank"),
+                    };
+                }
+
+                /// Selects the event starting the injected sequence
+                ///
+                /// Use `JEXTSEL::Jswstart` together with `start_injected` for software starts.
+                /// The injected sequence preempts an ongoing regular conversion.
+                #[inline(always)]
+                pub fn set_injected_trigger(&mut self, trigger: crate::pac::$adc::cr2::JEXTSEL) {
+                    self.rb.cr2().modify(|_, w| {
+                        w.jextsel().variant(trigger);
+                        w.jexttrig().set_bit()
+                    })
+                }
+
+                /// Starts the injected sequence by software
+                pub fn start_injected(&mut self) {
+                    self.rb.sr().modify(|_, w| w.jeoc().clear_bit());
+                    self.rb.cr2().modify(|_, w| w.jswstart().set_bit());
+                }
+
+                /// Returns true once the whole injected sequence is converted
+                pub fn is_injected_done(&self) -> bool {
+                    self.rb.sr().read().jeoc().bit_is_set()
+                }
+
+                /// Clears the injected end of conversion flag
+                pub fn clear_injected_done(&mut self) {
+                    self.rb.sr().modify(|_, w| w.jeoc().clear_bit());
+                }
+
+                /// Enables or disables the interrupt at the end of the injected sequence
+                pub fn listen_injected(&mut self, enable: bool) {
+                    self.rb.cr1().modify(|_, w| w.jeocie().bit(enable));
+                }
+
+                /// Reads the offset corrected result of the `rank`-th injected channel
+                ///
+                /// The value is sign extended with right alignment. With left alignment the
+                /// sign is in bit 15 and the 12 bit value is shifted left by 3 instead of 4 as
+                /// for regular results, so the result is 8 times the right aligned one.
+                pub fn read_injected(&self, rank: usize) -> i16 {
+                    let bits = match rank {
+                        0 => self.rb.jdr1().read().bits(),
+                        1 => self.rb.jdr2().read().bits(),
+                        2 => self.rb.jdr3().read().bits(),
+                        3 => self.rb.jdr4().read().bits(),
+                        _ => panic!("invalid injected rank"),
+                    };
+                    bits as u16 as i16
+                }
+
+                /// Enables the analog watchdog on `chan` with the interrupt
+                ///
+                /// The watchdog flag is set and the ADC interrupt is raised when a regular
+                /// conversion of `chan` gives a value below `low` or above `high`. The
+                /// thresholds are compared with the raw 12 bit result before alignment, so
+                /// they are unaffected by `set_align`. The ADCs of the F1 family have a fixed
+                /// 12 bit resolution, the thresholds are in the same scale.
+                ///
+                /// Panics if `chan` is above [`MAX_CHANNEL`], a threshold has more than
+                /// 12 bits or `low` is above `high`.
+                pub fn configure_watchdog(&mut self, chan: u8, low: u16, high: u16) {
+                    assert!(chan <= MAX_CHANNEL);
+                    assert!(high < 1 << 12 && low <= high);
+                    self.rb.ltr().write(|w| w.lt().set(low));
+                    self.rb.htr().write(|w| w.ht().set(high));
+                    self.rb.sr().modify(|_, w| w.awd().clear_bit());
+                    self.rb.cr1().modify(|_, w| unsafe {
+                        w.awdch().bits(chan);
+                        w.awdsgl().set_bit();
+                        w.awdie().set_bit();
+                        w.awden().set_bit()
+                    });
+                }
+
+                /// Disables the analog watchdog and its interrupt
+                pub fn disable_watchdog(&mut self) {
+                    self.rb.cr1().modify(|_, w| {
+                        w.awden().clear_bit();
+                        w.awdie().clear_bit()
+                    });
+                }
+
+                /// Returns true if a monitored conversion was outside the thresholds
+                pub fn is_watchdog_triggered(&self) -> bool {
+                    self.rb.sr().read().awd().bit_is_set()
+                }
+
+                /// Clears the analog watchdog flag
+                pub fn clear_watchdog_flag(&mut self) {
+                    self.rb.sr().modify(|_, w| w.awd().clear_bit());
                 }
 
                 fn power_up(&mut self) {
@@ -288,16 +557,6 @@ macro_rules! adc_hal {
                     <$ADC>::disable(rcc);
                 }
 
-                fn calibrate(&mut self) {
-                    /* reset calibration */
-                    self.rb.cr2().modify(|_, w| w.rstcal().set_bit());
-                    while self.rb.cr2().read().rstcal().bit_is_set() {}
-
-                    /* calibrate */
-                    self.rb.cr2().modify(|_, w| w.cal().set_bit());
-                    while self.rb.cr2().read().cal().bit_is_set() {}
-                }
-
                 fn setup_oneshot(&mut self) {
                     self.rb.cr2().modify(|_, w| w
                         .cont().clear_bit()
@@ -305,15 +564,16 @@ macro_rules! adc_hal {
                         .extsel().swstart()
                     );
 
+                    let scan = self.injected_scan();
                     self.rb.cr1().modify(|_, w| w
-                        .scan().clear_bit()
+                        .scan().bit(scan)
                         .discen().set_bit()
                     );
 
                     self.rb.sqr1().modify(|_, w| w.l().set(0b0));
                 }
 
-                fn set_channel_sample_time(&mut self, chan: u8, sample_time: SampleTime) {
+                fn write_sample_time(&mut self, chan: u8, sample_time: SampleTime) {
                     let sample_time = sample_time.into();
                     match chan {
                         0 => self.rb.smpr2().modify(|_, w| w.smp0().set(sample_time)),
@@ -335,12 +595,20 @@ macro_rules! adc_hal {
                         15 => self.rb.smpr1().modify(|_, w| w.smp15().set(sample_time)),
                         16 => self.rb.smpr1().modify(|_, w| w.smp16().set(sample_time)),
                         17 => self.rb.smpr1().modify(|_, w| w.smp17().set(sample_time)),
-                        _ => unreachable!(),
+                        _ => panic!("invalid ADC channel"),
                     }
                 }
 
                 fn set_regular_sequence (&mut self, channels: &[u8]) {
                     let len = channels.len();
+                    assert!(
+                        (1..=MAX_SEQUENCE_LEN).contains(&len),
+                        "regular sequence must have 1 to 16 channels"
+                    );
+                    assert!(
+                        channels.iter().all(|c| *c <= MAX_CHANNEL),
+                        "invalid ADC channel"
+                    );
                     let bits = channels.iter().take(6).enumerate().fold(0u32, |s, (i, c)|
                         s | ((*c as u32) << (i * 5))
                     );
@@ -396,7 +664,7 @@ macro_rules! adc_hal {
                     // of the bits
                     self.rb.dr().read().data().bits();
 
-                    self.set_channel_sample_time(chan, self.sample_time);
+                    self.write_sample_time(chan, self.channel_sample_time(chan));
                     self.rb.sqr3().modify(|_, w| unsafe { w.sq1().bits(chan) });
 
                     // ADC start conversion of regular sequence
@@ -456,6 +724,13 @@ macro_rules! adc_hal {
     }
 }
 
+/// Typical internal reference voltage, from section 5.3.4 "Embedded reference voltage"
+/// of the STM32F1xx datasheets
+const VREFINT_MV: u16 = 1200;
+
+/// Nominal analog supply voltage
+const VDDA_NOMINAL_MV: u32 = 3300;
+
 impl Adc<pac::ADC1> {
     fn read_aux(&mut self, chan: u8) -> u16 {
         let tsv_off = if self.rb.cr2().read().tsvrefe().bit_is_clear() {
@@ -502,10 +777,22 @@ impl Adc<pac::ADC1> {
 
         let prev_cfg = self.save_cfg();
 
-        // recommended ADC sampling for temperature sensor is 17.1 usec,
-        // so use the following approximate settings
-        // to support all ADC frequencies
-        let sample_time = match self.adcclk.raw() {
+        self.set_sample_time(self.aux_sample_time());
+        let val_temp: i32 = self.read_aux(16u8).into();
+        let val_vref: i32 = self.read_aux(17u8).into();
+        let v_sense = val_temp * VREFINT_MV as i32 / val_vref;
+
+        self.restore_cfg(prev_cfg);
+
+        (V_25 - v_sense) * 10 / AVG_SLOPE + 25
+    }
+
+    /// Sample time for the internal channels
+    ///
+    /// The recommended ADC sampling time for the temperature sensor and Vrefint is 17.1 usec,
+    /// so use the following approximate settings to support all ADC frequencies.
+    fn aux_sample_time(&self) -> SampleTime {
+        match self.adcclk.raw() {
             0..=1_200_000 => SampleTime::T_1,
             1_200_001..=1_500_000 => SampleTime::T_7,
             1_500_001..=2_400_000 => SampleTime::T_13,
@@ -514,16 +801,37 @@ impl Adc<pac::ADC1> {
             4_000_001..=5_000_000 => SampleTime::T_55,
             5_000_001..=14_000_000 => SampleTime::T_71,
             _ => SampleTime::T_239,
-        };
-
-        self.set_sample_time(sample_time);
-        let val_temp: i32 = self.read_aux(16u8).into();
-        let val_vref: i32 = self.read_aux(17u8).into();
-        let v_sense = val_temp * 1200 / val_vref;
+        }
+    }
 
+    /// Reads Vrefint with the sample time it requires
+    fn read_vrefint(&mut self) -> u16 {
+        let prev_cfg = self.save_cfg();
+        self.set_sample_time(self.aux_sample_time());
+        let val = self.read_aux(17u8);
         self.restore_cfg(prev_cfg);
+        val
+    }
 
-        (V_25 - v_sense) * 10 / AVG_SLOPE + 25
+    /// Analog supply voltage VDDA in mV, estimated from the typical Vrefint of 1200 mV
+    ///
+    /// The ADC measures relative to VDDA, so Vrefint reads lower when the supply is higher.
+    /// Vrefint varies from 1160 mV to 1240 mV between chips, which limits the accuracy to
+    /// about 3%.
+    pub fn read_vdda_mv(&mut self) -> u16 {
+        let val_vref = u32::from(self.read_vrefint());
+        let full_scale = u32::from(self.max_sample());
+        (u32::from(VREFINT_MV) * full_scale / val_vref.max(1)) as u16
+    }
+
+    /// Vrefint in mV, measured relative to a nominal VDDA of 3300 mV
+    ///
+    /// Deviations from 1200 mV mostly show a VDDA different from 3.3 V, see
+    /// [`read_vdda_mv`](Self::read_vdda_mv).
+    pub fn read_vref_mv(&mut self) -> u16 {
+        let val_vref = u32::from(self.read_vrefint());
+        let full_scale = u32::from(self.max_sample());
+        (val_vref * VDDA_NOMINAL_MV / full_scale) as u16
     }
 
     /// Internal reference voltage Vrefint is connected to channel 17 on ADC1.
@@ -535,7 +843,7 @@ impl Adc<pac::ADC1> {
     /// using the following formula:
     ///     v_chan = adc.read(chan) * 1200 / adc.read_vref()
     pub fn read_vref(&mut self) -> u16 {
-        self.read_aux(17u8)
+        self.read_vrefint()
     }
 }
 
@@ -565,6 +873,9 @@ pub trait ChannelTimeSequence {
     /// ADC Set a Regular Channel Conversion Sequence
     ///
     /// Define a sequence of channels to be converted as a regular group.
+    ///
+    /// Panics if the sequence is empty, longer than [`MAX_SEQUENCE_LEN`] or contains a
+    /// channel above [`MAX_CHANNEL`].
     fn set_regular_sequence(&mut self, channels: &[u8]);
     /// Set ADC continuous conversion
     ///
@@ -603,7 +914,7 @@ pub trait SetChannels<PINS>: ChannelTimeSequence {
 pub type AdcDma<ADC, PINS, MODE, CHANNEL> = RxDma<AdcPayload<ADC, PINS, MODE>, CHANNEL>;
 
 macro_rules! adcdma {
-    ($ADCX:ty: (
+    ($ADCX:ty: ($adc:ident,
         $rxdma:ident,
         $dmarxch:ty,
     )) => {
@@ -616,7 +927,12 @@ macro_rules! adcdma {
 
         impl<PINS> TransferPayload for AdcDma<$ADCX, PINS, Continuous, $dmarxch> {
             fn start(&mut self) {
+                // A conversion that finished after the last stop still has its DMA request
+                // pending, reading DR drops it so the buffer starts with a fresh sample
+                self.payload.adc.rb.dr().read();
                 self.channel.start();
+                // ADON only starts the conversion when it is written without changing
+                // other bits, so CONT needs its own write
                 self.payload.adc.rb.cr2().modify(|_, w| w.cont().set_bit());
                 self.payload.adc.rb.cr2().modify(|_, w| w.adon().set_bit());
             }
@@ -630,6 +946,18 @@ macro_rules! adcdma {
             }
         }
 
+        impl<PINS> TransferPayload for AdcDma<$ADCX, PINS, Triggered, $dmarxch> {
+            fn start(&mut self) {
+                // Drop a result left from before the transfer, conversions are then
+                // only started by the trigger
+                self.payload.adc.rb.dr().read();
+                self.channel.start();
+            }
+            fn stop(&mut self) {
+                self.channel.stop();
+            }
+        }
+
         impl<PINS> TransferPayload for AdcDma<$ADCX, PINS, Scan, $dmarxch> {
             fn start(&mut self) {
                 self.channel.start();
@@ -653,7 +981,7 @@ macro_rules! adcdma {
                 self.rb
                     .cr2()
                     .modify(|_, w| w.align().bit(self.align.into()));
-                self.set_channel_sample_time(PIN::channel(), self.sample_time);
+                self.write_sample_time(PIN::channel(), self.channel_sample_time(PIN::channel()));
                 self.rb
                     .sqr3()
                     .modify(|_, w| unsafe { w.sq1().bits(PIN::channel()) });
@@ -670,6 +998,73 @@ macro_rules! adcdma {
                 }
             }
 
+            /// Samples `pin` continuously into the circular double `buffer`
+            ///
+            /// The ADC runs in continuous mode and the DMA wraps around at the end of the
+            /// buffer, so one half can be processed with [`CircBuffer::peek`] while the
+            /// other half is filled. Listen to `dma::Event::HalfTransfer` and
+            /// `dma::Event::TransferComplete` on the DMA channel before calling this to get
+            /// an interrupt per filled half.
+            ///
+            /// The F1 ADC has no DMA disable selection: it requests a DMA transfer after
+            /// every conversion as long as `CR2.DMA` is set, independently of the DMA
+            /// channel. The conversion that completes after [`CircBuffer::stop`] is
+            /// therefore discarded when the transfer is started again.
+            pub fn into_continuous_dma<PIN, B>(
+                self,
+                pin: PIN,
+                dma_ch: $dmarxch,
+                buffer: &'static mut [B; 2],
+            ) -> CircBuffer<B, AdcDma<$ADCX, PIN, Continuous, $dmarxch>>
+            where
+                PIN: Channel<$ADCX, ID = u8>,
+                &'static mut [B; 2]: WriteBuffer<Word = u16>,
+                B: 'static,
+            {
+                crate::dma::CircReadDma::circ_read(self.with_dma(pin, dma_ch), buffer)
+            }
+
+            /// Converts the `channels` sequence on every `trigger` event, stored by DMA
+            ///
+            /// The conversions are paced by the trigger, e.g. a timer TRGO, which gives a
+            /// fixed sample rate without CPU involvement. The DMA buffer should hold a
+            /// multiple of `channels.len()` samples.
+            ///
+            /// Panics if the sequence is empty, longer than [`MAX_SEQUENCE_LEN`] or contains
+            /// a channel above [`MAX_CHANNEL`].
+            pub fn with_triggered_dma<PINS>(
+                mut self,
+                pins: PINS,
+                channels: &[u8],
+                trigger: crate::pac::$adc::cr2::EXTSEL,
+                dma_ch: $dmarxch,
+            ) -> AdcDma<$ADCX, PINS, Triggered, $dmarxch> {
+                self.rb.cr2().modify(|_, w| {
+                    w.dma().clear_bit();
+                    w.cont().clear_bit();
+                    w.align().bit(self.align.into())
+                });
+                self.rb
+                    .cr1()
+                    .modify(|_, w| w.scan().set_bit().discen().clear_bit());
+                self.set_regular_sequence(channels);
+                for chan in channels {
+                    self.write_sample_time(*chan, self.channel_sample_time(*chan));
+                }
+                self.set_external_trigger(trigger);
+                self.rb.cr2().modify(|_, w| w.dma().set_bit());
+
+                let payload = AdcPayload {
+                    adc: self,
+                    pins,
+                    _mode: PhantomData,
+                };
+                RxDma {
+                    payload,
+                    channel: dma_ch,
+                }
+            }
+
             pub fn with_scan_dma<PINS>(
                 mut self,
                 pins: PINS,
@@ -705,6 +1100,51 @@ macro_rules! adcdma {
             }
         }
 
+        impl Adc<$ADCX> {
+            /// Scan mode with the conversion sequence given at runtime
+            ///
+            /// The `channels` are converted in order, each with the current sample time,
+            /// after every start of the transfer. The DMA buffer should hold a multiple of
+            /// `channels.len()` samples, so each sample lands at the same position of the
+            /// sequence.
+            ///
+            /// Panics if the sequence is empty, longer than [`MAX_SEQUENCE_LEN`] or contains
+            /// a channel above [`MAX_CHANNEL`].
+            pub fn with_scan_dma_channels<PINS>(
+                mut self,
+                pins: PINS,
+                channels: &[u8],
+                dma_ch: $dmarxch,
+            ) -> AdcDma<$ADCX, PINS, Scan, $dmarxch> {
+                self.rb.cr2().modify(|_, w| {
+                    w.adon().clear_bit();
+                    w.dma().clear_bit();
+                    w.cont().clear_bit();
+                    w.align().bit(self.align.into())
+                });
+                self.rb
+                    .cr1()
+                    .modify(|_, w| w.scan().set_bit().discen().clear_bit());
+                self.set_regular_sequence(channels);
+                for chan in channels {
+                    self.write_sample_time(*chan, self.channel_sample_time(*chan));
+                }
+                self.rb
+                    .cr2()
+                    .modify(|_, w| w.dma().set_bit().adon().set_bit());
+
+                let payload = AdcPayload {
+                    adc: self,
+                    pins,
+                    _mode: PhantomData,
+                };
+                RxDma {
+                    payload,
+                    channel: dma_ch,
+                }
+            }
+        }
+
         impl<PINS> AdcDma<$ADCX, PINS, Continuous, $dmarxch>
         where
             Self: TransferPayload,
@@ -730,7 +1170,29 @@ macro_rules! adcdma {
                 let AdcDma { payload, channel } = self;
                 payload.adc.rb.cr2().modify(|_, w| w.dma().clear_bit());
                 payload.adc.rb.cr1().modify(|_, w| w.discen().set_bit());
-                payload.adc.rb.cr1().modify(|_, w| w.scan().clear_bit());
+                let scan = payload.adc.injected_scan();
+                payload.adc.rb.cr1().modify(|_, w| w.scan().bit(scan));
+
+                (payload.adc, payload.pins, channel)
+            }
+        }
+
+        impl<PINS> AdcDma<$ADCX, PINS, Triggered, $dmarxch>
+        where
+            Self: TransferPayload,
+        {
+            pub fn split(mut self) -> (Adc<$ADCX>, PINS, $dmarxch) {
+                self.stop();
+
+                let AdcDma { payload, channel } = self;
+                payload.adc.rb.cr2().modify(|_, w| {
+                    w.dma().clear_bit();
+                    w.extsel().swstart()
+                });
+                payload.adc.rb.cr1().modify(|_, w| w.discen().set_bit());
+                let scan = payload.adc.injected_scan();
+                payload.adc.rb.cr1().modify(|_, w| w.scan().bit(scan));
+                payload.adc.rb.sqr1().modify(|_, w| w.l().set(0b0));
 
                 (payload.adc, payload.pins, channel)
             }
@@ -804,7 +1266,7 @@ macro_rules! adcdma {
 }
 
 adcdma! {
-    pac::ADC1: (
+    pac::ADC1: (adc1,
         AdcDma1,
         dma1::C1,
     )
@@ -812,8 +1274,16 @@ adcdma! {
 
 #[cfg(all(feature = "stm32f103", any(feature = "high", feature = "xl")))]
 adcdma! {
-    pac::ADC3: (
+    pac::ADC3: (adc3,
         AdcDma3,
         dma2::C5,
     )
 }
+
+#[test]
+fn adc_jsqr_bits() {
+    assert_eq!(jsqr_bits(&[5]), 5 << 15);
+    // three ranks in JSQ2..JSQ4, JL = 2
+    assert_eq!(jsqr_bits(&[0, 1, 2]), 0x21_0400);
+    assert_eq!(jsqr_bits(&[17, 16, 3, 4]), 0x32_0e11);
+}
@@ -4,7 +4,7 @@ use crate::pac::{self, afio, AFIO, RCC};
 use crate::rcc::{Enable, Reset};
 
 use crate::gpio::{
-    Debugger, Floating, Input, PA15, {PB3, PB4},
+    Debugger, Floating, Input, PA13, PA14, PA15, {PB3, PB4},
 };
 use crate::sealed::Sealed;
 
@@ -20,7 +20,9 @@ impl AfioExt for AFIO {
 
         Parts {
             evcr: EVCR,
-            mapr: MAPR { jtag_enabled: true },
+            mapr: MAPR {
+                swj: SwjCfg::FullJtag,
+            },
             exticr1: EXTICR1,
             exticr2: EXTICR2,
             exticr3: EXTICR3,
@@ -56,6 +58,30 @@ impl EVCR {
     pub fn evcr(&mut self) -> &afio::EVCR {
         unsafe { (*AFIO::ptr()).evcr() }
     }
+
+    /// Selects the pin where the Cortex EVENTOUT signal is output
+    ///
+    /// The pin has to be configured as alternate push-pull output.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `port` is not in `A..=E` or `pin` is greater than 15
+    pub fn set_event_output(&mut self, port: Port, pin: u8) {
+        assert!(port as u8 <= Port::E as u8);
+        assert!(pin < 16);
+        self.evcr()
+            .modify(|_, w| unsafe { w.port().bits(port as u8).pin().bits(pin) });
+    }
+
+    /// Enables the Cortex EVENTOUT output, which pulses on every `SEV` instruction
+    pub fn enable(&mut self) {
+        self.evcr().modify(|_, w| w.evoe().set_bit());
+    }
+
+    /// Disables the Cortex EVENTOUT output
+    pub fn disable(&mut self) {
+        self.evcr().modify(|_, w| w.evoe().clear_bit());
+    }
 }
 
 /// AF remap and debug I/O configuration register (MAPR)
@@ -70,7 +96,20 @@ impl EVCR {
 /// ```
 #[non_exhaustive]
 pub struct MAPR {
-    jtag_enabled: bool,
+    swj: SwjCfg,
+}
+
+/// Serial wire JTAG configuration, ordered by the number of pins it frees
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum SwjCfg {
+    /// Full SWJ (JTAG-DP + SW-DP), reset state
+    FullJtag = 0b000,
+    /// Full SWJ (JTAG-DP + SW-DP) but without NJTRST, frees PB4
+    JtagNoReset = 0b001,
+    /// JTAG-DP disabled and SW-DP enabled, frees PA15, PB3 and PB4
+    SwdOnly = 0b010,
+    /// JTAG-DP disabled and SW-DP disabled, also frees PA13 and PA14
+    Disabled = 0b100,
 }
 
 impl MAPR {
@@ -82,11 +121,31 @@ impl MAPR {
     where
         F: for<'w> FnOnce(&afio::mapr::R, &'w mut afio::mapr::W) -> &'w mut afio::mapr::W,
     {
-        let debug_bits = if self.jtag_enabled { 0b000 } else { 0b010 };
+        // SWJ_CFG is write-only, so it has to be written on every modification
+        let debug_bits = self.swj as u8;
         self.mapr()
             .modify(unsafe { |r, w| mod_fn(r, w).swj_cfg().bits(debug_bits) });
     }
 
+    /// Disables debug ports down to `cfg`, already disabled ports stay disabled
+    fn disable_swj(&mut self, cfg: SwjCfg) {
+        if cfg as u8 > self.swj as u8 {
+            self.swj = cfg;
+            // Avoid duplicating swj_cfg write code
+            self.modify_mapr(|_, w| w);
+        }
+    }
+
+    /// Disables the JTAG reset NJTRST to free up pb4 for normal use, JTAG and SWD keep working
+    ///
+    /// Use [`disable_jtag`](Self::disable_jtag) instead to free pb4 together with pa15 and pb3.
+    pub fn disable_jtag_reset(&mut self, pb4: PB4<Debugger>) -> PB4<Input<Floating>> {
+        self.disable_swj(SwjCfg::JtagNoReset);
+
+        // NOTE(unsafe) The pin is now in the good state.
+        unsafe { pb4.activate() }
+    }
+
     /// Disables the JTAG to free up pa15, pb3 and pb4 for normal use
     #[allow(clippy::redundant_field_names, clippy::type_complexity)]
     pub fn disable_jtag(
@@ -99,48 +158,106 @@ impl MAPR {
         PB3<Input<Floating>>,
         PB4<Input<Floating>>,
     ) {
-        self.jtag_enabled = false;
-        // Avoid duplicating swj_cfg write code
-        self.modify_mapr(|_, w| w);
+        self.disable_swj(SwjCfg::SwdOnly);
 
         // NOTE(unsafe) The pins are now in the good state.
         unsafe { (pa15.activate(), pb3.activate(), pb4.activate()) }
     }
-}
 
-#[non_exhaustive]
-pub struct EXTICR1;
+    /// Disables the SWD and JTAG to free up pa13 and pa14 for normal use
+    ///
+    /// This also frees the JTAG pins pa15, pb3 and pb4 in hardware, use
+    /// [`disable_jtag`](Self::disable_jtag) to get them as regular pins.
+    /// The debugger can't connect anymore until the next reset.
+    pub fn disable_swd(
+        &mut self,
+        pa13: PA13<Debugger>,
+        pa14: PA14<Debugger>,
+    ) -> (PA13<Input<Floating>>, PA14<Input<Floating>>) {
+        self.disable_swj(SwjCfg::Disabled);
 
-impl EXTICR1 {
-    pub fn exticr1(&mut self) -> &afio::EXTICR1 {
-        unsafe { (*AFIO::ptr()).exticr1() }
+        // NOTE(unsafe) The pins are now in the good state.
+        unsafe { (pa13.activate(), pa14.activate()) }
     }
 }
 
-#[non_exhaustive]
-pub struct EXTICR2;
+/// GPIO port used as source of an EXTI line
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Port {
+    A = 0,
+    B = 1,
+    C = 2,
+    D = 3,
+    E = 4,
+    F = 5,
+    G = 6,
+}
 
-impl EXTICR2 {
-    pub fn exticr2(&mut self) -> &afio::EXTICR2 {
-        unsafe { (*AFIO::ptr()).exticr2() }
+impl Port {
+    pub(crate) fn from_id(id: u8) -> Self {
+        match id {
+            0 => Self::A,
+            1 => Self::B,
+            2 => Self::C,
+            3 => Self::D,
+            4 => Self::E,
+            5 => Self::F,
+            6 => Self::G,
+            _ => unreachable!(),
+        }
     }
 }
 
-#[non_exhaustive]
-pub struct EXTICR3;
-
-impl EXTICR3 {
-    pub fn exticr3(&mut self) -> &afio::EXTICR3 {
-        unsafe { (*AFIO::ptr()).exticr3() }
-    }
+macro_rules! exticr {
+    ($($EXTICR:ident: $exticr:ident, $lines:expr;)+) => {
+        $(
+            #[non_exhaustive]
+            pub struct $EXTICR;
+
+            impl $EXTICR {
+                pub fn $exticr(&mut self) -> &afio::$EXTICR {
+                    unsafe { (*AFIO::ptr()).$exticr() }
+                }
+
+                /// Selects `port` as source of the EXTI `line`
+                ///
+                /// # Panics
+                ///
+                #[doc = concat!("Panics if `line` is not in `", stringify!($lines), "`")]
+                pub fn set_exti_source(&mut self, line: u8, port: Port) {
+                    assert!(($lines).contains(&line));
+                    let offset = 4 * (line % 4);
+                    self.$exticr().modify(|r, w| unsafe {
+                        w.bits((r.bits() & !(0xf << offset)) | ((port as u32) << offset))
+                    });
+                }
+            }
+        )+
+    };
 }
 
-#[non_exhaustive]
-pub struct EXTICR4;
+exticr! {
+    EXTICR1: exticr1, 0..=3;
+    EXTICR2: exticr2, 4..=7;
+    EXTICR3: exticr3, 8..=11;
+    EXTICR4: exticr4, 12..=15;
+}
 
-impl EXTICR4 {
-    pub fn exticr4(&mut self) -> &afio::EXTICR4 {
-        unsafe { (*AFIO::ptr()).exticr4() }
+impl Parts {
+    /// Selects `port` as source of the EXTI `line`, using the matching EXTICR register
+    ///
+    /// # Panics
+    ///
+    /// Panics if `line` is greater than 15
+    pub fn configure_exti(&mut self, line: u8, port: Port) {
+        match line {
+            0..=3 => self.exticr1.set_exti_source(line, port),
+            4..=7 => self.exticr2.set_exti_source(line, port),
+            8..=11 => self.exticr3.set_exti_source(line, port),
+            12..=15 => self.exticr4.set_exti_source(line, port),
+            _ => panic!("EXTI line {} has no GPIO source", line),
+        }
     }
 }
 
@@ -190,7 +307,6 @@ macro_rules! remap {
         }
     };
 }
-use remap;
 
 remap! {
     pac::SPI1: MAPR, bool: spi1_remap;
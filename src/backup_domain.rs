@@ -10,11 +10,51 @@
   Write access to the backup domain is enabled in RCC using the `rcc::Rcc::BKP::constrain()`
   function.
 
-  Only the RTC functionality is currently implemented.
+  Besides the RTC, the backup domain holds 16-bit data registers, 10 on low- and
+  medium-density devices and 42 on high-density and connectivity line devices. They can
+  keep e.g. a boot flag or a counter across resets.
+
+  ```rust
+  let boots = backup_domain.read_data_register(0).wrapping_add(1);
+  backup_domain.write_data_register(0, boots);
+  ```
+
+  ## RTC calibration
+
+  The RTC can be slowed down by up to 121 ppm to compensate a fast crystal. To measure the
+  deviation, enable the calibration output by
+  [`enable_calibration_output`](BackupDomain::enable_calibration_output) with the
+  calibration value set to 0. The TAMPER pin (PC13) outputs the RTC clock divided by 64,
+  which is 512 Hz for a 32.768 kHz crystal. Measure its frequency `f` with a frequency
+  counter and compute the calibration value as
+
+  ```text
+  ppm = (f / 512 Hz - 1) * 1_000_000
+  value = round(ppm / 0.954)
+  ```
+
+  as each step removes one of 2^20 clock cycles (0.954 ppm). Negative values mean that
+  the crystal is too slow, which can not be compensated. The output is not affected by the
+  calibration and the TAMPER pin can not be used for anything else while it is enabled.
+
+  ## Tamper detection
+
+  After [`enable_tamper`](BackupDomain::enable_tamper) the active level on the TAMPER pin
+  (PC13) resets all data registers, e.g. to wipe secrets when an enclosure is opened, and
+  sets the tamper event flag. The data registers can not be written until the flag is cleared
+  by [`clear_tamper_event`](BackupDomain::clear_tamper_event). The TAMPER interrupt is
+  raised on the event after [`listen_tamper`](BackupDomain::listen_tamper).
 */
 
 use crate::pac::BKP;
 
+/// Level of the TAMPER pin which triggers a tamper event
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TamperLevel {
+    High,
+    Low,
+}
+
 /**
   The existence of this struct indicates that writing to the the backup
   domain has been enabled. It is acquired by calling `constrain` on `rcc::Rcc::BKP`
@@ -36,6 +76,46 @@ macro_rules! read_drx {
 }
 
 impl BackupDomain {
+    /// Number of backup data registers
+    #[cfg(any(feature = "high", feature = "connectivity"))]
+    pub const DATA_REGISTERS: usize = 42;
+    /// Number of backup data registers
+    #[cfg(not(any(feature = "high", feature = "connectivity")))]
+    pub const DATA_REGISTERS: usize = 10;
+
+    /// Read a 16-bit value from a backup data register. `index` is zero based:
+    /// 0 is DR1, up to [`DATA_REGISTERS`](Self::DATA_REGISTERS) - 1.
+    /// Providing a larger index will panic.
+    pub fn read_data_register(&self, index: usize) -> u16 {
+        assert!(
+            index < Self::DATA_REGISTERS,
+            "backup data register out of range"
+        );
+        #[cfg(any(feature = "high", feature = "connectivity"))]
+        if index >= 10 {
+            return read_drx!(self, bkp_dr, index - 10);
+        }
+        read_drx!(self, dr, index)
+    }
+
+    /// Write a 16-bit value to a backup data register. `index` is zero based:
+    /// 0 is DR1, up to [`DATA_REGISTERS`](Self::DATA_REGISTERS) - 1.
+    /// Providing a larger index will panic.
+    ///
+    /// Write access (PWR DBP) has been enabled when the `BackupDomain` was created.
+    pub fn write_data_register(&self, index: usize, data: u16) {
+        assert!(
+            index < Self::DATA_REGISTERS,
+            "backup data register out of range"
+        );
+        #[cfg(any(feature = "high", feature = "connectivity"))]
+        if index >= 10 {
+            write_drx!(self, bkp_dr, index - 10, data);
+            return;
+        }
+        write_drx!(self, dr, index, data);
+    }
+
     /// Read a 16-bit value from one of the DR1 to DR10 registers part of the
     /// Backup Data Register. The register argument is a zero based index to the
     /// DRx registers: 0 is DR1, up to 9 for DR10. Providing a number above 9
@@ -71,4 +151,68 @@ impl BackupDomain {
     pub fn write_data_register_high(&self, register: usize, data: u16) {
         write_drx!(self, bkp_dr, register, data);
     }
+
+    /// Slows the RTC down by `value` clock cycles out of every 2^20 (0.954 ppm per step).
+    ///
+    /// `value` ranges from 0 (no calibration) to 127, larger values will panic.
+    pub fn set_rtc_calibration(&mut self, value: u8) {
+        assert!(value <= 127, "RTC calibration value out of range");
+        self._regs
+            .rtccr()
+            .modify(|_, w| unsafe { w.cal().bits(value) });
+    }
+
+    /// Returns the RTC calibration value
+    pub fn rtc_calibration(&self) -> u8 {
+        self._regs.rtccr().read().cal().bits()
+    }
+
+    /// Outputs the RTC clock divided by 64 on the TAMPER pin (PC13) for calibration
+    pub fn enable_calibration_output(&mut self) {
+        self._regs.rtccr().modify(|_, w| w.cco().set_bit());
+    }
+
+    pub fn disable_calibration_output(&mut self) {
+        self._regs.rtccr().modify(|_, w| w.cco().clear_bit());
+    }
+
+    /// Enables tamper detection on `level` of the TAMPER pin.
+    ///
+    /// The data registers are always reset on a tamper event. If the pin is already at the
+    /// active level, the event happens immediately.
+    pub fn enable_tamper(&mut self, level: TamperLevel) {
+        // TPAL must only be changed while TPE is reset
+        self._regs.cr().modify(|_, w| w.tpe().clear_bit());
+        self._regs
+            .cr()
+            .modify(|_, w| w.tpal().bit(level == TamperLevel::Low));
+        self._regs.cr().modify(|_, w| w.tpe().set_bit());
+    }
+
+    /// Disables tamper detection, the TAMPER pin can be used as GPIO again
+    pub fn disable_tamper(&mut self) {
+        self._regs.cr().modify(|_, w| w.tpe().clear_bit());
+    }
+
+    /// Enables the TAMPER interrupt on a tamper event
+    pub fn listen_tamper(&mut self) {
+        self._regs.csr().modify(|_, w| w.tpie().set_bit());
+    }
+
+    pub fn unlisten_tamper(&mut self) {
+        self._regs.csr().modify(|_, w| w.tpie().clear_bit());
+    }
+
+    /// Returns `true` after a tamper event, the data registers are kept in reset until
+    /// the flag is cleared
+    pub fn is_tamper_event(&self) -> bool {
+        self._regs.csr().read().tef().bit_is_set()
+    }
+
+    /// Clears the tamper event and interrupt flags, which re-arms the tamper detection
+    pub fn clear_tamper_event(&mut self) {
+        self._regs
+            .csr()
+            .modify(|_, w| w.cte().set_bit().cti().set_bit());
+    }
 }
@@ -1,9 +1,14 @@
 //! Bit banding
 //!
-//! Support for the manipulation of peripheral registers through bit-banding.
-//! Not all peripherals are mapped to the bit-banding alias region, the peripheral bit-band region
-//! is from `0x4000_0000` to `0x400F_FFFF`. Bit-banding allows the manipulation of individual bits
-//! atomically.
+//! Support for the manipulation of peripheral registers and SRAM through bit-banding.
+//! Not all memory is mapped to a bit-banding alias region, the peripheral bit-band region
+//! is from `0x4000_0000` to `0x400F_FFFF` and the SRAM bit-band region from `0x2000_0000` to
+//! `0x200F_FFFF`. Bit-banding allows the manipulation of individual bits atomically, without
+//! a read-modify-write that could race with an interrupt.
+//!
+//! Each bit of a region is mapped to a word in its alias region:
+//!
+//! `alias = alias_base + (address - region_base) * 32 + bit * 4`
 
 use core::ptr;
 
@@ -13,6 +18,29 @@ const PERI_ADDRESS_END: usize = 0x400F_FFFF;
 
 const PERI_BIT_BAND_BASE: usize = 0x4200_0000;
 
+// Start address of the SRAM region capable of being addressed by bit-banding
+const SRAM_ADDRESS_START: usize = 0x2000_0000;
+const SRAM_ADDRESS_END: usize = 0x200F_FFFF;
+
+const SRAM_BIT_BAND_BASE: usize = 0x2200_0000;
+
+/// Returns the bit-band alias address of `bit` at `addr`.
+///
+/// Panics if `addr` is outside of the bit-band regions or `bit` is greater than 31.
+#[inline]
+pub fn alias_address(addr: usize, bit: u8) -> usize {
+    assert!(bit < 32);
+
+    let (start, base) = if (PERI_ADDRESS_START..=PERI_ADDRESS_END).contains(&addr) {
+        (PERI_ADDRESS_START, PERI_BIT_BAND_BASE)
+    } else if (SRAM_ADDRESS_START..=SRAM_ADDRESS_END).contains(&addr) {
+        (SRAM_ADDRESS_START, SRAM_BIT_BAND_BASE)
+    } else {
+        panic!("address is not in a bit-band region");
+    };
+    base + (addr - start) * 32 + 4 * bit as usize
+}
+
 /// Clears the bit on the provided register without modifying other bits.
 ///
 /// # Safety
@@ -40,12 +68,36 @@ pub unsafe fn set<T>(register: *const T, bit: u8) {
 /// Some registers have reserved bits which should not be modified.
 #[inline]
 pub unsafe fn write<T>(register: *const T, bit: u8, set: bool) {
-    let addr = register as usize;
+    let bb_addr = alias_address(register as usize, bit);
+    ptr::write_volatile(bb_addr as *mut u32, u32::from(set));
+}
 
-    assert!((PERI_ADDRESS_START..=PERI_ADDRESS_END).contains(&addr));
-    assert!(bit < 32);
+/// Reads the bit of the provided register or memory location.
+///
+/// # Safety
+///
+/// Reading some registers has side effects, e.g. clearing flags.
+#[inline]
+pub unsafe fn read<T>(register: *const T, bit: u8) -> bool {
+    let bb_addr = alias_address(register as usize, bit);
+    ptr::read_volatile(bb_addr as *const u32) & 1 != 0
+}
 
-    let bit = bit as usize;
-    let bb_addr = (PERI_BIT_BAND_BASE + (addr - PERI_ADDRESS_START) * 32) + 4 * bit;
-    ptr::write_volatile(bb_addr as *mut u32, u32::from(set));
+#[test]
+fn alias_address_arithmetic() {
+    assert_eq!(alias_address(0x4000_0000, 0), 0x4200_0000);
+    assert_eq!(alias_address(0x4000_0000, 31), 0x4200_007C);
+    // RCC_APB2ENR bit 2 (IOPAEN)
+    assert_eq!(alias_address(0x4002_1018, 2), 0x4242_0308);
+    assert_eq!(alias_address(0x400F_FFFC, 31), 0x43FF_FFFC);
+    // Examples from the Cortex-M3 technical reference manual
+    assert_eq!(alias_address(0x2000_0300, 0), 0x2200_6000);
+    assert_eq!(alias_address(0x2000_0300, 7), 0x2200_601C);
+    assert_eq!(alias_address(0x200F_FFFF, 0), 0x23FF_FFE0);
+}
+
+#[test]
+#[should_panic]
+fn alias_address_outside_region() {
+    alias_address(0x4010_0000, 0);
 }
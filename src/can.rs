@@ -18,10 +18,34 @@
 //! |----------|---------|-------|
 //! | TX       | PB6     | PB13  |
 //! | RX       | PB5     | PB12  |
+//!
+//! ## Filter banks
+//!
+//! Received frames are only stored if a filter bank accepts them. Connectivity line
+//! devices have 28 filter banks shared by CAN1 and CAN2, the other devices 14 banks for CAN1.
+//!
+//! The filter banks are owned by the master instance CAN1: the filter registers of both
+//! instances are in the CAN1 register block and can only be changed through CAN1, even
+//! while CAN1 is not used otherwise. CAN2 is a slave instance using the banks from
+//! `CAN_FMR.CAN2SB` on, set with `Can::set_can2_start_bank` (14 after reset).
+//! CAN1 has to be enabled in RCC to configure the filters of CAN2.
+//!
+//! ```rust
+//! let mut can1 = dp.CAN1.can(pins);
+//! can1.set_can2_start_bank(4);
+//! can1.filters()
+//!     .accept_standard(StandardId::new(0x100).unwrap(), 0x700)
+//!     .fifo(Fifo::Fifo1)
+//!     .accept_extended_range(ExtendedId::new(0x1000).unwrap(), ExtendedId::new(0x10ff).unwrap());
+//! can1.can2_filters().accept_all();
+//! ```
 
 use crate::afio::Remap;
 use crate::gpio::{self, Alternate, Cr, Floating, Input, NoPin, PinMode, PullUp, PushPull};
 use crate::pac::{self, RCC};
+use crate::rcc::Clocks;
+use crate::time::Hertz;
+use core::ops::{Deref, DerefMut};
 
 pub trait InMode {}
 impl InMode for Floating {}
@@ -162,7 +186,7 @@ impl<CAN: Instance> CanExt for CAN {
     }
 }
 
-pub trait Instance: crate::rcc::Enable {
+pub trait Instance: crate::rcc::Enable + Deref<Target = pac::can1::RegisterBlock> {
     type Tx;
     type Rx<PULL>;
 }
@@ -177,7 +201,6 @@ impl Instance for pac::CAN2 {
 }
 
 /// Interface to the CAN peripheral.
-#[allow(unused)]
 pub struct Can<CAN: Instance, PULL = Floating> {
     can: CAN,
     pins: Option<Pins<CAN::Tx, CAN::Rx<PULL>>>,
@@ -210,6 +233,450 @@ impl<CAN: Instance, PULL> Can<CAN, PULL> {
 
         Can { can, pins: None }
     }
+
+    /// Releases the CAN peripheral and the pins, if any were assigned
+    #[allow(clippy::type_complexity)]
+    pub fn release(self) -> (CAN, Option<Pins<CAN::Tx, CAN::Rx<PULL>>>) {
+        (self.can, self.pins)
+    }
+}
+
+impl<CAN: Instance, PULL> Can<CAN, PULL> {
+    /// Returns the receive error counter
+    pub fn rx_error_count(&self) -> u8 {
+        self.can.esr().read().rec().bits()
+    }
+
+    /// Returns the transmit error counter
+    pub fn tx_error_count(&self) -> u8 {
+        self.can.esr().read().tec().bits()
+    }
+
+    /// Returns the error code of the last error detected on the bus
+    pub fn last_error(&self) -> Option<CanError> {
+        let lec = self.can.esr().read().lec();
+        if lec.is_stuff() {
+            Some(CanError::Stuff)
+        } else if lec.is_form() {
+            Some(CanError::Form)
+        } else if lec.is_ack() {
+            Some(CanError::Acknowledge)
+        } else if lec.is_bit_recessive() {
+            Some(CanError::BitRecessive)
+        } else if lec.is_bit_dominant() {
+            Some(CanError::BitDominant)
+        } else if lec.is_crc() {
+            Some(CanError::Crc)
+        } else {
+            None
+        }
+    }
+
+    /// Computes the bit timing for `bitrate` from the APB1 clock.
+    ///
+    /// Pass [`BitTiming::btr`] to `bxcan::CanBuilder::set_bit_timing`.
+    /// Returns `None` if `bitrate` can not be reached within 0.5% tolerance.
+    pub fn btr_for_bitrate(clocks: &Clocks, bitrate: Hertz) -> Option<BitTiming> {
+        BitTiming::new(clocks.pclk1(), bitrate)
+    }
+}
+
+impl<CAN: Instance, PULL> Can<CAN, PULL>
+where
+    Self: bxcan::Instance,
+{
+    /// Enables the peripheral in self-test mode.
+    ///
+    /// Loopback (LBKM) and silent (SILM) mode are combined: transmitted frames are received
+    /// internally and nothing is driven onto or sampled from the pins, so this needs no
+    /// transceiver. `btr` is the bit timing for the APB1 clock, see [`Can::btr_for_bitrate`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidBitTiming`] if `btr` sets the mode or reserved bits of BTR
+    pub fn into_self_test(self, btr: u32) -> Result<bxcan::Can<Self>, Error> {
+        // The receiver sees the recessive transmitter, so synchronization cannot stall
+        Ok(self.builder_with_mode(btr, true, true)?.enable())
+    }
+
+    /// Enables the peripheral in silent mode.
+    ///
+    /// The peripheral receives frames but only sends recessive bits, so it neither acknowledges
+    /// frames nor disturbs the bus. Useful for bus monitoring.
+    ///
+    /// The peripheral has left initialization mode, but is still asleep. It synchronizes to
+    /// the bus after 11 recessive bits, which never happens on a bus held dominant, so this
+    /// is left to the caller: `bxcan::Can::enable_non_blocking` returns `WouldBlock` until
+    /// the peripheral is synchronized.
+    ///
+    /// ```rust
+    /// let mut can = can.into_silent(timing.btr())?;
+    /// while let Err(nb::Error::WouldBlock) = can.enable_non_blocking() {
+    ///     // give up after a timeout
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidBitTiming`] if `btr` sets the mode or reserved bits of BTR
+    pub fn into_silent(self, btr: u32) -> Result<bxcan::Can<Self>, Error> {
+        // Polls INAK until initialization mode is left
+        Ok(self.builder_with_mode(btr, false, true)?.leave_disabled())
+    }
+
+    fn builder_with_mode(
+        self,
+        btr: u32,
+        loopback: bool,
+        silent: bool,
+    ) -> Result<bxcan::CanBuilder<Self>, Error> {
+        // Bits bxcan writes from `btr`, the mode flags are set separately
+        const TIMING_MASK: u32 = 0x037f_03ff;
+        if btr & !TIMING_MASK != 0 {
+            return Err(Error::InvalidBitTiming);
+        }
+        Ok(bxcan::Can::builder(self)
+            .set_bit_timing(btr)
+            .set_loopback(loopback)
+            .set_silent(silent))
+    }
+}
+
+/// Bus error reported by the last error code of the ESR register
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CanError {
+    /// More than 5 equal bits in a sequence
+    Stuff,
+    /// A fixed format part of a frame has the wrong format
+    Form,
+    /// A transmitted frame was not acknowledged
+    Acknowledge,
+    /// A recessive bit was sent but a dominant one was monitored
+    BitRecessive,
+    /// A dominant bit was sent but a recessive one was monitored
+    BitDominant,
+    /// The CRC of a received frame does not match
+    Crc,
+}
+
+impl embedded_can::Error for CanError {
+    fn kind(&self) -> embedded_can::ErrorKind {
+        match self {
+            Self::Stuff => embedded_can::ErrorKind::Stuff,
+            Self::Form => embedded_can::ErrorKind::Form,
+            Self::Acknowledge => embedded_can::ErrorKind::Acknowledge,
+            Self::BitRecessive | Self::BitDominant => embedded_can::ErrorKind::Bit,
+            Self::Crc => embedded_can::ErrorKind::Crc,
+        }
+    }
+}
+
+/// CAN bit timing configuration
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BitTiming {
+    prescaler: u16,
+    sjw: u8,
+    bs1: u8,
+    bs2: u8,
+    bitrate: Hertz,
+}
+
+impl BitTiming {
+    /// Maximum deviation from the requested bitrate in parts per million
+    const TOLERANCE_PPM: u64 = 5_000;
+    /// Targeted sample point in per mille
+    const SAMPLE_POINT: u32 = 875;
+
+    /// Searches for a prescaler and segment combination reaching `bitrate` from `pclk1`
+    /// with a sample point as close to 87.5% as possible.
+    pub fn new(pclk1: Hertz, bitrate: Hertz) -> Option<Self> {
+        let pclk1 = pclk1.raw();
+        let bitrate = bitrate.raw();
+        if bitrate == 0 {
+            return None;
+        }
+
+        let mut best: Option<(u64, u32, Self)> = None;
+        // A bit consists of the sync segment (1 tq), BS1 (1..=16 tq) and BS2 (1..=8 tq).
+        // Less than 8 tq per bit make the sample point too coarse, so don't use them.
+        for tq in (8..=25u32).rev() {
+            let prescaler = (pclk1 + bitrate * tq / 2) / (bitrate * tq);
+            if !(1..=1024).contains(&prescaler) {
+                continue;
+            }
+            let actual = pclk1 / (prescaler * tq);
+            let error =
+                (actual.max(bitrate) - actual.min(bitrate)) as u64 * 1_000_000 / bitrate as u64;
+            if error > Self::TOLERANCE_PPM {
+                continue;
+            }
+
+            // Number of tq before the sample point
+            let before = ((tq * Self::SAMPLE_POINT + 500) / 1000).clamp(tq - 8, 17);
+            let bs1 = before - 1;
+            let bs2 = tq - before;
+            let sp = before * 1000 / tq;
+            let sp_error = sp.max(Self::SAMPLE_POINT) - sp.min(Self::SAMPLE_POINT);
+
+            let timing = Self {
+                prescaler: prescaler as u16,
+                sjw: 1,
+                bs1: bs1 as u8,
+                bs2: bs2 as u8,
+                bitrate: Hertz::from_raw(actual),
+            };
+            let better = match best {
+                Some((best_error, best_sp_error, _)) => {
+                    (error, sp_error) < (best_error, best_sp_error)
+                }
+                None => true,
+            };
+            if better {
+                best = Some((error, sp_error, timing));
+            }
+        }
+
+        best.map(|(_, _, timing)| timing)
+    }
+
+    /// Value of the BTR register
+    pub const fn btr(&self) -> u32 {
+        ((self.sjw as u32 - 1) << 24)
+            | ((self.bs2 as u32 - 1) << 20)
+            | ((self.bs1 as u32 - 1) << 16)
+            | (self.prescaler as u32 - 1)
+    }
+
+    /// Actual bitrate
+    pub const fn bitrate(&self) -> Hertz {
+        self.bitrate
+    }
+
+    /// Sample point in per mille of the bit time
+    pub const fn sample_point(&self) -> u16 {
+        let before = 1 + self.bs1 as u16;
+        before * 1000 / (before + self.bs2 as u16)
+    }
+
+    /// Baud rate prescaler
+    pub const fn prescaler(&self) -> u16 {
+        self.prescaler
+    }
+
+    /// Resynchronization jump width in time quanta
+    pub const fn sjw(&self) -> u8 {
+        self.sjw
+    }
+
+    /// Time segment 1 (propagation and phase segment 1) in time quanta
+    pub const fn bs1(&self) -> u8 {
+        self.bs1
+    }
+
+    /// Time segment 2 (phase segment 2) in time quanta
+    pub const fn bs2(&self) -> u8 {
+        self.bs2
+    }
+}
+
+impl<PULL> Can<pac::CAN1, PULL> {
+    /// Clears the filter banks owned by CAN1 and returns a builder to configure them.
+    ///
+    /// The filter banks are configured in the order the builder methods are called,
+    /// starting at bank 0. Filter initialization mode is left when the builder is dropped.
+    ///
+    /// After enabling the peripheral the filters are accessible with
+    /// `bxcan::Can::instance`.
+    pub fn filters(&mut self) -> Filters<'_> {
+        Filters::new(&self.can, 0, self.filter_split())
+    }
+
+    /// Clears the filter banks owned by CAN2 and returns a builder to configure them.
+    ///
+    /// The first bank is [`Can::can2_start_bank`].
+    #[cfg(feature = "connectivity")]
+    pub fn can2_filters(&mut self) -> Filters<'_> {
+        Filters::new(&self.can, self.filter_split(), NUM_FILTER_BANKS)
+    }
+
+    /// Returns the first filter bank owned by CAN2
+    #[cfg(feature = "connectivity")]
+    pub fn can2_start_bank(&self) -> u8 {
+        self.filter_split()
+    }
+
+    /// Assigns the filter banks `0..bank` to CAN1 and `bank..28` to CAN2.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bank` is not in the range `1..=27`.
+    #[cfg(feature = "connectivity")]
+    pub fn set_can2_start_bank(&mut self, bank: u8) {
+        assert!((1..NUM_FILTER_BANKS).contains(&bank));
+        let fmr = self.can.fmr();
+        fmr.modify(|_, w| w.finit().set_bit());
+        fmr.modify(|_, w| unsafe { w.can2sb().bits(bank) });
+        fmr.modify(|_, w| w.finit().clear_bit());
+    }
+
+    #[cfg(feature = "connectivity")]
+    fn filter_split(&self) -> u8 {
+        self.can.fmr().read().can2sb().bits()
+    }
+
+    #[cfg(not(feature = "connectivity"))]
+    fn filter_split(&self) -> u8 {
+        NUM_FILTER_BANKS
+    }
+}
+
+/// Filter bank builder, see [`Can::filters`]
+///
+/// Each method configures the next free filter bank as a 32 bit identifier mask
+/// and passes the accepted frames to the FIFO selected with [`Filters::fifo`],
+/// FIFO 0 by default. Frames accepted by several banks are stored in the FIFO
+/// of the bank with the lowest number. Frames not accepted by any bank are discarded.
+///
+/// The methods panic if the banks owned by the CAN instance are exhausted.
+pub struct Filters<'a> {
+    can: &'a pac::can1::RegisterBlock,
+    next: u8,
+    end: u8,
+    fifo: bxcan::Fifo,
+}
+
+impl<'a> Filters<'a> {
+    fn new(can: &'a pac::can1::RegisterBlock, start: u8, end: u8) -> Self {
+        can.fmr().modify(|_, w| w.finit().set_bit());
+        let banks: u32 = ((1 << end) - 1) & !((1 << start) - 1);
+        can.fa1r()
+            .modify(|r, w| unsafe { w.bits(r.bits() & !banks) });
+        Self {
+            can,
+            next: start,
+            end,
+            fifo: bxcan::Fifo::Fifo0,
+        }
+    }
+
+    /// Selects the FIFO for the frames accepted by the following filters
+    pub fn fifo(&mut self, fifo: bxcan::Fifo) -> &mut Self {
+        self.fifo = fifo;
+        self
+    }
+
+    /// Accepts all frames
+    pub fn accept_all(&mut self) -> &mut Self {
+        self.enable_bank(0, 0)
+    }
+
+    /// Accepts standard frames whose identifier matches `id` in the bits set in `mask`
+    pub fn accept_standard(&mut self, id: bxcan::StandardId, mask: u16) -> &mut Self {
+        self.enable_bank(
+            (id.as_raw() as u32) << 21,
+            (mask as u32 & 0x7ff) << 21 | IDE,
+        )
+    }
+
+    /// Accepts extended frames whose identifier matches `id` in the bits set in `mask`
+    pub fn accept_extended(&mut self, id: bxcan::ExtendedId, mask: u32) -> &mut Self {
+        self.enable_bank(id.as_raw() << 3 | IDE, (mask & EXTENDED_ID_MASK) << 3 | IDE)
+    }
+
+    /// Accepts extended frames with an identifier in the range `lo..=hi`
+    ///
+    /// A range not aligned to powers of two needs several filter banks.
+    pub fn accept_extended_range(
+        &mut self,
+        lo: bxcan::ExtendedId,
+        hi: bxcan::ExtendedId,
+    ) -> &mut Self {
+        for (id, mask) in RangeMasks::new(lo.as_raw(), hi.as_raw()) {
+            self.enable_bank(id << 3 | IDE, mask << 3 | IDE);
+        }
+        self
+    }
+
+    /// Returns the number of banks that are still free
+    pub fn free_banks(&self) -> u8 {
+        self.end - self.next
+    }
+
+    fn enable_bank(&mut self, id: u32, mask: u32) -> &mut Self {
+        assert!(self.next < self.end, "no free filter bank");
+        let bank = self.next as usize;
+        let bit = 1 << bank;
+        self.next += 1;
+
+        // 32 bit scale, identifier mask mode
+        self.can
+            .fs1r()
+            .modify(|r, w| unsafe { w.bits(r.bits() | bit) });
+        self.can
+            .fm1r()
+            .modify(|r, w| unsafe { w.bits(r.bits() & !bit) });
+        self.can.ffa1r().modify(|r, w| unsafe {
+            w.bits(match self.fifo {
+                bxcan::Fifo::Fifo0 => r.bits() & !bit,
+                bxcan::Fifo::Fifo1 => r.bits() | bit,
+            })
+        });
+        let fb = self.can.fb(bank);
+        fb.fr1().write(|w| unsafe { w.bits(id) });
+        fb.fr2().write(|w| unsafe { w.bits(mask) });
+        self.can
+            .fa1r()
+            .modify(|r, w| unsafe { w.bits(r.bits() | bit) });
+        self
+    }
+}
+
+impl Drop for Filters<'_> {
+    fn drop(&mut self) {
+        self.can.fmr().modify(|_, w| w.finit().clear_bit());
+    }
+}
+
+/// Identifier extension bit of the filter registers
+const IDE: u32 = 1 << 2;
+const EXTENDED_ID_MASK: u32 = 0x1fff_ffff;
+
+/// Splits an identifier range into blocks matched by a single identifier and mask
+struct RangeMasks {
+    lo: u32,
+    hi: u32,
+    done: bool,
+}
+
+impl RangeMasks {
+    fn new(lo: u32, hi: u32) -> Self {
+        assert!(lo <= hi);
+        Self {
+            lo,
+            hi,
+            done: false,
+        }
+    }
+}
+
+impl Iterator for RangeMasks {
+    type Item = (u32, u32);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        // Largest block aligned to `lo` that does not extend beyond `hi`
+        let len = self.hi - self.lo + 1;
+        let bits = self.lo.trailing_zeros().min(31 - len.leading_zeros());
+        let size = 1 << bits;
+        let block = (self.lo, EXTENDED_ID_MASK & !(size - 1));
+        match self.lo.checked_add(size) {
+            Some(lo) if lo <= self.hi => self.lo = lo,
+            _ => self.done = true,
+        }
+        Some(block)
+    }
 }
 
 unsafe impl<PULL> bxcan::Instance for Can<pac::CAN1, PULL> {
@@ -221,9 +688,266 @@ unsafe impl<PULL> bxcan::Instance for Can<pac::CAN2, PULL> {
     const REGISTERS: *mut bxcan::RegisterBlock = pac::CAN2::ptr() as *mut _;
 }
 
+/// Number of filter banks shared by CAN1 and CAN2
+#[cfg(feature = "connectivity")]
+const NUM_FILTER_BANKS: u8 = 28;
+/// Number of filter banks of CAN1
+#[cfg(not(feature = "connectivity"))]
+const NUM_FILTER_BANKS: u8 = 14;
+
 unsafe impl<PULL> bxcan::FilterOwner for Can<pac::CAN1, PULL> {
-    const NUM_FILTER_BANKS: u8 = 28;
+    const NUM_FILTER_BANKS: u8 = NUM_FILTER_BANKS;
 }
 
 #[cfg(feature = "connectivity")]
 unsafe impl<PULL> bxcan::MasterInstance for Can<pac::CAN1, PULL> {}
+
+/// CAN error
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Error {
+    /// A receive FIFO overflowed and at least one frame was lost
+    Overrun,
+    /// The transmit error counter exceeded 255 and the peripheral went bus-off
+    BusOff,
+    /// The bit timing sets the mode or reserved bits of the BTR register
+    InvalidBitTiming,
+}
+
+impl From<bxcan::OverrunError> for Error {
+    fn from(_: bxcan::OverrunError) -> Self {
+        Self::Overrun
+    }
+}
+
+impl embedded_can::Error for Error {
+    fn kind(&self) -> embedded_can::ErrorKind {
+        match self {
+            Self::Overrun => embedded_can::ErrorKind::Overrun,
+            Self::BusOff | Self::InvalidBitTiming => embedded_can::ErrorKind::Other,
+        }
+    }
+}
+
+/// Converts an `embedded-can` identifier into a `bxcan` identifier
+pub fn to_bxcan_id(id: embedded_can::Id) -> bxcan::Id {
+    // Safety: both crates use the same value ranges for their identifiers
+    match id {
+        embedded_can::Id::Standard(id) => unsafe {
+            bxcan::Id::Standard(bxcan::StandardId::new_unchecked(id.as_raw()))
+        },
+        embedded_can::Id::Extended(id) => unsafe {
+            bxcan::Id::Extended(bxcan::ExtendedId::new_unchecked(id.as_raw()))
+        },
+    }
+}
+
+/// Converts a `bxcan` identifier into an `embedded-can` identifier
+pub fn from_bxcan_id(id: bxcan::Id) -> embedded_can::Id {
+    // Safety: both crates use the same value ranges for their identifiers
+    match id {
+        bxcan::Id::Standard(id) => unsafe {
+            embedded_can::Id::Standard(embedded_can::StandardId::new_unchecked(id.as_raw()))
+        },
+        bxcan::Id::Extended(id) => unsafe {
+            embedded_can::Id::Extended(embedded_can::ExtendedId::new_unchecked(id.as_raw()))
+        },
+    }
+}
+
+/// [`bxcan::Can`] driver implementing the `embedded-can` traits.
+///
+/// Unlike the `embedded_can::nb::Can` implementation provided by `bxcan` itself, this
+/// reports the bus-off state and additionally implements `embedded_can::blocking::Can`.
+pub struct EmbeddedCan<CAN: Instance, PULL = Floating>(bxcan::Can<Can<CAN, PULL>>)
+where
+    Can<CAN, PULL>: bxcan::Instance;
+
+impl<CAN: Instance, PULL> EmbeddedCan<CAN, PULL>
+where
+    Can<CAN, PULL>: bxcan::Instance,
+{
+    /// Wraps an enabled `bxcan` driver
+    pub fn new(can: bxcan::Can<Can<CAN, PULL>>) -> Self {
+        Self(can)
+    }
+
+    /// Returns the wrapped `bxcan` driver
+    pub fn release(self) -> bxcan::Can<Can<CAN, PULL>> {
+        self.0
+    }
+
+    /// Returns `true` if the peripheral is in the bus-off state
+    pub fn is_bus_off(&mut self) -> bool {
+        self.0.instance().can.esr().read().boff().bit_is_set()
+    }
+
+    /// Returns an iterator over the frames received so far, see [`RxFrames`]
+    pub fn rx_frames(&mut self) -> RxFrames<'_, Can<CAN, PULL>> {
+        RxFrames::new(&mut self.0)
+    }
+}
+
+/// Iterator draining the receive FIFOs
+///
+/// Yields the frames of FIFO 0 first and then those of FIFO 1, each FIFO in the
+/// order of reception, and ends when both are empty. Each frame is released from
+/// its FIFO when it is read. Lost frames are recorded in [`RxFrames::overrun`].
+///
+/// ```rust
+/// let mut frames = can.rx_frames();
+/// for frame in &mut frames {
+///     handle(frame);
+/// }
+/// if frames.overrun() {
+///     // frames were lost, poll more often
+/// }
+/// ```
+pub struct RxFrames<'a, I: bxcan::Instance> {
+    can: &'a mut bxcan::Can<I>,
+    overrun: bool,
+}
+
+impl<'a, I: bxcan::Instance> RxFrames<'a, I> {
+    pub fn new(can: &'a mut bxcan::Can<I>) -> Self {
+        Self {
+            can,
+            overrun: false,
+        }
+    }
+
+    /// Returns `true` if a FIFO overflowed and at least one frame was lost
+    pub fn overrun(&self) -> bool {
+        self.overrun
+    }
+}
+
+impl<I: bxcan::Instance> Iterator for RxFrames<'_, I> {
+    type Item = bxcan::Frame;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.can.receive() {
+                Ok(frame) => return Some(frame),
+                Err(nb::Error::WouldBlock) => return None,
+                // The overrun flag is cleared, the frames in the FIFO are still available
+                Err(nb::Error::Other(bxcan::OverrunError { .. })) => self.overrun = true,
+            }
+        }
+    }
+}
+
+impl<CAN: Instance, PULL> From<bxcan::Can<Can<CAN, PULL>>> for EmbeddedCan<CAN, PULL>
+where
+    Can<CAN, PULL>: bxcan::Instance,
+{
+    fn from(can: bxcan::Can<Can<CAN, PULL>>) -> Self {
+        Self::new(can)
+    }
+}
+
+impl<CAN: Instance, PULL> Deref for EmbeddedCan<CAN, PULL>
+where
+    Can<CAN, PULL>: bxcan::Instance,
+{
+    type Target = bxcan::Can<Can<CAN, PULL>>;
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<CAN: Instance, PULL> DerefMut for EmbeddedCan<CAN, PULL>
+where
+    Can<CAN, PULL>: bxcan::Instance,
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<CAN: Instance, PULL> embedded_can::nb::Can for EmbeddedCan<CAN, PULL>
+where
+    Can<CAN, PULL>: bxcan::Instance,
+{
+    type Frame = bxcan::Frame;
+    type Error = Error;
+
+    fn transmit(&mut self, frame: &Self::Frame) -> nb::Result<Option<Self::Frame>, Self::Error> {
+        if self.is_bus_off() {
+            return Err(nb::Error::Other(Error::BusOff));
+        }
+        match self.0.transmit(frame) {
+            Ok(status) => Ok(status.dequeued_frame().cloned()),
+            Err(nb::Error::WouldBlock) => Err(nb::Error::WouldBlock),
+            Err(nb::Error::Other(e)) => match e {},
+        }
+    }
+
+    fn receive(&mut self) -> nb::Result<Self::Frame, Self::Error> {
+        match self.0.receive() {
+            Ok(frame) => Ok(frame),
+            Err(nb::Error::Other(e)) => Err(nb::Error::Other(e.into())),
+            Err(nb::Error::WouldBlock) if self.is_bus_off() => Err(nb::Error::Other(Error::BusOff)),
+            Err(nb::Error::WouldBlock) => Err(nb::Error::WouldBlock),
+        }
+    }
+}
+
+impl<CAN: Instance, PULL> embedded_can::blocking::Can for EmbeddedCan<CAN, PULL>
+where
+    Can<CAN, PULL>: bxcan::Instance,
+{
+    type Frame = bxcan::Frame;
+    type Error = Error;
+
+    fn transmit(&mut self, frame: &Self::Frame) -> Result<(), Self::Error> {
+        // A lower priority frame may be dequeued to make room, so it has to be queued again
+        let mut frame = frame.clone();
+        while let Some(dequeued) = nb::block!(embedded_can::nb::Can::transmit(self, &frame))? {
+            frame = dequeued;
+        }
+        Ok(())
+    }
+
+    fn receive(&mut self) -> Result<Self::Frame, Self::Error> {
+        nb::block!(embedded_can::nb::Can::receive(self))
+    }
+}
+
+#[test]
+fn can_bit_timing() {
+    use fugit::RateExtU32;
+
+    for pclk1 in [8.MHz(), 36.MHz()] {
+        for bitrate in [125.kHz(), 250.kHz(), 500.kHz(), 1.MHz()] {
+            let timing = BitTiming::new(pclk1, bitrate).unwrap();
+            assert_eq!(timing.bitrate(), bitrate);
+            let tq = 1 + timing.bs1() as u32 + timing.bs2() as u32;
+            assert_eq!(pclk1.raw(), bitrate.raw() * tq * timing.prescaler() as u32);
+            assert!((850..=900).contains(&timing.sample_point()));
+        }
+    }
+
+    // APB1: 8MHz, 125kBit/s, Sample Point 87.5% as calculated by http://www.bittiming.can-wiki.info/
+    let timing = BitTiming::new(8.MHz(), 125.kHz()).unwrap();
+    assert_eq!(timing.btr(), 0x001c_0003);
+    assert_eq!(timing.sample_point(), 875);
+
+    assert_eq!(BitTiming::new(8.MHz(), 2.MHz()), None);
+    assert_eq!(BitTiming::new(36.MHz(), 0.Hz()), None);
+}
+
+#[test]
+fn can_filter_range_masks() {
+    // Aligned range matched by a single mask
+    assert!(RangeMasks::new(0x100, 0x1ff).eq([(0x100, 0x1fff_ff00)]));
+    assert!(RangeMasks::new(0x42, 0x42).eq([(0x42, 0x1fff_ffff)]));
+    assert!(RangeMasks::new(0, 0x1fff_ffff).eq([(0, 0)]));
+    // 0x0f..=0x22 = 0x0f, 0x10..=0x1f, 0x20..=0x21, 0x22
+    assert!(RangeMasks::new(0x0f, 0x22).eq([
+        (0x0f, 0x1fff_ffff),
+        (0x10, 0x1fff_fff0),
+        (0x20, 0x1fff_fffe),
+        (0x22, 0x1fff_ffff),
+    ]));
+}
@@ -1,4 +1,20 @@
 //! CRC
+//!
+//! The CRC unit calculates a CRC-32 with the polynomial `0x04C11DB7` and the
+//! initial value `0xFFFFFFFF` over 32 bit words, most significant bit first.
+//! The input and the result are not reflected and not inverted, so this is
+//! not the common CRC-32 of zlib or Ethernet but CRC-32/MPEG-2.
+//!
+//! [`Crc::feed_bytes`] packs the bytes big endian into words, so the result
+//! matches CRC-32/MPEG-2 over the byte stream. The remaining 1 to 3 bytes that
+//! do not fill a word are added in software by [`Crc::result`].
+//!
+//! ```rust
+//! let mut crc = dp.CRC.new();
+//! crc.reset();
+//! crc.feed_bytes(b"123456789");
+//! assert_eq!(crc.result(), 0x0376E6E7);
+//! ```
 
 use crate::pac::{CRC, RCC};
 use crate::rcc::Enable;
@@ -15,15 +31,24 @@ impl CrcExt for CRC {
         let rcc = unsafe { &(*RCC::ptr()) };
         CRC::enable(rcc);
 
-        Crc { crc: self }
+        Crc {
+            crc: self,
+            pending: 0,
+            pending_len: 0,
+        }
     }
 }
 
 /// Constrained CRC peripheral
 pub struct Crc {
     crc: CRC,
+    /// Bytes of an incomplete word
+    pending: u32,
+    pending_len: u8,
 }
 
+const POLY: u32 = 0x04C1_1DB7;
+
 impl Crc {
     pub fn read(&self) -> u32 {
         self.crc.dr().read().bits()
@@ -33,7 +58,51 @@ impl Crc {
         self.crc.dr().write(|w| w.dr().set(val))
     }
 
-    pub fn reset(&self) {
+    /// Adds `data` to the CRC
+    pub fn feed(&mut self, data: &[u32]) {
+        if self.pending_len != 0 {
+            for word in data {
+                self.feed_bytes(&word.to_be_bytes());
+            }
+        } else {
+            for word in data {
+                self.write(*word);
+            }
+        }
+    }
+
+    /// Adds `data` to the CRC, four bytes at a time as big endian words
+    pub fn feed_bytes(&mut self, data: &[u8]) {
+        for byte in data {
+            self.pending = self.pending << 8 | *byte as u32;
+            self.pending_len += 1;
+            if self.pending_len == 4 {
+                self.write(self.pending);
+                self.pending = 0;
+                self.pending_len = 0;
+            }
+        }
+    }
+
+    /// Returns the CRC of all data fed since the last reset
+    pub fn result(&self) -> u32 {
+        let mut crc = self.read();
+        for i in (0..self.pending_len).rev() {
+            crc ^= (self.pending >> (i * 8)) << 24;
+            for _ in 0..8 {
+                crc = if crc & 0x8000_0000 != 0 {
+                    crc << 1 ^ POLY
+                } else {
+                    crc << 1
+                };
+            }
+        }
+        crc
+    }
+
+    pub fn reset(&mut self) {
+        self.pending = 0;
+        self.pending_len = 0;
         self.crc.cr().write(|w| w.reset().set_bit());
         // calling CRC::dr::write() just after CRC::cr::reset() will not work as expected, and
         // inserting single nop() seems to solve the problem.
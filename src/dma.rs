@@ -1,4 +1,27 @@
 //! # Direct Memory Access
+//!
+//! The DMA requests of the peripherals are fixed to these channels:
+//!
+//! | DMA1 | Requests |
+//! |:----:|----------|
+//! | C1 | ADC1, TIM2_CH3, TIM4_CH1 |
+//! | C2 | SPI1_RX, USART3_TX, TIM1_CH1, TIM2_UP, TIM3_CH3 |
+//! | C3 | SPI1_TX, USART3_RX, TIM1_CH2, TIM3_CH4, TIM3_UP |
+//! | C4 | SPI2_RX, USART1_TX, I2C2_TX, TIM1_CH4, TIM1_TRIG, TIM1_COM, TIM4_CH2 |
+//! | C5 | SPI2_TX, USART1_RX, I2C2_RX, TIM1_UP, TIM2_CH1, TIM4_CH3 |
+//! | C6 | USART2_RX, I2C1_TX, TIM1_CH3, TIM3_CH1, TIM3_TRIG |
+//! | C7 | USART2_TX, I2C1_RX, TIM2_CH2, TIM2_CH4, TIM4_UP |
+//!
+//! | DMA2 | Requests |
+//! |:----:|----------|
+//! | C1 | SPI3_RX, TIM5_CH4, TIM5_TRIG, TIM8_CH3, TIM8_UP |
+//! | C2 | SPI3_TX, TIM5_CH3, TIM5_UP, TIM8_CH4, TIM8_TRIG, TIM8_COM |
+//! | C3 | UART4_RX, TIM6_UP/DAC1, TIM8_CH1 |
+//! | C4 | SDIO, TIM5_CH2, TIM7_UP/DAC2 |
+//! | C5 | ADC3, UART4_TX, TIM5_CH1, TIM8_CH2 |
+//!
+//! Requests of several channels at the same time are served by [`Priority`],
+//! then by the lower channel number.
 #![allow(dead_code)]
 
 use crate::pac;
@@ -22,12 +45,31 @@ pub enum Event {
     TransferComplete,
 }
 
+/// Channel priority
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Priority {
+    Low,
+    Medium,
+    High,
+    VeryHigh,
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Half {
     First,
     Second,
 }
 
+/// Circular (double buffered) DMA reception.
+///
+/// The DMA fills both halves of the buffer alternately and never stops. The half
+/// transfer and transfer complete flags tell which half has been filled, so it can
+/// be processed by [`peek`](CircBuffer::peek) while the DMA fills the other one.
+/// If the DMA finishes the other half too before the processed half has been
+/// released, data is lost and an [`Error::Overrun`] is returned.
+///
+/// For interrupt driven processing enable both events by
+/// [`listen`](CircBuffer::listen) and call `peek` in the DMA channel interrupt.
 pub struct CircBuffer<BUFFER, PAYLOAD>
 where
     BUFFER: 'static,
@@ -99,6 +141,23 @@ where
     }
 }
 
+impl<MODE, BUFFER, PAYLOAD> Transfer<MODE, BUFFER, PAYLOAD>
+where
+    PAYLOAD: TransferPayload,
+{
+    /// Takes the buffer and the payload without stopping the transfer
+    pub(crate) fn into_parts(self) -> (BUFFER, PAYLOAD) {
+        // NOTE(unsafe) There is no panic branch between getting the resources
+        // and forgetting `self`.
+        unsafe {
+            let buffer = ptr::read(&self.buffer);
+            let payload = ptr::read(&self.payload);
+            mem::forget(self);
+            (buffer, payload)
+        }
+    }
+}
+
 impl<MODE, BUFFER, PAYLOAD> Drop for Transfer<MODE, BUFFER, PAYLOAD>
 where
     PAYLOAD: TransferPayload,
@@ -175,6 +234,34 @@ impl<DMA: DmaExt, const C: u8> Ch<DMA, C> {
         }
     }
 
+    /// Sets the priority of the channel against the other channels of this DMA
+    pub fn set_priority(&mut self, priority: Priority) {
+        self.ch().cr().modify(|_, w| match priority {
+            Priority::Low => w.pl().low(),
+            Priority::Medium => w.pl().medium(),
+            Priority::High => w.pl().high(),
+            Priority::VeryHigh => w.pl().very_high(),
+        });
+    }
+
+    /// Returns `true` if the channel is enabled and has data left to transfer,
+    /// which is always the case in circular mode
+    pub fn is_active(&self) -> bool {
+        // NOTE(unsafe) atomic read with no side effects
+        let enabled = unsafe { &(*DMA::ptr()) }
+            .ch(C as usize)
+            .cr()
+            .read()
+            .en()
+            .bit_is_set();
+        enabled && self.remaining() != 0
+    }
+
+    /// Number of data items left to transfer
+    pub fn remaining(&self) -> u16 {
+        self.get_ndtr() as u16
+    }
+
     pub fn ch(&mut self) -> &pac::dma1::CH {
         unsafe { (*DMA::ptr()).ch(C as usize) }
     }
@@ -198,6 +285,108 @@ impl<DMA: DmaExt, const C: u8> Ch<DMA, C> {
     }
 }
 
+/// Memory-to-memory transfer on a DMA channel
+pub struct MemToMem<CH> {
+    pub channel: CH,
+}
+
+impl<DMA: DmaExt, const C: u8> TransferPayload for MemToMem<Ch<DMA, C>> {
+    fn start(&mut self) {
+        self.channel.start();
+    }
+
+    fn stop(&mut self) {
+        self.channel.stop();
+    }
+}
+
+impl<DMA: DmaExt, const C: u8> Ch<DMA, C> {
+    /// Copies `src` to `dst`, e.g. to move large blocks without the CPU.
+    ///
+    /// The DMA transfers 32 or 16 bit words if both addresses and the length
+    /// are aligned accordingly, otherwise bytes. Panics if the buffers are empty,
+    /// the lengths differ or the copy needs more than 65535 transfers.
+    pub fn mem_to_mem<S, D, T>(mut self, src: S, mut dst: D) -> Transfer<W, (S, D), MemToMem<Self>>
+    where
+        S: ReadBuffer<Word = T>,
+        D: WriteBuffer<Word = T>,
+    {
+        // NOTE(unsafe) the buffers are owned by the transfer until it is done
+        let (src_ptr, src_len) = unsafe { src.read_buffer() };
+        let (dst_ptr, dst_len) = unsafe { dst.write_buffer() };
+        assert_eq!(src_len, dst_len, "source and destination lengths differ");
+        // An empty transfer never completes
+        assert!(src_len != 0, "empty buffers");
+
+        let (src_addr, dst_addr) = (src_ptr as u32, dst_ptr as u32);
+        let bytes = (src_len * mem::size_of::<T>()) as u32;
+        let size = [4, 2, 1]
+            .into_iter()
+            .find(|size| (src_addr | dst_addr | bytes) % size == 0)
+            .unwrap();
+
+        self.stop();
+        // The peripheral address is the source in memory-to-memory mode
+        self.set_peripheral_address(src_addr, true);
+        self.set_memory_address(dst_addr, true);
+        self.set_transfer_length((bytes / size) as usize);
+        self.ch().cr().modify(|_, w| {
+            match size {
+                4 => {
+                    w.msize().bits32();
+                    w.psize().bits32();
+                }
+                2 => {
+                    w.msize().bits16();
+                    w.psize().bits16();
+                }
+                _ => {
+                    w.msize().bits8();
+                    w.psize().bits8();
+                }
+            }
+            w.dir().clear_bit().circ().clear_bit().mem2mem().set_bit()
+        });
+
+        compiler_fence(Ordering::Release);
+        let mut payload = MemToMem { channel: self };
+        payload.start();
+        Transfer::w((src, dst), payload)
+    }
+}
+
+impl<BUFFER, MODE, DMA: DmaExt, const C: u8> Transfer<MODE, BUFFER, MemToMem<Ch<DMA, C>>> {
+    pub fn is_done(&self) -> bool {
+        !self.payload.channel.in_progress()
+    }
+
+    pub fn wait(mut self) -> (BUFFER, MemToMem<Ch<DMA, C>>) {
+        while !self.is_done() {}
+
+        atomic::compiler_fence(Ordering::Acquire);
+
+        self.payload.stop();
+
+        // we need a read here to make the Acquire fence effective
+        // we do *not* need this if `dma.stop` does a RMW operation
+        unsafe {
+            ptr::read_volatile(&0);
+        }
+
+        // we need a fence here for the same reason we need one in `Transfer.wait`
+        atomic::compiler_fence(Ordering::Acquire);
+
+        // NOTE(unsafe) There is no panic branch between getting the resources
+        // and forgetting `self`.
+        unsafe {
+            let buffer = ptr::read(&self.buffer);
+            let payload = ptr::read(&self.payload);
+            mem::forget(self);
+            (buffer, payload)
+        }
+    }
+}
+
 impl<B, PAYLOAD, DMA: DmaExt, const C: u8> CircBuffer<B, RxDma<PAYLOAD, Ch<DMA, C>>>
 where
     RxDma<PAYLOAD, Ch<DMA, C>>: TransferPayload,
@@ -214,8 +403,10 @@ where
             Half::Second => &self.buffer[1],
         };
 
-        // XXX does this need a compiler barrier?
+        // The DMA writes of this half have to be observed before reading it
+        atomic::compiler_fence(Ordering::Acquire);
         let ret = f(buf, half_being_read);
+        atomic::compiler_fence(Ordering::Release);
 
         let isr = self.payload.channel.isr();
         let first_half_is_done = isr.htif(C).bit_is_set();
@@ -266,6 +457,15 @@ where
         })
     }
 
+    /// Enables the interrupt of a half transfer or transfer complete event
+    pub fn listen(&mut self, event: Event) {
+        self.payload.channel.listen(event);
+    }
+
+    pub fn unlisten(&mut self, event: Event) {
+        self.payload.channel.unlisten(event);
+    }
+
     /// Stops the transfer and returns the underlying buffer and RxDma
     pub fn stop(mut self) -> (&'static mut [B; 2], RxDma<PAYLOAD, Ch<DMA, C>>) {
         self.payload.stop();
@@ -551,7 +751,7 @@ where
     fn write(self, buffer: B) -> Transfer<R, B, Self>;
 }
 
-/// Trait for DMA simultaneously reading and writing within one synchronous operation. Panics if both buffers are not of equal length.
+/// Trait for DMA simultaneously reading and writing within one synchronous operation.
 pub trait ReadWriteDma<RXB, TXB, TS>: Transmit
 where
     RXB: WriteBuffer<Word = TS>,
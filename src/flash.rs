@@ -1,16 +1,23 @@
 //! Flash memory
 
 use crate::pac::{flash, FLASH};
+use crate::rcc::Clocks;
+use crate::time::MHz;
 
 pub const FLASH_START: u32 = 0x0800_0000;
 pub const FLASH_END: u32 = 0x080F_FFFF;
 
-const _RDPRT_KEY: u16 = 0x00A5;
+const RDPRT_KEY: u8 = 0xA5;
 const KEY1: u32 = 0x45670123;
 const KEY2: u32 = 0xCDEF89AB;
 
 pub const SZ_1K: u16 = 1024;
 
+/// Start of the option bytes
+const OPTION_BYTES: u32 = 0x1FFF_F800;
+/// Size of the flash protected by one write protection bit
+const WRP_BLOCK: u32 = 4 * SZ_1K as u32;
+
 pub type Result<T> = core::result::Result<T, Error>;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Ord, PartialOrd)]
@@ -57,6 +64,43 @@ impl FlashSize {
     }
 }
 
+/// Flash read protection level
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReadProtection {
+    /// No protection
+    Level0,
+    /// The flash can not be read by the debugger or from SRAM, the first 4K are write protected
+    Level1,
+}
+
+/// Contents of the option bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OptionBytes {
+    pub read_protection: ReadProtection,
+    /// The independent watchdog is enabled by software (`true`) or always on (`false`)
+    pub software_watchdog: bool,
+    /// A reset is generated instead of entering Stop mode
+    pub reset_on_stop: bool,
+    /// A reset is generated instead of entering Standby mode
+    pub reset_on_standby: bool,
+    /// User data byte 0
+    pub data0: u8,
+    /// User data byte 1
+    pub data1: u8,
+    /// Write protection bits, each cleared bit protects 4K of flash.
+    ///
+    /// Bit 31 protects everything from 124K up to the end of flash.
+    pub write_protection: u32,
+}
+
+impl OptionBytes {
+    /// Returns `true` if the flash at `offset` is write protected
+    pub fn is_write_protected(&self, offset: u32) -> bool {
+        let bit = (offset / WRP_BLOCK).min(31);
+        self.write_protection & (1 << bit) == 0
+    }
+}
+
 pub struct FlashWriter<'a> {
     flash: &'a mut Parts,
     sector_sz: SectorSize,
@@ -68,6 +112,11 @@ impl<'a> FlashWriter<'a> {
         // Wait for any ongoing operations
         while self.flash.sr.sr().read().bsy().bit_is_set() {}
 
+        // Writing the keys again to an unlocked FPEC would lock it until reset
+        if self.flash.cr.cr().read().lock().bit_is_clear() {
+            return Ok(());
+        }
+
         // NOTE(unsafe) write Keys to the key register. This is safe because the
         // only side effect of these writes is to unlock the flash control
         // register, which is the intent of this function. Do not rearrange the
@@ -102,7 +151,7 @@ impl<'a> FlashWriter<'a> {
     }
 
     fn valid_address(&self, offset: u32) -> Result<()> {
-        if FLASH_START + offset > FLASH_END {
+        if offset >= self.flash_sz.kbytes() {
             Err(Error::AddressLargerThanFlash)
         } else if offset & 0x1 != 0 {
             Err(Error::AddressMisaligned)
@@ -112,7 +161,7 @@ impl<'a> FlashWriter<'a> {
     }
 
     fn valid_length(&self, offset: u32, length: usize) -> Result<()> {
-        if offset + length as u32 > self.flash_sz.kbytes() {
+        if offset as usize + length > self.flash_sz.kbytes() as usize {
             Err(Error::LengthTooLong)
         } else if length & 0x1 != 0 {
             Err(Error::LengthNotMultiple2)
@@ -121,6 +170,27 @@ impl<'a> FlashWriter<'a> {
         }
     }
 
+    /// Waits for the end of the operation and returns and clears the error flags
+    fn wait_and_clear_flags(&mut self) -> (bool, bool) {
+        // Wait for at least one clock cycle before reading the
+        // BSY bit, because there is a one-cycle delay between
+        // setting the STRT bit and the BSY bit being asserted
+        // by hardware. See STM32F105xx, STM32F107xx device errata,
+        // section 2.2.8
+        cortex_m::asm::nop();
+
+        // Wait for operation to finish
+        while self.flash.sr.sr().read().bsy().bit_is_set() {}
+
+        let sr = self.flash.sr.sr().read();
+        // reset by writing 1
+        self.flash
+            .sr
+            .sr()
+            .write(|w| w.eop().bit(true).pgerr().bit(true).wrprterr().bit(true));
+        (sr.pgerr().bit_is_set(), sr.wrprterr().bit_is_set())
+    }
+
     /// Erase sector which contains `start_offset`
     pub fn page_erase(&mut self, start_offset: u32) -> Result<()> {
         self.valid_address(start_offset)?;
@@ -128,6 +198,9 @@ impl<'a> FlashWriter<'a> {
         // Unlock Flash
         self.unlock()?;
 
+        // Clear flags of previous operations
+        self.wait_and_clear_flags();
+
         // Set Page Erase
         self.flash.cr.cr().modify(|_, w| w.per().set_bit());
 
@@ -146,18 +219,8 @@ impl<'a> FlashWriter<'a> {
         // Start Operation
         self.flash.cr.cr().modify(|_, w| w.strt().set_bit());
 
-        // Wait for at least one clock cycle before reading the
-        // BSY bit, because there is a one-cycle delay between
-        // setting the STRT bit and the BSY bit being asserted
-        // by hardware. See STM32F105xx, STM32F107xx device errata,
-        // section 2.2.8
-        cortex_m::asm::nop();
-
-        // Wait for operation to finish
-        while self.flash.sr.sr().read().bsy().bit_is_set() {}
-
-        // Check for errors
-        let sr = self.flash.sr.sr().read();
+        // Wait for the operation and check for errors
+        let (pgerr, wrprterr) = self.wait_and_clear_flags();
 
         // Remove Page Erase Operation bit
         self.flash.cr.cr().modify(|_, w| w.per().clear_bit());
@@ -165,9 +228,7 @@ impl<'a> FlashWriter<'a> {
         // Re-lock flash
         self.lock()?;
 
-        if sr.wrprterr().bit_is_set() {
-            // reset by writing 1
-            self.flash.sr.sr().modify(|_, w| w.wrprterr().bit(true));
+        if pgerr || wrprterr {
             Err(Error::EraseError)
         } else {
             if self.verify {
@@ -196,8 +257,9 @@ impl<'a> FlashWriter<'a> {
         self.valid_length(start_offset, length)?;
 
         // Erase every sector touched by start_offset + length
+        let size = self.sector_sz.kbytes() as u32;
         for offset in
-            (start_offset..start_offset + length as u32).step_by(self.sector_sz.kbytes() as usize)
+            (start_offset & !(size - 1)..start_offset + length as u32).step_by(size as usize)
         {
             self.page_erase(offset)?;
         }
@@ -210,7 +272,7 @@ impl<'a> FlashWriter<'a> {
     pub fn read(&self, offset: u32, length: usize) -> Result<&[u8]> {
         self.valid_address(offset)?;
 
-        if offset + length as u32 > self.flash_sz.kbytes() {
+        if offset as usize + length > self.flash_sz.kbytes() as usize {
             return Err(Error::LengthTooLong);
         }
 
@@ -226,13 +288,20 @@ impl<'a> FlashWriter<'a> {
         )
     }
 
-    /// Write data to `FLASH_START + offset`
+    /// Write data to `FLASH_START + offset`.
+    ///
+    /// `offset` and the length of `data` have to be multiples of 2 and the
+    /// half-words have to be erased before.
     pub fn write(&mut self, offset: u32, data: &[u8]) -> Result<()> {
+        self.valid_address(offset)?;
         self.valid_length(offset, data.len())?;
 
         // Unlock Flash
         self.unlock()?;
 
+        // Clear flags of previous operations
+        self.wait_and_clear_flags();
+
         for idx in (0..data.len()).step_by(2) {
             self.valid_address(offset + idx as u32)?;
 
@@ -251,22 +320,16 @@ impl<'a> FlashWriter<'a> {
             unsafe { core::ptr::write_volatile(write_address, hword) };
 
             // Wait for write
-            while self.flash.sr.sr().read().bsy().bit_is_set() {}
+            let (pgerr, wrprterr) = self.wait_and_clear_flags();
 
             // Set Page Programming to 0
             self.flash.cr.cr().modify(|_, w| w.pg().clear_bit());
 
             // Check for errors
-            if self.flash.sr.sr().read().pgerr().bit_is_set() {
-                // reset by writing 1
-                self.flash.sr.sr().modify(|_, w| w.pgerr().bit(true));
-
+            if pgerr {
                 self.lock()?;
                 return Err(Error::ProgrammingError);
-            } else if self.flash.sr.sr().read().wrprterr().bit_is_set() {
-                // reset by writing 1
-                self.flash.sr.sr().modify(|_, w| w.wrprterr().bit(true));
-
+            } else if wrprterr {
                 self.lock()?;
                 return Err(Error::WriteError);
             } else if self.verify {
@@ -285,6 +348,88 @@ impl<'a> FlashWriter<'a> {
         Ok(())
     }
 
+    /// Returns `true` if page `page` of the configured sector size is write
+    /// protected and can neither be erased nor programmed
+    pub fn is_page_write_protected(&self, page: u32) -> bool {
+        self.flash
+            .option_bytes()
+            .is_write_protected(page * self.sector_sz.kbytes() as u32)
+    }
+
+    /// Erases and programs the option bytes. The new values take effect after
+    /// the next reset.
+    ///
+    /// # Safety
+    ///
+    /// The option bytes are erased before they are programmed, a reset or power
+    /// loss in between leaves the device read protected. Changing the read
+    /// protection from level 1 to level 0 mass erases the flash, including the
+    /// running program. Write protection can lock the program out of its own
+    /// flash and a hardware watchdog can not be stopped anymore.
+    pub unsafe fn write_option_bytes(&mut self, option_bytes: &OptionBytes) -> Result<()> {
+        self.unlock()?;
+
+        // Unlock the option bytes with the same key sequence
+        self.flash
+            .optkeyr
+            .optkeyr()
+            .write(|w| w.optkey().bits(KEY1));
+        self.flash
+            .optkeyr
+            .optkeyr()
+            .write(|w| w.optkey().bits(KEY2));
+        if self.flash.cr.cr().read().optwre().bit_is_clear() {
+            self.lock()?;
+            return Err(Error::UnlockError);
+        }
+
+        // Clear flags of previous operations
+        self.wait_and_clear_flags();
+
+        // Erase all option bytes
+        self.flash.cr.cr().modify(|_, w| w.opter().set_bit());
+        self.flash.cr.cr().modify(|_, w| w.strt().set_bit());
+        let (pgerr, wrprterr) = self.wait_and_clear_flags();
+        self.flash.cr.cr().modify(|_, w| w.opter().clear_bit());
+        if pgerr || wrprterr {
+            self.flash.cr.cr().modify(|_, w| w.optwre().clear_bit());
+            self.lock()?;
+            return Err(Error::EraseError);
+        }
+
+        let ob = option_bytes;
+        let rdp = match ob.read_protection {
+            ReadProtection::Level0 => RDPRT_KEY,
+            ReadProtection::Level1 => 0x00,
+        };
+        let user = 0xF8
+            | ob.software_watchdog as u8
+            | (!ob.reset_on_stop as u8) << 1
+            | (!ob.reset_on_standby as u8) << 2;
+        let wrp = ob.write_protection.to_le_bytes();
+        let bytes = [
+            rdp, user, ob.data0, ob.data1, wrp[0], wrp[1], wrp[2], wrp[3],
+        ];
+
+        // The complement in the upper byte of each half-word is generated by hardware
+        self.flash.cr.cr().modify(|_, w| w.optpg().set_bit());
+        let mut result = Ok(());
+        for (i, byte) in bytes.iter().enumerate() {
+            let address = (OPTION_BYTES + 2 * i as u32) as *mut u16;
+            core::ptr::write_volatile(address, *byte as u16);
+            let (pgerr, wrprterr) = self.wait_and_clear_flags();
+            if pgerr || wrprterr {
+                result = Err(Error::ProgrammingError);
+                break;
+            }
+        }
+        self.flash.cr.cr().modify(|_, w| w.optpg().clear_bit());
+        self.flash.cr.cr().modify(|_, w| w.optwre().clear_bit());
+
+        self.lock()?;
+        result
+    }
+
     /// Enable/disable verifying that each erase or write operation completed
     /// successfuly.
     ///
@@ -313,10 +458,10 @@ impl FlashExt for FLASH {
             ar: AR,
             cr: CR,
             keyr: KEYR,
-            _obr: OBR,
-            _optkeyr: OPTKEYR,
+            obr: OBR,
+            optkeyr: OPTKEYR,
             sr: SR,
-            _wrpr: WRPR,
+            wrpr: WRPR,
         }
     }
 }
@@ -336,19 +481,19 @@ pub struct Parts {
     pub(crate) keyr: KEYR,
 
     /// Opaque OBR register
-    pub(crate) _obr: OBR,
+    pub(crate) obr: OBR,
 
     /// Opaque OPTKEYR register
-    pub(crate) _optkeyr: OPTKEYR,
+    pub(crate) optkeyr: OPTKEYR,
 
     /// Opaque SR register
     pub(crate) sr: SR,
 
     /// Opaque WRPR register
-    pub(crate) _wrpr: WRPR,
+    pub(crate) wrpr: WRPR,
 }
 impl Parts {
-    pub fn writer(&mut self, sector_sz: SectorSize, flash_sz: FlashSize) -> FlashWriter {
+    pub fn writer(&mut self, sector_sz: SectorSize, flash_sz: FlashSize) -> FlashWriter<'_> {
         FlashWriter {
             flash: self,
             sector_sz,
@@ -356,6 +501,29 @@ impl Parts {
             verify: true,
         }
     }
+
+    /// Reads the option bytes loaded at the last reset
+    pub fn option_bytes(&self) -> OptionBytes {
+        let obr = self.obr.obr().read();
+        OptionBytes {
+            read_protection: if obr.rdprt().bit_is_set() {
+                ReadProtection::Level1
+            } else {
+                ReadProtection::Level0
+            },
+            software_watchdog: obr.wdg_sw().bit_is_set(),
+            reset_on_stop: obr.n_rst_stop().bit_is_clear(),
+            reset_on_standby: obr.n_rst_stdby().bit_is_clear(),
+            data0: (obr.bits() >> 10) as u8,
+            data1: (obr.bits() >> 18) as u8,
+            write_protection: self.wrpr.wrpr().read().bits(),
+        }
+    }
+
+    /// Returns the active read protection level
+    pub fn read_protection_level(&self) -> ReadProtection {
+        self.option_bytes().read_protection
+    }
 }
 
 /// Opaque ACR register
@@ -368,6 +536,42 @@ impl ACR {
         // NOTE(unsafe) this proxy grants exclusive access to this register
         unsafe { (*FLASH::ptr()).acr() }
     }
+
+    /// Enables the prefetch buffer, which is enabled after reset.
+    ///
+    /// The prefetch buffer may only be switched while SYSCLK is below 24 MHz
+    /// and equal to HCLK (AHB prescaler 1), i.e. before `freeze`.
+    #[cfg(not(feature = "stm32f100"))]
+    pub fn enable_prefetch(&mut self) {
+        self.acr().modify(|_, w| w.prftbe().set_bit());
+    }
+
+    /// Disables the prefetch buffer, see [`enable_prefetch`](Self::enable_prefetch)
+    #[cfg(not(feature = "stm32f100"))]
+    pub fn disable_prefetch(&mut self) {
+        self.acr().modify(|_, w| w.prftbe().clear_bit());
+    }
+
+    /// Returns the status of the prefetch buffer
+    #[cfg(not(feature = "stm32f100"))]
+    pub fn is_prefetch_enabled(&mut self) -> bool {
+        self.acr().read().prftbs().bit_is_set()
+    }
+
+    /// Enables half-cycle flash access to save power at low frequencies.
+    ///
+    /// Panics if SYSCLK is above 8 MHz, half-cycle access only works without
+    /// wait states and AHB prescaler 1.
+    pub fn enable_half_cycle_access(&mut self, clocks: &Clocks) {
+        assert!(clocks.sysclk() <= MHz(8) && clocks.hclk() == clocks.sysclk());
+        self.acr().modify(|_, w| w.hlfcya().set_bit());
+    }
+
+    /// Disables half-cycle flash access, which has to be done before SYSCLK is
+    /// raised above 8 MHz
+    pub fn disable_half_cycle_access(&mut self) {
+        self.acr().modify(|_, w| w.hlfcya().clear_bit());
+    }
 }
 
 /// Opaque AR register
@@ -412,7 +616,7 @@ pub struct OBR;
 
 #[allow(dead_code)]
 impl OBR {
-    pub(crate) fn obr(&mut self) -> &flash::OBR {
+    pub(crate) fn obr(&self) -> &flash::OBR {
         // NOTE(unsafe) this proxy grants exclusive access to this register
         unsafe { (*FLASH::ptr()).obr() }
     }
@@ -448,7 +652,7 @@ pub struct WRPR;
 
 #[allow(dead_code)]
 impl WRPR {
-    pub(crate) fn wrpr(&mut self) -> &flash::WRPR {
+    pub(crate) fn wrpr(&self) -> &flash::WRPR {
         // NOTE(unsafe) this proxy grants exclusive access to this register
         unsafe { (*FLASH::ptr()).wrpr() }
     }
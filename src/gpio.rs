@@ -23,6 +23,9 @@
 //! Each GPIO pin can be set to various modes:
 //!
 //! - **Alternate**: Pin mode required when the pin is driven by other peripherals
+//!     - **PushPull**: Output driven high or low by the peripheral, e.g. USART TX or SPI SCK
+//!     - **OpenDrain**: Output only pulled low by the peripheral. Required for I2C SCL and SDA,
+//!       which do not work in push-pull mode, and for USART TX in half-duplex mode
 //! - **Dynamic**: Pin mode is selected at runtime. See changing configurations for more details
 //! - Input
 //!     - **PullUp**: Input connected to high with a weak pull-up resistor. Will be high when nothing
@@ -34,6 +37,8 @@
 //!     - **PushPull**: Output which either drives the pin high or low
 //!     - **OpenDrain**: Output which leaves the gate floating, or pulls it to ground in drain
 //!       mode. Can be used as an input in the `open` configuration
+//! - **OpenDrainPullUp**: Pulls the line to ground or releases it as a pulled up input, for
+//!   buses like 1-Wire without an external pull-up resistor
 //! - **Debugger**: Some pins start out being used by the debugger. A pin in this mode can only be
 //!   used if the [JTAG peripheral has been turned off](#accessing-pa15-pb3-and-pb14).
 //!
@@ -53,13 +58,24 @@
 //! the risk of runtime errors.
 //!
 //! To make a pin dynamic, use the `into_dynamic` function, and then use the `make_<mode>` functions to
-//! change the mode
+//! change the mode. `erase_dynamic` additionally erases port and pin number, which allows
+//! to store pins of different ports in an array.
 //!
 //! ## Accessing PA15, PB3, and PB14
 //!
 //! These pins are used by the JTAG peripheral by default. To use them in your program, you need to
 //! disable that peripheral. This is done using the [afio::MAPR::disable_jtag](../afio/struct.MAPR.html#method.disable_jtag) function
 //!
+//! ## Locking pins
+//!
+//! The configuration of pins can be frozen until the next reset with `Pin::lock` or, for
+//! several pins of a port, with `PortWriter::lock_pins`. A port can only be locked once.
+//!
+//! ```rust
+//! let mut brake = gpiob.pb0.into_push_pull_output(&mut gpiob.crl);
+//! assert!(brake.lock());
+//! ```
+//!
 //! # Interfacing with v1 traits
 //!
 //! `embedded-hal` has two versions of the digital traits, `v2` which is used by this crate and
@@ -83,6 +99,10 @@ mod partially_erased;
 pub use partially_erased::{PEPin, PartiallyErasedPin};
 mod erased;
 pub use erased::{AnyPin, ErasedPin};
+mod dynamic;
+pub use dynamic::DynamicPin;
+mod debounce;
+pub use debounce::Debouncer;
 
 mod hal_02;
 mod hal_1;
@@ -90,12 +110,13 @@ mod hal_1;
 /// Slew rates available for Output and relevant AlternateMode Pins
 ///
 /// See Table 21 "Output MODE bits" in the reference
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum IOPinSpeed {
-    /// Slew at 10Mhz
+    /// Slew at 10Mhz, MODE bits `0b01`
     Mhz10 = 0b01, // (yes, this one is "less" then 2Mhz)
-    /// Slew at 2Mhz
+    /// Slew at 2Mhz, MODE bits `0b10`
     Mhz2 = 0b10,
-    /// Slew at 50Mhz
+    /// Slew at 50Mhz, MODE bits `0b11`
     Mhz50 = 0b11,
 }
 
@@ -190,6 +211,14 @@ pub struct PushPull;
 #[derive(Default)]
 pub struct OpenDrain;
 
+/// Open-drain output that is released as a pulled up input (type state)
+///
+/// See [`Pin::into_open_drain_pull_up`].
+#[derive(Default)]
+pub struct OpenDrainPullUp;
+
+impl Active for OpenDrainPullUp {}
+
 /// Analog mode (type state)
 #[derive(Default)]
 pub struct Analog;
@@ -235,11 +264,34 @@ impl<MODE> Interruptable for Input<MODE> {}
 impl Interruptable for Dynamic {}
 
 /// External Interrupt Pin
+///
+/// The EXTI line is the pin number, so pins with the same number on different ports share a line.
+///
+/// ```rust
+/// let mut button = gpioa.pa0.into_pull_up_input(&mut gpioa.crl);
+/// button.make_interrupt_source(&mut afio);
+/// button.trigger_on_edge(&mut dp.EXTI, Edge::Falling);
+/// button.enable_interrupt(&mut dp.EXTI);
+/// unsafe { pac::NVIC::unmask(pac::Interrupt::EXTI0) };
+///
+/// #[interrupt]
+/// fn EXTI0() {
+///     // `button` moved into a static beforehand
+///     if button.check_interrupt() {
+///         // Handle button press
+///
+///         // If we don't clear this bit, the ISR would trigger indefinitely
+///         button.clear_interrupt_pending_bit();
+///     }
+/// }
+/// ```
 pub trait ExtiPin {
     fn make_interrupt_source(&mut self, afio: &mut afio::Parts);
     fn trigger_on_edge(&mut self, exti: &mut EXTI, level: Edge);
     fn enable_interrupt(&mut self, exti: &mut EXTI);
     fn disable_interrupt(&mut self, exti: &mut EXTI);
+    fn enable_event(&mut self, exti: &mut EXTI);
+    fn disable_event(&mut self, exti: &mut EXTI);
     fn clear_interrupt_pending_bit(&mut self);
     fn check_interrupt(&self) -> bool;
 }
@@ -251,32 +303,7 @@ where
 {
     /// Make corresponding EXTI line sensitive to this pin
     fn make_interrupt_source(&mut self, afio: &mut afio::Parts) {
-        let pin_number = self.pin_id();
-        let port = self.port_id() as u32;
-        let offset = 4 * (pin_number % 4);
-        match pin_number {
-            0..=3 => {
-                afio.exticr1.exticr1().modify(|r, w| unsafe {
-                    w.bits((r.bits() & !(0xf << offset)) | (port << offset))
-                });
-            }
-            4..=7 => {
-                afio.exticr2.exticr2().modify(|r, w| unsafe {
-                    w.bits((r.bits() & !(0xf << offset)) | (port << offset))
-                });
-            }
-            8..=11 => {
-                afio.exticr3.exticr3().modify(|r, w| unsafe {
-                    w.bits((r.bits() & !(0xf << offset)) | (port << offset))
-                });
-            }
-            12..=15 => {
-                afio.exticr4.exticr4().modify(|r, w| unsafe {
-                    w.bits((r.bits() & !(0xf << offset)) | (port << offset))
-                });
-            }
-            _ => unreachable!(),
-        }
+        afio.configure_exti(self.pin_id(), afio::Port::from_id(self.port_id()));
     }
 
     /// Generate interrupt on rising edge, falling edge or both
@@ -316,6 +343,18 @@ where
             .modify(|r, w| unsafe { w.bits(r.bits() & !(1 << self.pin_id())) });
     }
 
+    /// Enable events from this pin, which wake up the core from `WFE`
+    fn enable_event(&mut self, exti: &mut EXTI) {
+        exti.emr()
+            .modify(|r, w| unsafe { w.bits(r.bits() | (1 << self.pin_id())) });
+    }
+
+    /// Disable events from this pin
+    fn disable_event(&mut self, exti: &mut EXTI) {
+        exti.emr()
+            .modify(|r, w| unsafe { w.bits(r.bits() & !(1 << self.pin_id())) });
+    }
+
     /// Clear the interrupt pending bit for this pin
     fn clear_interrupt_pending_bit(&mut self) {
         unsafe { (*EXTI::ptr()).pr().write(|w| w.bits(1 << self.pin_id())) };
@@ -328,6 +367,7 @@ where
 }
 
 /// Tracks the current pin state for dynamic pins
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Dynamic {
     InputFloating,
     InputPullUp,
@@ -375,7 +415,7 @@ macro_rules! gpio {
         pub mod $gpiox {
             use crate::pac::{$GPIOX, RCC};
             use crate::rcc::{Enable, Reset};
-            use super::{Active, Floating, GpioExt, Input, PartiallyErasedPin, ErasedPin, Pin, Cr};
+            use super::{Active, Floating, GpioExt, Input, PartiallyErasedPin, ErasedPin, Pin, Cr, PortWriter};
             #[allow(unused)]
             use super::Debugger;
 
@@ -385,6 +425,8 @@ macro_rules! gpio {
                 pub crl: Cr<$port_id, false>,
                 /// Opaque CRH register
                 pub crh: Cr<$port_id, true>,
+                /// Writer for several pins at once
+                pub writer: PortWriter<$port_id>,
                 $(
                     /// Pin
                     pub $pxi: $PXi $(<$MODE>)?,
@@ -406,6 +448,7 @@ macro_rules! gpio {
                     Parts {
                         crl: Cr::<$port_id, false>,
                         crh: Cr::<$port_id, true>,
+                        writer: PortWriter::<$port_id>,
                         $(
                             $pxi: $PXi::new(),
                         )+
@@ -419,6 +462,7 @@ macro_rules! gpio {
                     Parts {
                         crl: Cr::<$port_id, false>,
                         crh: Cr::<$port_id, true>,
+                        writer: PortWriter::<$port_id>,
                         $(
                             $pxi: $PXi::new(),
                         )+
@@ -427,11 +471,31 @@ macro_rules! gpio {
             }
 
             impl<MODE> PartiallyErasedPin<$port_id, MODE> {
+                /// Erases the port from the type
                 pub fn erase(self) -> ErasedPin<MODE> {
                     ErasedPin::$PXx(self)
                 }
             }
 
+            impl<MODE> From<PartiallyErasedPin<$port_id, MODE>> for ErasedPin<MODE> {
+                fn from(p: PartiallyErasedPin<$port_id, MODE>) -> Self {
+                    p.erase()
+                }
+            }
+
+            impl<MODE> TryFrom<ErasedPin<MODE>> for PartiallyErasedPin<$port_id, MODE> {
+                type Error = ErasedPin<MODE>;
+
+                /// Restores the port, returns the pin as error if it belongs to another port
+                fn try_from(p: ErasedPin<MODE>) -> Result<Self, Self::Error> {
+                    #[allow(unreachable_patterns)]
+                    match p {
+                        ErasedPin::$PXx(p) => Ok(p),
+                        p => Err(p),
+                    }
+                }
+            }
+
             impl<const N: u8, MODE> Pin<$port_id, N, MODE>
             where
                 MODE: Active,
@@ -522,7 +586,6 @@ impl<const P: char, const N: u8, MODE> Pin<P, N, MODE> {
       before changing its mode to an output to avoid
       a short spike of an incorrect value
     */
-
     #[inline(always)]
     fn _set_state(&mut self, state: PinState) {
         match state {
@@ -560,17 +623,73 @@ impl<const P: char, const N: u8, MODE> Pin<P, N, MODE> {
     }
 }
 
+impl<const P: char, const N: u8, MODE> Pin<P, N, MODE> {
+    /// Locks the configuration of this pin until the next reset, e.g. for a safety critical
+    /// output.
+    ///
+    /// This locks the whole port against further locking, use [`PortWriter::lock_pins`]
+    /// to lock several pins of a port. Returns `true` if the pin is locked afterwards.
+    pub fn lock(&mut self) -> bool {
+        lock::<P>(1 << N)
+    }
+
+    /// Returns `true` if the configuration of this pin is locked
+    pub fn is_locked(&self) -> bool {
+        // NOTE(unsafe) atomic read with no side effects
+        let gpio = unsafe { &(*gpiox::<P>()) };
+        gpio.lckr().read().bits() & (1 << 16 | 1 << N) == (1 << 16 | 1 << N)
+    }
+}
+
 impl<const P: char, const N: u8, MODE> Pin<P, N, MODE>
 where
     MODE: Active,
 {
     /// Erases the pin number from the type
+    ///
+    /// Pins of the same port and mode have the same type afterwards, e.g. to store a row
+    /// of LEDs in an array, and still use a single register access for each operation.
     #[inline]
     pub fn erase_number(self) -> PartiallyErasedPin<P, MODE> {
         PartiallyErasedPin::new(N)
     }
 }
 
+impl<const P: char, const N: u8, MODE> From<Pin<P, N, MODE>> for PartiallyErasedPin<P, MODE>
+where
+    MODE: Active,
+{
+    fn from(p: Pin<P, N, MODE>) -> Self {
+        p.erase_number()
+    }
+}
+
+impl<const P: char, const N: u8, MODE> From<Pin<P, N, MODE>> for ErasedPin<MODE>
+where
+    MODE: Active,
+    PartiallyErasedPin<P, MODE>: Into<ErasedPin<MODE>>,
+{
+    fn from(p: Pin<P, N, MODE>) -> Self {
+        p.erase_number().into()
+    }
+}
+
+impl<const P: char, const N: u8, MODE> TryFrom<PartiallyErasedPin<P, MODE>> for Pin<P, N, MODE>
+where
+    MODE: Active + Default,
+{
+    type Error = PartiallyErasedPin<P, MODE>;
+
+    /// Restores the pin number, returns the pin as error if it has another number
+    fn try_from(p: PartiallyErasedPin<P, MODE>) -> Result<Self, Self::Error> {
+        if p.pin_id() == N {
+            Ok(Pin::new())
+        } else {
+            Err(p)
+        }
+    }
+}
+
 impl<const P: char, const N: u8, MODE> Pin<P, N, Output<MODE>> {
     #[inline]
     pub fn set_high(&mut self) {
@@ -638,12 +757,184 @@ impl<const P: char, const N: u8> Pin<P, N, Output<OpenDrain>> {
     pub fn is_low(&self) -> bool {
         self._is_low()
     }
+
+    /// Releases the line, same as `set_high`
+    #[inline]
+    pub fn set_open(&mut self) {
+        self._set_high()
+    }
+
+    /// Pulls the line to ground, same as `set_low`
+    #[inline]
+    pub fn set_drive_low(&mut self) {
+        self._set_low()
+    }
+}
+
+impl<const P: char, const N: u8> Pin<P, N, OpenDrainPullUp>
+where
+    Self: HL,
+{
+    /// Releases the line by switching to a pulled up input.
+    ///
+    /// The output register is set first, which already releases the line, then the
+    /// CRL/CRH register is changed. The pull-up is active a few cycles after the call.
+    #[inline]
+    pub fn set_open(&mut self, cr: &mut <Self as HL>::Cr) {
+        self.mode::<Input<PullUp>>(cr);
+    }
+
+    /// Pulls the line to ground by switching to an open-drain output.
+    ///
+    /// The CRL/CRH register is changed first while the output is still released, so the
+    /// line goes low a few cycles after the call without a glitch.
+    #[inline]
+    pub fn set_drive_low(&mut self, cr: &mut <Self as HL>::Cr) {
+        self.mode::<Output<OpenDrain>>(cr);
+        self._set_low();
+    }
+
+    /// Borrows the CR register of the port for the embedded-hal output traits
+    #[inline]
+    pub fn with_cr(self, cr: &mut <Self as HL>::Cr) -> OpenDrainPullUpLine<'_, P, N> {
+        OpenDrainPullUpLine { pin: self, cr }
+    }
+}
+
+impl<const P: char, const N: u8> Pin<P, N, OpenDrainPullUp> {
+    /// Returns `true` if the line is released
+    #[inline]
+    pub fn is_open(&self) -> bool {
+        !self._is_set_low()
+    }
+
+    #[inline]
+    pub fn is_high(&self) -> bool {
+        !self._is_low()
+    }
+
+    #[inline]
+    pub fn is_low(&self) -> bool {
+        self._is_low()
+    }
+}
+
+/// [`OpenDrainPullUp`] pin together with the CR register of its port
+///
+/// Implements the embedded-hal output and input traits, which can't pass the CR register
+/// to [`set_open`](Pin::set_open) and [`set_drive_low`](Pin::set_drive_low). Holding the
+/// CR register prevents other pins of the port from changing their mode in between.
+pub struct OpenDrainPullUpLine<'a, const P: char, const N: u8>
+where
+    Pin<P, N, OpenDrainPullUp>: HL,
+{
+    pin: Pin<P, N, OpenDrainPullUp>,
+    cr: &'a mut <Pin<P, N, OpenDrainPullUp> as HL>::Cr,
+}
+
+impl<const P: char, const N: u8> OpenDrainPullUpLine<'_, P, N>
+where
+    Pin<P, N, OpenDrainPullUp>: HL,
+{
+    /// Releases the line, see [`Pin::set_open`]
+    #[inline]
+    pub fn set_open(&mut self) {
+        self.pin.set_open(self.cr);
+    }
+
+    /// Pulls the line to ground, see [`Pin::set_drive_low`]
+    #[inline]
+    pub fn set_drive_low(&mut self) {
+        self.pin.set_drive_low(self.cr);
+    }
+
+    /// Returns `true` if the line is released
+    #[inline]
+    pub fn is_open(&self) -> bool {
+        self.pin.is_open()
+    }
+
+    #[inline]
+    pub fn is_high(&self) -> bool {
+        self.pin.is_high()
+    }
+
+    #[inline]
+    pub fn is_low(&self) -> bool {
+        self.pin.is_low()
+    }
+
+    /// Returns the pin and ends the borrow of the CR register
+    #[inline]
+    pub fn release(self) -> Pin<P, N, OpenDrainPullUp> {
+        self.pin
+    }
 }
 
 /// Opaque CR register
 #[non_exhaustive]
 pub struct Cr<const P: char, const H: bool>;
 
+/// Writes the outputs of several pins of a port at once
+///
+/// Stays usable after the pins have been moved out of the port `Parts`.
+#[non_exhaustive]
+pub struct PortWriter<const P: char>;
+
+impl<const P: char> PortWriter<P> {
+    /// Sets the pins in `set_mask` high and the pins in `reset_mask` low with a single write
+    /// to the BSRR register, so all listed pins change simultaneously.
+    ///
+    /// Bit `n` of the masks corresponds to pin `n`. If a pin is in both masks, it is set high.
+    /// For pins in pull-up or pull-down input mode this changes the pull direction.
+    #[inline(always)]
+    pub fn write_pins(&mut self, set_mask: u16, reset_mask: u16) {
+        // NOTE(unsafe) atomic write to a stateless register
+        let gpio = unsafe { &(*gpiox::<P>()) };
+        gpio.bsrr()
+            .write(|w| unsafe { w.bits(((reset_mask as u32) << 16) | set_mask as u32) });
+    }
+
+    /// Reads the output data register of the port
+    #[inline(always)]
+    pub fn read_outputs(&self) -> u16 {
+        // NOTE(unsafe) atomic read with no side effects
+        let gpio = unsafe { &(*gpiox::<P>()) };
+        gpio.odr().read().bits() as u16
+    }
+
+    /// Locks the configuration of the pins in `mask` until the next reset.
+    ///
+    /// Bit `n` of the mask corresponds to pin `n`. The lock register of the port is locked
+    /// as well, so the port can only be locked once and all pins to lock have to be passed
+    /// at once. Changing the mode of a locked pin has no effect.
+    ///
+    /// Returns `true` if all pins in `mask` are locked afterwards.
+    pub fn lock_pins(&mut self, mask: u16) -> bool {
+        lock::<P>(mask)
+    }
+}
+
+/// Runs the lock key sequence on LCKR and checks that LCKK and the pins are set
+fn lock<const P: char>(mask: u16) -> bool {
+    const LCKK: u32 = 1 << 16;
+    // NOTE(unsafe) only the lock key sequence writes to LCKR
+    let gpio = unsafe { &(*gpiox::<P>()) };
+    let mask = mask as u32;
+
+    if gpio.lckr().read().bits() & LCKK == 0 {
+        cortex_m::interrupt::free(|_| {
+            gpio.lckr().write(|w| unsafe { w.bits(LCKK | mask) });
+            gpio.lckr().write(|w| unsafe { w.bits(mask) });
+            gpio.lckr().write(|w| unsafe { w.bits(LCKK | mask) });
+            // The first read returns 0, the second one 1 if the sequence was successful
+            gpio.lckr().read();
+        });
+    }
+    let lckr = gpio.lckr().read().bits();
+    lckr & LCKK != 0 && lckr & mask == mask
+}
+
 impl<const P: char, const N: u8, MODE> Pin<P, N, MODE>
 where
     MODE: Active,
@@ -661,6 +952,9 @@ where
 
     /// Configures the pin to operate as an alternate function open-drain output
     /// pin.
+    ///
+    /// This is the mode of I2C SCL and SDA pins and of the USART TX pin in
+    /// half-duplex mode. The I2C constructors set it for pins in any mode.
     #[inline]
     pub fn into_alternate_open_drain(
         self,
@@ -706,6 +1000,19 @@ where
         self.into_mode(cr)
     }
 
+    /// Configures the pin as an open-drain output that is released as a pulled up
+    /// input, e.g. for 1-Wire or DHT22 sensors without an external pull-up.
+    /// Initial state will be released.
+    ///
+    /// [`set_open`](Pin::set_open) and [`set_drive_low`](Pin::set_drive_low) reconfigure
+    /// the CRL/CRH register and take it as argument like the `into_<mode>` functions. Use
+    /// [`with_cr`](Pin::with_cr) for the embedded-hal traits. The internal pull-up of about 40 kΩ gives slow rising edges, use an external
+    /// pull-up for fast or long buses. See [`onewire`](crate::onewire) for a 1-Wire driver.
+    #[inline]
+    pub fn into_open_drain_pull_up(self, cr: &mut <Self as HL>::Cr) -> Pin<P, N, OpenDrainPullUp> {
+        self.into_mode(cr)
+    }
+
     /// Configures the pin to operate as an push-pull output pin.
     /// Initial state will be low.
     #[inline]
@@ -725,6 +1032,20 @@ where
         self.into_mode(cr)
     }
 
+    /// Configures the pin to operate as an push-pull output pin with the given slew rate.
+    /// Initial state will be low.
+    #[inline]
+    pub fn into_push_pull_output_with_speed(
+        mut self,
+        cr: &mut <Self as HL>::Cr,
+        speed: IOPinSpeed,
+    ) -> Pin<P, N, Output<PushPull>> {
+        self._set_state(PinState::Low);
+        self.mode::<Output<PushPull>>(cr);
+        self._set_speed(cr, speed);
+        Pin::new()
+    }
+
     /// Configures the pin to operate as an push-pull output pin.
     /// The state will not be changed.
     #[inline]
@@ -845,7 +1166,7 @@ where
             }
             8..=15 => {
                 gpio.crh()
-                    .modify(|_, w| unsafe { w.mode(N - 16).bits(speed as u8) });
+                    .modify(|_, w| unsafe { w.mode(N - 8).bits(speed as u8) });
             }
             _ => unreachable!(),
         }
@@ -872,6 +1193,24 @@ where
 
 // Dynamic pin
 
+impl<const P: char, const N: u8> Pin<P, N, Dynamic> {
+    /// Returns `true` if the pin is driven high, fails if the pin is not in an output mode
+    #[inline]
+    pub fn is_set_high(&self) -> Result<bool, PinModeError> {
+        self.is_set_low().map(|b| !b)
+    }
+
+    /// Returns `true` if the pin is driven low, fails if the pin is not in an output mode
+    #[inline]
+    pub fn is_set_low(&self) -> Result<bool, PinModeError> {
+        if self.mode.is_output() {
+            Ok(self._is_set_low())
+        } else {
+            Err(PinModeError::IncorrectMode)
+        }
+    }
+}
+
 impl<const P: char, const N: u8> Pin<P, N, Dynamic>
 where
     Self: HL,
@@ -934,6 +1273,12 @@ impl PinMode for Input<PullUp> {
     const PULL: Option<bool> = Some(true);
 }
 
+impl PinMode for OpenDrainPullUp {
+    const MODE: Mode = Mode::Input;
+    const CNF: Cnf = Cnf::AltPushPull;
+    const PULL: Option<bool> = Some(true);
+}
+
 impl PinMode for Output<PushPull> {
     const MODE: Mode = Mode::Output50;
     const CNF: Cnf = Cnf::PushPull;
@@ -959,6 +1304,20 @@ where
     Self: HL,
 {
     fn mode<MODE: PinMode>(&mut self, _cr: &mut <Self as HL>::Cr) {
+        self._mode::<MODE>()
+    }
+
+    #[inline]
+    pub(crate) fn into_mode<MODE: PinMode>(mut self, cr: &mut <Self as HL>::Cr) -> Pin<P, N, MODE> {
+        self.mode::<MODE>(cr);
+        Pin::new()
+    }
+}
+
+impl<const P: char, const N: u8, M> Pin<P, N, M> {
+    /// Changes the mode without exclusive access to the CR register,
+    /// callers have to prevent concurrent modifications
+    fn _mode<MODE: PinMode>(&mut self) {
         let gpio = unsafe { &(*gpiox::<P>()) };
 
         // Input<PullUp> or Input<PullDown> mode
@@ -979,18 +1338,34 @@ where
             }
             8..=15 => {
                 gpio.crh().modify(|_, w| unsafe {
-                    w.mode(N - 16).bits(MODE::MODE as u8);
-                    w.cnf(N - 16).bits(MODE::CNF as u8)
+                    w.mode(N - 8).bits(MODE::MODE as u8);
+                    w.cnf(N - 8).bits(MODE::CNF as u8)
                 });
             }
             _ => unreachable!(),
         }
     }
+}
 
-    #[inline]
-    pub(crate) fn into_mode<MODE: PinMode>(mut self, cr: &mut <Self as HL>::Cr) -> Pin<P, N, MODE> {
-        self.mode::<MODE>(cr);
-        Pin::new()
+impl<const P: char, const N: u8> Pin<P, N, Alternate<OpenDrain>>
+where
+    Self: HL,
+{
+    /// Temporarily configures the pin as a general purpose open-drain output
+    /// while `f` runs, e.g. to bit-bang a bus owned by a peripheral
+    ///
+    /// The output is released (set high) before the switch, so the line does
+    /// not glitch low. The alternate function mode is restored afterwards.
+    pub fn with_open_drain_output<R>(
+        &mut self,
+        cr: &mut <Self as HL>::Cr,
+        f: impl FnOnce(&mut Pin<P, N, Output<OpenDrain>>) -> R,
+    ) -> R {
+        self._set_high();
+        self.mode::<Output<OpenDrain>>(cr);
+        let ret = f(&mut Pin::new());
+        self.mode::<Alternate<OpenDrain>>(cr);
+        ret
     }
 }
 
@@ -0,0 +1,136 @@
+//! Debouncing of inputs like buttons
+
+use core::convert::Infallible;
+
+use embedded_hal::digital::InputPin;
+
+use super::{Edge, PinState};
+
+/// Debounces an input by sampling it periodically
+///
+/// A new level is only taken over after it was read by `samples` consecutive calls to
+/// [`Debouncer::update`], which has to be called periodically, e.g. every millisecond
+/// from a timer interrupt or the main loop. Mechanical buttons usually settle within
+/// 5 to 20 ms.
+///
+/// ```rust
+/// let button = gpioa.pa0.into_pull_up_input(&mut gpioa.crl);
+/// // The button connects the pin to ground
+/// let mut button = Debouncer::new(button, 10, PinState::Low);
+///
+/// loop {
+///     block!(timer.wait()).unwrap();
+///     if button.update() == Some(Edge::Falling) {
+///         led.toggle();
+///     }
+/// }
+/// ```
+pub struct Debouncer<PIN> {
+    pin: PIN,
+    samples: u8,
+    pressed: PinState,
+    state: bool,
+    count: u8,
+}
+
+impl<PIN> Debouncer<PIN>
+where
+    PIN: InputPin<Error = Infallible>,
+{
+    /// Creates a debouncer taking over a new level after `samples` equal reads
+    ///
+    /// `pressed` is the level of the pin while the button is pressed. The initial
+    /// debounced level is the current level of the pin.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `samples` is 0.
+    pub fn new(mut pin: PIN, samples: u8, pressed: PinState) -> Self {
+        assert!(samples > 0);
+        let state = pin.is_high().unwrap();
+        Self {
+            pin,
+            samples,
+            pressed,
+            state,
+            count: 0,
+        }
+    }
+
+    /// Samples the pin, returns the edge if the debounced level changed
+    ///
+    /// The edge is either [`Edge::Rising`] or [`Edge::Falling`].
+    pub fn update(&mut self) -> Option<Edge> {
+        if self.pin.is_high().unwrap() == self.state {
+            self.count = 0;
+            return None;
+        }
+        self.count += 1;
+        if self.count < self.samples {
+            return None;
+        }
+        self.count = 0;
+        self.state = !self.state;
+        Some(if self.state {
+            Edge::Rising
+        } else {
+            Edge::Falling
+        })
+    }
+
+    /// Debounced level of the pin
+    pub fn is_high(&self) -> bool {
+        self.state
+    }
+
+    /// Debounced level of the pin
+    pub fn is_low(&self) -> bool {
+        !self.state
+    }
+
+    /// Returns `true` if the debounced level is the pressed level
+    pub fn is_pressed(&self) -> bool {
+        self.state == (self.pressed == PinState::High)
+    }
+
+    /// Returns the pin
+    pub fn release(self) -> PIN {
+        self.pin
+    }
+}
+
+#[test]
+fn debouncer_bouncing() {
+    struct Samples<'a>(core::slice::Iter<'a, u8>);
+    impl embedded_hal::digital::ErrorType for Samples<'_> {
+        type Error = Infallible;
+    }
+    impl InputPin for Samples<'_> {
+        fn is_high(&mut self) -> Result<bool, Infallible> {
+            Ok(*self.0.next().unwrap() != 0)
+        }
+        fn is_low(&mut self) -> Result<bool, Infallible> {
+            self.is_high().map(|high| !high)
+        }
+    }
+
+    // Button to ground, bouncing when pressed and released
+    let levels = [1, 1, 0, 1, 0, 1, 0, 0, 0, 0, 0, 1, 0, 1, 1, 1, 1];
+    let pin = Samples(levels.iter());
+    let mut button = Debouncer::new(pin, 3, PinState::Low);
+    assert!(!button.is_pressed());
+
+    let mut edges = [None; 16];
+    let mut pressed = [false; 16];
+    for (edge, pressed) in edges.iter_mut().zip(&mut pressed) {
+        *edge = button.update();
+        *pressed = button.is_pressed();
+    }
+    let mut expected = [None; 16];
+    expected[7] = Some(Edge::Falling);
+    expected[14] = Some(Edge::Rising);
+    assert_eq!(edges, expected);
+    assert!(pressed[7..14].iter().all(|&p| p));
+    assert!(!pressed[..7].iter().any(|&p| p) && !pressed[14..].iter().any(|&p| p));
+    assert!(button.is_high());
+}
@@ -0,0 +1,249 @@
+use super::*;
+
+/// Fully erased pin with a mode that is selected at runtime
+///
+/// Created from a [`Dynamic`] pin with [`Pin::erase_dynamic`]. Like the `into_<mode>`
+/// functions, mode changes take the CR register of the port, which is only known at
+/// runtime and checked against the pin.
+pub struct DynamicPin {
+    port_id: u8,
+    pin_number: u8,
+    mode: Dynamic,
+}
+
+impl PinExt for DynamicPin {
+    type Mode = Dynamic;
+
+    #[inline(always)]
+    fn pin_id(&self) -> u8 {
+        self.pin_number
+    }
+
+    #[inline(always)]
+    fn port_id(&self) -> u8 {
+        self.port_id
+    }
+}
+
+impl<const P: char, const N: u8> Pin<P, N, Dynamic> {
+    /// Erases the pin number and the port from the type, keeping track of the mode at runtime
+    #[inline]
+    pub fn erase_dynamic(self) -> DynamicPin {
+        DynamicPin {
+            port_id: P as u8 - b'A',
+            pin_number: N,
+            mode: self.mode,
+        }
+    }
+}
+
+impl<const P: char, const N: u8> From<Pin<P, N, Dynamic>> for DynamicPin {
+    fn from(pin: Pin<P, N, Dynamic>) -> Self {
+        pin.erase_dynamic()
+    }
+}
+
+impl DynamicPin {
+    #[inline(always)]
+    fn block(&self) -> &crate::pac::gpioa::RegisterBlock {
+        // NOTE(unsafe) we only access registers of our own pin
+        let ptr = match self.port_id {
+            0 => gpiox::<'A'>(),
+            1 => gpiox::<'B'>(),
+            2 => gpiox::<'C'>(),
+            3 => gpiox::<'D'>(),
+            4 => gpiox::<'E'>(),
+            #[cfg(any(feature = "xl", feature = "high"))]
+            5 => gpiox::<'F'>(),
+            #[cfg(any(feature = "xl", feature = "high"))]
+            6 => gpiox::<'G'>(),
+            _ => unreachable!(),
+        };
+        unsafe { &*ptr }
+    }
+
+    fn configure<MODE: PinMode, const P: char, const H: bool>(
+        &mut self,
+        _cr: &mut Cr<P, H>,
+        mode: Dynamic,
+    ) {
+        assert!(
+            self.port_id == P as u8 - b'A' && (self.pin_number >= 8) == H,
+            "CR register of another port or pin range"
+        );
+        let gpio = self.block();
+        let n = self.pin_number;
+
+        // Input<PullUp> or Input<PullDown> mode
+        if let Some(pull) = MODE::PULL {
+            gpio.bsrr().write(|w| {
+                if pull {
+                    w.bs(n).set_bit()
+                } else {
+                    w.br(n).set_bit()
+                }
+            })
+        }
+
+        match n {
+            0..=7 => {
+                gpio.crl()
+                    .modify(|_, w| w.mode(n).variant(MODE::MODE).cnf(n).variant(MODE::CNF));
+            }
+            8..=15 => {
+                gpio.crh().modify(|_, w| unsafe {
+                    w.mode(n - 8).bits(MODE::MODE as u8);
+                    w.cnf(n - 8).bits(MODE::CNF as u8)
+                });
+            }
+            _ => unreachable!(),
+        }
+
+        self.mode = mode;
+    }
+
+    /// Returns the current mode of the pin
+    #[inline]
+    pub fn mode(&self) -> &Dynamic {
+        &self.mode
+    }
+    /// Switches to a pulled up input, `cr` is `crl` of the port for pins 0 to 7 and `crh` otherwise
+    ///
+    /// # Panics
+    ///
+    /// Panics if `cr` belongs to another port or pin range
+    #[inline]
+    pub fn make_pull_up_input<const P: char, const H: bool>(&mut self, cr: &mut Cr<P, H>) {
+        self.configure::<Input<PullUp>, P, H>(cr, Dynamic::InputPullUp);
+    }
+
+    /// Switches to a pulled down input, `cr` is `crl` of the port for pins 0 to 7 and `crh` otherwise
+    ///
+    /// # Panics
+    ///
+    /// Panics if `cr` belongs to another port or pin range
+    #[inline]
+    pub fn make_pull_down_input<const P: char, const H: bool>(&mut self, cr: &mut Cr<P, H>) {
+        self.configure::<Input<PullDown>, P, H>(cr, Dynamic::InputPullDown);
+    }
+
+    /// Switches to a floating input, `cr` is `crl` of the port for pins 0 to 7 and `crh` otherwise
+    ///
+    /// # Panics
+    ///
+    /// Panics if `cr` belongs to another port or pin range
+    #[inline]
+    pub fn make_floating_input<const P: char, const H: bool>(&mut self, cr: &mut Cr<P, H>) {
+        self.configure::<Input<Floating>, P, H>(cr, Dynamic::InputFloating);
+    }
+
+    /// Switches to a push-pull output, `cr` is `crl` of the port for pins 0 to 7 and `crh` otherwise
+    ///
+    /// # Panics
+    ///
+    /// Panics if `cr` belongs to another port or pin range
+    #[inline]
+    pub fn make_push_pull_output<const P: char, const H: bool>(&mut self, cr: &mut Cr<P, H>) {
+        self.configure::<Output<PushPull>, P, H>(cr, Dynamic::OutputPushPull);
+    }
+
+    /// Switches to an open-drain output, `cr` is `crl` of the port for pins 0 to 7 and `crh` otherwise
+    ///
+    /// # Panics
+    ///
+    /// Panics if `cr` belongs to another port or pin range
+    #[inline]
+    pub fn make_open_drain_output<const P: char, const H: bool>(&mut self, cr: &mut Cr<P, H>) {
+        self.configure::<Output<OpenDrain>, P, H>(cr, Dynamic::OutputOpenDrain);
+    }
+
+    /// Drives the pin high, fails if the pin is not in an output mode
+    #[inline]
+    pub fn set_high(&mut self) -> Result<(), PinModeError> {
+        self.set_state(PinState::High)
+    }
+
+    /// Drives the pin low, fails if the pin is not in an output mode
+    #[inline]
+    pub fn set_low(&mut self) -> Result<(), PinModeError> {
+        self.set_state(PinState::Low)
+    }
+
+    /// Drives the pin to `state`, fails if the pin is not in an output mode
+    pub fn set_state(&mut self, state: PinState) -> Result<(), PinModeError> {
+        if !self.mode.is_output() {
+            return Err(PinModeError::IncorrectMode);
+        }
+        // NOTE(unsafe) atomic write to a stateless register
+        let n = self.pin_number;
+        self.block().bsrr().write(|w| match state {
+            PinState::High => w.bs(n).set_bit(),
+            PinState::Low => w.br(n).set_bit(),
+        });
+        Ok(())
+    }
+
+    /// Returns `true` if the pin is driven high, fails if the pin is not in an output mode
+    #[inline]
+    pub fn is_set_high(&self) -> Result<bool, PinModeError> {
+        self.is_set_low().map(|b| !b)
+    }
+
+    /// Returns `true` if the pin is driven low, fails if the pin is not in an output mode
+    pub fn is_set_low(&self) -> Result<bool, PinModeError> {
+        if !self.mode.is_output() {
+            return Err(PinModeError::IncorrectMode);
+        }
+        // NOTE(unsafe) atomic read with no side effects
+        Ok(self
+            .block()
+            .odr()
+            .read()
+            .odr(self.pin_number)
+            .bit_is_clear())
+    }
+
+    /// Toggles the pin output, fails if the pin is not in an output mode
+    pub fn toggle(&mut self) -> Result<(), PinModeError> {
+        if self.is_set_low()? {
+            self.set_high()
+        } else {
+            self.set_low()
+        }
+    }
+
+    /// Reads the pin state.
+    ///
+    /// In push-pull output mode this returns the output data register,
+    /// otherwise the input data register.
+    #[inline]
+    pub fn is_high(&self) -> Result<bool, PinModeError> {
+        self.is_low().map(|b| !b)
+    }
+
+    /// Reads the pin state.
+    ///
+    /// In push-pull output mode this returns the output data register,
+    /// otherwise the input data register.
+    pub fn is_low(&self) -> Result<bool, PinModeError> {
+        let gpio = self.block();
+        // NOTE(unsafe) atomic read with no side effects
+        Ok(match self.mode {
+            Dynamic::OutputPushPull => gpio.odr().read().odr(self.pin_number).bit_is_clear(),
+            _ => gpio.idr().read().idr(self.pin_number).bit_is_clear(),
+        })
+    }
+}
+
+#[test]
+fn dynamic_pin_output_state_needs_output_mode() {
+    // Fails before any register access
+    let mut pin = DynamicPin {
+        port_id: 0,
+        pin_number: 5,
+        mode: Dynamic::InputPullUp,
+    };
+    assert_eq!(pin.toggle(), Err(PinModeError::IncorrectMode));
+    assert_eq!(pin.is_set_high(), Err(PinModeError::IncorrectMode));
+    assert_eq!(pin.set_high(), Err(PinModeError::IncorrectMode));
+}
@@ -23,6 +23,15 @@ impl<const P: char, const N: u8> OutputPin for Pin<P, N, Dynamic> {
     }
 }
 
+impl<const P: char, const N: u8> StatefulOutputPin for Pin<P, N, Dynamic> {
+    fn is_set_high(&self) -> Result<bool, Self::Error> {
+        self.is_set_high()
+    }
+    fn is_set_low(&self) -> Result<bool, Self::Error> {
+        self.is_set_low()
+    }
+}
+
 impl<const P: char, const N: u8> InputPin for Pin<P, N, Dynamic> {
     type Error = PinModeError;
     fn is_high(&self) -> Result<bool, Self::Error> {
@@ -98,6 +107,66 @@ impl<const P: char, const N: u8> InputPin for Pin<P, N, Output<OpenDrain>> {
     }
 }
 
+impl<const P: char, const N: u8> OutputPin for OpenDrainPullUpLine<'_, P, N>
+where
+    Pin<P, N, OpenDrainPullUp>: HL,
+{
+    type Error = Infallible;
+    #[inline]
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.set_open();
+        Ok(())
+    }
+    #[inline]
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.set_drive_low();
+        Ok(())
+    }
+}
+
+impl<const P: char, const N: u8> StatefulOutputPin for OpenDrainPullUpLine<'_, P, N>
+where
+    Pin<P, N, OpenDrainPullUp>: HL,
+{
+    #[inline]
+    fn is_set_high(&self) -> Result<bool, Self::Error> {
+        Ok(self.is_open())
+    }
+    #[inline]
+    fn is_set_low(&self) -> Result<bool, Self::Error> {
+        Ok(!self.is_open())
+    }
+}
+
+impl<const P: char, const N: u8> InputPin for OpenDrainPullUpLine<'_, P, N>
+where
+    Pin<P, N, OpenDrainPullUp>: HL,
+{
+    type Error = Infallible;
+    #[inline]
+    fn is_high(&self) -> Result<bool, Self::Error> {
+        Ok(self.is_high())
+    }
+
+    #[inline]
+    fn is_low(&self) -> Result<bool, Self::Error> {
+        Ok(self.is_low())
+    }
+}
+
+impl<const P: char, const N: u8> InputPin for Pin<P, N, OpenDrainPullUp> {
+    type Error = Infallible;
+    #[inline]
+    fn is_high(&self) -> Result<bool, Self::Error> {
+        Ok(self.is_high())
+    }
+
+    #[inline]
+    fn is_low(&self) -> Result<bool, Self::Error> {
+        Ok(self.is_low())
+    }
+}
+
 // PartiallyErasedPin
 
 impl<const P: char, MODE> OutputPin for PartiallyErasedPin<P, Output<MODE>> {
@@ -191,6 +260,15 @@ impl<MODE> StatefulOutputPin for ErasedPin<Output<MODE>> {
     }
 }
 
+impl<MODE> ToggleableOutputPin for ErasedPin<Output<MODE>> {
+    type Error = Infallible;
+
+    fn toggle(&mut self) -> Result<(), Infallible> {
+        self.toggle();
+        Ok(())
+    }
+}
+
 impl<MODE> InputPin for ErasedPin<Input<MODE>> {
     type Error = Infallible;
     fn is_high(&self) -> Result<bool, Infallible> {
@@ -212,3 +290,54 @@ impl InputPin for ErasedPin<Output<OpenDrain>> {
         Ok(self.is_low())
     }
 }
+
+// DynamicPin
+
+impl OutputPin for DynamicPin {
+    type Error = PinModeError;
+
+    #[inline(always)]
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.set_high()
+    }
+
+    #[inline(always)]
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.set_low()
+    }
+}
+
+impl StatefulOutputPin for DynamicPin {
+    #[inline(always)]
+    fn is_set_high(&self) -> Result<bool, Self::Error> {
+        self.is_set_high()
+    }
+
+    #[inline(always)]
+    fn is_set_low(&self) -> Result<bool, Self::Error> {
+        self.is_set_low()
+    }
+}
+
+impl ToggleableOutputPin for DynamicPin {
+    type Error = PinModeError;
+
+    #[inline(always)]
+    fn toggle(&mut self) -> Result<(), Self::Error> {
+        self.toggle()
+    }
+}
+
+impl InputPin for DynamicPin {
+    type Error = PinModeError;
+
+    #[inline(always)]
+    fn is_high(&self) -> Result<bool, Self::Error> {
+        self.is_high()
+    }
+
+    #[inline(always)]
+    fn is_low(&self) -> Result<bool, Self::Error> {
+        self.is_low()
+    }
+}
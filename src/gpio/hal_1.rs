@@ -1,6 +1,9 @@
 use core::convert::Infallible;
 
-use super::{Dynamic, ErasedPin, Input, OpenDrain, Output, PartiallyErasedPin, Pin, PinModeError};
+use super::{
+    Dynamic, DynamicPin, ErasedPin, Input, OpenDrain, OpenDrainPullUp, OpenDrainPullUpLine, Output,
+    PartiallyErasedPin, Pin, PinModeError, HL,
+};
 
 pub use embedded_hal::digital::PinState;
 use embedded_hal::digital::{ErrorType, InputPin, OutputPin, StatefulOutputPin};
@@ -28,6 +31,17 @@ impl embedded_hal::digital::Error for PinModeError {
     }
 }
 
+impl<const P: char, const N: u8> ErrorType for OpenDrainPullUpLine<'_, P, N>
+where
+    Pin<P, N, OpenDrainPullUp>: HL,
+{
+    type Error = Infallible;
+}
+
+impl<const P: char, const N: u8> ErrorType for Pin<P, N, OpenDrainPullUp> {
+    type Error = Infallible;
+}
+
 impl<const P: char, const N: u8> ErrorType for Pin<P, N, Dynamic> {
     type Error = PinModeError;
 }
@@ -51,6 +65,17 @@ impl<const P: char, const N: u8> OutputPin for Pin<P, N, Dynamic> {
     }
 }
 
+impl<const P: char, const N: u8> StatefulOutputPin for Pin<P, N, Dynamic> {
+    #[inline]
+    fn is_set_high(&mut self) -> Result<bool, Self::Error> {
+        (*self).is_set_high()
+    }
+    #[inline]
+    fn is_set_low(&mut self) -> Result<bool, Self::Error> {
+        (*self).is_set_low()
+    }
+}
+
 impl<const P: char, const N: u8> InputPin for Pin<P, N, Dynamic> {
     fn is_high(&mut self) -> Result<bool, Self::Error> {
         self.is_low().map(|b| !b)
@@ -112,6 +137,63 @@ impl<const P: char, const N: u8> InputPin for Pin<P, N, Output<OpenDrain>> {
     }
 }
 
+impl<const P: char, const N: u8> OutputPin for OpenDrainPullUpLine<'_, P, N>
+where
+    Pin<P, N, OpenDrainPullUp>: HL,
+{
+    #[inline]
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.set_open();
+        Ok(())
+    }
+    #[inline]
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.set_drive_low();
+        Ok(())
+    }
+}
+
+impl<const P: char, const N: u8> StatefulOutputPin for OpenDrainPullUpLine<'_, P, N>
+where
+    Pin<P, N, OpenDrainPullUp>: HL,
+{
+    #[inline]
+    fn is_set_high(&mut self) -> Result<bool, Self::Error> {
+        Ok(self.is_open())
+    }
+    #[inline]
+    fn is_set_low(&mut self) -> Result<bool, Self::Error> {
+        Ok(!self.is_open())
+    }
+}
+
+impl<const P: char, const N: u8> InputPin for OpenDrainPullUpLine<'_, P, N>
+where
+    Pin<P, N, OpenDrainPullUp>: HL,
+{
+    #[inline]
+    fn is_high(&mut self) -> Result<bool, Self::Error> {
+        Ok((*self).is_high())
+    }
+
+    #[inline]
+    fn is_low(&mut self) -> Result<bool, Self::Error> {
+        Ok((*self).is_low())
+    }
+}
+
+impl<const P: char, const N: u8> InputPin for Pin<P, N, OpenDrainPullUp> {
+    #[inline]
+    fn is_high(&mut self) -> Result<bool, Self::Error> {
+        Ok((*self).is_high())
+    }
+
+    #[inline]
+    fn is_low(&mut self) -> Result<bool, Self::Error> {
+        Ok((*self).is_low())
+    }
+}
+
 // PartiallyErasedPin
 
 impl<const P: char, MODE> ErrorType for PartiallyErasedPin<P, MODE> {
@@ -215,3 +297,49 @@ impl InputPin for ErasedPin<Output<OpenDrain>> {
         Ok((*self).is_low())
     }
 }
+
+// Implementations for `DynamicPin`
+impl ErrorType for DynamicPin {
+    type Error = PinModeError;
+}
+
+impl OutputPin for DynamicPin {
+    #[inline(always)]
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.set_high()
+    }
+
+    #[inline(always)]
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.set_low()
+    }
+}
+
+impl StatefulOutputPin for DynamicPin {
+    #[inline(always)]
+    fn is_set_high(&mut self) -> Result<bool, Self::Error> {
+        (*self).is_set_high()
+    }
+
+    #[inline(always)]
+    fn is_set_low(&mut self) -> Result<bool, Self::Error> {
+        (*self).is_set_low()
+    }
+
+    #[inline(always)]
+    fn toggle(&mut self) -> Result<(), Self::Error> {
+        self.toggle()
+    }
+}
+
+impl InputPin for DynamicPin {
+    #[inline(always)]
+    fn is_high(&mut self) -> Result<bool, Self::Error> {
+        (*self).is_high()
+    }
+
+    #[inline(always)]
+    fn is_low(&mut self) -> Result<bool, Self::Error> {
+        (*self).is_low()
+    }
+}
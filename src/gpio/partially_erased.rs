@@ -6,6 +6,20 @@ pub type PEPin<const P: char, MODE> = PartiallyErasedPin<P, MODE>;
 ///
 /// - `MODE` is one of the pin modes (see [Modes](crate::gpio#modes) section).
 /// - `P` is port name: `A` for GPIOA, `B` for GPIOB, etc.
+///
+/// Only the pin number is stored at runtime, so pins of one port can be kept in an
+/// array. Created with [`Pin::erase_number`] or `From`, the pin number is restored
+/// with `TryFrom`.
+///
+/// ```rust
+/// let mut leds: [PEPin<'B', Output>; 2] = [
+///     gpiob.pb12.into_push_pull_output(&mut gpiob.crh).erase_number(),
+///     gpiob.pb13.into_push_pull_output(&mut gpiob.crh).into(),
+/// ];
+/// for led in &mut leds {
+///     led.toggle();
+/// }
+/// ```
 pub struct PartiallyErasedPin<const P: char, MODE> {
     pin_number: u8,
     _mode: PhantomData<MODE>,
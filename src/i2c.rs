@@ -1,21 +1,63 @@
 //! Inter-Integrated Circuit (I2C) bus
+//!
+//! ## Alternate function remapping
+//!
+//! SCL, SDA: Alternate Open-Drain Output. Pins in any other mode, including alternate
+//! push-pull, are converted automatically.
+//!
+//! Pass the pins together with `&mut afio.mapr` to select the remap of I2C1.
+//!
+//! ### I2C1
+//!
+//! | Function | NoRemap | Remap |
+//! |----------|---------|-------|
+//! | SCL      | PB6     | PB8   |
+//! | SDA      | PB7     | PB9   |
+//!
+//! ### I2C2
+//!
+//! | Function | NoRemap |
+//! |----------|---------|
+//! | SCL      | PB10    |
+//! | SDA      | PB11    |
+//!
+//! ## DMA
+//!
+//! `BlockingI2c::with_tx_dma` and `BlockingI2c::with_rx_dma` move the data bytes
+//! of a master transfer by DMA, while the address phase is handled by the CPU
+//! within the start and address timeouts of the `BlockingI2c`. Waiting for the
+//! returned `Transfer` generates the STOP condition. `Transfer::wait` only
+//! watches the DMA channel, so prefer `Transfer::wait_checked`, which also
+//! returns NACKs, bus errors and a data timeout.
+//!
+//! | Peripheral | TX         | RX         |
+//! |------------|------------|------------|
+//! | I2C1       | DMA1 Ch. 6 | DMA1 Ch. 7 |
+//! | I2C2       | DMA1 Ch. 4 | DMA1 Ch. 5 |
 
 // This document describes a correct i2c implementation and is what
 // parts of this code is based on
 // https://www.st.com/content/ccc/resource/technical/document/application_note/5d/ae/a3/6f/08/69/4e/9b/CD00209826.pdf/files/CD00209826.pdf/jcr:content/translations/en.CD00209826.pdf
 
 use crate::afio::{Remap, MAPR};
-use crate::gpio::{self, Alternate, Cr, OpenDrain};
+use crate::gpio::{self, Alternate, Cr, OpenDrain, Output};
 use crate::pac::{self, DWT, RCC};
 use crate::rcc::{BusClock, Clocks, Enable, Reset};
 use crate::time::{kHz, Hertz};
 use core::ops::Deref;
+use cortex_m::asm;
 
+#[macro_use]
 pub mod blocking;
 pub use blocking::BlockingI2c;
 
+mod dma;
 mod hal_02;
 mod hal_1;
+mod slave;
+
+pub use dma::{I2c1RxDma, I2c1TxDma, I2c2RxDma, I2c2TxDma};
+pub use slave::{Direction, I2cSlave, SlaveEvent};
 
 pub use embedded_hal::i2c::NoAcknowledgeSource;
 
@@ -32,10 +74,28 @@ pub enum Error {
     /// Overrun/underrun
     Overrun,
     // Pec, // SMBUS mode only
+    /// Timeout waiting for the bus, see [`BlockingI2c::with_timeout`]
     Timeout,
+    /// A DMA transfer was started with an empty buffer
+    EmptyBuffer,
     // Alert, // SMBUS mode only
 }
 
+/// I2C device address
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Address {
+    /// 7-bit address
+    SevenBit(u8),
+    /// 10-bit address, sent as a two byte header `0b11110xx0`
+    TenBit(u16),
+}
+
+impl From<u8> for Address {
+    fn from(addr: u8) -> Self {
+        Self::SevenBit(addr)
+    }
+}
+
 #[derive(Debug, Eq, PartialEq)]
 pub enum DutyCycle {
     Ratio2to1,
@@ -136,15 +196,9 @@ macro_rules! remap {
         }
 
         $(
-            impl From<(gpio::$SCL<Alternate<OpenDrain>>, gpio::$SDA<Alternate<OpenDrain>> $(, &mut $MAPR)?)> for Pins<Scl, Sda> {
-                fn from(p: (gpio::$SCL<Alternate<OpenDrain>>, gpio::$SDA<Alternate<OpenDrain>> $(, &mut $MAPR)?)) -> Self {
-                    $(<$PER>::remap(p.2, $remap);)?
-                    Self { scl: Scl::$SCL(p.0), sda: Sda::$SDA(p.1) }
-                }
-            }
-
-            impl From<(gpio::$SCL, gpio::$SDA $(, &mut $MAPR)?)> for Pins<Scl, Sda> {
-                fn from(p: (gpio::$SCL, gpio::$SDA $(, &mut $MAPR)?)) -> Self {
+            impl<SCLMODE, SDAMODE> From<(gpio::$SCL<SCLMODE>, gpio::$SDA<SDAMODE> $(, &mut $MAPR)?)> for Pins<Scl, Sda> {
+                fn from(p: (gpio::$SCL<SCLMODE>, gpio::$SDA<SDAMODE> $(, &mut $MAPR)?)) -> Self {
+                    // I2C does not work with push-pull outputs
                     let mut cr = Cr;
                     let scl = p.0.into_mode(&mut cr);
                     let sda = p.1.into_mode(&mut cr);
@@ -153,10 +207,54 @@ macro_rules! remap {
                 }
             }
         )+
+
+        pub(crate) fn recover_bus(pins: &mut (Scl, Sda), half_period: u32) -> bool {
+            #[allow(unreachable_patterns)]
+            match pins {
+                $(
+                    (Scl::$SCL(scl), Sda::$SDA(sda)) => scl.with_open_drain_output(&mut Cr, |scl| {
+                        sda.with_open_drain_output(&mut Cr, |sda| clock_out(scl, sda, half_period))
+                    }),
+                )+
+                _ => unreachable!(),
+            }
+        }
     }
 }
 use remap;
 
+/// Clocks SCL until the device holding SDA low releases it, then generates a STOP
+///
+/// Returns `false` if SDA is still held low afterwards.
+fn clock_out<const P1: char, const N1: u8, const P2: char, const N2: u8>(
+    scl: &mut gpio::Pin<P1, N1, Output<OpenDrain>>,
+    sda: &mut gpio::Pin<P2, N2, Output<OpenDrain>>,
+    half_period: u32,
+) -> bool {
+    // a device in the middle of sending a byte releases SDA after at most 9 clocks
+    for _ in 0..9 {
+        if sda.is_high() {
+            break;
+        }
+        scl.set_low();
+        asm::delay(half_period);
+        scl.set_high();
+        asm::delay(half_period);
+    }
+
+    // STOP: SDA rises while SCL is high
+    scl.set_low();
+    asm::delay(half_period);
+    sda.set_low();
+    asm::delay(half_period);
+    scl.set_high();
+    asm::delay(half_period);
+    sda.set_high();
+    asm::delay(half_period);
+
+    sda.is_high()
+}
+
 pub trait I2cExt: Sized + Instance {
     fn i2c(
         self,
@@ -203,6 +301,7 @@ pub struct I2c<I2C: Instance> {
     pins: (I2C::Scl, I2C::Sda),
     mode: Mode,
     pclk1: Hertz,
+    sysclk: Hertz,
 }
 
 pub trait Instance:
@@ -210,15 +309,26 @@ pub trait Instance:
 {
     type Scl;
     type Sda;
+
+    #[doc(hidden)]
+    fn recover_bus(pins: &mut (Self::Scl, Self::Sda), half_period: u32) -> bool;
 }
 
 impl Instance for pac::I2C1 {
     type Scl = i2c1::Scl;
     type Sda = i2c1::Sda;
+
+    fn recover_bus(pins: &mut (Self::Scl, Self::Sda), half_period: u32) -> bool {
+        i2c1::recover_bus(pins, half_period)
+    }
 }
 impl Instance for pac::I2C2 {
     type Scl = i2c2::Scl;
     type Sda = i2c2::Sda;
+
+    fn recover_bus(pins: &mut (Self::Scl, Self::Sda), half_period: u32) -> bool {
+        i2c2::recover_bus(pins, half_period)
+    }
 }
 
 impl<I2C: Instance> I2c<I2C> {
@@ -244,6 +354,7 @@ impl<I2C: Instance> I2c<I2C> {
             pins: (pins.scl, pins.sda),
             mode,
             pclk1,
+            sysclk: clocks.sysclk(),
         };
         i2c.init();
         i2c
@@ -315,6 +426,30 @@ impl<I2C: Instance> I2c<I2C> {
         self.i2c.cr1().modify(|_, w| w.stop().set_bit());
     }
 
+    /// Frees a bus where a device holds SDA low, e.g. after a reset in the middle of a transfer
+    ///
+    /// The pins stay owned by the peripheral: they are switched to general purpose
+    /// open-drain outputs in place, SCL is clocked up to 9 times until SDA is released,
+    /// a STOP is generated and the pins are switched back to their alternate function.
+    /// Finally the peripheral is reset and re-initialized.
+    ///
+    /// Call this after a transfer failed with [`Error::Timeout`] or [`Error::Bus`].
+    /// Returns [`Error::Bus`] if SDA is still held low.
+    pub fn recover_bus(&mut self) -> Result<(), Error> {
+        // SCL half period in core cycles
+        let half_period = self.sysclk.raw() / self.mode.get_frequency().raw() / 2;
+
+        self.i2c.cr1().modify(|_, w| w.pe().clear_bit());
+        let released = I2C::recover_bus(&mut self.pins, half_period);
+        self.reset();
+
+        if released {
+            Ok(())
+        } else {
+            Err(Error::Bus)
+        }
+    }
+
     /// Releases the I2C peripheral and associated pins
     pub fn release(self) -> (I2C, (I2C::Scl, I2C::Sda)) {
         (self.i2c, self.pins)
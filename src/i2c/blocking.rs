@@ -1,20 +1,21 @@
 use super::*;
+use embedded_hal::i2c::Operation;
 
 /// embedded-hal compatible blocking I2C implementation
 ///
 /// **NOTE**: Before using blocking I2C, you need to enable the DWT cycle counter using the
 /// [DWT::enable_cycle_counter] method.
 pub struct BlockingI2c<I2C: Instance> {
-    nb: I2c<I2C>,
+    pub(super) nb: I2c<I2C>,
     start_retries: u8,
-    timeouts: DwtTimeouts,
+    pub(super) timeouts: DwtTimeouts,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct DwtTimeouts {
-    start: u32,
-    addr: u32,
-    data: u32,
+    pub(super) start: u32,
+    pub(super) addr: u32,
+    pub(super) data: u32,
 }
 
 impl<I2C: Instance> BlockingI2c<I2C> {
@@ -38,6 +39,23 @@ impl<I2C: Instance> BlockingI2c<I2C> {
             clocks,
         )
     }
+
+    /// Frees a bus where a device holds SDA low, see [`I2c::recover_bus`]
+    pub fn recover_bus(&mut self) -> Result<(), Error> {
+        self.nb.recover_bus()
+    }
+
+    /// Sets the start, address and data timeouts to `cycles` system clock cycles
+    ///
+    /// Every wait for a status flag returns [`Error::Timeout`] once it took longer.
+    pub fn with_timeout(mut self, cycles: u32) -> Self {
+        self.timeouts = DwtTimeouts {
+            start: cycles,
+            addr: cycles,
+            data: cycles,
+        };
+        self
+    }
 }
 
 impl<I2C: Instance> I2c<I2C> {
@@ -163,35 +181,75 @@ impl<I2C: Instance> BlockingI2c<I2C> {
         last_ret
     }
 
-    fn send_addr_and_wait(&mut self, addr: u8, read: bool) -> Result<(), Error> {
-        self.nb.i2c.sr1().read();
-        self.nb.send_addr(addr, read);
-        let ret = busy_wait_cycles!(
-            wait_for_flag!(self.nb.i2c, addr, Address),
-            self.timeouts.addr
-        );
+    fn send_addr_and_wait(&mut self, addr: Address, read: bool) -> Result<(), Error> {
+        let ret = match addr {
+            Address::SevenBit(addr) => {
+                self.nb.i2c.sr1().read();
+                self.nb.send_addr(addr, read);
+                busy_wait_cycles!(
+                    wait_for_flag!(self.nb.i2c, addr, Address),
+                    self.timeouts.addr
+                )
+            }
+            Address::TenBit(addr) => self.send_ten_bit_addr_and_wait(addr, read),
+        };
         if let Err(Error::NoAcknowledge(_)) = ret {
             self.nb.send_stop();
         }
         ret
     }
 
-    fn write_bytes_and_wait(&mut self, bytes: &[u8]) -> Result<(), Error> {
+    fn send_ten_bit_addr_and_wait(&mut self, addr: u16, read: bool) -> Result<(), Error> {
+        let header = ten_bit_header(addr);
+
         self.nb.i2c.sr1().read();
-        self.nb.i2c.sr2().read();
+        self.nb.i2c.dr().write(|w| w.dr().set(header));
+        busy_wait_cycles!(
+            wait_for_flag!(self.nb.i2c, add10, Address),
+            self.timeouts.addr
+        )?;
+        self.nb.i2c.dr().write(|w| w.dr().set(addr as u8));
+        busy_wait_cycles!(
+            wait_for_flag!(self.nb.i2c, addr, Address),
+            self.timeouts.addr
+        )?;
+
+        if read {
+            // clear ADDR, then switch to receiving with a repeated START and the read header
+            self.nb.i2c.sr2().read();
+            self.send_start_and_wait()?;
+            self.nb.i2c.dr().write(|w| w.dr().set(header | 1));
+            busy_wait_cycles!(
+                wait_for_flag!(self.nb.i2c, addr, Address),
+                self.timeouts.addr
+            )?;
+        }
+        Ok(())
+    }
 
-        self.nb.i2c.dr().write(|w| w.dr().set(bytes[0]));
+    fn write_bytes_and_wait(&mut self, bytes: impl Iterator<Item = u8>) -> Result<(), Error> {
+        // clear ADDR, TXE is set afterwards
+        self.nb.i2c.sr1().read();
+        self.nb.i2c.sr2().read();
 
-        for byte in &bytes[1..] {
+        let mut sent = false;
+        for byte in bytes {
             busy_wait_cycles!(wait_for_flag!(self.nb.i2c, tx_e, Data), self.timeouts.data)?;
-            self.nb.i2c.dr().write(|w| w.dr().set(*byte));
+            self.nb.i2c.dr().write(|w| w.dr().set(byte));
+            sent = true;
+        }
+        if sent {
+            busy_wait_cycles!(wait_for_flag!(self.nb.i2c, btf, Data), self.timeouts.data)?;
         }
-        busy_wait_cycles!(wait_for_flag!(self.nb.i2c, btf, Data), self.timeouts.data)?;
 
         Ok(())
     }
 
-    fn write_without_stop(&mut self, addr: u8, bytes: &[u8]) -> Result<(), Error> {
+    fn write_without_stop(
+        &mut self,
+        addr: Address,
+        bytes: impl Iterator<Item = u8>,
+    ) -> Result<(), Error> {
         self.send_start_and_wait()?;
         self.send_addr_and_wait(addr, false)?;
 
@@ -202,29 +260,43 @@ impl<I2C: Instance> BlockingI2c<I2C> {
         ret
     }
 
-    pub fn write(&mut self, addr: u8, bytes: &[u8]) -> Result<(), Error> {
-        self.write_without_stop(addr, bytes)?;
-        self.nb.send_stop();
-        busy_wait_cycles!(self.wait_for_stop(), self.timeouts.data)?;
-
-        Ok(())
+    pub fn write(&mut self, addr: impl Into<Address>, bytes: &[u8]) -> Result<(), Error> {
+        self.transaction(addr.into(), &mut [Operation::Write(bytes)])
     }
 
-    pub fn read(&mut self, addr: u8, buffer: &mut [u8]) -> Result<(), Error> {
-        self.send_start_and_wait()?;
-        self.send_addr_and_wait(addr, true)?;
+    /// Generates the condition that ends a read: STOP or a repeated START
+    fn send_stop_or_restart(&mut self, stop: bool) {
+        if stop {
+            self.nb.send_stop();
+        } else {
+            self.nb.send_start();
+        }
+    }
 
-        match buffer.len() {
+    /// Receives `len` bytes after the address was sent and hands them to `store`
+    ///
+    /// The end of the read is signalled with a STOP if `stop` is set and with a
+    /// repeated START otherwise.
+    fn read_bytes_and_wait(
+        &mut self,
+        len: usize,
+        stop: bool,
+        mut store: impl FnMut(usize, u8),
+    ) -> Result<(), Error> {
+        match len {
+            0 => {}
             1 => {
                 self.nb.i2c.cr1().modify(|_, w| w.ack().clear_bit());
                 self.nb.i2c.sr1().read();
                 self.nb.i2c.sr2().read();
-                self.nb.send_stop();
+                self.send_stop_or_restart(stop);
 
                 busy_wait_cycles!(wait_for_flag!(self.nb.i2c, rx_ne, Data), self.timeouts.data)?;
-                buffer[0] = self.nb.i2c.dr().read().dr().bits();
+                store(0, self.nb.i2c.dr().read().dr().bits());
 
-                busy_wait_cycles!(self.wait_for_stop(), self.timeouts.data)?;
+                if stop {
+                    busy_wait_cycles!(self.wait_for_stop(), self.timeouts.data)?;
+                }
                 self.nb.i2c.cr1().modify(|_, w| w.ack().set_bit());
             }
             2 => {
@@ -237,40 +309,43 @@ impl<I2C: Instance> BlockingI2c<I2C> {
                 self.nb.i2c.cr1().modify(|_, w| w.ack().clear_bit());
 
                 busy_wait_cycles!(wait_for_flag!(self.nb.i2c, btf, Data), self.timeouts.data)?;
-                self.nb.send_stop();
-                buffer[0] = self.nb.i2c.dr().read().dr().bits();
-                buffer[1] = self.nb.i2c.dr().read().dr().bits();
+                self.send_stop_or_restart(stop);
+                store(0, self.nb.i2c.dr().read().dr().bits());
+                store(1, self.nb.i2c.dr().read().dr().bits());
 
-                busy_wait_cycles!(self.wait_for_stop(), self.timeouts.data)?;
+                if stop {
+                    busy_wait_cycles!(self.wait_for_stop(), self.timeouts.data)?;
+                }
                 self.nb
                     .i2c
                     .cr1()
                     .modify(|_, w| w.pos().clear_bit().ack().clear_bit());
                 self.nb.i2c.cr1().modify(|_, w| w.ack().set_bit());
             }
-            buffer_len => {
+            len => {
                 self.nb.i2c.cr1().modify(|_, w| w.ack().set_bit());
                 self.nb.i2c.sr1().read();
                 self.nb.i2c.sr2().read();
 
-                let (first_bytes, last_two_bytes) = buffer.split_at_mut(buffer_len - 3);
-                for byte in first_bytes {
+                for i in 0..len - 3 {
                     busy_wait_cycles!(
                         wait_for_flag!(self.nb.i2c, rx_ne, Data),
                         self.timeouts.data
                     )?;
-                    *byte = self.nb.i2c.dr().read().dr().bits();
+                    store(i, self.nb.i2c.dr().read().dr().bits());
                 }
 
                 busy_wait_cycles!(wait_for_flag!(self.nb.i2c, btf, Data), self.timeouts.data)?;
                 self.nb.i2c.cr1().modify(|_, w| w.ack().clear_bit());
-                last_two_bytes[0] = self.nb.i2c.dr().read().dr().bits();
-                self.nb.send_stop();
-                last_two_bytes[1] = self.nb.i2c.dr().read().dr().bits();
+                store(len - 3, self.nb.i2c.dr().read().dr().bits());
+                self.send_stop_or_restart(stop);
+                store(len - 2, self.nb.i2c.dr().read().dr().bits());
                 busy_wait_cycles!(wait_for_flag!(self.nb.i2c, rx_ne, Data), self.timeouts.data)?;
-                last_two_bytes[2] = self.nb.i2c.dr().read().dr().bits();
+                store(len - 1, self.nb.i2c.dr().read().dr().bits());
 
-                busy_wait_cycles!(self.wait_for_stop(), self.timeouts.data)?;
+                if stop {
+                    busy_wait_cycles!(self.wait_for_stop(), self.timeouts.data)?;
+                }
                 self.nb.i2c.cr1().modify(|_, w| w.ack().set_bit());
             }
         }
@@ -278,16 +353,79 @@ impl<I2C: Instance> BlockingI2c<I2C> {
         Ok(())
     }
 
-    pub fn write_read(&mut self, addr: u8, bytes: &[u8], buffer: &mut [u8]) -> Result<(), Error> {
-        if !bytes.is_empty() {
-            self.write_without_stop(addr, bytes)?;
+    fn read_with_end(
+        &mut self,
+        addr: Address,
+        len: usize,
+        stop: bool,
+        store: impl FnMut(usize, u8),
+    ) -> Result<(), Error> {
+        self.send_start_and_wait()?;
+        self.send_addr_and_wait(addr, true)?;
+        self.read_bytes_and_wait(len, stop, store)
+    }
+
+    pub fn read(&mut self, addr: impl Into<Address>, buffer: &mut [u8]) -> Result<(), Error> {
+        self.transaction(addr.into(), &mut [Operation::Read(buffer)])
+    }
+
+    pub fn write_read(
+        &mut self,
+        addr: impl Into<Address>,
+        bytes: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<(), Error> {
+        let addr = addr.into();
+        if bytes.is_empty() {
+            self.read(addr, buffer)
+        } else {
+            self.transaction(
+                addr,
+                &mut [Operation::Write(bytes), Operation::Read(buffer)],
+            )
         }
+    }
 
-        if !buffer.is_empty() {
-            self.read(addr, buffer)?;
-        } else if !bytes.is_empty() {
+    /// Executes a sequence of operations as a single transaction
+    ///
+    /// Adjacent operations of the same direction are merged, a repeated START is
+    /// generated between operations of differing direction and a STOP at the end.
+    /// Zero-length reads are skipped, while a zero-length write still addresses
+    /// the device.
+    ///
+    /// On a timeout, a STOP is generated and the peripheral is reset, so that
+    /// the next transaction starts from a clean state.
+    fn transaction<O: TransactionOp>(&mut self, addr: Address, ops: &mut [O]) -> Result<(), Error> {
+        let ret = self.transaction_phases(addr, ops);
+        if let Err(Error::Timeout) = ret {
             self.nb.send_stop();
-            busy_wait_cycles!(self.wait_for_stop(), self.timeouts.data)?;
+            self.nb.reset();
+        }
+        ret
+    }
+
+    fn transaction_phases<O: TransactionOp>(
+        &mut self,
+        addr: Address,
+        ops: &mut [O],
+    ) -> Result<(), Error> {
+        let mut from = 0;
+        while let Some(phase) = next_phase(ops, from) {
+            from = phase.ops.end;
+            let ops = &mut ops[phase.ops];
+            if phase.read {
+                let len = ops.iter().map(|op| op.read_len()).sum();
+                self.read_with_end(addr, len, phase.stop, |i, b| {
+                    *nth_read_byte(ops, i) = b;
+                })?;
+            } else {
+                let bytes = ops.iter().flat_map(|op| op.write_bytes().iter().copied());
+                self.write_without_stop(addr, bytes)?;
+                if phase.stop {
+                    self.nb.send_stop();
+                    busy_wait_cycles!(self.wait_for_stop(), self.timeouts.data)?;
+                }
+            }
         }
 
         Ok(())
@@ -295,17 +433,218 @@ impl<I2C: Instance> BlockingI2c<I2C> {
 
     pub fn transaction_slice(
         &mut self,
-        _addr: u8,
-        _ops_slice: &mut [embedded_hal::i2c::Operation<'_>],
+        addr: impl Into<Address>,
+        ops_slice: &mut [Operation<'_>],
     ) -> Result<(), Error> {
-        todo!();
+        self.transaction(addr.into(), ops_slice)
     }
 
     pub(crate) fn transaction_slice_hal_02(
         &mut self,
-        _addr: u8,
-        _ops_slice: &mut [embedded_hal_02::blocking::i2c::Operation<'_>],
+        addr: u8,
+        ops_slice: &mut [embedded_hal_02::blocking::i2c::Operation<'_>],
     ) -> Result<(), Error> {
-        todo!();
+        self.transaction(Address::SevenBit(addr), ops_slice)
+    }
+}
+
+/// Returns the header `0b11110xx0` carrying the two most significant bits of a 10-bit address
+fn ten_bit_header(addr: u16) -> u8 {
+    0xf0 | ((addr >> 7) as u8 & 0x06)
+}
+
+/// Read or write operation of a transaction
+pub(crate) trait TransactionOp {
+    fn is_read(&self) -> bool;
+    /// Bytes to write, empty for reads
+    fn write_bytes(&self) -> &[u8];
+    /// Number of bytes to read, zero for writes
+    fn read_len(&self) -> usize;
+    /// Buffer to read into, empty for writes
+    fn read_buffer(&mut self) -> &mut [u8];
+}
+
+impl TransactionOp for Operation<'_> {
+    fn is_read(&self) -> bool {
+        matches!(self, Self::Read(_))
+    }
+    fn write_bytes(&self) -> &[u8] {
+        match self {
+            Self::Write(bytes) => bytes,
+            Self::Read(_) => &[],
+        }
+    }
+    fn read_len(&self) -> usize {
+        match self {
+            Self::Read(buffer) => buffer.len(),
+            Self::Write(_) => 0,
+        }
+    }
+    fn read_buffer(&mut self) -> &mut [u8] {
+        match self {
+            Self::Read(buffer) => buffer,
+            Self::Write(_) => &mut [],
+        }
+    }
+}
+
+impl TransactionOp for embedded_hal_02::blocking::i2c::Operation<'_> {
+    fn is_read(&self) -> bool {
+        matches!(self, Self::Read(_))
+    }
+    fn write_bytes(&self) -> &[u8] {
+        match self {
+            Self::Write(bytes) => bytes,
+            Self::Read(_) => &[],
+        }
+    }
+    fn read_len(&self) -> usize {
+        match self {
+            Self::Read(buffer) => buffer.len(),
+            Self::Write(_) => 0,
+        }
+    }
+    fn read_buffer(&mut self) -> &mut [u8] {
+        match self {
+            Self::Read(buffer) => buffer,
+            Self::Write(_) => &mut [],
+        }
     }
 }
+
+/// Operations transferred between two START conditions
+#[derive(Debug, PartialEq, Eq)]
+struct Phase {
+    read: bool,
+    ops: core::ops::Range<usize>,
+    /// Whether the phase ends with a STOP instead of a repeated START
+    stop: bool,
+}
+
+/// Returns the next phase of the transaction, starting at operation `from`
+fn next_phase<O: TransactionOp>(ops: &[O], from: usize) -> Option<Phase> {
+    let skipped = |op: &O| op.is_read() && op.read_len() == 0;
+    let start = from + ops[from..].iter().position(|op| !skipped(op))?;
+    let read = ops[start].is_read();
+    let end = ops[start..]
+        .iter()
+        .position(|op| !skipped(op) && op.is_read() != read)
+        .map_or(ops.len(), |n| start + n);
+    Some(Phase {
+        read,
+        ops: start..end,
+        stop: ops[end..].iter().all(skipped),
+    })
+}
+
+/// Returns byte `i` of the concatenated read buffers
+fn nth_read_byte<O: TransactionOp>(ops: &mut [O], mut i: usize) -> &mut u8 {
+    for op in ops {
+        let buffer = op.read_buffer();
+        if i < buffer.len() {
+            return &mut buffer[i];
+        }
+        i -= buffer.len();
+    }
+    unreachable!()
+}
+
+#[test]
+fn transaction_write_then_read() {
+    let mut buf = [0; 2];
+    let ops = [Operation::Write(&[0x10]), Operation::Read(&mut buf)];
+    let first = next_phase(&ops, 0).unwrap();
+    assert_eq!(
+        first,
+        Phase {
+            read: false,
+            ops: 0..1,
+            stop: false
+        }
+    );
+    let second = next_phase(&ops, first.ops.end).unwrap();
+    assert_eq!(
+        second,
+        Phase {
+            read: true,
+            ops: 1..2,
+            stop: true
+        }
+    );
+    assert_eq!(next_phase(&ops, second.ops.end), None);
+}
+
+#[test]
+fn transaction_read_then_write() {
+    let (mut a, mut b) = ([0; 1], [0; 3]);
+    let mut ops = [
+        Operation::Read(&mut a),
+        Operation::Read(&mut b),
+        Operation::Write(&[1, 2]),
+        Operation::Write(&[]),
+    ];
+    let first = next_phase(&ops, 0).unwrap();
+    assert_eq!(
+        first,
+        Phase {
+            read: true,
+            ops: 0..2,
+            stop: false
+        }
+    );
+    let second = next_phase(&ops, first.ops.end).unwrap();
+    assert_eq!(
+        second,
+        Phase {
+            read: false,
+            ops: 2..4,
+            stop: true
+        }
+    );
+
+    // the merged reads are stored across both buffers
+    for i in 0..4 {
+        *nth_read_byte(&mut ops[first.ops.clone()], i) = i as u8 + 1;
+    }
+    assert_eq!((a, b), ([1], [2, 3, 4]));
+}
+
+#[test]
+fn transaction_zero_length() {
+    // a zero-length read neither splits nor ends a phase
+    let ops = [
+        Operation::Write(&[1]),
+        Operation::Read(&mut []),
+        Operation::Write(&[2]),
+        Operation::Read(&mut []),
+    ];
+    assert_eq!(
+        next_phase(&ops, 0),
+        Some(Phase {
+            read: false,
+            ops: 0..4,
+            stop: true
+        })
+    );
+    assert_eq!(next_phase(&ops, 4), None);
+
+    // a zero-length write addresses the device
+    let ops = [Operation::Write(&[])];
+    assert_eq!(
+        next_phase(&ops, 0),
+        Some(Phase {
+            read: false,
+            ops: 0..1,
+            stop: true
+        })
+    );
+}
+
+#[test]
+fn ten_bit_address_header() {
+    assert_eq!(ten_bit_header(0x000), 0xf0);
+    assert_eq!(ten_bit_header(0x0ff), 0xf0);
+    assert_eq!(ten_bit_header(0x100), 0xf2);
+    assert_eq!(ten_bit_header(0x2a5), 0xf4);
+    assert_eq!(ten_bit_header(0x3ff), 0xf6);
+}
@@ -0,0 +1,317 @@
+use super::*;
+use crate::dma::{dma1, Ch, DmaExt, RxDma, Transfer, TransferPayload, TxDma, R, W};
+use core::sync::atomic::{self, Ordering};
+use embedded_dma::{ReadBuffer, WriteBuffer};
+
+impl<I2C: Instance> BlockingI2c<I2C> {
+    /// Returns and clears the error flags raised during a transfer
+    fn check_dma_errors(&self) -> Result<(), Error> {
+        let sr1 = self.nb.i2c.sr1().read();
+        if sr1.berr().bit_is_set() {
+            self.nb.i2c.sr1().write(|w| w.berr().clear_bit());
+            Err(Error::Bus)
+        } else if sr1.arlo().bit_is_set() {
+            self.nb.i2c.sr1().write(|w| w.arlo().clear_bit());
+            Err(Error::ArbitrationLoss)
+        } else if sr1.af().bit_is_set() {
+            self.nb.i2c.sr1().write(|w| w.af().clear_bit());
+            Err(Error::NoAcknowledge(NoAcknowledgeSource::Data))
+        } else if sr1.ovr().bit_is_set() {
+            self.nb.i2c.sr1().write(|w| w.ovr().clear_bit());
+            Err(Error::Overrun)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Generates a START and sends the address, leaving ADDR set
+    fn start_dma_transfer(&mut self, addr: u8, read: bool) -> Result<(), Error> {
+        self.nb.send_start();
+        let mut ret = busy_wait_cycles!(
+            wait_for_flag!(self.nb.i2c, sb, Unknown),
+            self.timeouts.start
+        );
+        if ret.is_ok() {
+            self.nb.send_addr(addr, read);
+            ret = busy_wait_cycles!(
+                wait_for_flag!(self.nb.i2c, addr, Address),
+                self.timeouts.addr
+            );
+        }
+        if ret.is_err() {
+            self.nb.send_stop();
+        }
+        if let Err(Error::Timeout) = ret {
+            self.nb.reset();
+        }
+        ret
+    }
+
+    /// Busy waits until the DMA `channel` finished
+    ///
+    /// Returns bus errors and NACKs, and [`Error::Timeout`] once no byte was moved
+    /// for the data timeout.
+    fn wait_dma<DMA: DmaExt, const C: u8>(&self, channel: &Ch<DMA, C>) -> Result<(), Error> {
+        let mut remaining = channel.get_ndtr();
+        let mut started = DWT::cycle_count();
+        while channel.in_progress() {
+            self.check_dma_errors()?;
+            let ndtr = channel.get_ndtr();
+            if ndtr != remaining {
+                remaining = ndtr;
+                started = DWT::cycle_count();
+            } else if DWT::cycle_count().wrapping_sub(started) >= self.timeouts.data {
+                return Err(Error::Timeout);
+            }
+        }
+        Ok(())
+    }
+
+    /// Generates the STOP after the DMA transfer and leaves DMA mode
+    ///
+    /// The peripheral is reset if the STOP could not be generated within the
+    /// data timeout.
+    fn finish_dma_transfer(&mut self, wait_btf: bool) -> Result<(), Error> {
+        let mut ret = Ok(());
+        if self.nb.i2c.sr2().read().msl().bit_is_set() {
+            if wait_btf {
+                // the last byte is still in the shift register
+                ret = busy_wait_cycles!(wait_for_flag!(self.nb.i2c, btf, Data), self.timeouts.data);
+            }
+            self.nb.send_stop();
+            let stopped = busy_wait_cycles!(
+                if self.nb.i2c.cr1().read().stop().is_stop() {
+                    Err(nb::Error::WouldBlock)
+                } else {
+                    Ok(())
+                },
+                self.timeouts.data
+            );
+            ret = ret.and(stopped);
+        }
+        self.nb
+            .i2c
+            .cr2()
+            .modify(|_, w| w.dmaen().clear_bit().last().clear_bit());
+        self.nb.i2c.cr1().modify(|_, w| w.ack().set_bit());
+        if let Err(Error::Timeout) = ret {
+            self.nb.reset();
+        }
+        ret
+    }
+}
+
+macro_rules! i2c_dma {
+    ($I2Cx:ty, rx: $RCi:ty, tx: $TCi:ty, $rxdma:ident, $txdma:ident) => {
+        pub type $rxdma = RxDma<BlockingI2c<$I2Cx>, $RCi>;
+        pub type $txdma = TxDma<BlockingI2c<$I2Cx>, $TCi>;
+
+        impl BlockingI2c<$I2Cx> {
+            pub fn with_tx_dma(self, channel: $TCi) -> $txdma {
+                TxDma {
+                    payload: self,
+                    channel,
+                }
+            }
+            pub fn with_rx_dma(self, channel: $RCi) -> $rxdma {
+                RxDma {
+                    payload: self,
+                    channel,
+                }
+            }
+        }
+
+        impl $txdma {
+            pub fn release(self) -> (BlockingI2c<$I2Cx>, $TCi) {
+                let TxDma { payload, channel } = self;
+                (payload, channel)
+            }
+
+            /// Writes `buffer` to the device at `addr`
+            ///
+            /// Returns the error, the DMA transmitter and the buffer if `buffer` is
+            /// empty or the device does not acknowledge its address.
+            ///
+            /// A device that stops acknowledging data bytes halts the transfer, use
+            /// [`Transfer::wait_checked`] to wait for it.
+            #[allow(clippy::type_complexity)]
+            pub fn write<B>(
+                mut self,
+                addr: u8,
+                buffer: B,
+            ) -> Result<Transfer<R, B, Self>, (Error, Self, B)>
+            where
+                B: ReadBuffer<Word = u8>,
+            {
+                // NOTE(unsafe) We own the buffer now and we won't call other `&mut` on it
+                // until the end of the transfer.
+                let (ptr, len) = unsafe { buffer.read_buffer() };
+                if len == 0 {
+                    return Err((Error::EmptyBuffer, self, buffer));
+                }
+                if let Err(e) = self.payload.start_dma_transfer(addr, false) {
+                    return Err((e, self, buffer));
+                }
+
+                self.channel.set_peripheral_address(
+                    unsafe { (*<$I2Cx>::ptr()).dr().as_ptr() as u32 },
+                    false,
+                );
+                self.channel.set_memory_address(ptr as u32, true);
+                self.channel.set_transfer_length(len);
+
+                atomic::compiler_fence(Ordering::Release);
+
+                self.channel.ch().cr().modify(|_, w| {
+                    w.mem2mem().clear_bit();
+                    w.pl().medium();
+                    w.msize().bits8();
+                    w.psize().bits8();
+                    w.circ().clear_bit();
+                    w.dir().set_bit()
+                });
+                self.payload.nb.i2c.cr2().modify(|_, w| w.dmaen().set_bit());
+                self.start();
+
+                // clear ADDR, the first TXE triggers the DMA
+                self.payload.nb.i2c.sr2().read();
+
+                Ok(Transfer::r(buffer, self))
+            }
+        }
+
+        impl $rxdma {
+            pub fn release(self) -> (BlockingI2c<$I2Cx>, $RCi) {
+                let RxDma { payload, channel } = self;
+                (payload, channel)
+            }
+
+            /// Reads from the device at `addr` into `buffer`
+            ///
+            /// The last byte is NACKed automatically. Returns the error, the DMA
+            /// receiver and the buffer if `buffer` is empty or the device does not
+            /// acknowledge its address.
+            #[allow(clippy::type_complexity)]
+            pub fn read<B>(
+                mut self,
+                addr: u8,
+                mut buffer: B,
+            ) -> Result<Transfer<W, B, Self>, (Error, Self, B)>
+            where
+                B: WriteBuffer<Word = u8>,
+            {
+                // NOTE(unsafe) We own the buffer now and we won't call other `&mut` on it
+                // until the end of the transfer.
+                let (ptr, len) = unsafe { buffer.write_buffer() };
+                if len == 0 {
+                    return Err((Error::EmptyBuffer, self, buffer));
+                }
+
+                self.payload.nb.i2c.cr1().modify(|_, w| w.ack().set_bit());
+                self.payload
+                    .nb
+                    .i2c
+                    .cr2()
+                    .modify(|_, w| w.dmaen().set_bit().last().set_bit());
+                if let Err(e) = self.payload.start_dma_transfer(addr, true) {
+                    self.payload.finish_dma_transfer(false).ok();
+                    return Err((e, self, buffer));
+                }
+
+                self.channel.set_peripheral_address(
+                    unsafe { (*<$I2Cx>::ptr()).dr().as_ptr() as u32 },
+                    false,
+                );
+                self.channel.set_memory_address(ptr as u32, true);
+                self.channel.set_transfer_length(len);
+
+                atomic::compiler_fence(Ordering::Release);
+
+                self.channel.ch().cr().modify(|_, w| {
+                    w.mem2mem().clear_bit();
+                    w.pl().medium();
+                    w.msize().bits8();
+                    w.psize().bits8();
+                    w.circ().clear_bit();
+                    w.dir().clear_bit()
+                });
+                self.start();
+
+                if len == 1 {
+                    // a single byte must be NACKed before ADDR is cleared
+                    self.payload.nb.i2c.cr1().modify(|_, w| w.ack().clear_bit());
+                    self.payload.nb.i2c.sr2().read();
+                    self.payload.nb.send_stop();
+                } else {
+                    self.payload.nb.i2c.sr2().read();
+                }
+
+                Ok(Transfer::w(buffer, self))
+            }
+        }
+
+        impl<B> Transfer<R, B, $txdma> {
+            /// Waits for the end of the transfer and generates the STOP condition
+            ///
+            /// Unlike [`Transfer::wait`], which only watches the DMA channel, this
+            /// stops early when the device does not acknowledge a data byte or the
+            /// bus fails, and returns [`Error::Timeout`] once no byte was sent for
+            /// the data timeout of the [`BlockingI2c`].
+            #[allow(clippy::type_complexity)]
+            pub fn wait_checked(self) -> Result<(B, $txdma), (Error, B, $txdma)> {
+                let (buffer, mut payload) = self.into_parts();
+                let ret = payload.payload.wait_dma(&payload.channel);
+                atomic::compiler_fence(Ordering::Acquire);
+                payload.channel.stop();
+                let finished = payload.payload.finish_dma_transfer(ret.is_ok());
+                match ret.and(finished) {
+                    Ok(()) => Ok((buffer, payload)),
+                    Err(e) => Err((e, buffer, payload)),
+                }
+            }
+        }
+
+        impl<B> Transfer<W, B, $rxdma> {
+            /// Waits for the end of the transfer and generates the STOP condition
+            ///
+            /// Unlike [`Transfer::wait`], which only watches the DMA channel, this
+            /// stops early when the bus fails and returns [`Error::Timeout`] once
+            /// no byte was received for the data timeout of the [`BlockingI2c`].
+            #[allow(clippy::type_complexity)]
+            pub fn wait_checked(self) -> Result<(B, $rxdma), (Error, B, $rxdma)> {
+                let (buffer, mut payload) = self.into_parts();
+                let ret = payload.payload.wait_dma(&payload.channel);
+                atomic::compiler_fence(Ordering::Acquire);
+                payload.channel.stop();
+                let finished = payload.payload.finish_dma_transfer(false);
+                match ret.and(finished) {
+                    Ok(()) => Ok((buffer, payload)),
+                    Err(e) => Err((e, buffer, payload)),
+                }
+            }
+        }
+
+        impl TransferPayload for $txdma {
+            fn start(&mut self) {
+                self.channel.start();
+            }
+            fn stop(&mut self) {
+                self.channel.stop();
+                self.payload.finish_dma_transfer(true).ok();
+            }
+        }
+
+        impl TransferPayload for $rxdma {
+            fn start(&mut self) {
+                self.channel.start();
+            }
+            fn stop(&mut self) {
+                self.channel.stop();
+                self.payload.finish_dma_transfer(false).ok();
+            }
+        }
+    };
+}
+
+i2c_dma!(pac::I2C1, rx: dma1::C7, tx: dma1::C6, I2c1RxDma, I2c1TxDma);
+i2c_dma!(pac::I2C2, rx: dma1::C5, tx: dma1::C4, I2c2RxDma, I2c2TxDma);
@@ -7,7 +7,7 @@ impl Error for super::Error {
             Self::Bus => ErrorKind::Bus,
             Self::ArbitrationLoss => ErrorKind::ArbitrationLoss,
             Self::NoAcknowledge(nack) => ErrorKind::NoAcknowledge(nack),
-            Self::Timeout => ErrorKind::Other,
+            Self::Timeout | Self::EmptyBuffer => ErrorKind::Other,
         }
     }
 }
@@ -17,8 +17,8 @@ impl<I2C: super::Instance> ErrorType for super::BlockingI2c<I2C> {
 }
 
 mod blocking {
-    use super::super::{BlockingI2c, Instance};
-    use embedded_hal::i2c::Operation;
+    use super::super::{Address, BlockingI2c, Instance};
+    use embedded_hal::i2c::{Operation, TenBitAddress};
 
     impl<I2C: Instance> embedded_hal::i2c::I2c for BlockingI2c<I2C> {
         fn read(&mut self, addr: u8, buffer: &mut [u8]) -> Result<(), Self::Error> {
@@ -46,4 +46,31 @@ mod blocking {
             self.transaction_slice(addr, operations)
         }
     }
+
+    impl<I2C: Instance> embedded_hal::i2c::I2c<TenBitAddress> for BlockingI2c<I2C> {
+        fn read(&mut self, addr: u16, buffer: &mut [u8]) -> Result<(), Self::Error> {
+            self.read(Address::TenBit(addr), buffer)
+        }
+
+        fn write(&mut self, addr: u16, bytes: &[u8]) -> Result<(), Self::Error> {
+            self.write(Address::TenBit(addr), bytes)
+        }
+
+        fn write_read(
+            &mut self,
+            addr: u16,
+            bytes: &[u8],
+            buffer: &mut [u8],
+        ) -> Result<(), Self::Error> {
+            self.write_read(Address::TenBit(addr), bytes, buffer)
+        }
+
+        fn transaction(
+            &mut self,
+            addr: u16,
+            operations: &mut [Operation<'_>],
+        ) -> Result<(), Self::Error> {
+            self.transaction_slice(Address::TenBit(addr), operations)
+        }
+    }
 }
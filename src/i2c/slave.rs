@@ -0,0 +1,110 @@
+use super::*;
+
+/// Transfer direction requested by the master
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    /// The master writes, the slave receives
+    Write,
+    /// The master reads, the slave transmits
+    Read,
+}
+
+/// Event of an I2C slave
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SlaveEvent {
+    /// The master addressed this device
+    AddressMatched(Direction),
+    /// The master wrote a byte
+    DataReceived(u8),
+    /// The master reads a byte, answer with [`I2cSlave::write`]
+    DataRequested,
+    /// The master ended the transfer
+    Stop,
+}
+
+/// I2C peripheral operating in slave mode
+///
+/// The bus is clock stretched while an event is pending, so the master waits
+/// until the event is handled.
+pub struct I2cSlave<I2C: Instance> {
+    i2c: I2c<I2C>,
+}
+
+impl<I2C: Instance> I2c<I2C> {
+    /// Switches to slave mode, responding to the 7-bit `own_address`
+    ///
+    /// The event, buffer and error interrupts are enabled. Call
+    /// [`I2cSlave::next_event`] from the I2C event and error interrupt handlers.
+    pub fn into_slave(self, own_address: u8) -> I2cSlave<I2C> {
+        // bit 14 must be kept at 1 by software
+        self.i2c.oar1().write(|w| {
+            unsafe { w.bits(1 << 14) }
+                .add()
+                .set((own_address as u16) << 1)
+        });
+        self.i2c.cr1().modify(|_, w| w.ack().set_bit());
+        self.i2c.cr2().modify(|_, w| {
+            w.itevten().set_bit();
+            w.itbufen().set_bit();
+            w.iterren().set_bit()
+        });
+        I2cSlave { i2c: self }
+    }
+}
+
+impl<I2C: Instance> I2cSlave<I2C> {
+    /// Returns the next pending event
+    ///
+    /// Returns `WouldBlock` if no event is pending.
+    pub fn next_event(&mut self) -> nb::Result<SlaveEvent, Error> {
+        let i2c = &self.i2c.i2c;
+        let sr1 = i2c.sr1().read();
+
+        if sr1.berr().bit_is_set() {
+            i2c.sr1().write(|w| w.berr().clear_bit());
+            Err(nb::Error::Other(Error::Bus))
+        } else if sr1.ovr().bit_is_set() {
+            i2c.sr1().write(|w| w.ovr().clear_bit());
+            Err(nb::Error::Other(Error::Overrun))
+        } else if sr1.addr().bit_is_set() {
+            // reading SR2 after SR1 clears ADDR and releases the clock
+            let direction = if i2c.sr2().read().tra().bit_is_set() {
+                Direction::Read
+            } else {
+                Direction::Write
+            };
+            Ok(SlaveEvent::AddressMatched(direction))
+        } else if sr1.rx_ne().bit_is_set() {
+            Ok(SlaveEvent::DataReceived(i2c.dr().read().dr().bits()))
+        } else if sr1.af().bit_is_set() {
+            // the master NACKs the last byte it reads
+            i2c.sr1().write(|w| w.af().clear_bit());
+            Ok(SlaveEvent::Stop)
+        } else if sr1.stopf().bit_is_set() {
+            // cleared by reading SR1 followed by a write to CR1
+            i2c.cr1().modify(|_, w| w);
+            Ok(SlaveEvent::Stop)
+        } else if sr1.tx_e().bit_is_set() && i2c.sr2().read().tra().bit_is_set() {
+            Ok(SlaveEvent::DataRequested)
+        } else {
+            Err(nb::Error::WouldBlock)
+        }
+    }
+
+    /// Sends a byte to the master after [`SlaveEvent::DataRequested`]
+    pub fn write(&mut self, byte: u8) {
+        self.i2c.i2c.dr().write(|w| w.dr().set(byte));
+    }
+
+    /// Returns to master mode
+    pub fn release(self) -> I2c<I2C> {
+        let mut i2c = self.i2c;
+        i2c.i2c.cr2().modify(|_, w| {
+            w.itevten().clear_bit();
+            w.itbufen().clear_bit();
+            w.iterren().clear_bit()
+        });
+        i2c.reset();
+        i2c
+    }
+}
@@ -0,0 +1,554 @@
+/*!
+  # Inter-IC Sound
+
+  `SPI2` and `SPI3` of high-density and connectivity line devices can be used in I2S mode
+  to exchange audio samples with codecs and DACs.
+
+  - `SPI2` uses `(WS, CK, SD)` = `(PB12, PB13, PB15)` and `PC6` as master clock output (MCK)
+  - `SPI3` uses `(WS, CK, SD)` = `(PA15, PB3, PB5)` and `PC7` as master clock output (MCK).
+    `PA15` and `PB3` are JTAG pins, JTAG has to be disabled with `MAPR::disable_jtag`.
+
+  In master mode `WS`, `CK` and `MCK` are outputs and have to be configured by
+  `into_alternate_push_pull`, in slave mode `WS` and `CK` are inputs. `SD` is an output
+  when transmitting and an input when receiving. Pass the 4-tuple with the `MCK` pin to
+  enable the master clock output, which many codecs need as their system clock.
+
+  ## Clock
+
+  The I2S clock is the system clock, divided by a prescaler of at least 4. The sample rate is
+
+  - `sysclk / (32 * div)` for 16 bit channels without master clock output
+  - `sysclk / (64 * div)` for 32 bit channels without master clock output
+  - `sysclk / (256 * div)` with master clock output
+
+  so the sample rate is only exact if the system clock is a multiple of it. With a 72 MHz
+  system clock 48 kHz become 47.872 kHz, while 64 MHz give 16 kHz and 8 kHz exactly. Use
+  `I2s::sample_rate` to get the actual value.
+
+  ## Example
+
+  ```rust
+    let pins = (
+        gpiob.pb12.into_alternate_push_pull(&mut gpiob.crh),
+        gpiob.pb13.into_alternate_push_pull(&mut gpiob.crh),
+        gpiob.pb15.into_alternate_push_pull(&mut gpiob.crh),
+    );
+    let config = I2sConfig::new(I2sMode::MasterTx, 16.kHz());
+    let mut i2s = I2s::new(dp.SPI2, pins, config, &clocks);
+    i2s.write_frame(left, right).unwrap();
+  ```
+*/
+
+use core::ops::Deref;
+use core::sync::atomic::{self, Ordering};
+
+use embedded_dma::{ReadBuffer, WriteBuffer};
+
+use crate::dma::{self, dma1, dma2, Receive, RxDma, Transfer, TransferPayload, Transmit, TxDma};
+use crate::gpio::{self, Alternate, Input};
+use crate::pac::{self, RCC};
+use crate::rcc::{Clocks, Enable, Reset};
+use crate::time::Hertz;
+
+/// I2S operating mode
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum I2sMode {
+    SlaveTx = 0b00,
+    SlaveRx = 0b01,
+    MasterTx = 0b10,
+    MasterRx = 0b11,
+}
+
+/// Frame format
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Standard {
+    /// I2S Philips standard
+    Philips,
+    /// Left justified
+    MsbJustified,
+    /// Right justified
+    LsbJustified,
+    /// PCM with short frame synchronization
+    PcmShort,
+    /// PCM with long frame synchronization
+    PcmLong,
+}
+
+/// Sample and channel length
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DataFormat {
+    /// 16 bit samples in 16 bit channels
+    Data16Channel16,
+    /// 16 bit samples in 32 bit channels
+    Data16Channel32,
+    /// 24 bit samples in 32 bit channels
+    Data24Channel32,
+    /// 32 bit samples in 32 bit channels
+    Data32Channel32,
+}
+
+impl DataFormat {
+    fn channel_bits(self) -> u32 {
+        match self {
+            Self::Data16Channel16 => 16,
+            _ => 32,
+        }
+    }
+}
+
+/// Clock level when idle
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ClockPolarity {
+    IdleLow,
+    IdleHigh,
+}
+
+/// Channel of a stereo frame
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Channel {
+    Left,
+    Right,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct I2sConfig {
+    pub mode: I2sMode,
+    pub standard: Standard,
+    pub data_format: DataFormat,
+    pub clock_polarity: ClockPolarity,
+    /// Sample rate, only used in master mode
+    pub sample_rate: Hertz,
+}
+
+impl I2sConfig {
+    /// Philips standard with 16 bit samples
+    pub fn new(mode: I2sMode, sample_rate: Hertz) -> Self {
+        Self {
+            mode,
+            standard: Standard::Philips,
+            data_format: DataFormat::Data16Channel16,
+            clock_polarity: ClockPolarity::IdleLow,
+            sample_rate,
+        }
+    }
+
+    pub fn standard(mut self, standard: Standard) -> Self {
+        self.standard = standard;
+        self
+    }
+
+    pub fn data_format(mut self, data_format: DataFormat) -> Self {
+        self.data_format = data_format;
+        self
+    }
+
+    pub fn clock_polarity(mut self, clock_polarity: ClockPolarity) -> Self {
+        self.clock_polarity = clock_polarity;
+        self
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Error {
+    /// A received sample was overwritten before it has been read
+    Overrun,
+    /// No sample was written in time in slave transmit mode
+    Underrun,
+}
+
+pub trait Instance:
+    crate::Sealed + Deref<Target = crate::pac::spi1::RegisterBlock> + Enable + Reset
+{
+}
+
+impl Instance for pac::SPI2 {}
+impl Instance for pac::SPI3 {}
+
+pub trait Pins<SPI> {
+    /// The master clock output is used
+    const MCK: bool;
+}
+
+macro_rules! pins {
+    ($($SPI:ty: $WS:ident, $CK:ident, $SD:ident, $MCK:ident;)+) => {
+        $(
+            impl<WS, CK, SD> Pins<$SPI> for (gpio::$WS<WS>, gpio::$CK<CK>, gpio::$SD<SD>)
+            where
+                WS: PinMode,
+                CK: PinMode,
+                SD: PinMode,
+            {
+                const MCK: bool = false;
+            }
+
+            impl<WS, CK, SD, Otype> Pins<$SPI>
+                for (gpio::$WS<WS>, gpio::$CK<CK>, gpio::$SD<SD>, gpio::$MCK<Alternate<Otype>>)
+            where
+                WS: PinMode,
+                CK: PinMode,
+                SD: PinMode,
+            {
+                const MCK: bool = true;
+            }
+        )+
+    };
+}
+
+/// Pin modes usable for I2S signals
+pub trait PinMode {}
+impl<Otype> PinMode for Alternate<Otype> {}
+impl<PULL> PinMode for Input<PULL> {}
+
+pins!(
+    pac::SPI2: PB12, PB13, PB15, PC6;
+    pac::SPI3: PA15, PB3, PB5, PC7;
+);
+
+/// Returns the `I2SPR` value and the resulting sample rate
+fn prescaler(clk: u32, sample_rate: u32, channel_bits: u32, mck: bool) -> (u16, u32) {
+    let bits_per_frame = if mck { 256 } else { channel_bits * 2 };
+    let div = (clk + bits_per_frame * sample_rate / 2) / (bits_per_frame * sample_rate);
+    assert!(
+        (4..=511).contains(&div),
+        "sample rate not possible with this clock"
+    );
+    let i2spr = (div / 2) | (div & 1) << 8 | u32::from(mck) << 9;
+    (i2spr as u16, clk / (bits_per_frame * div))
+}
+
+/// I2S peripheral
+pub struct I2s<SPI, PINS> {
+    spi: SPI,
+    pins: PINS,
+    data_format: DataFormat,
+    sample_rate: Hertz,
+}
+
+impl<SPI: Instance, PINS: Pins<SPI>> I2s<SPI, PINS> {
+    /// Configures the SPI peripheral in I2S mode and enables it
+    pub fn new(spi: SPI, pins: PINS, config: I2sConfig, clocks: &Clocks) -> Self {
+        let rcc = unsafe { &(*RCC::ptr()) };
+        SPI::enable(rcc);
+        SPI::reset(rcc);
+
+        let master = matches!(config.mode, I2sMode::MasterTx | I2sMode::MasterRx);
+        let sample_rate = if master {
+            let (i2spr, sample_rate) = prescaler(
+                clocks.sysclk().raw(),
+                config.sample_rate.raw(),
+                config.data_format.channel_bits(),
+                PINS::MCK,
+            );
+            spi.i2spr().write(|w| unsafe { w.bits(i2spr) });
+            Hertz::from_raw(sample_rate)
+        } else {
+            config.sample_rate
+        };
+
+        let (chlen, datlen) = match config.data_format {
+            DataFormat::Data16Channel16 => (0, 0b00),
+            DataFormat::Data16Channel32 => (1, 0b00),
+            DataFormat::Data24Channel32 => (1, 0b01),
+            DataFormat::Data32Channel32 => (1, 0b10),
+        };
+        let (i2sstd, pcmsync) = match config.standard {
+            Standard::Philips => (0b00, 0),
+            Standard::MsbJustified => (0b01, 0),
+            Standard::LsbJustified => (0b10, 0),
+            Standard::PcmShort => (0b11, 0),
+            Standard::PcmLong => (0b11, 1),
+        };
+        let ckpol = u32::from(config.clock_polarity == ClockPolarity::IdleHigh);
+        // I2SMOD
+        let i2scfgr = 1 << 11
+            | (config.mode as u32) << 8
+            | pcmsync << 7
+            | i2sstd << 4
+            | ckpol << 3
+            | datlen << 1
+            | chlen;
+        spi.i2scfgr().write(|w| unsafe { w.bits(i2scfgr as u16) });
+        spi.i2scfgr().modify(|_, w| w.i2se().set_bit());
+
+        Self {
+            spi,
+            pins,
+            data_format: config.data_format,
+            sample_rate,
+        }
+    }
+
+    /// Actual sample rate in master mode, the configured one in slave mode
+    pub fn sample_rate(&self) -> Hertz {
+        self.sample_rate
+    }
+
+    /// Channel of the next sample to write or of the last read sample
+    pub fn channel(&self) -> Channel {
+        if self.spi.sr().read().chside().bit_is_set() {
+            Channel::Right
+        } else {
+            Channel::Left
+        }
+    }
+
+    /// Writes a sample, right aligned for 24 bit samples.
+    ///
+    /// For 24 and 32 bit samples the second half is written as soon as the first one
+    /// has been sent.
+    pub fn write(&mut self, sample: u32) -> nb::Result<(), Error> {
+        let sr = self.spi.sr().read();
+        // Reading SR clears UDR
+        if sr.udr().bit_is_set() {
+            return Err(nb::Error::Other(Error::Underrun));
+        }
+        if sr.txe().bit_is_clear() {
+            return Err(nb::Error::WouldBlock);
+        }
+        match self.data_format {
+            DataFormat::Data16Channel16 | DataFormat::Data16Channel32 => {
+                self.write_dr(sample as u16)
+            }
+            DataFormat::Data24Channel32 => {
+                self.write_dr((sample >> 8) as u16);
+                self.wait_txe();
+                self.write_dr((sample << 8) as u16);
+            }
+            DataFormat::Data32Channel32 => {
+                self.write_dr((sample >> 16) as u16);
+                self.wait_txe();
+                self.write_dr(sample as u16);
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads a sample, right aligned for 24 bit samples
+    pub fn read(&mut self) -> nb::Result<u32, Error> {
+        let sr = self.spi.sr().read();
+        if sr.ovr().bit_is_set() {
+            // Reading DR and SR clears OVR
+            self.read_dr();
+            self.spi.sr().read();
+            return Err(nb::Error::Other(Error::Overrun));
+        }
+        if sr.rxne().bit_is_clear() {
+            return Err(nb::Error::WouldBlock);
+        }
+        let high = self.read_dr() as u32;
+        Ok(match self.data_format {
+            DataFormat::Data16Channel16 | DataFormat::Data16Channel32 => high,
+            DataFormat::Data24Channel32 => {
+                self.wait_rxne();
+                high << 8 | (self.read_dr() >> 8) as u32
+            }
+            DataFormat::Data32Channel32 => {
+                self.wait_rxne();
+                high << 16 | self.read_dr() as u32
+            }
+        })
+    }
+
+    /// Writes a stereo frame, waits until the left channel is next
+    pub fn write_frame(&mut self, left: u32, right: u32) -> Result<(), Error> {
+        while !(self.spi.sr().read().txe().bit_is_set() && self.channel() == Channel::Left) {}
+        nb::block!(self.write(left))?;
+        nb::block!(self.write(right))
+    }
+
+    /// Reads a stereo frame, skips a right channel sample to synchronize
+    pub fn read_frame(&mut self) -> Result<(u32, u32), Error> {
+        let mut left = nb::block!(self.read())?;
+        if self.channel() == Channel::Right {
+            left = nb::block!(self.read())?;
+        }
+        let right = nb::block!(self.read())?;
+        Ok((left, right))
+    }
+
+    /// Disables the peripheral and returns it with the pins
+    pub fn release(self) -> (SPI, PINS) {
+        // Wait for the end of the frame before disabling
+        while self.spi.sr().read().bsy().bit_is_set() {}
+        self.spi.i2scfgr().reset();
+        (self.spi, self.pins)
+    }
+
+    fn write_dr(&mut self, data: u16) {
+        self.spi.dr().write(|w| unsafe { w.bits(data) });
+    }
+
+    fn read_dr(&mut self) -> u16 {
+        self.spi.dr().read().bits()
+    }
+
+    fn wait_txe(&self) {
+        while self.spi.sr().read().txe().bit_is_clear() {}
+    }
+
+    fn wait_rxne(&self) {
+        while self.spi.sr().read().rxne().bit_is_clear() {}
+    }
+}
+
+pub type I2sTxDma<SPI, PINS, CHANNEL> = TxDma<I2s<SPI, PINS>, CHANNEL>;
+pub type I2sRxDma<SPI, PINS, CHANNEL> = RxDma<I2s<SPI, PINS>, CHANNEL>;
+
+macro_rules! i2s_dma {
+    ($SPIi:ty, rx: $RCi:ty, tx: $TCi:ty) => {
+        impl<PINS> Transmit for I2sTxDma<$SPIi, PINS, $TCi> {
+            type TxChannel = $TCi;
+            type ReceivedWord = u16;
+        }
+
+        impl<PINS> Receive for I2sRxDma<$SPIi, PINS, $RCi> {
+            type RxChannel = $RCi;
+            type TransmittedWord = u16;
+        }
+
+        impl<PINS> I2s<$SPIi, PINS> {
+            /// Transmits the buffer of half words by DMA, 24 and 32 bit samples take two
+            /// half words with the most significant one first
+            pub fn with_tx_dma(self, channel: $TCi) -> I2sTxDma<$SPIi, PINS, $TCi> {
+                self.spi.cr2().modify(|_, w| w.txdmaen().set_bit());
+                I2sTxDma {
+                    payload: self,
+                    channel,
+                }
+            }
+
+            /// Receives into a buffer of half words by DMA, 24 and 32 bit samples take two
+            /// half words with the most significant one first
+            pub fn with_rx_dma(self, channel: $RCi) -> I2sRxDma<$SPIi, PINS, $RCi> {
+                self.spi.cr2().modify(|_, w| w.rxdmaen().set_bit());
+                I2sRxDma {
+                    payload: self,
+                    channel,
+                }
+            }
+        }
+
+        impl<PINS> I2sTxDma<$SPIi, PINS, $TCi> {
+            pub fn release(self) -> (I2s<$SPIi, PINS>, $TCi) {
+                let I2sTxDma { payload, channel } = self;
+                payload.spi.cr2().modify(|_, w| w.txdmaen().clear_bit());
+                (payload, channel)
+            }
+        }
+
+        impl<PINS> I2sRxDma<$SPIi, PINS, $RCi> {
+            pub fn release(self) -> (I2s<$SPIi, PINS>, $RCi) {
+                let I2sRxDma { payload, channel } = self;
+                payload.spi.cr2().modify(|_, w| w.rxdmaen().clear_bit());
+                (payload, channel)
+            }
+        }
+
+        impl<PINS> TransferPayload for I2sTxDma<$SPIi, PINS, $TCi> {
+            fn start(&mut self) {
+                self.channel.start();
+            }
+            fn stop(&mut self) {
+                self.channel.stop();
+            }
+        }
+
+        impl<PINS> TransferPayload for I2sRxDma<$SPIi, PINS, $RCi> {
+            fn start(&mut self) {
+                self.channel.start();
+            }
+            fn stop(&mut self) {
+                self.channel.stop();
+            }
+        }
+
+        impl<B, PINS> dma::ReadDma<B, u16> for I2sRxDma<$SPIi, PINS, $RCi>
+        where
+            B: WriteBuffer<Word = u16>,
+        {
+            fn read(mut self, mut buffer: B) -> Transfer<dma::W, B, Self> {
+                // NOTE(unsafe) We own the buffer now and we won't call other `&mut` on it
+                // until the end of the transfer.
+                let (ptr, len) = unsafe { buffer.write_buffer() };
+                self.channel.set_peripheral_address(
+                    unsafe { (*<$SPIi>::ptr()).dr().as_ptr() as u32 },
+                    false,
+                );
+                self.channel.set_memory_address(ptr as u32, true);
+                self.channel.set_transfer_length(len);
+
+                atomic::compiler_fence(Ordering::Release);
+                self.channel.ch().cr().modify(|_, w| {
+                    w.mem2mem().clear_bit();
+                    // high channel priority level, so no sample is lost
+                    w.pl().high();
+                    w.msize().bits16();
+                    w.psize().bits16();
+                    w.circ().clear_bit();
+                    w.dir().clear_bit()
+                });
+                self.start();
+
+                Transfer::w(buffer, self)
+            }
+        }
+
+        impl<B, PINS> dma::WriteDma<B, u16> for I2sTxDma<$SPIi, PINS, $TCi>
+        where
+            B: ReadBuffer<Word = u16>,
+        {
+            fn write(mut self, buffer: B) -> Transfer<dma::R, B, Self> {
+                // NOTE(unsafe) We own the buffer now and we won't call other `&mut` on it
+                // until the end of the transfer.
+                let (ptr, len) = unsafe { buffer.read_buffer() };
+                self.channel.set_peripheral_address(
+                    unsafe { (*<$SPIi>::ptr()).dr().as_ptr() as u32 },
+                    false,
+                );
+                self.channel.set_memory_address(ptr as u32, true);
+                self.channel.set_transfer_length(len);
+
+                atomic::compiler_fence(Ordering::Release);
+                self.channel.ch().cr().modify(|_, w| {
+                    w.mem2mem().clear_bit();
+                    // high channel priority level, so the transmitter does not run dry
+                    w.pl().high();
+                    w.msize().bits16();
+                    w.psize().bits16();
+                    w.circ().clear_bit();
+                    w.dir().set_bit()
+                });
+                self.start();
+
+                Transfer::r(buffer, self)
+            }
+        }
+    };
+}
+
+i2s_dma!(pac::SPI2, rx: dma1::C4, tx: dma1::C5);
+i2s_dma!(pac::SPI3, rx: dma2::C1, tx: dma2::C2);
+
+#[test]
+fn i2s_prescaler() {
+    // 72 MHz / (32 * 47)
+    assert_eq!(
+        prescaler(72_000_000, 48_000, 16, false),
+        (23 | 1 << 8, 47_872)
+    );
+    // 72 MHz / (256 * 6)
+    assert_eq!(
+        prescaler(72_000_000, 48_000, 16, true),
+        (3 | 1 << 9, 46_875)
+    );
+    // 64 MHz / (64 * 125)
+    assert_eq!(
+        prescaler(64_000_000, 8_000, 32, false),
+        (62 | 1 << 8, 8_000)
+    );
+    assert_eq!(
+        prescaler(64_000_000, 16_000, 16, false),
+        (62 | 1 << 8, 16_000)
+    );
+}
@@ -141,7 +141,11 @@ pub mod dma;
 pub mod flash;
 pub mod gpio;
 pub mod i2c;
+#[cfg(any(all(feature = "stm32f103", feature = "high"), feature = "connectivity"))]
+pub mod i2s;
+pub mod onewire;
 pub mod prelude;
+pub mod pwr;
 pub mod qei;
 pub mod rcc;
 pub mod rtc;
@@ -0,0 +1,305 @@
+//! # Bit-banged 1-Wire bus
+//!
+//! Dallas/Maxim 1-Wire master on a single GPIO, e.g. for DS18B20 temperature sensors.
+//!
+//! The pin has to be an open-drain output that can be read back, either
+//! [`Output<OpenDrain>`](crate::gpio::OpenDrain) with an external pull-up resistor
+//! (4.7 kΩ) or for short wires an [`OpenDrainPullUp`](crate::gpio::OpenDrainPullUp) pin,
+//! which needs the CR register of its port and is passed as
+//! [`OpenDrainPullUpLine`](crate::gpio::OpenDrainPullUpLine).
+//!
+//! The time slots are timed with the cycle counter of the [`MonoTimer`], so the
+//! timing is accurate at any system clock. Each time slot runs in a critical section,
+//! interrupts are delayed by up to 70 µs. The 480 µs low phase of a reset runs with
+//! interrupts enabled, only its 70 µs presence window is a critical section.
+//!
+//! ```rust
+//! let timer = MonoTimer::new(cp.DWT, cp.DCB, &clocks);
+//! let pin = gpiob.pb9.into_open_drain_pull_up(&mut gpiob.crh).with_cr(&mut gpiob.crh);
+//! let mut bus = OneWire::new(pin, timer);
+//!
+//! for rom in bus.search_rom() {
+//!     let rom = rom.unwrap();
+//! }
+//! ```
+
+use core::convert::Infallible;
+
+use embedded_hal::digital::{InputPin, OutputPin};
+
+use crate::time::MonoTimer;
+
+/// Searches the ROM codes of all devices
+pub const SEARCH_ROM: u8 = 0xf0;
+/// Reads the ROM code if there is a single device
+pub const READ_ROM: u8 = 0x33;
+/// Addresses the device with the following ROM code
+pub const MATCH_ROM: u8 = 0x55;
+/// Addresses all devices
+pub const SKIP_ROM: u8 = 0xcc;
+
+/// 64 bit ROM code, family code first and CRC last
+pub type Rom = [u8; 8];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Error {
+    /// No device answered the reset pulse
+    NoPresence,
+    /// The bus did not go high after the reset pulse
+    BusLow,
+    /// No device took part in the ROM search
+    SearchFailed,
+    /// A CRC check failed
+    CrcMismatch,
+}
+
+/// 1-Wire bus master
+pub struct OneWire<PIN> {
+    pin: PIN,
+    timer: MonoTimer,
+    cycles_per_us: u32,
+}
+
+impl<PIN> OneWire<PIN>
+where
+    PIN: OutputPin<Error = Infallible> + InputPin<Error = Infallible>,
+{
+    /// Creates the bus master and releases the line
+    pub fn new(mut pin: PIN, timer: MonoTimer) -> Self {
+        pin.set_high().unwrap();
+        let cycles_per_us = timer.frequency().raw() / 1_000_000;
+        assert!(cycles_per_us > 0);
+        Self {
+            pin,
+            timer,
+            cycles_per_us,
+        }
+    }
+
+    /// Sends a reset pulse and waits for the presence pulse of the devices
+    pub fn reset(&mut self) -> Result<(), Error> {
+        self.pin.set_low().unwrap();
+        self.delay_us(480);
+        let present = cortex_m::interrupt::free(|_| {
+            self.pin.set_high().unwrap();
+            self.delay_us(70);
+            self.pin.is_low().unwrap()
+        });
+        self.delay_us(410);
+        if self.pin.is_low().unwrap() {
+            Err(Error::BusLow)
+        } else if present {
+            Ok(())
+        } else {
+            Err(Error::NoPresence)
+        }
+    }
+
+    /// Writes a single bit in a write time slot
+    pub fn write_bit(&mut self, bit: bool) {
+        cortex_m::interrupt::free(|_| {
+            self.pin.set_low().unwrap();
+            if bit {
+                self.delay_us(6);
+                self.pin.set_high().unwrap();
+                self.delay_us(64);
+            } else {
+                self.delay_us(60);
+                self.pin.set_high().unwrap();
+                self.delay_us(10);
+            }
+        })
+    }
+
+    /// Reads a single bit in a read time slot
+    pub fn read_bit(&mut self) -> bool {
+        cortex_m::interrupt::free(|_| {
+            self.pin.set_low().unwrap();
+            self.delay_us(6);
+            self.pin.set_high().unwrap();
+            self.delay_us(9);
+            let bit = self.pin.is_high().unwrap();
+            self.delay_us(55);
+            bit
+        })
+    }
+
+    /// Writes a byte, least significant bit first
+    pub fn write_byte(&mut self, byte: u8) {
+        for i in 0..8 {
+            self.write_bit(byte & (1 << i) != 0);
+        }
+    }
+
+    /// Reads a byte, least significant bit first
+    pub fn read_byte(&mut self) -> u8 {
+        (0..8).fold(0, |byte, i| byte | (self.read_bit() as u8) << i)
+    }
+
+    pub fn write_bytes(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.write_byte(byte);
+        }
+    }
+
+    pub fn read_bytes(&mut self, bytes: &mut [u8]) {
+        for byte in bytes {
+            *byte = self.read_byte();
+        }
+    }
+
+    /// Resets the bus and addresses all devices
+    pub fn skip_rom(&mut self) -> Result<(), Error> {
+        self.reset()?;
+        self.write_byte(SKIP_ROM);
+        Ok(())
+    }
+
+    /// Resets the bus and addresses the device with the ROM code `rom`
+    pub fn match_rom(&mut self, rom: &Rom) -> Result<(), Error> {
+        self.reset()?;
+        self.write_byte(MATCH_ROM);
+        self.write_bytes(rom);
+        Ok(())
+    }
+
+    /// Reads the ROM code of the only device on the bus
+    pub fn read_rom(&mut self) -> Result<Rom, Error> {
+        self.reset()?;
+        self.write_byte(READ_ROM);
+        let mut rom = [0; 8];
+        self.read_bytes(&mut rom);
+        if crc8(&rom) == 0 {
+            Ok(rom)
+        } else {
+            Err(Error::CrcMismatch)
+        }
+    }
+
+    /// Returns an iterator over the ROM codes of all devices on the bus
+    pub fn search_rom(&mut self) -> Search<'_, PIN> {
+        Search {
+            bus: self,
+            rom: [0; 8],
+            last_discrepancy: 0,
+            done: false,
+        }
+    }
+
+    /// Returns the pin
+    pub fn release(self) -> PIN {
+        self.pin
+    }
+
+    fn delay_us(&self, us: u32) {
+        let start = self.timer.now();
+        let cycles = us * self.cycles_per_us;
+        while start.elapsed() < cycles {}
+    }
+}
+
+/// ROM search, see [`OneWire::search_rom`]
+///
+/// Yields the ROM codes in the order of their bit values, least significant bit first.
+pub struct Search<'a, PIN> {
+    bus: &'a mut OneWire<PIN>,
+    rom: Rom,
+    last_discrepancy: u8,
+    done: bool,
+}
+
+impl<PIN> Search<'_, PIN>
+where
+    PIN: OutputPin<Error = Infallible> + InputPin<Error = Infallible>,
+{
+    fn next_rom(&mut self) -> Result<Option<Rom>, Error> {
+        match self.bus.reset() {
+            Ok(()) => {}
+            Err(Error::NoPresence) => return Ok(None),
+            Err(e) => return Err(e),
+        }
+        self.bus.write_byte(SEARCH_ROM);
+
+        let mut last_zero = 0;
+        for n in 1..=64u8 {
+            let (byte, mask) = (usize::from((n - 1) / 8), 1 << ((n - 1) % 8));
+            let bit = self.bus.read_bit();
+            let complement = self.bus.read_bit();
+            let direction = match (bit, complement) {
+                (true, true) => return Err(Error::SearchFailed),
+                (bit, complement) if bit != complement => bit,
+                // Discrepancy, devices with 0 and with 1 at this position
+                _ => {
+                    let direction = if n < self.last_discrepancy {
+                        self.rom[byte] & mask != 0
+                    } else {
+                        n == self.last_discrepancy
+                    };
+                    if !direction {
+                        last_zero = n;
+                    }
+                    direction
+                }
+            };
+            if direction {
+                self.rom[byte] |= mask;
+            } else {
+                self.rom[byte] &= !mask;
+            }
+            self.bus.write_bit(direction);
+        }
+
+        self.last_discrepancy = last_zero;
+        self.done = last_zero == 0;
+        if crc8(&self.rom) == 0 {
+            Ok(Some(self.rom))
+        } else {
+            Err(Error::CrcMismatch)
+        }
+    }
+}
+
+impl<PIN> Iterator for Search<'_, PIN>
+where
+    PIN: OutputPin<Error = Infallible> + InputPin<Error = Infallible>,
+{
+    type Item = Result<Rom, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let rom = self.next_rom();
+        if !matches!(rom, Ok(Some(_))) {
+            self.done = true;
+        }
+        rom.transpose()
+    }
+}
+
+/// Dallas/Maxim CRC8 with polynomial x^8 + x^5 + x^4 + 1
+///
+/// The CRC over data followed by its CRC is 0.
+pub fn crc8(data: &[u8]) -> u8 {
+    data.iter().fold(0, |mut crc, &byte| {
+        crc ^= byte;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                crc >> 1 ^ 0x8c
+            } else {
+                crc >> 1
+            };
+        }
+        crc
+    })
+}
+
+#[test]
+fn onewire_crc8() {
+    assert_eq!(crc8(b"123456789"), 0xa1);
+    // ROM code of a DS18B20
+    let rom = [0x28, 0xff, 0x64, 0x1e, 0x0f, 0x16, 0x04, 0x13];
+    assert_eq!(crc8(&rom[..7]), rom[7]);
+    assert_eq!(crc8(&rom), 0);
+}
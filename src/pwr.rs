@@ -0,0 +1,196 @@
+//! # Power control and low power modes
+//!
+//! | Mode    | Stopped                        | Wakeup sources                                    | Resumes                 |
+//! |---------|--------------------------------|---------------------------------------------------|-------------------------|
+//! | Sleep   | CPU clock                      | Any interrupt                                     | After `WFI`             |
+//! | Stop    | All clocks of the 1.8 V domain | Any EXTI line: pins, PVD, RTC alarm, USB wakeup   | After `WFI`, on the HSI |
+//! | Standby | 1.8 V domain is powered off    | WKUP pin (PA0) rising edge, RTC alarm, NRST, IWDG | With a reset            |
+//!
+//! The RTC second interrupt is not an EXTI line and only wakes the device from Sleep mode,
+//! use the RTC alarm with [`Rtc::listen_alarm_exti`](crate::rtc::Rtc::listen_alarm_exti)
+//! to wake from Stop mode.
+//!
+//! ## Stop mode
+//!
+//! The HSE and the PLL are switched off in Stop mode and the device wakes up running
+//! from the 8 MHz HSI. [`Pwr::enter_stop`] restarts them before it returns, so the
+//! [`Clocks`](crate::rcc::Clocks) of `freeze` stay valid. The wakeup interrupt handler
+//! runs after the clocks are restored. The EXTI pending bits and the RTC alarm flag
+//! have to be cleared before entering Stop mode, otherwise the device wakes up at once.
+//!
+//! ## Standby mode
+//!
+//! SRAM and registers are lost, only the backup domain (RTC and backup registers)
+//! is kept. After wakeup the program starts again, [`Pwr::was_standby_wakeup`]
+//! tells it apart from a power-on reset.
+//!
+//! Besides the RTC alarm, a rising edge on the WKUP pin (PA0) wakes the device
+//! if enabled with [`Pwr::enable_wakeup_pin`]:
+//!
+//! ```rust
+//! let mut pwr = dp.PWR.constrain();
+//! if pwr.was_standby_wakeup() {
+//!     // The button on PA0 was pressed
+//! }
+//! pwr.clear_wakeup_flag();
+//! // ...
+//! pwr.enable_wakeup_pin();
+//! pwr.enter_standby(&mut cp.SCB);
+//! ```
+//!
+//! The debugger connection is lost in Stop and Standby mode unless the
+//! DBGMCU `DBG_STOP` and `DBG_STANDBY` bits are set.
+//!
+//! ```rust
+//! let mut pwr = dp.PWR.constrain();
+//! let mut backup_domain = rcc.bkp.constrain(dp.BKP, &mut pwr);
+//! let mut rtc = Rtc::new(dp.RTC, &mut backup_domain);
+//! rtc.listen_alarm();
+//! rtc.listen_alarm_exti(&mut dp.EXTI);
+//!
+//! loop {
+//!     rtc.clear_alarm_exti(&mut dp.EXTI);
+//!     rtc.set_alarm(rtc.current_time() + 10);
+//!     pwr.enter_stop(&mut cp.SCB, StopMode::LowPowerRegulator).unwrap();
+//!     // 10 s later, running at the configured clocks again
+//! }
+//! ```
+
+use core::ops::{Deref, DerefMut};
+
+use cortex_m::peripheral::SCB;
+
+use crate::pac::{PWR, RCC};
+use crate::rcc::{self, ClockSources, Enable};
+
+/// Extension trait that constrains the `PWR` peripheral
+pub trait PwrExt {
+    /// Enables the clock of the `PWR` peripheral
+    fn constrain(self) -> Pwr;
+}
+
+impl PwrExt for PWR {
+    fn constrain(self) -> Pwr {
+        let rcc = unsafe { &*RCC::ptr() };
+        PWR::enable(rcc);
+        Pwr { pwr: self }
+    }
+}
+
+/// Voltage regulator state in Stop mode
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StopMode {
+    /// The regulator stays in normal mode, for a faster wakeup
+    MainRegulator,
+    /// The regulator runs in low-power mode, for a lower current
+    LowPowerRegulator,
+}
+
+/// Power control
+///
+/// Dereferences to the `PWR` peripheral, e.g. for `BKP::constrain`.
+pub struct Pwr {
+    pwr: PWR,
+}
+
+impl Pwr {
+    /// Enters Sleep mode until an interrupt occurs
+    ///
+    /// Only the CPU clock is stopped, the peripherals keep running.
+    pub fn enter_sleep(&mut self, scb: &mut SCB) {
+        scb.clear_sleepdeep();
+        cortex_m::asm::dsb();
+        cortex_m::asm::wfi();
+    }
+
+    /// Enters Stop mode until an EXTI line is triggered
+    ///
+    /// The HSE and PLL are restarted after wakeup before the pending interrupt
+    /// is handled. Returns an error if they do not start again, then the device
+    /// keeps running from the HSI.
+    pub fn enter_stop(&mut self, scb: &mut SCB, mode: StopMode) -> Result<(), rcc::Error> {
+        cortex_m::interrupt::free(|_| {
+            let clocks = ClockSources::save();
+            self.pwr.cr().modify(|_, w| {
+                w.pdds()
+                    .stop_mode()
+                    .lpds()
+                    .bit(mode == StopMode::LowPowerRegulator)
+                    .cwuf()
+                    .set_bit()
+            });
+            scb.set_sleepdeep();
+            // A pending interrupt wakes the core even while interrupts are masked
+            cortex_m::asm::dsb();
+            cortex_m::asm::wfi();
+            scb.clear_sleepdeep();
+            clocks.restore()
+        })
+    }
+
+    /// Enters Standby mode, the device is reset on wakeup
+    pub fn enter_standby(&mut self, scb: &mut SCB) -> ! {
+        self.pwr
+            .cr()
+            .modify(|_, w| w.pdds().standby_mode().cwuf().set_bit());
+        scb.set_sleepdeep();
+        loop {
+            cortex_m::asm::dsb();
+            cortex_m::asm::wfi();
+        }
+    }
+
+    /// Enables waking up from Standby mode by the WKUP pin (PA0)
+    ///
+    /// Only a rising edge wakes the device, e.g. from a button to VDD. While enabled, the
+    /// pin is an input with pull-down in any mode, overriding its GPIO configuration.
+    /// If the pin is already high, enabling it triggers a wakeup event at once.
+    pub fn enable_wakeup_pin(&mut self) {
+        self.pwr.csr().modify(|_, w| w.ewup().set_bit());
+    }
+
+    /// Releases the WKUP pin (PA0) to its GPIO configuration
+    pub fn disable_wakeup_pin(&mut self) {
+        self.pwr.csr().modify(|_, w| w.ewup().clear_bit());
+    }
+
+    /// Returns `true` if the device was reset by a wakeup from Standby mode
+    /// instead of a power-on or other reset
+    ///
+    /// The flag is kept until [`clear_wakeup_flag`](Self::clear_wakeup_flag) is called.
+    pub fn was_standby_wakeup(&self) -> bool {
+        self.pwr.csr().read().sbf().bit_is_set()
+    }
+
+    /// Returns `true` if a wakeup event from the WKUP pin or the RTC alarm occurred
+    pub fn is_wakeup_flag_set(&self) -> bool {
+        self.pwr.csr().read().wuf().bit_is_set()
+    }
+
+    /// Clears the wakeup (WUF) and standby (SBF) flags
+    ///
+    /// Call it on boot after checking [`was_standby_wakeup`](Self::was_standby_wakeup).
+    pub fn clear_wakeup_flag(&mut self) {
+        self.pwr
+            .cr()
+            .modify(|_, w| w.cwuf().set_bit().csbf().set_bit());
+    }
+
+    /// Releases the `PWR` peripheral
+    pub fn release(self) -> PWR {
+        self.pwr
+    }
+}
+
+impl Deref for Pwr {
+    type Target = PWR;
+    fn deref(&self) -> &Self::Target {
+        &self.pwr
+    }
+}
+
+impl DerefMut for Pwr {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.pwr
+    }
+}
@@ -3,6 +3,17 @@
 
   NOTE: In some cases you need to specify remap you need, especially for TIM2
   (see [Alternate function remapping](super::timer)):
+
+  ## Position tracking
+
+  The hardware counter is only 16 bits wide. To track positions beyond its range
+  call [`Qei::handle_overflow`] from the timer interrupt after [`Qei::listen_overflow`]
+  or poll it often enough. It counts the wraps of the counter, [`Qei::position`]
+  combines them with the counter into a 64 bit position.
+
+  The direction of a wrap is derived from the counter value when it is handled,
+  so `handle_overflow` has to be called within half a counter period
+  (`auto_reload_value / 2` counts) after the wrap.
 */
 use core::marker::PhantomData;
 
@@ -13,7 +24,7 @@ pub use hal::Direction;
 use crate::afio::MAPR;
 
 use crate::timer::pwm_input::Pins;
-use crate::timer::{pins::sealed::Remap, Timer};
+use crate::timer::{pins::sealed::Remap, Event, Timer};
 
 /// SMS (Slave Mode Selection) register
 #[derive(Copy, Clone, Debug)]
@@ -63,9 +74,24 @@ impl Default for QeiOptions {
 pub struct Qei<TIM, REMAP, PINS> {
     tim: TIM,
     pins: PINS,
+    wraps: i64,
     _remap: PhantomData<REMAP>,
 }
 
+/// Returns `1` if the counter overflowed to `count`, `-1` if it underflowed
+fn wrap_direction(count: u16, arr: u16) -> i64 {
+    if count <= arr / 2 {
+        1
+    } else {
+        -1
+    }
+}
+
+/// Combines the counted `wraps` of a counter with reload value `arr` and its `count`
+fn extended_position(wraps: i64, count: u16, arr: u16) -> i64 {
+    wraps * (arr as i64 + 1) + count as i64
+}
+
 #[cfg(any(feature = "stm32f100", feature = "stm32f103", feature = "connectivity"))]
 impl Timer<pac::TIM1> {
     pub fn qei<REMAP, PINS>(
@@ -164,6 +190,7 @@ macro_rules! hal {
                 Qei {
                     tim,
                     pins,
+                    wraps: 0,
                     _remap: PhantomData,
                 }
             }
@@ -171,22 +198,83 @@ macro_rules! hal {
             pub fn release(self) -> ($TIMX, PINS) {
                 (self.tim, self.pins)
             }
-        }
-
-        impl<REMAP, PINS> hal::Qei for Qei<$TIMX, REMAP, PINS> {
-            type Count = u16;
 
-            fn count(&self) -> u16 {
+            /// Current value of the 16 bit counter
+            pub fn count(&self) -> u16 {
                 self.tim.cnt().read().cnt().bits()
             }
 
-            fn direction(&self) -> Direction {
+            /// Direction of the last counted edge
+            pub fn direction(&self) -> Direction {
                 if self.tim.cr1().read().dir().bit_is_clear() {
                     Direction::Upcounting
                 } else {
                     Direction::Downcounting
                 }
             }
+
+            /// Enables the update interrupt raised on counter overflow and underflow
+            pub fn listen_overflow(&mut self) {
+                self.tim.dier().modify(|_, w| w.uie().set_bit());
+            }
+
+            pub fn unlisten_overflow(&mut self) {
+                self.tim.dier().modify(|_, w| w.uie().clear_bit());
+            }
+
+            /// Accounts a pending counter overflow or underflow for [`position`](Self::position).
+            ///
+            /// Returns `true` if there was one. Call it from the timer interrupt or poll it
+            /// at least every half counter period.
+            pub fn handle_overflow(&mut self) -> bool {
+                if self.tim.sr().read().uif().bit_is_clear() {
+                    return false;
+                }
+                self.tim
+                    .sr()
+                    .write(|w| unsafe { w.bits(0xffff & !Event::Update.bits()) });
+                let arr = self.tim.arr().read().arr().bits();
+                self.wraps += wrap_direction(self.count(), arr);
+                true
+            }
+
+            /// Position extended beyond the 16 bit counter range by the handled overflows
+            pub fn position(&self) -> i64 {
+                let (count, pending) = loop {
+                    let pending = self.tim.sr().read().uif().bit_is_set();
+                    let count = self.count();
+                    if pending == self.tim.sr().read().uif().bit_is_set() {
+                        break (count, pending);
+                    }
+                };
+                let arr = self.tim.arr().read().arr().bits();
+                let mut wraps = self.wraps;
+                if pending {
+                    wraps += wrap_direction(count, arr);
+                }
+                extended_position(wraps, count, arr)
+            }
+
+            /// Sets the counter and the position to 0
+            pub fn reset_position(&mut self) {
+                self.tim.cnt().reset();
+                self.tim
+                    .sr()
+                    .write(|w| unsafe { w.bits(0xffff & !Event::Update.bits()) });
+                self.wraps = 0;
+            }
+        }
+
+        impl<REMAP, PINS> hal::Qei for Qei<$TIMX, REMAP, PINS> {
+            type Count = u16;
+
+            fn count(&self) -> u16 {
+                Self::count(self)
+            }
+
+            fn direction(&self) -> Direction {
+                Self::direction(self)
+            }
         }
     };
 }
@@ -199,3 +287,20 @@ hal!(pac::TIM3: _tim3, tim3en, tim3rst);
 
 #[cfg(feature = "medium")]
 hal!(pac::TIM4: _tim4, tim4en, tim4rst);
+
+#[test]
+fn qei_wrap_direction() {
+    // up-wrap from arr to a small count, down-wrap from 0 to a large count
+    assert_eq!(wrap_direction(0, 999), 1);
+    assert_eq!(wrap_direction(3, 999), 1);
+    assert_eq!(wrap_direction(998, 999), -1);
+    assert_eq!(wrap_direction(999, u16::MAX), 1);
+    assert_eq!(wrap_direction(u16::MAX, u16::MAX), -1);
+
+    // without wraps the position is the count, each wrap adds or removes arr + 1
+    assert_eq!(extended_position(0, 500, 999), 500);
+    assert_eq!(extended_position(wrap_direction(3, 999), 3, 999), 1003);
+    assert_eq!(extended_position(wrap_direction(998, 999), 998, 999), -2);
+    assert_eq!(extended_position(-2, u16::MAX, u16::MAX), -65537);
+    assert_eq!(extended_position(70_000, 0, u16::MAX), 70_000 << 16);
+}
@@ -3,7 +3,6 @@
 use crate::pac::{rcc, PWR, RCC};
 
 use crate::flash::ACR;
-#[cfg(any(feature = "stm32f103", feature = "connectivity"))]
 use crate::time::MHz;
 use fugit::{HertzU32 as Hertz, RateExtU32};
 
@@ -11,6 +10,20 @@ use crate::backup_domain::BackupDomain;
 
 mod enable;
 
+// The HSE startup time is a few milliseconds, see the datasheet
+const HSE_STARTUP_TIMEOUT_MS: u32 = 100;
+const PLL_LOCK_TIMEOUT_MS: u32 = 10;
+
+/// Clock configuration error
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Error {
+    /// The HSE oscillator did not start, e.g. because the crystal is missing or broken
+    HseTimeout,
+    /// The PLL did not lock
+    PllTimeout,
+}
+
 /// Extension trait that constrains the `RCC` peripheral
 pub trait RccExt {
     /// Constrains the `RCC` peripheral so it plays nicely with the other abstractions
@@ -28,6 +41,7 @@ impl RccExt for RCC {
                 pclk2: None,
                 sysclk: None,
                 adcclk: None,
+                mco: McoSource::NoClock,
             },
             bkp: BKP,
         }
@@ -105,7 +119,7 @@ const HSI: u32 = 8_000_000; // Hz
 ///
 /// **NOTE**: Currently, it is not guaranteed that the exact frequencies selected will be
 /// used, only frequencies close to it.
-#[derive(Debug, Default, PartialEq, Eq)]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
 pub struct CFGR {
     hse: Option<u32>,
     hse_bypass: bool,
@@ -114,6 +128,7 @@ pub struct CFGR {
     pclk2: Option<u32>,
     sysclk: Option<u32>,
     adcclk: Option<u32>,
+    mco: McoSource,
 }
 
 impl CFGR {
@@ -175,10 +190,71 @@ impl CFGR {
         self
     }
 
+    /// Runs all clocks directly from the 8 MHz HSI with the PLL off, the lowest power
+    /// configuration that keeps the full speed of the internal oscillator.
+    ///
+    /// Replaces all frequencies set before. The resulting clocks are
+    ///
+    /// - SYSCLK, HCLK, PCLK1 and PCLK2: 8 MHz
+    /// - timer clocks: 8 MHz
+    /// - ADCCLK: 4 MHz
+    /// - USB: not available, it needs the PLL
+    ///
+    /// The flash runs without wait states.
+    pub fn hsi_8mhz(self) -> Self {
+        Self {
+            hse: None,
+            hse_bypass: false,
+            hclk: None,
+            pclk1: None,
+            pclk2: None,
+            sysclk: None,
+            adcclk: Some(4_000_000),
+            ..self
+        }
+    }
+
+    /// Like [`hsi_8mhz`](Self::hsi_8mhz), but divides HCLK by 4 to save more power.
+    ///
+    /// The resulting clocks are
+    ///
+    /// - SYSCLK: 8 MHz
+    /// - HCLK, PCLK1 and PCLK2: 2 MHz
+    /// - timer clocks: 2 MHz
+    /// - ADCCLK: 1 MHz
+    /// - USB: not available, it needs the PLL
+    ///
+    /// The flash runs without wait states.
+    pub fn hsi_2mhz(self) -> Self {
+        Self {
+            hclk: Some(2_000_000),
+            adcclk: Some(1_000_000),
+            ..self.hsi_8mhz()
+        }
+    }
+
+    /// Outputs `source` on the MCO pin PA8.
+    ///
+    /// The pin has to be configured by `into_alternate_push_pull`, which selects the
+    /// 50 MHz output speed. The pin can not output more than 50 MHz, so the PLL clock
+    /// is only available divided by 2.
+    ///
+    /// ```rust
+    /// let clocks = rcc.cfgr.mco(McoSource::PllDiv2).freeze(&mut flash.acr);
+    /// let _mco = gpioa.pa8.into_alternate_push_pull(&mut gpioa.crh);
+    /// ```
+    pub fn mco(mut self, source: McoSource) -> Self {
+        self.mco = source;
+        self
+    }
+
     /// Applies the clock configuration and returns a `Clocks` struct that signifies that the
     /// clocks are frozen, and contains the frequencies used. After this function is called,
     /// the clocks can not change
     ///
+    /// Panics if the HSE oscillator does not start or the PLL does not lock, see
+    /// [`try_freeze`](Self::try_freeze).
+    ///
     /// Usage:
     ///
     /// ```rust
@@ -187,20 +263,48 @@ impl CFGR {
     /// let mut rcc = dp.RCC.constrain();
     /// let clocks = rcc.cfgr.freeze(&mut flash.acr);
     /// ```
-
     #[inline(always)]
     pub fn freeze(self, acr: &mut ACR) -> Clocks {
         let cfg = Config::from_cfgr(self);
-        Self::_freeze_with_config(cfg, acr)
+        Self::_freeze_with_config(cfg, acr).expect("clock configuration failed")
+    }
+
+    /// Applies the clock configuration like [`freeze`](Self::freeze), but returns an error
+    /// instead of panicking if the HSE oscillator does not start within 100 ms or the PLL
+    /// does not lock within 10 ms.
+    ///
+    /// On error the system keeps running from the HSI and the HSE and PLL are turned off
+    /// again. The unchanged `CFGR` is returned with the error, e.g. to retry, or a fallback
+    /// configuration can be applied.
+    ///
+    /// ```rust
+    /// let clocks = match rcc.cfgr.use_hse(8.MHz()).sysclk(72.MHz()).try_freeze(&mut flash.acr) {
+    ///     Ok(clocks) => clocks,
+    ///     Err(_) => CFGR::default().sysclk(64.MHz()).freeze(&mut flash.acr),
+    /// };
+    /// ```
+    pub fn try_freeze(self, acr: &mut ACR) -> Result<Clocks, (Self, Error)> {
+        let cfg = Config::from_cfgr(self.clone());
+        Self::_freeze_with_config(cfg, acr).map_err(|e| (self, e))
     }
 
     #[inline(always)]
     pub fn freeze_with_config(self, cfg: Config, acr: &mut ACR) -> Clocks {
-        Self::_freeze_with_config(cfg, acr)
+        Self::_freeze_with_config(cfg, acr).expect("clock configuration failed")
+    }
+
+    /// Like [`freeze_with_config`](Self::freeze_with_config), but returns an error instead
+    /// of panicking, see [`try_freeze`](Self::try_freeze)
+    pub fn try_freeze_with_config(
+        self,
+        cfg: Config,
+        acr: &mut ACR,
+    ) -> Result<Clocks, (Self, Error)> {
+        Self::_freeze_with_config(cfg, acr).map_err(|e| (self, e))
     }
 
     #[allow(unused_variables)]
-    fn _freeze_with_config(cfg: Config, acr: &mut ACR) -> Clocks {
+    fn _freeze_with_config(cfg: Config, acr: &mut ACR) -> Result<Clocks, Error> {
         let clocks = cfg.get_clocks();
         // adjust flash wait states
         #[cfg(any(feature = "stm32f103", feature = "connectivity"))]
@@ -215,6 +319,10 @@ impl CFGR {
                 })
             })
         }
+        // half-cycle flash access only works up to 8 MHz
+        if clocks.sysclk > MHz(8) {
+            acr.acr().modify(|_, w| w.hlfcya().clear_bit());
+        }
 
         let rcc = unsafe { &*RCC::ptr() };
 
@@ -228,7 +336,13 @@ impl CFGR {
                 w.hseon().set_bit()
             });
 
-            while rcc.cr().read().hserdy().bit_is_clear() {}
+            if !wait_ready(
+                || rcc.cr().read().hserdy().bit_is_set(),
+                HSE_STARTUP_TIMEOUT_MS,
+            ) {
+                rcc.cr().modify(|_, w| w.hseon().clear_bit());
+                return Err(Error::HseTimeout);
+            }
         }
 
         if let Some(pllmul_bits) = cfg.pllmul {
@@ -241,7 +355,14 @@ impl CFGR {
 
             rcc.cr().modify(|_, w| w.pllon().set_bit());
 
-            while rcc.cr().read().pllrdy().bit_is_clear() {}
+            if !wait_ready(
+                || rcc.cr().read().pllrdy().bit_is_set(),
+                PLL_LOCK_TIMEOUT_MS,
+            ) {
+                rcc.cr()
+                    .modify(|_, w| w.pllon().clear_bit().hseon().clear_bit());
+                return Err(Error::PllTimeout);
+            }
         }
 
         // set prescalers and clock source
@@ -312,7 +433,135 @@ impl CFGR {
                 })
         });
 
-        clocks
+        rcc.cfgr()
+            .modify(|r, w| unsafe { w.bits(r.bits() & !(0b1111 << 24) | (cfg.mco as u32) << 24) });
+
+        Ok(clocks)
+    }
+}
+
+/// Polls `ready` for `timeout_ms`, assuming the CPU still runs from the HSI
+fn wait_ready(ready: impl Fn() -> bool, timeout_ms: u32) -> bool {
+    for _ in 0..timeout_ms * 10 {
+        if ready() {
+            return true;
+        }
+        cortex_m::asm::delay(HSI / 10_000);
+    }
+    ready()
+}
+
+/// Cause of the last reset, from the flags of the `RCC_CSR` register
+///
+/// Every reset pulls NRST low, so the pin reset flag is set for all causes. If several
+/// flags are set, the most specific one is returned in the order of the variants.
+/// The F1 has no brownout flag, a brownout is reported as [`ResetReason::PowerOn`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ResetReason {
+    /// Entering Stop or Standby mode while it is disabled by the `nRST_STOP` or
+    /// `nRST_STDBY` option bits
+    LowPower,
+    /// Window watchdog (WWDG) timeout or early write
+    WindowWatchdog,
+    /// Independent watchdog (IWDG) timeout
+    IndependentWatchdog,
+    /// `SCB::sys_reset` (SYSRESETREQ)
+    Software,
+    /// Power-on or power-down reset, including a brownout
+    PowerOn,
+    /// External reset on the NRST pin
+    Pin,
+    /// No flag is set, e.g. after [`clear_reset_flags`] without a reset
+    Unknown,
+}
+
+impl ResetReason {
+    const fn from_csr(csr: u32) -> Self {
+        if csr & 1 << 31 != 0 {
+            Self::LowPower
+        } else if csr & 1 << 30 != 0 {
+            Self::WindowWatchdog
+        } else if csr & 1 << 29 != 0 {
+            Self::IndependentWatchdog
+        } else if csr & 1 << 28 != 0 {
+            Self::Software
+        } else if csr & 1 << 27 != 0 {
+            Self::PowerOn
+        } else if csr & 1 << 26 != 0 {
+            Self::Pin
+        } else {
+            Self::Unknown
+        }
+    }
+}
+
+/// Returns the cause of the last reset
+///
+/// The flags are kept over resets until they are cleared by [`clear_reset_flags`],
+/// reading them does not clear them.
+///
+/// ```rust
+/// let reason = rcc::reset_reason();
+/// rcc::clear_reset_flags();
+/// ```
+pub fn reset_reason() -> ResetReason {
+    let rcc = unsafe { &*RCC::ptr() };
+    ResetReason::from_csr(rcc.csr().read().bits())
+}
+
+/// Clears the reset flags (RMVF), so the next reset reports only its own cause
+pub fn clear_reset_flags() {
+    let rcc = unsafe { &*RCC::ptr() };
+    rcc.csr().modify(|_, w| w.rmvf().set_bit());
+}
+
+/// Clock sources and system clock selected before entering Stop mode
+pub(crate) struct ClockSources {
+    hse: bool,
+    pll: bool,
+    sw: u8,
+}
+
+impl ClockSources {
+    pub(crate) fn save() -> Self {
+        let rcc = unsafe { &*RCC::ptr() };
+        let cr = rcc.cr().read();
+        Self {
+            hse: cr.hseon().bit_is_set(),
+            pll: cr.pllon().bit_is_set(),
+            sw: rcc.cfgr().read().sws().bits(),
+        }
+    }
+
+    /// Restarts the HSE and PLL, which are stopped in Stop mode, and selects
+    /// the system clock again. The prescalers keep their values in Stop mode.
+    pub(crate) fn restore(&self) -> Result<(), Error> {
+        let rcc = unsafe { &*RCC::ptr() };
+        if self.hse {
+            rcc.cr().modify(|_, w| w.hseon().set_bit());
+            if !wait_ready(
+                || rcc.cr().read().hserdy().bit_is_set(),
+                HSE_STARTUP_TIMEOUT_MS,
+            ) {
+                rcc.cr().modify(|_, w| w.hseon().clear_bit());
+                return Err(Error::HseTimeout);
+            }
+        }
+        if self.pll {
+            rcc.cr().modify(|_, w| w.pllon().set_bit());
+            if !wait_ready(
+                || rcc.cr().read().pllrdy().bit_is_set(),
+                PLL_LOCK_TIMEOUT_MS,
+            ) {
+                rcc.cr()
+                    .modify(|_, w| w.pllon().clear_bit().hseon().clear_bit());
+                return Err(Error::PllTimeout);
+            }
+        }
+        rcc.cfgr()
+            .modify(|r, w| unsafe { w.bits(r.bits() & !0b11 | self.sw as u32) });
+        while rcc.cfgr().read().sws().bits() != self.sw {}
+        Ok(())
     }
 }
 
@@ -504,6 +753,7 @@ pub struct Config {
     pub usbpre: UsbPre,
     pub adcpre: AdcPre,
     pub allow_overclock: bool,
+    pub mco: McoSource,
 }
 
 impl Default for Config {
@@ -519,10 +769,41 @@ impl Default for Config {
             usbpre: UsbPre::Div1_5,
             adcpre: AdcPre::Div2,
             allow_overclock: false,
+            mco: McoSource::NoClock,
         }
     }
 }
 
+/// Clock output on the MCO pin
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum McoSource {
+    /// No output
+    NoClock = 0b0000,
+    Sysclk = 0b0100,
+    Hsi = 0b0101,
+    Hse = 0b0110,
+    /// PLL clock divided by 2
+    PllDiv2 = 0b0111,
+    #[cfg(feature = "connectivity")]
+    Pll2 = 0b1000,
+    /// PLL3 clock divided by 2
+    #[cfg(feature = "connectivity")]
+    Pll3Div2 = 0b1001,
+    /// External 3-25 MHz oscillator (XT1) for Ethernet
+    #[cfg(feature = "connectivity")]
+    Xt1 = 0b1010,
+    /// PLL3 clock for Ethernet
+    #[cfg(feature = "connectivity")]
+    Pll3 = 0b1011,
+}
+
+impl Default for McoSource {
+    fn default() -> Self {
+        Self::NoClock
+    }
+}
+
 #[repr(u8)]
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum HPre {
@@ -680,6 +961,7 @@ impl Config {
             usbpre,
             adcpre: apre_bits,
             allow_overclock: false,
+            mco: cfgr.mco,
         }
     }
 
@@ -715,11 +997,12 @@ impl Config {
         let adcclk = pclk2 / (apre as u32);
 
         // the USB clock is only valid if an external crystal is used, the PLL is enabled, and the
-        // PLL output frequency is a supported one.
+        // PLL output frequency divided by the USB prescaler is 48 MHz.
         #[cfg(any(feature = "stm32f103", feature = "connectivity"))]
         let usbclk_valid = matches!(
-            (self.hse, self.pllmul, sysclk),
-            (Some(_), Some(_), 72_000_000) | (Some(_), Some(_), 48_000_000)
+            (self.hse, self.pllmul, sysclk, self.usbpre),
+            (Some(_), Some(_), 72_000_000, UsbPre::Div1_5)
+                | (Some(_), Some(_), 48_000_000, UsbPre::Div1)
         );
 
         assert!(
@@ -764,6 +1047,7 @@ fn rcc_config_usb() {
         usbpre: UsbPre::Div1,
         adcpre: AdcPre::Div8,
         allow_overclock: false,
+        mco: McoSource::NoClock,
     };
     assert_eq!(config, config_expected);
 
@@ -781,3 +1065,52 @@ fn rcc_config_usb() {
     };
     assert_eq!(clocks, clocks_expected);
 }
+
+#[test]
+fn rcc_config_hsi_presets() {
+    let config = Config::from_cfgr(CFGR::default().use_hse(8.MHz()).sysclk(72.MHz()).hsi_8mhz());
+    assert_eq!(config.hse, None);
+    assert_eq!(config.pllmul, None);
+    let clocks = config.get_clocks();
+    assert_eq!(clocks.sysclk(), MHz(8));
+    assert_eq!(clocks.hclk(), MHz(8));
+    assert_eq!(clocks.pclk1(), MHz(8));
+    assert_eq!(clocks.pclk2(), MHz(8));
+    assert_eq!(clocks.pclk1_tim(), MHz(8));
+    assert_eq!(clocks.adcclk(), MHz(4));
+
+    let clocks = Config::from_cfgr(CFGR::default().hsi_2mhz()).get_clocks();
+    assert_eq!(clocks.sysclk(), MHz(8));
+    assert_eq!(clocks.hclk(), MHz(2));
+    assert_eq!(clocks.pclk1(), MHz(2));
+    assert_eq!(clocks.pclk2_tim(), MHz(2));
+    assert_eq!(clocks.adcclk(), MHz(1));
+}
+
+#[test]
+#[cfg(any(feature = "stm32f103", feature = "connectivity"))]
+fn rcc_config_usb_prescaler() {
+    let mut config = Config::from_cfgr(CFGR::default().use_hse(8.MHz()).sysclk(72.MHz()));
+    assert!(config.get_clocks().usbclk_valid);
+
+    config.usbpre = UsbPre::Div1;
+    assert!(!config.get_clocks().usbclk_valid);
+}
+
+#[test]
+fn rcc_reset_reason() {
+    assert_eq!(ResetReason::from_csr(0), ResetReason::Unknown);
+    assert_eq!(ResetReason::from_csr(0x0c00_0000), ResetReason::PowerOn);
+    assert_eq!(ResetReason::from_csr(0x0400_0000), ResetReason::Pin);
+    assert_eq!(
+        ResetReason::from_csr(0x2400_0000),
+        ResetReason::IndependentWatchdog
+    );
+    // A software reset after a power-on reset without clearing the flags
+    assert_eq!(ResetReason::from_csr(0x1c00_0000), ResetReason::Software);
+    // LSION is not a reset flag
+    assert_eq!(
+        ResetReason::from_csr(0x4400_0001),
+        ResetReason::WindowWatchdog
+    );
+}
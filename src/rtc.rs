@@ -1,9 +1,16 @@
 /*!
   Real time clock
+
+  The RTC counts seconds since an epoch, [`Rtc::now`] and [`Rtc::set_date_time`] convert
+  the counter from and to a calendar [`DateTime`]. The epoch is the Unix epoch
+  (1970-01-01 00:00:00) unless changed by [`Rtc::set_epoch`].
+
+  With the `chrono` feature, [`DateTime`] converts from and to `chrono::NaiveDateTime`.
 */
-use crate::pac::{RCC, RTC};
+use crate::pac::{EXTI, RCC, RTC};
 
 use crate::backup_domain::BackupDomain;
+use crate::rcc::Clocks;
 use crate::time::{Hertz, Hz};
 
 use core::convert::Infallible;
@@ -12,6 +19,8 @@ use core::marker::PhantomData;
 // The LSE runs at at 32 768 hertz unless an external clock is provided
 const LSE_HERTZ: Hertz = Hz(32_768);
 const LSI_HERTZ: Hertz = Hz(40_000);
+// Typical LSE startup time is 1 s, leave some margin for slow crystals
+const LSE_STARTUP_TIMEOUT_MS: u32 = 3_000;
 
 /// RTC clock source HSE clock divided by 128 (type state)
 pub struct RtcClkHseDiv128;
@@ -20,6 +29,13 @@ pub struct RtcClkLse;
 /// RTC clock source LSI oscillator clock (type state)
 pub struct RtcClkLsi;
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Error {
+    /// The LSE crystal did not start within the timeout
+    LseTimeout,
+}
+
 pub enum RestoredOrNewRtc<CS> {
     Restored(Rtc<CS>),
     New(Rtc<CS>),
@@ -41,9 +57,9 @@ pub enum RestoredOrNewRtc<CS> {
   [examples/rtc.rs]: https://github.com/stm32-rs/stm32f1xx-hal/blob/v0.7.0/examples/rtc.rs
   [examples/blinky_rtc.rs]: https://github.com/stm32-rs/stm32f1xx-hal/blob/v0.7.0/examples/blinky_rtc.rs
 */
-
 pub struct Rtc<CS = RtcClkLse> {
     regs: RTC,
+    epoch: DateTime,
     _clock_source: PhantomData<CS>,
 }
 
@@ -66,6 +82,7 @@ impl Rtc<RtcClkLse> {
     pub fn new(regs: RTC, bkp: &mut BackupDomain) -> Self {
         let mut result = Rtc {
             regs,
+            epoch: DateTime::UNIX_EPOCH,
             _clock_source: PhantomData,
         };
 
@@ -84,6 +101,39 @@ impl Rtc<RtcClkLse> {
         result
     }
 
+    /// Initialises the RTC like [`new`](Rtc::<RtcClkLse>::new), but waits for the LSE crystal
+    /// to start first.
+    ///
+    /// Write access to the backup domain is enabled by the `BackupDomain`. Returns
+    /// [`Error::LseTimeout`] and stops the oscillator again if the crystal is not ready
+    /// within 3 seconds, e.g. because it is missing or broken. `clocks` is only used to
+    /// measure the timeout.
+    pub fn new_lse(regs: RTC, bkp: &mut BackupDomain, clocks: &Clocks) -> Result<Self, Error> {
+        Self::start_lse(bkp, clocks)?;
+        Ok(Self::new(regs, bkp))
+    }
+
+    /// Starts the LSE oscillator and waits until it is ready
+    fn start_lse(_bkp: &mut BackupDomain, clocks: &Clocks) -> Result<(), Error> {
+        // NOTE: Safe RCC access because we are only accessing bdcr
+        // and we have a &mut on BackupDomain
+        let rcc = unsafe { &*RCC::ptr() };
+        if rcc.bdcr().read().lserdy().bit_is_set() {
+            return Ok(());
+        }
+        rcc.bdcr().modify(|_, w| w.lseon().set_bit());
+
+        let cycles_per_ms = clocks.sysclk().raw() / 1000;
+        for _ in 0..LSE_STARTUP_TIMEOUT_MS {
+            if rcc.bdcr().read().lserdy().bit_is_set() {
+                return Ok(());
+            }
+            cortex_m::asm::delay(cycles_per_ms);
+        }
+        rcc.bdcr().modify(|_, w| w.lseon().clear_bit());
+        Err(Error::LseTimeout)
+    }
+
     /// Tries to obtain currently running RTC to prevent a reset in case it was running from VBAT.
     /// If the RTC is not running, or is not LSE, it will be reinitialized.
     ///
@@ -104,6 +154,7 @@ impl Rtc<RtcClkLse> {
         } else {
             RestoredOrNewRtc::Restored(Rtc {
                 regs,
+                epoch: DateTime::UNIX_EPOCH,
                 _clock_source: PhantomData,
             })
         }
@@ -151,6 +202,7 @@ impl Rtc<RtcClkLsi> {
     pub fn new_lsi(regs: RTC, bkp: &mut BackupDomain) -> Self {
         let mut result = Rtc {
             regs,
+            epoch: DateTime::UNIX_EPOCH,
             _clock_source: PhantomData,
         };
 
@@ -177,6 +229,7 @@ impl Rtc<RtcClkLsi> {
         } else {
             RestoredOrNewRtc::Restored(Rtc {
                 regs,
+                epoch: DateTime::UNIX_EPOCH,
                 _clock_source: PhantomData,
             })
         }
@@ -226,6 +279,7 @@ impl Rtc<RtcClkHseDiv128> {
     pub fn new_hse(regs: RTC, bkp: &mut BackupDomain, hse: Hertz) -> Self {
         let mut result = Rtc {
             regs,
+            epoch: DateTime::UNIX_EPOCH,
             _clock_source: PhantomData,
         };
 
@@ -256,6 +310,7 @@ impl Rtc<RtcClkHseDiv128> {
         } else {
             RestoredOrNewRtc::Restored(Rtc {
                 regs,
+                epoch: DateTime::UNIX_EPOCH,
                 _clock_source: PhantomData,
             })
         }
@@ -321,7 +376,7 @@ impl<CS> Rtc<CS> {
     pub fn set_alarm(&mut self, counter_value: u32) {
         // Set alarm time
         // See section 18.3.5 for explanation
-        let alarm_value = counter_value - 1;
+        let alarm_value = counter_value.wrapping_sub(1);
 
         // TODO: Remove this `allow` once these fields are made safe for stm32f100
         #[allow(unused_unsafe)]
@@ -355,6 +410,26 @@ impl<CS> Rtc<CS> {
         })
     }
 
+    /// Routes the alarm to the rising edge of EXTI line 17, which raises the RTCALARM
+    /// interrupt and wakes the device from Stop mode. The RTC interrupt has to be
+    /// enabled too by [`listen_alarm`](Self::listen_alarm).
+    pub fn listen_alarm_exti(&mut self, exti: &mut EXTI) {
+        exti.rtsr().modify(|_, w| w.tr17().set_bit());
+        exti.imr().modify(|_, w| w.mr17().set_bit());
+    }
+
+    /// Disconnects the alarm from EXTI line 17
+    pub fn unlisten_alarm_exti(&mut self, exti: &mut EXTI) {
+        exti.imr().modify(|_, w| w.mr17().clear_bit());
+        exti.rtsr().modify(|_, w| w.tr17().clear_bit());
+    }
+
+    /// Clears the alarm flag and the pending EXTI line 17, call it from the RTCALARM interrupt
+    pub fn clear_alarm_exti(&mut self, exti: &mut EXTI) {
+        self.clear_alarm_flag();
+        exti.pr().write(|w| w.pr17().clear_bit_by_one());
+    }
+
     /// Reads the current counter
     pub fn current_time(&self) -> u32 {
         // Wait for the APB1 interface to be ready
@@ -363,9 +438,47 @@ impl<CS> Rtc<CS> {
         self.regs.cnth().read().bits() << 16 | self.regs.cntl().read().bits()
     }
 
+    /// Sets the date and time of counter value 0.
+    ///
+    /// The epoch is not stored in the backup domain and has to be set again after a reset.
+    pub fn set_epoch(&mut self, epoch: DateTime) {
+        self.epoch = epoch;
+    }
+
+    /// Date and time of counter value 0
+    pub fn epoch(&self) -> DateTime {
+        self.epoch
+    }
+
+    /// Reads the current date and time, assuming the counter runs at 1 Hz
+    pub fn now(&self) -> DateTime {
+        DateTime::from_seconds_since(&self.epoch, self.current_time())
+    }
+
+    /// Sets the counter to the seconds from the epoch to `date_time`.
+    ///
+    /// Panics if `date_time` is before the epoch or more than `u32::MAX` seconds after it.
+    pub fn set_date_time(&mut self, date_time: DateTime) {
+        let seconds = date_time
+            .seconds_since(&self.epoch)
+            .expect("date and time out of the counter range");
+        self.set_time(seconds);
+    }
+
     /// Enables triggering the RTC interrupt every time the RTC counter is increased
+    ///
+    /// A pending second flag is cleared first, so the first interrupt occurs with the
+    /// next counter increment. Clear the flag with [`clear_second_flag`](Self::clear_second_flag)
+    /// in the RTC interrupt handler.
+    ///
+    /// The RTC interrupt is not connected to the EXTI controller, so it can wake the
+    /// device from Sleep mode, but not from Stop mode. To wake from Stop mode every
+    /// second, set the alarm to the next second in the RTCALARM interrupt instead.
     pub fn listen_seconds(&mut self) {
-        self.perform_write(|s| s.regs.crh().modify(|_, w| w.secie().set_bit()))
+        self.perform_write(|s| {
+            s.regs.crl().modify(|_, w| w.secf().clear_bit());
+            s.regs.crh().modify(|_, w| w.secie().set_bit());
+        })
     }
 
     /// Disables the RTC second interrupt
@@ -403,6 +516,26 @@ impl<CS> Rtc<CS> {
         }
     }
 
+    /**
+      Return `Ok(())` if the counter was increased since the last call, `Err(nb::WouldBlock)`
+      otherwise.
+
+      ```rust
+      loop {
+          block!(rtc.wait_second()).unwrap();
+          update_display(rtc.now());
+      }
+      ```
+    */
+    pub fn wait_second(&mut self) -> nb::Result<(), Infallible> {
+        if self.regs.crl().read().secf().bit() {
+            self.regs.crl().modify(|_, w| w.secf().clear_bit());
+            Ok(())
+        } else {
+            Err(nb::Error::WouldBlock)
+        }
+    }
+
     /**
       The RTC registers can not be written to at any time as documented on page
       485 of the manual. Performing writes using this function ensures that
@@ -423,3 +556,173 @@ impl<CS> Rtc<CS> {
         while !self.regs.crl().read().rtoff().bit() {}
     }
 }
+
+/// Calendar date and time without time zone
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct DateTime {
+    pub year: u16,
+    /// 1 to 12
+    pub month: u8,
+    /// 1 to 31
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+}
+
+impl DateTime {
+    /// 1970-01-01 00:00:00
+    pub const UNIX_EPOCH: Self = Self {
+        year: 1970,
+        month: 1,
+        day: 1,
+        hour: 0,
+        minute: 0,
+        second: 0,
+    };
+
+    /// Panics if the date or time is invalid
+    pub fn new(year: u16, month: u8, day: u8, hour: u8, minute: u8, second: u8) -> Self {
+        assert!((1..=12).contains(&month));
+        assert!(day >= 1 && day <= days_in_month(year, month));
+        assert!(hour < 24 && minute < 60 && second < 60);
+        Self {
+            year,
+            month,
+            day,
+            hour,
+            minute,
+            second,
+        }
+    }
+
+    /// Date and time `seconds` after `epoch`
+    pub fn from_seconds_since(epoch: &DateTime, seconds: u32) -> Self {
+        let seconds = epoch.seconds_since_unix_epoch() + seconds as i64;
+        let (year, month, day) = civil_from_days(seconds.div_euclid(86_400));
+        let time = seconds.rem_euclid(86_400) as u32;
+        Self {
+            year: year as u16,
+            month,
+            day,
+            hour: (time / 3600) as u8,
+            minute: (time / 60 % 60) as u8,
+            second: (time % 60) as u8,
+        }
+    }
+
+    /// Seconds from `epoch` to this date and time, `None` if not representable as `u32`
+    pub fn seconds_since(&self, epoch: &DateTime) -> Option<u32> {
+        u32::try_from(self.seconds_since_unix_epoch() - epoch.seconds_since_unix_epoch()).ok()
+    }
+
+    fn seconds_since_unix_epoch(&self) -> i64 {
+        days_from_civil(self.year, self.month, self.day) * 86_400
+            + self.hour as i64 * 3600
+            + self.minute as i64 * 60
+            + self.second as i64
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl From<DateTime> for chrono::NaiveDateTime {
+    fn from(dt: DateTime) -> Self {
+        chrono::NaiveDate::from_ymd_opt(dt.year as i32, dt.month as u32, dt.day as u32)
+            .and_then(|date| date.and_hms_opt(dt.hour as u32, dt.minute as u32, dt.second as u32))
+            .expect("invalid date or time")
+    }
+}
+
+/// Fails for years outside of `0..=65535`, fractions of a second are dropped
+#[cfg(feature = "chrono")]
+impl TryFrom<chrono::NaiveDateTime> for DateTime {
+    type Error = core::num::TryFromIntError;
+
+    fn try_from(dt: chrono::NaiveDateTime) -> Result<Self, Self::Error> {
+        use chrono::{Datelike, Timelike};
+        Ok(Self {
+            year: u16::try_from(dt.year())?,
+            month: dt.month() as u8,
+            day: dt.day() as u8,
+            hour: dt.hour() as u8,
+            minute: dt.minute() as u8,
+            second: dt.second() as u8,
+        })
+    }
+}
+
+/// Returns `true` for leap years of the Gregorian calendar
+pub fn is_leap_year(year: u16) -> bool {
+    year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+}
+
+/// Number of days in `month` (1 to 12) of `year`
+pub fn days_in_month(year: u16, month: u8) -> u8 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// Conversions between dates and days since 1970-01-01, see
+// http://howardhinnant.github.io/date_algorithms.html
+fn days_from_civil(year: u16, month: u8, day: u8) -> i64 {
+    let (month, day) = (month as i64, day as i64);
+    let year = year as i64 - (month <= 2) as i64;
+    let era = year.div_euclid(400);
+    let yoe = year - era * 400;
+    let doy = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+fn civil_from_days(days: i64) -> (i64, u8, u8) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let doe = days - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u8;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u8;
+    (yoe + era * 400 + (month <= 2) as i64, month, day)
+}
+
+#[test]
+fn date_time_conversion() {
+    let epoch = DateTime::UNIX_EPOCH;
+    for (seconds, date_time) in [
+        (0, DateTime::new(1970, 1, 1, 0, 0, 0)),
+        (951_782_400, DateTime::new(2000, 2, 29, 0, 0, 0)),
+        (1_700_000_000, DateTime::new(2023, 11, 14, 22, 13, 20)),
+        (u32::MAX, DateTime::new(2106, 2, 7, 6, 28, 15)),
+    ] {
+        assert_eq!(DateTime::from_seconds_since(&epoch, seconds), date_time);
+        assert_eq!(date_time.seconds_since(&epoch), Some(seconds));
+    }
+
+    let epoch = DateTime::new(2000, 1, 1, 0, 0, 0);
+    let leap_day = DateTime::new(2000, 2, 29, 12, 0, 0);
+    assert_eq!(leap_day.seconds_since(&epoch), Some((59 * 24 + 12) * 3600));
+    assert_eq!(DateTime::UNIX_EPOCH.seconds_since(&epoch), None);
+}
+
+#[cfg(feature = "chrono")]
+#[test]
+fn date_time_chrono() {
+    let date_time = DateTime::new(2024, 2, 29, 23, 59, 58);
+    let naive = chrono::NaiveDateTime::from(date_time);
+    let expected = chrono::NaiveDate::from_ymd_opt(2024, 2, 29)
+        .unwrap()
+        .and_hms_opt(23, 59, 58)
+        .unwrap();
+    assert_eq!(naive, expected);
+    assert_eq!(DateTime::try_from(naive), Ok(date_time));
+    let before_year_0 = chrono::NaiveDate::from_ymd_opt(-1, 1, 1)
+        .unwrap()
+        .and_hms_opt(0, 0, 0)
+        .unwrap();
+    assert!(DateTime::try_from(before_year_0).is_err());
+}
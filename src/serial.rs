@@ -11,6 +11,44 @@
 //! When receiving with the parity enabled, the value read in the MSB
 //! is the received parity bit.
 //!
+//! ## Alternate function remapping
+//!
+//! TX: Alternate Push-Pull or Open-Drain Output
+//! RX: Input
+//! RTS: Alternate Push-Pull or Open-Drain Output (optional)
+//! CTS: Input (optional)
+//!
+//! Pass the pins together with `&mut afio.mapr` to select the remap.
+//!
+//! ### USART1
+//!
+//! | Function | NoRemap | Remap |
+//! |----------|---------|-------|
+//! | TX       | PA9     | PB6   |
+//! | RX       | PA10    | PB7   |
+//! | CTS      | PA11    | PA11  |
+//! | RTS      | PA12    | PA12  |
+//!
+//! ### USART2
+//!
+//! | Function | NoRemap | Remap |
+//! |----------|---------|-------|
+//! | TX       | PA2     | PD5   |
+//! | RX       | PA3     | PD6   |
+//! | CTS      | PA0     | PD3   |
+//! | RTS      | PA1     | PD4   |
+//!
+//! ### USART3
+//!
+//! | Function | NoRemap | PartialRemap | FullRemap |
+//! |----------|---------|--------------|-----------|
+//! | TX       | PB10    | PC10         | PD8       |
+//! | RX       | PB11    | PC11         | PD9       |
+//! | CTS      | PB13    | PB13         | PD11      |
+//! | RTS      | PB14    | PB14         | PD12      |
+//!
+//! ## Frame format
+//!
 //! | Frame format               | Word Length | Parity |
 //! | -------------------------- |:----------- |:------ |
 //! | 7 data bits + 1 parity bit | 8 bits      | V      |
@@ -35,8 +73,7 @@
 //! // Create an interface struct for USART1 with 9600 Baud
 //! let serial = Serial::new(
 //!     dp.USART1,
-//!     (pin_tx, pin_rx),
-//!     &mut afio.mapr,
+//!     (pin_tx, pin_rx, &mut afio.mapr),
 //!     Config::default()
 //!         .baudrate(9600.bps())
 //!         .wordlength_9bits()
@@ -67,15 +104,20 @@ use core::sync::atomic::{self, Ordering};
 use embedded_dma::{ReadBuffer, WriteBuffer};
 
 use crate::afio::Remap;
-use crate::dma::{dma1, CircBuffer, RxDma, Transfer, TxDma, R, W};
-use crate::gpio::{self, Alternate, Cr, Floating, Input, NoPin, PinMode, PullUp, PushPull};
+use crate::dma::{dma1, Ch, CircBuffer, DmaExt, RxDma, Transfer, TxDma, R, W};
+use crate::gpio::{
+    self, Alternate, Cr, Floating, Input, NoPin, OpenDrain, PinMode, PullUp, PushPull,
+};
 use crate::pac::{self, RCC};
 use crate::rcc::{BusClock, Clocks, Enable, Reset};
 use crate::time::{Bps, U32Ext};
 
+mod buffered;
 mod hal_02;
 mod hal_1;
 
+pub use buffered::BufferedTx;
+
 pub trait InMode {}
 impl InMode for Floating {}
 impl InMode for PullUp {}
@@ -105,6 +147,15 @@ pub mod usart1 {
             PB6, PB7  => 1;
         ]
     }
+
+    flow_control! {
+        rts: [PA12],
+        cts: [PA11],
+        remaps: [
+            PA12, PA11 => 0;
+            PA12, PA11 => 1;
+        ],
+    }
 }
 
 pub mod usart2 {
@@ -116,6 +167,15 @@ pub mod usart2 {
             PD5, PD6 => 1;
         ]
     }
+
+    flow_control! {
+        rts: [PA1, PD4],
+        cts: [PA0, PD3],
+        remaps: [
+            PA1, PA0 => 0;
+            PD4, PD3 => 1;
+        ],
+    }
 }
 
 pub mod usart3 {
@@ -128,6 +188,16 @@ pub mod usart3 {
             PD8, PD9 => 3;
         ]
     }
+
+    flow_control! {
+        rts: [PB14, PD12],
+        cts: [PB13, PD11],
+        remaps: [
+            PB14, PB13 => 0;
+            PB14, PB13 => 1;
+            PD12, PD11 => 3;
+        ],
+    }
 }
 
 macro_rules! remap {
@@ -186,6 +256,43 @@ macro_rules! remap {
                 }
             }
 
+            impl<Otype, PULL, RTS, CTS> From<(gpio::$TX<Alternate<Otype>>, gpio::$RX<Input<PULL>>, RTS, CTS, &mut <$PER as Remap>::Mapr)> for FlowControlPins<Tx<Otype>, Rx<PULL>, Rts<Otype>, Cts<PULL>>
+            where
+                PULL: InMode,
+                RTS: RtsPin<Otype, $remap>,
+                CTS: CtsPin<PULL, $remap>,
+            {
+                fn from(p: (gpio::$TX<Alternate<Otype>>, gpio::$RX<Input<PULL>>, RTS, CTS, &mut <$PER as Remap>::Mapr)) -> Self {
+                    <$PER>::remap(p.4, $remap);
+                    Self {
+                        pins: Pins { tx: Tx::$TX(p.0), rx: Rx::$RX(p.1) },
+                        rts: p.2.into(),
+                        cts: p.3.into(),
+                    }
+                }
+            }
+
+            impl<Otype, PULL, RTS, CTS> From<(gpio::$TX, gpio::$RX, RTS, CTS, &mut <$PER as Remap>::Mapr)> for FlowControlPins<Tx<Otype>, Rx<PULL>, Rts<Otype>, Cts<PULL>>
+            where
+                Alternate<Otype>: PinMode,
+                Input<PULL>: PinMode,
+                PULL: InMode,
+                RTS: RtsPin<Otype, $remap>,
+                CTS: CtsPin<PULL, $remap>,
+            {
+                fn from(p: (gpio::$TX, gpio::$RX, RTS, CTS, &mut <$PER as Remap>::Mapr)) -> Self {
+                    let mut cr = Cr;
+                    let tx = p.0.into_mode(&mut cr);
+                    let rx = p.1.into_mode(&mut cr);
+                    <$PER>::remap(p.4, $remap);
+                    Self {
+                        pins: Pins { tx: Tx::$TX(tx), rx: Rx::$RX(rx) },
+                        rts: p.2.into(),
+                        cts: p.3.into(),
+                    }
+                }
+            }
+
             impl<PULL> From<(gpio::$RX<Input<PULL>>, &mut <$PER as Remap>::Mapr)> for Pins<Tx<PushPull>, Rx<PULL>>
             where
                 PULL: InMode,
@@ -200,6 +307,97 @@ macro_rules! remap {
 }
 use remap;
 
+macro_rules! flow_control {
+    (
+        rts: [$($RTS:ident),+],
+        cts: [$($CTS:ident),+],
+        remaps: [$($RRTS:ident, $RCTS:ident => $remap:literal;)+],
+    ) => {
+        pub enum Rts<Otype> {
+            $(
+                $RTS(gpio::$RTS<Alternate<Otype>>),
+            )+
+            None(NoPin<Otype>),
+        }
+        pub enum Cts<PULL> {
+            $(
+                $CTS(gpio::$CTS<Input<PULL>>),
+            )+
+            None(NoPin<PULL>),
+        }
+
+        impl<Otype> FlowControlPin for Rts<Otype> {
+            fn is_connected(&self) -> bool {
+                !matches!(self, Self::None(_))
+            }
+        }
+
+        impl<PULL> FlowControlPin for Cts<PULL> {
+            fn is_connected(&self) -> bool {
+                !matches!(self, Self::None(_))
+            }
+        }
+
+        impl<Otype> From<NoPin<Otype>> for Rts<Otype> {
+            fn from(p: NoPin<Otype>) -> Self {
+                Self::None(p)
+            }
+        }
+
+        impl<PULL> From<NoPin<PULL>> for Cts<PULL> {
+            fn from(p: NoPin<PULL>) -> Self {
+                Self::None(p)
+            }
+        }
+
+        $(
+            impl<Otype> From<gpio::$RTS<Alternate<Otype>>> for Rts<Otype> {
+                fn from(p: gpio::$RTS<Alternate<Otype>>) -> Self {
+                    Self::$RTS(p)
+                }
+            }
+
+            impl<Otype> From<gpio::$RTS> for Rts<Otype>
+            where
+                Alternate<Otype>: PinMode,
+            {
+                fn from(p: gpio::$RTS) -> Self {
+                    Self::$RTS(p.into_mode(&mut Cr))
+                }
+            }
+        )+
+
+        $(
+            impl<PULL: InMode> From<gpio::$CTS<Input<PULL>>> for Cts<PULL> {
+                fn from(p: gpio::$CTS<Input<PULL>>) -> Self {
+                    Self::$CTS(p)
+                }
+            }
+        )+
+
+        /// RTS pin, or [`NoPin`], that fits the TX/RX pins of remap `REMAP`
+        pub trait RtsPin<Otype, const REMAP: u8>: Into<Rts<Otype>> {}
+        /// CTS pin, or [`NoPin`], that fits the TX/RX pins of remap `REMAP`
+        pub trait CtsPin<PULL, const REMAP: u8>: Into<Cts<PULL>> {}
+
+        impl<Otype, const REMAP: u8> RtsPin<Otype, REMAP> for NoPin<Otype> {}
+        impl<PULL, const REMAP: u8> CtsPin<PULL, REMAP> for NoPin<PULL> {}
+
+        $(
+            impl<Otype> RtsPin<Otype, $remap> for gpio::$RRTS<Alternate<Otype>> {}
+            impl<Otype> RtsPin<Otype, $remap> for gpio::$RRTS where Alternate<Otype>: PinMode {}
+            impl<PULL: InMode> CtsPin<PULL, $remap> for gpio::$RCTS<Input<PULL>> {}
+        )+
+    }
+}
+use flow_control;
+
+/// RTS or CTS pin that may also be absent
+pub trait FlowControlPin {
+    /// Returns `false` if no pin is connected
+    fn is_connected(&self) -> bool;
+}
+
 pub trait SerialExt: Sized + Instance {
     fn serial(
         self,
@@ -243,6 +441,8 @@ pub trait Instance:
 {
     type Tx<Otype>;
     type Rx<PULL>;
+    type Rts<Otype>: FlowControlPin;
+    type Cts<PULL>: FlowControlPin;
 
     #[doc(hidden)]
     fn ptr() -> *const uart_base::RegisterBlock;
@@ -254,6 +454,8 @@ macro_rules! inst {
             impl Instance for $USARTX {
                 type Tx<Otype> = $usart::Tx<Otype>;
                 type Rx<PULL> = $usart::Rx<PULL>;
+                type Rts<Otype> = $usart::Rts<Otype>;
+                type Cts<PULL> = $usart::Cts<PULL>;
 
                 fn ptr() -> *const uart_base::RegisterBlock {
                     <$USARTX>::ptr()
@@ -286,6 +488,7 @@ pub enum Error {
     Other,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum WordLength {
     /// When parity is enabled, a word has 7 data bits + 1 parity bit,
     /// otherwise 8 data bits.
@@ -295,12 +498,14 @@ pub enum WordLength {
     Bits9,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Parity {
     ParityNone,
     ParityEven,
     ParityOdd,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum StopBits {
     /// 1 stop bit
     STOP1,
@@ -312,6 +517,16 @@ pub enum StopBits {
     STOP1P5,
 }
 
+/// LIN break detection length
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LinBreakLength {
+    /// 10 bit break detection
+    Bits10,
+    /// 11 bit break detection
+    Bits11,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct Config {
     pub baudrate: Bps,
     pub wordlength: WordLength,
@@ -320,6 +535,12 @@ pub struct Config {
 }
 
 impl Config {
+    /// Sets the baud rate.
+    ///
+    /// The USART of the F1 family always oversamples by 16 (there is no `CR1.OVER8`), so the
+    /// highest baud rate is a 16th of the peripheral clock, 4.5 Mbaud for USART1 at 72 MHz
+    /// and 2.25 Mbaud for the other USARTs at 36 MHz. The `BRR` value is the peripheral clock
+    /// divided by the baud rate, its lowest 4 bits are the fraction of `USARTDIV`.
     pub fn baudrate(mut self, baudrate: Bps) -> Self {
         self.baudrate = baudrate;
         self
@@ -364,6 +585,26 @@ impl Config {
         self.stopbits = stopbits;
         self
     }
+
+    /// Number of data bits per word, excluding the parity bit
+    pub fn data_bits(&self) -> u8 {
+        let bits = match self.wordlength {
+            WordLength::Bits8 => 8,
+            WordLength::Bits9 => 9,
+        };
+        match self.parity {
+            Parity::ParityNone => bits,
+            _ => bits - 1,
+        }
+    }
+
+    /// Values of the `M`, `PCE` and `PS` bits of `CR1`
+    fn cr1_word_bits(&self) -> (bool, bool, bool) {
+        let m = matches!(self.wordlength, WordLength::Bits9);
+        let pce = !matches!(self.parity, Parity::ParityNone);
+        let ps = matches!(self.parity, Parity::ParityOdd);
+        (m, pce, ps)
+    }
 }
 
 impl Default for Config {
@@ -407,6 +648,48 @@ pub struct ReleaseToken<USART, PINS> {
     pins: PINS,
 }
 
+/// TX/RX pins together with the RTS/CTS pins of the same remap
+///
+/// Created from a `(tx, rx, rts, cts, &mut mapr)` tuple, where `rts` and `cts` can be
+/// [`NoPin`]. Flow control pins of another remap than the TX/RX pins don't compile.
+pub struct FlowControlPins<TX, RX, RTS, CTS> {
+    pins: Pins<TX, RX>,
+    rts: RTS,
+    cts: CTS,
+}
+
+/// Hardware flow control
+///
+/// Owns the RTS and CTS pins while flow control is enabled.
+pub struct FlowControl<USART: Instance, Otype = PushPull, PULL = Floating> {
+    _usart: PhantomData<USART>,
+    rts: USART::Rts<Otype>,
+    cts: USART::Cts<PULL>,
+}
+
+impl<USART: Instance, Otype, PULL> FlowControl<USART, Otype, PULL> {
+    /// Returns true if RTS is driven by the USART
+    pub fn is_rts_enabled(&self) -> bool {
+        self.rts.is_connected()
+    }
+
+    /// Returns true if transmission is gated by CTS
+    pub fn is_cts_enabled(&self) -> bool {
+        self.cts.is_connected()
+    }
+
+    /// Disables hardware flow control and returns the pins
+    pub fn release(self) -> (USART::Rts<Otype>, USART::Cts<PULL>) {
+        unsafe {
+            (*USART::ptr()).cr3().modify(|_, w| {
+                w.rtse().clear_bit();
+                w.ctse().clear_bit()
+            });
+        }
+        (self.rts, self.cts)
+    }
+}
+
 impl<USART: Instance, Otype> Serial<USART, Otype, Floating> {
     pub fn tx(
         usart: USART,
@@ -484,9 +767,91 @@ impl<USART: Instance, Otype, PULL> Serial<USART, Otype, PULL> {
         }
     }
 
-    /// Reconfigure the USART instance.
+    /// Configures the serial interface with hardware flow control
     ///
-    /// If a transmission is currently in progress, this returns
+    /// Works like [`new`](Self::new), but takes a `(tx, rx, rts, cts, &mut mapr)` tuple.
+    /// Pass [`NoPin`] for a signal that is not used. The RTS/CTS pins must belong to
+    /// the same remap as the TX/RX pins, otherwise this does not compile.
+    ///
+    /// Flow control stays enabled until [`FlowControl::release`] is called.
+    #[allow(clippy::type_complexity)]
+    pub fn with_flow_control(
+        usart: USART,
+        pins: impl Into<
+            FlowControlPins<USART::Tx<Otype>, USART::Rx<PULL>, USART::Rts<Otype>, USART::Cts<PULL>>,
+        >,
+        config: impl Into<Config>,
+        clocks: &Clocks,
+    ) -> (Self, FlowControl<USART, Otype, PULL>) {
+        let FlowControlPins { pins, rts, cts } = pins.into();
+        let mut serial = Self::new(usart, pins, config, clocks);
+        let flow_control = serial.enable_flow_control(rts, cts);
+        (serial, flow_control)
+    }
+
+    fn enable_flow_control(
+        &mut self,
+        rts: USART::Rts<Otype>,
+        cts: USART::Cts<PULL>,
+    ) -> FlowControl<USART, Otype, PULL> {
+        self.token.usart.cr3().modify(|_, w| {
+            w.rtse().bit(rts.is_connected());
+            w.ctse().bit(cts.is_connected())
+        });
+        FlowControl {
+            _usart: PhantomData,
+            rts,
+            cts,
+        }
+    }
+
+    /// Switches the USART to LIN mode
+    ///
+    /// LIN frames always consist of 8 data bits, no parity and 1 stop bit,
+    /// so the word length, parity and stop bit settings of the current
+    /// configuration are overridden. `break_length` selects the length of a
+    /// low level on RX that is detected as a LIN break.
+    pub fn into_lin_mode(self, break_length: LinBreakLength) -> Self {
+        let usart = &self.token.usart;
+        usart.cr1().modify(|_, w| {
+            w.ue().clear_bit();
+            w.m().clear_bit();
+            w.pce().clear_bit()
+        });
+        usart.cr2().modify(|_, w| {
+            w.stop().set(0b00);
+            w.clken().clear_bit();
+            w.lbdl().bit(break_length == LinBreakLength::Bits11);
+            w.linen().set_bit()
+        });
+        usart.cr3().modify(|_, w| {
+            w.scen().clear_bit();
+            w.hdsel().clear_bit();
+            w.iren().clear_bit()
+        });
+        usart.cr1().modify(|_, w| w.ue().set_bit());
+        self
+    }
+
+    /// Sends a LIN break
+    pub fn send_break(&mut self) {
+        self.tx.send_break();
+    }
+
+    /// Returns true if a LIN break was detected
+    pub fn is_break_detected(&self) -> bool {
+        self.rx.is_break_detected()
+    }
+
+    /// Clears the LIN break detection flag
+    pub fn clear_break_detected(&mut self) {
+        self.rx.clear_break_detected();
+    }
+
+    /// Reconfigure the USART instance, e.g. to change the baud rate.
+    ///
+    /// The USART is disabled while the configuration is changed, a reception in progress is
+    /// aborted. If a transmission is currently in progress, this returns
     /// [`nb::Error::WouldBlock`].
     pub fn reconfigure(
         &mut self,
@@ -496,6 +861,21 @@ impl<USART: Instance, Otype, PULL> Serial<USART, Otype, PULL> {
         reconfigure(&mut self.tx, &mut self.rx, config, clocks)
     }
 
+    /// Baud rate resulting from the integer `BRR` value, which differs from the
+    /// configured one by the rounding error
+    pub fn baud_rate(&self, clocks: &Clocks) -> Bps {
+        let usart = unsafe { &*USART::ptr() };
+        Bps(USART::clock(clocks).raw() / usart.brr().read().bits())
+    }
+
+    /// Deviation of the actual baud rate from `requested` in parts per million.
+    ///
+    /// The deviations of both sides of a link should add up to less than about
+    /// ±2 % (±20000 ppm).
+    pub fn baud_rate_error(&self, requested: Bps, clocks: &Clocks) -> i32 {
+        baud_rate_error(requested.0, self.baud_rate(clocks).0)
+    }
+
     /// Returns ownership of the borrowed register handles
     ///
     /// # Examples
@@ -533,27 +913,121 @@ impl<USART: Instance, Otype, PULL> Serial<USART, Otype, PULL> {
     }
 }
 
+impl<USART: Instance, PULL> Serial<USART, OpenDrain, PULL> {
+    /// Switches to single-wire half-duplex mode on the open-drain TX pin
+    ///
+    /// RX is connected internally to the TX pin, so the RX pin is not used and
+    /// is returned to the caller. The line needs an external (or the sensor's)
+    /// pull-up resistor.
+    ///
+    /// The returned interface starts in transmit direction.
+    pub fn into_half_duplex(self) -> (HalfDuplex<USART>, USART::Rx<PULL>) {
+        let Serial { tx, rx, token } = self;
+        let usart = &token.usart;
+        usart.cr1().modify(|_, w| w.ue().clear_bit());
+        usart.cr2().modify(|_, w| {
+            w.linen().clear_bit();
+            w.clken().clear_bit()
+        });
+        usart.cr3().modify(|_, w| {
+            w.scen().clear_bit();
+            w.iren().clear_bit();
+            w.hdsel().set_bit()
+        });
+        // receiver stays disabled while transmitting to avoid reading back our own bytes
+        usart.cr1().modify(|_, w| {
+            w.re().clear_bit();
+            w.ue().set_bit()
+        });
+        let (tx_pin, rx_pin) = token.pins;
+        (
+            HalfDuplex {
+                tx,
+                rx,
+                token: ReleaseToken {
+                    usart: token.usart,
+                    pins: tx_pin,
+                },
+            },
+            rx_pin,
+        )
+    }
+}
+
+/// Single-wire half-duplex serial interface
+///
+/// Transmission and reception share the TX pin. The receiver is disabled while
+/// transmitting, so transmitted bytes are not echoed into the receiver. Use
+/// [`flush_then_listen`](Self::flush_then_listen) after a request to turn the
+/// line around as soon as the last byte left the shift register.
+pub struct HalfDuplex<USART: Instance> {
+    pub tx: Tx<USART>,
+    pub rx: Rx<USART>,
+    token: ReleaseToken<USART, USART::Tx<OpenDrain>>,
+}
+
+impl<USART: Instance> HalfDuplex<USART> {
+    /// Switches the line to transmission
+    pub fn transmit(&mut self) {
+        self.token.usart.cr1().modify(|_, w| w.re().clear_bit());
+    }
+
+    /// Switches the line to reception
+    ///
+    /// Data still being transmitted is read back, see
+    /// [`flush_then_listen`](Self::flush_then_listen).
+    pub fn listen(&mut self) {
+        let usart = &self.token.usart;
+        // discard a stale byte and error flags
+        let _ = usart.sr().read();
+        let _ = usart.dr().read();
+        usart.cr1().modify(|_, w| w.re().set_bit());
+    }
+
+    /// Switches the line to reception once the transmission is complete
+    pub fn flush_then_listen(&mut self) -> nb::Result<(), Error> {
+        self.tx.flush()?;
+        self.listen();
+        Ok(())
+    }
+
+    /// Returns true if the receiver is enabled
+    pub fn is_listening(&self) -> bool {
+        self.token.usart.cr1().read().re().bit_is_set()
+    }
+
+    /// Switches to transmission and writes all bytes, blocking
+    pub fn bwrite_all(&mut self, buffer: &[u8]) -> Result<(), Error> {
+        self.transmit();
+        self.tx.bwrite_all_u8(buffer)
+    }
+
+    /// Reads a byte, if the line is switched to reception
+    pub fn read(&mut self) -> nb::Result<u8, Error> {
+        self.rx.read()
+    }
+
+    /// Leaves half-duplex mode and returns the USART and the TX pin
+    pub fn release(self) -> (USART, USART::Tx<OpenDrain>) {
+        self.token.usart.cr3().modify(|_, w| w.hdsel().clear_bit());
+        (self.token.usart, self.token.pins)
+    }
+}
+
 fn apply_config<USART: Instance>(config: Config, clocks: &Clocks) {
     let usart = unsafe { &*USART::ptr() };
 
     // Configure baud rate
-    let brr = USART::clock(clocks).raw() / config.baudrate.0;
+    let brr = brr(USART::clock(clocks).raw(), config.baudrate.0);
     assert!(brr >= 16, "impossible baud rate");
     usart.brr().write(|w| unsafe { w.bits(brr) });
 
     // Configure word
+    let (m, pce, ps) = config.cr1_word_bits();
     usart.cr1().modify(|_r, w| {
-        w.m().bit(match config.wordlength {
-            WordLength::Bits8 => false,
-            WordLength::Bits9 => true,
-        });
-        use crate::pac::usart1::cr1::PS;
-        w.ps().variant(match config.parity {
-            Parity::ParityOdd => PS::Odd,
-            _ => PS::Even,
-        });
-        w.pce().bit(!matches!(config.parity, Parity::ParityNone));
-        w
+        w.m().bit(m);
+        w.ps().bit(ps);
+        w.pce().bit(pce)
     });
 
     // Configure stop bits
@@ -566,9 +1040,20 @@ fn apply_config<USART: Instance>(config: Config, clocks: &Clocks) {
     usart.cr2().modify(|_r, w| w.stop().set(stop_bits));
 }
 
+/// `BRR` value for `baudrate`, rounded to the nearest value
+const fn brr(clk: u32, baudrate: u32) -> u32 {
+    (clk + baudrate / 2) / baudrate
+}
+
+/// Deviation of `actual` from `requested` in parts per million
+const fn baud_rate_error(requested: u32, actual: u32) -> i32 {
+    ((actual as i64 - requested as i64) * 1_000_000 / requested as i64) as i32
+}
+
 /// Reconfigure the USART instance.
 ///
-/// If a transmission is currently in progress, this returns
+/// The USART is disabled while the configuration is changed, a reception in progress is
+/// aborted. If a transmission is currently in progress, this returns
 /// [`nb::Error::WouldBlock`].
 pub fn reconfigure<USART: Instance>(
     tx: &mut Tx<USART>,
@@ -581,7 +1066,11 @@ pub fn reconfigure<USART: Instance>(
     // exclusive access to the Serial instance due to &mut self -- knows
     // what they're doing.
     tx.flush()?;
+    let usart = unsafe { &*USART::ptr() };
+    let enabled = usart.cr1().read().ue().bit_is_set();
+    usart.cr1().modify(|_, w| w.ue().clear_bit());
     apply_config::<USART>(config.into(), clocks);
+    usart.cr1().modify(|_, w| w.ue().bit(enabled));
     Ok(())
 }
 
@@ -652,17 +1141,113 @@ impl<USART: Instance> Tx<USART> {
     pub fn is_tx_complete(&self) -> bool {
         unsafe { (*USART::ptr()).sr().read().tc().bit_is_set() }
     }
+
+    /// Sends a break character
+    ///
+    /// The break is sent after the current transmission. In LIN mode this is
+    /// a LIN break of 13 bit times.
+    pub fn send_break(&mut self) {
+        unsafe { (*USART::ptr()).cr1().modify(|_, w| w.sbk().set_bit()) };
+    }
 }
 
 impl<USART: Instance> core::fmt::Write for Tx<USART> {
+    /// Writes the string byte by byte, blocking until each byte was accepted
+    ///
+    /// Fails if the USART or its transmitter is disabled, or if a byte is not accepted
+    /// within [`TX_TIMEOUT_FRAMES`] frame times, e.g. while CTS is held or the clock is
+    /// gated. Line errors are receive errors, they are reported and cleared by the `Rx`
+    /// half, see [`Rx::check_errors`].
     fn write_str(&mut self, s: &str) -> core::fmt::Result {
-        s.bytes()
-            .try_for_each(|c| nb::block!(self.write_u8(c)))
-            .map_err(|_| core::fmt::Error)
+        let usart = unsafe { &*USART::ptr() };
+        // A frame of up to 12 bits takes 12 * BRR bus clock cycles, each status register
+        // read takes at least one
+        let polls = usart.brr().read().bits() * 12 * TX_TIMEOUT_FRAMES;
+        write_polled(
+            s.as_bytes(),
+            polls,
+            || {
+                let cr1 = usart.cr1().read();
+                if cr1.ue().bit_is_clear() || cr1.te().bit_is_clear() {
+                    TxState::Disabled
+                } else if usart.sr().read().txe().bit_is_set() {
+                    TxState::Ready
+                } else {
+                    TxState::Busy
+                }
+            },
+            |byte| usart.dr().write(|w| w.dr().set(byte.into())),
+        )
+    }
+}
+
+/// Frame times `fmt::Write` for `Tx` waits for a byte to be accepted
+pub const TX_TIMEOUT_FRAMES: u32 = 4;
+
+enum TxState {
+    Ready,
+    Busy,
+    Disabled,
+}
+
+/// Writes `bytes` once `state` is ready, giving up after `polls` busy polls per byte
+fn write_polled(
+    bytes: &[u8],
+    polls: u32,
+    mut state: impl FnMut() -> TxState,
+    mut write: impl FnMut(u8),
+) -> core::fmt::Result {
+    for &byte in bytes {
+        let mut budget = polls;
+        loop {
+            match state() {
+                TxState::Ready => break,
+                TxState::Busy if budget > 0 => budget -= 1,
+                TxState::Busy | TxState::Disabled => return Err(core::fmt::Error),
+            }
+        }
+        write(byte);
+    }
+    Ok(())
+}
+
+/// Status register bits of the error flags
+const SR_PE: u32 = 1 << 0;
+const SR_FE: u32 = 1 << 1;
+const SR_NE: u32 = 1 << 2;
+const SR_ORE: u32 = 1 << 3;
+
+/// Decodes the error flags of the status register
+fn sr_error(sr: u32) -> Option<Error> {
+    if sr & SR_PE != 0 {
+        Some(Error::Parity)
+    } else if sr & SR_FE != 0 {
+        Some(Error::FrameFormat)
+    } else if sr & SR_NE != 0 {
+        Some(Error::Noise)
+    } else if sr & SR_ORE != 0 {
+        Some(Error::Overrun)
+    } else {
+        None
     }
 }
 
 impl<USART: Instance> Rx<USART> {
+    /// Checks for overrun, framing, noise or parity errors and clears them
+    ///
+    /// Clearing an error discards the byte in the receive data register.
+    pub fn check_errors(&mut self) -> Result<(), Error> {
+        let usart = unsafe { &*USART::ptr() };
+        match sr_error(usart.sr().read().bits()) {
+            Some(err) => {
+                // cleared by the status register read above followed by a data register read
+                let _ = usart.dr().read();
+                Err(err)
+            }
+            None => Ok(()),
+        }
+    }
+
     /// Reads 9-bit words from the UART/USART
     ///
     /// If the UART/USART was configured with `WordLength::Bits9`, the returned value will contain
@@ -673,19 +1258,7 @@ impl<USART: Instance> Rx<USART> {
         let sr = usart.sr().read();
 
         // Check for any errors
-        let err = if sr.pe().bit_is_set() {
-            Some(Error::Parity)
-        } else if sr.fe().bit_is_set() {
-            Some(Error::FrameFormat)
-        } else if sr.ne().bit_is_set() {
-            Some(Error::Noise)
-        } else if sr.ore().bit_is_set() {
-            Some(Error::Overrun)
-        } else {
-            None
-        };
-
-        if let Some(err) = err {
+        if let Some(err) = sr_error(sr.bits()) {
             // Some error occurred. In order to clear that error flag, you have to
             // do a read from the sr register followed by a read from the dr register.
             let _ = usart.sr().read();
@@ -743,6 +1316,32 @@ impl<USART: Instance> Rx<USART> {
             let _ = (*USART::ptr()).dr().read();
         }
     }
+
+    /// Start listening for LIN break detection interrupt event
+    pub fn listen_break(&mut self) {
+        unsafe { (*USART::ptr()).cr2().modify(|_, w| w.lbdie().set_bit()) };
+    }
+
+    /// Stop listening for LIN break detection interrupt event
+    pub fn unlisten_break(&mut self) {
+        unsafe { (*USART::ptr()).cr2().modify(|_, w| w.lbdie().clear_bit()) };
+    }
+
+    /// Returns true if a LIN break was detected
+    pub fn is_break_detected(&self) -> bool {
+        unsafe { (*USART::ptr()).sr().read().lbd().bit_is_set() }
+    }
+
+    /// Clears the LIN break detection flag
+    pub fn clear_break_detected(&mut self) {
+        // writing 1 to the other rc_w0 flags leaves them unchanged
+        unsafe {
+            (*USART::ptr()).sr().write(|w| {
+                w.bits(0xffff);
+                w.lbd().clear_bit()
+            })
+        };
+    }
 }
 
 /// Interrupt event
@@ -753,6 +1352,8 @@ pub enum Event {
     Rxne,
     /// Idle line state detected
     Idle,
+    /// LIN break detected
+    LinBreak,
 }
 
 impl<USART: Instance, Otype, PULL> Serial<USART, Otype, PULL> {
@@ -764,6 +1365,7 @@ impl<USART: Instance, Otype, PULL> Serial<USART, Otype, PULL> {
             Event::Rxne => self.rx.listen(),
             Event::Txe => self.tx.listen(),
             Event::Idle => self.rx.listen_idle(),
+            Event::LinBreak => self.rx.listen_break(),
         }
     }
 
@@ -775,6 +1377,7 @@ impl<USART: Instance, Otype, PULL> Serial<USART, Otype, PULL> {
             Event::Rxne => self.rx.unlisten(),
             Event::Txe => self.tx.unlisten(),
             Event::Idle => self.rx.unlisten_idle(),
+            Event::LinBreak => self.rx.unlisten_break(),
         }
     }
 
@@ -903,6 +1506,48 @@ macro_rules! serialdma {
             }
         }
 
+        impl $rxdma {
+            /// Starts a circular DMA reception into `buffer` and splits the byte stream
+            /// into frames at idle line events
+            ///
+            /// See [`FrameReader`] for details. An empty `buffer` fails to build.
+            pub fn frame_read<const N: usize>(
+                mut self,
+                buffer: &'static mut [u8; N],
+            ) -> FrameReader<N, Self> {
+                let () = FrameReader::<N, Self>::NOT_EMPTY;
+
+                self.channel.set_peripheral_address(
+                    unsafe { (*<$USARTX>::ptr()).dr().as_ptr() as u32 },
+                    false,
+                );
+                self.channel
+                    .set_memory_address(buffer.as_ptr() as u32, true);
+                self.channel.set_transfer_length(N);
+
+                atomic::compiler_fence(Ordering::Release);
+
+                self.channel.ch().cr().modify(|_, w| {
+                    w.mem2mem().clear_bit();
+                    w.pl().medium();
+                    w.msize().bits8();
+                    w.psize().bits8();
+                    w.circ().set_bit();
+                    w.dir().clear_bit()
+                });
+
+                // discard a stale idle flag, so that the first frame starts now
+                self.payload.clear_idle_interrupt();
+                self.start();
+
+                FrameReader {
+                    buffer,
+                    payload: self,
+                    read_pos: 0,
+                }
+            }
+        }
+
         impl<B> crate::dma::CircReadDma<B, u8> for $rxdma
         where
             &'static mut [B; 2]: WriteBuffer<Word = u8>,
@@ -1022,3 +1667,245 @@ serialdma! {
     rx: dma1::C3,
     tx: dma1::C2
 }
+
+/// Variable-length frame receiver based on circular DMA and idle line detection
+///
+/// The DMA channel continuously writes received bytes into a ring buffer of `N` bytes.
+/// Every time the USART detects an idle line, [`read_frame`](Self::read_frame) returns the
+/// bytes received since the previous frame. Call it from the USART interrupt handler after
+/// enabling the idle interrupt with [`listen`](Self::listen), or poll it from a loop.
+///
+/// A frame must be shorter than `N` bytes and must be consumed before the DMA wraps around
+/// to it again, otherwise its contents are overwritten.
+pub struct FrameReader<const N: usize, PAYLOAD> {
+    buffer: &'static mut [u8; N],
+    payload: PAYLOAD,
+    read_pos: usize,
+}
+
+impl<const N: usize, PAYLOAD> FrameReader<N, PAYLOAD> {
+    // Evaluated when `frame_read` is instantiated, so `N == 0` is a compile time error
+    const NOT_EMPTY: () = assert!(N != 0, "FrameReader buffer must not be empty");
+}
+
+/// Frame returned by [`FrameReader::read_frame`]
+///
+/// The frame consists of two slices, since it may straddle the end of the ring buffer.
+/// The second slice is empty unless the frame wrapped around.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Frame<'a> {
+    first: &'a [u8],
+    second: &'a [u8],
+}
+
+impl<'a> Frame<'a> {
+    /// Number of bytes in the frame
+    pub fn len(&self) -> usize {
+        self.first.len() + self.second.len()
+    }
+
+    /// Returns true if the frame contains no bytes
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the frame contents as two consecutive slices
+    pub fn as_slices(&self) -> (&'a [u8], &'a [u8]) {
+        (self.first, self.second)
+    }
+
+    /// Copies the frame into `buf` and returns the number of bytes copied
+    ///
+    /// Bytes that do not fit into `buf` are dropped.
+    pub fn copy_to(&self, buf: &mut [u8]) -> usize {
+        let n1 = self.first.len().min(buf.len());
+        buf[..n1].copy_from_slice(&self.first[..n1]);
+        let n2 = self.second.len().min(buf.len() - n1);
+        buf[n1..n1 + n2].copy_from_slice(&self.second[..n2]);
+        n1 + n2
+    }
+
+    /// Iterates over the bytes of the frame
+    pub fn iter(&self) -> impl Iterator<Item = &'a u8> {
+        self.first.iter().chain(self.second.iter())
+    }
+}
+
+/// Returns the ranges of a frame starting at `read_pos` in a ring buffer of `len` bytes,
+/// given the remaining DMA transfer count `ndtr`
+fn frame_ranges(
+    read_pos: usize,
+    ndtr: usize,
+    len: usize,
+) -> (core::ops::Range<usize>, core::ops::Range<usize>) {
+    // NDTR reloads to `len` on wrap-around, which corresponds to position 0
+    let write_pos = (len - ndtr) % len;
+    if write_pos >= read_pos {
+        (read_pos..write_pos, 0..0)
+    } else {
+        (read_pos..len, 0..write_pos)
+    }
+}
+
+impl<const N: usize, USART, DMA, const C: u8> FrameReader<N, RxDma<Rx<USART>, Ch<DMA, C>>>
+where
+    USART: Instance,
+    DMA: DmaExt,
+    RxDma<Rx<USART>, Ch<DMA, C>>: TransferPayload,
+{
+    /// Returns the bytes received since the last frame, once the line went idle
+    ///
+    /// Returns `WouldBlock` if no idle line was detected since the last call.
+    /// A USART overrun is reported as an error; the frame is still returned by the next call.
+    pub fn read_frame(&mut self) -> nb::Result<Frame<'_>, Error> {
+        let usart = unsafe { &*USART::ptr() };
+        let sr = usart.sr().read();
+        if sr.ore().bit_is_set() {
+            // cleared by the SR read above followed by a DR read
+            let _ = usart.dr().read();
+            return Err(nb::Error::Other(Error::Overrun));
+        }
+        if sr.idle().bit_is_clear() {
+            return Err(nb::Error::WouldBlock);
+        }
+        self.payload.payload.clear_idle_interrupt();
+
+        let ndtr = self.payload.channel.get_ndtr() as usize;
+        let (first, second) = frame_ranges(self.read_pos, ndtr, N);
+        self.read_pos = if second.is_empty() {
+            first.end % N
+        } else {
+            second.end
+        };
+
+        atomic::compiler_fence(Ordering::Acquire);
+
+        let frame = Frame {
+            first: &self.buffer[first],
+            second: &self.buffer[second],
+        };
+        if frame.is_empty() {
+            Err(nb::Error::WouldBlock)
+        } else {
+            Ok(frame)
+        }
+    }
+
+    /// Start listening for the idle line interrupt
+    pub fn listen(&mut self) {
+        self.payload.payload.listen_idle();
+    }
+
+    /// Stop listening for the idle line interrupt
+    pub fn unlisten(&mut self) {
+        self.payload.payload.unlisten_idle();
+    }
+
+    /// Stops the transfer and returns the underlying buffer and RxDma
+    pub fn stop(mut self) -> (&'static mut [u8; N], RxDma<Rx<USART>, Ch<DMA, C>>) {
+        self.payload.stop();
+
+        (self.buffer, self.payload)
+    }
+}
+
+#[test]
+fn frame_ranges_wrap_around() {
+    // nothing received
+    assert_eq!(frame_ranges(0, 16, 16), (0..0, 0..0));
+    // frame inside the buffer
+    assert_eq!(frame_ranges(2, 10, 16), (2..6, 0..0));
+    // frame ending exactly at the end of the buffer
+    assert_eq!(frame_ranges(10, 16, 16), (10..16, 0..0));
+    // frame straddling the end of the buffer
+    assert_eq!(frame_ranges(12, 13, 16), (12..16, 0..3));
+}
+
+#[test]
+fn config_word_bits() {
+    let cfg = Config::default();
+    assert_eq!(cfg.cr1_word_bits(), (false, false, false));
+    assert_eq!(cfg.data_bits(), 8);
+
+    let cfg = Config::default().parity_even();
+    assert_eq!(cfg.cr1_word_bits(), (false, true, false));
+    assert_eq!(cfg.data_bits(), 7);
+
+    let cfg = Config::default().parity_odd();
+    assert_eq!(cfg.cr1_word_bits(), (false, true, true));
+    assert_eq!(cfg.data_bits(), 7);
+
+    let cfg = Config::default().wordlength_9bits();
+    assert_eq!(cfg.cr1_word_bits(), (true, false, false));
+    assert_eq!(cfg.data_bits(), 9);
+
+    let cfg = Config::default().wordlength_9bits().parity_even();
+    assert_eq!(cfg.cr1_word_bits(), (true, true, false));
+    assert_eq!(cfg.data_bits(), 8);
+
+    let cfg = Config::default().wordlength_9bits().parity_odd();
+    assert_eq!(cfg.cr1_word_bits(), (true, true, true));
+    assert_eq!(cfg.data_bits(), 8);
+}
+
+#[test]
+fn sr_error_flags() {
+    // TXE and TC set, no error
+    assert!(sr_error(0x00c0).is_none());
+    // simulated overrun with RXNE set
+    assert!(matches!(sr_error(0x00e8), Some(Error::Overrun)));
+    assert!(matches!(sr_error(SR_FE), Some(Error::FrameFormat)));
+    assert!(matches!(sr_error(SR_NE), Some(Error::Noise)));
+    // parity takes precedence over the other errors
+    assert!(matches!(sr_error(SR_PE | SR_ORE), Some(Error::Parity)));
+}
+
+#[test]
+fn write_polled_timeout() {
+    let mut written = [0; 4];
+    let mut n = 0;
+    let mut busy = 3;
+    let ready_after_3 = || {
+        if busy == 0 {
+            TxState::Ready
+        } else {
+            busy -= 1;
+            TxState::Busy
+        }
+    };
+    assert!(write_polled(b"ab", 3, ready_after_3, |b| {
+        written[n] = b;
+        n += 1;
+    })
+    .is_ok());
+    assert_eq!(&written[..n], b"ab");
+
+    // TXE never set, e.g. CTS held
+    let mut n = 0;
+    assert!(write_polled(b"ab", 10, || TxState::Busy, |_| n += 1).is_err());
+    assert_eq!(n, 0);
+    assert!(write_polled(b"ab", 10, || TxState::Disabled, |_| n += 1).is_err());
+    assert!(write_polled(b"", 0, || TxState::Busy, |_| n += 1).is_ok());
+}
+
+#[test]
+fn baud_rate_reference_manual() {
+    // Examples of table 192 in RM0008 for fPCLK = 72 MHz
+    assert_eq!(brr(72_000_000, 2_400), 1875 << 4);
+    assert_eq!(brr(72_000_000, 9_600), 0x1d4c);
+    assert_eq!(brr(72_000_000, 115_200), 0x271);
+    assert_eq!(brr(72_000_000, 2_250_000), 2 << 4);
+    assert_eq!(brr(72_000_000, 4_500_000), 1 << 4);
+    // fPCLK = 36 MHz
+    assert_eq!(brr(36_000_000, 9_600), 0xea6);
+    assert_eq!(brr(36_000_000, 2_250_000), 1 << 4);
+}
+
+#[test]
+fn baud_rate_rounding() {
+    assert_eq!(brr(72_000_000, 115_200), 625);
+    // 138.9 is rounded up
+    assert_eq!(brr(8_000_000, 57_600), 139);
+    assert_eq!(baud_rate_error(57_600, 8_000_000 / 139), -815);
+    assert_eq!(baud_rate_error(115_200, 8_000_000 / 69), 6440);
+}
@@ -0,0 +1,155 @@
+use super::{Error, Instance, Tx};
+
+/// Interrupt driven serial transmitter
+///
+/// Written data is copied into a ring buffer of `N` bytes and the TXE
+/// interrupt is enabled, so that `write!` returns without waiting for the
+/// transmission. Call [`on_interrupt`](Self::on_interrupt) from the USART
+/// interrupt handler to move the queued bytes into the data register.
+///
+/// Since the buffer is accessed from both the application and the interrupt
+/// handler, the `BufferedTx` is usually shared through a
+/// `cortex_m::interrupt::Mutex`.
+pub struct BufferedTx<USART, const N: usize> {
+    tx: Tx<USART>,
+    queue: Queue<N>,
+}
+
+impl<USART: Instance, const N: usize> BufferedTx<USART, N> {
+    pub fn new(tx: Tx<USART>) -> Self {
+        Self {
+            tx,
+            queue: Queue::new(),
+        }
+    }
+
+    /// Queues as many bytes of `bytes` as fit into the buffer
+    ///
+    /// Returns the number of bytes queued.
+    pub fn write(&mut self, bytes: &[u8]) -> usize {
+        let n = bytes.iter().take_while(|&&b| self.queue.push(b)).count();
+        if n > 0 {
+            self.tx.listen();
+        }
+        n
+    }
+
+    /// Moves the next queued byte into the data register
+    ///
+    /// Disables the TXE interrupt once the buffer is empty.
+    pub fn on_interrupt(&mut self) {
+        if !self.tx.is_tx_empty() {
+            return;
+        }
+        match self.queue.pop() {
+            Some(b) => {
+                self.tx.write_u8(b).ok();
+            }
+            None => self.tx.unlisten(),
+        }
+    }
+
+    /// Returns `Ok` once all queued bytes are transmitted
+    pub fn flush(&mut self) -> nb::Result<(), Error> {
+        if self.queue.is_empty() {
+            self.tx.flush()
+        } else {
+            Err(nb::Error::WouldBlock)
+        }
+    }
+
+    /// Number of bytes waiting for transmission
+    pub fn len(&self) -> usize {
+        self.queue.len
+    }
+
+    /// Returns true if no bytes are waiting for transmission
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    /// Drops the queued bytes and returns the transmitter
+    pub fn release(mut self) -> Tx<USART> {
+        self.tx.unlisten();
+        self.tx
+    }
+}
+
+impl<USART: Instance, const N: usize> From<Tx<USART>> for BufferedTx<USART, N> {
+    fn from(tx: Tx<USART>) -> Self {
+        Self::new(tx)
+    }
+}
+
+impl<USART: Instance, const N: usize> core::fmt::Write for BufferedTx<USART, N> {
+    /// Queues the string without blocking
+    ///
+    /// Returns an error if the string does not fit into the buffer. The part
+    /// that fits is still transmitted.
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        if self.write(s.as_bytes()) == s.len() {
+            Ok(())
+        } else {
+            Err(core::fmt::Error)
+        }
+    }
+}
+
+/// Fixed capacity FIFO of bytes
+struct Queue<const N: usize> {
+    buf: [u8; N],
+    head: usize,
+    len: usize,
+}
+
+impl<const N: usize> Queue<N> {
+    const fn new() -> Self {
+        Self {
+            buf: [0; N],
+            head: 0,
+            len: 0,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn push(&mut self, b: u8) -> bool {
+        if self.len == N {
+            return false;
+        }
+        self.buf[(self.head + self.len) % N] = b;
+        self.len += 1;
+        true
+    }
+
+    fn pop(&mut self) -> Option<u8> {
+        if self.is_empty() {
+            return None;
+        }
+        let b = self.buf[self.head];
+        self.head = (self.head + 1) % N;
+        self.len -= 1;
+        Some(b)
+    }
+}
+
+#[test]
+fn queue_wrap_around() {
+    let mut q = Queue::<4>::new();
+    assert_eq!(q.pop(), None);
+    for b in 0..4 {
+        assert!(q.push(b));
+    }
+    assert!(!q.push(4));
+    assert_eq!(q.pop(), Some(0));
+    assert_eq!(q.pop(), Some(1));
+    assert!(q.push(4));
+    assert!(q.push(5));
+    assert_eq!(q.len, 4);
+    for b in 2..6 {
+        assert_eq!(q.pop(), Some(b));
+    }
+    assert!(q.is_empty());
+}
@@ -1,17 +1,19 @@
 /*!
   # Serial Peripheral Interface
-  To construct the SPI instances, use the `Spi::spiX` functions.
+  To construct the SPI instances, use the `SpiExt::spi` and `SpiExt::spi_slave` functions.
 
   The pin parameter is a tuple containing `(sck, miso, mosi)` which should be configured as `(Alternate<...>, Input<...>, Alternate<...>)`.
   As some STM32F1xx chips have 5V tolerant SPI pins, it is also possible to configure Sck and Mosi outputs as `Alternate<PushPull>`. Then
   a simple Pull-Up to 5V can be used to use SPI on a 5V bus without a level shifter.
 
-  You can also use `NoSck`, `NoMiso` or `NoMosi` if you don't want to use the pins
+  You can also pass only `(sck, miso)` or `(sck, mosi)` if you don't want to use all pins.
 
   - `SPI1` can use `(PA5, PA6, PA7)` or `(PB3, PB4, PB5)`.
   - `SPI2` can use `(PB13, PB14, PB15)`
   - `SPI3` can use `(PB3, PB4, PB5)` or only in connectivity line devices `(PC10, PC11, PC12)`
 
+  For peripherals with a remap, `&mut afio.mapr` must be added as last tuple element.
+  The remap is selected by the pins, so pins of different remaps can't be mixed.
 
   ## Initialisation example
 
@@ -29,12 +31,68 @@
         polarity: Polarity::IdleLow,
         phase: Phase::CaptureOnFirstTransition,
     };
-    let spi = Spi::spi2(dp.SPI2, pins, spi_mode, 100.khz(), clocks);
+    let spi = dp.SPI2.spi(pins, spi_mode, 100.kHz(), &clocks);
+
+    // SPI1 with remapped pins
+    let spi1 = dp.SPI1.spi(
+        (gpiob.pb3, gpiob.pb4, gpiob.pb5, &mut afio.mapr),
+        spi_mode,
+        1.MHz(),
+        &clocks,
+    );
+  ```
+
+  ## Frame format
+
+  The bit order can be selected with `SpiConfig`, which is accepted everywhere a `Mode` is.
+  The data frame size follows the word type, use `Spi::frame_size` (or `frame_size_16bit`)
+  to switch between 8 and 16 bit frames.
+
+  ```rust
+    let config = SpiConfig::from(spi_mode).lsb_first();
+    let spi: Spi<_, u16> = dp.SPI2.spi(pins, config, 1.MHz(), &clocks).frame_size();
+  ```
+
+  ## Hardware CRC
+
+  `SpiConfig::crc` enables the CRC calculation with the given polynomial. The CRC has
+  the width of a data frame, 8 bit for `u8` and 16 bit for `u16` words, with 8 bit frames
+  only the lower 8 bits of the polynomial are used. Changing the frame size restarts the
+  calculation.
+
+  The CRC is only sent by `transfer_with_crc` and `write_with_crc`. They restart the
+  calculation, transfer the words and let the hardware append the CRC (`CR1.CRCNEXT`)
+  after the last one. The CRC received in the same frame is compared with the calculated
+  one and `Error::Crc` is returned on a mismatch. All other transfers are unchanged.
+
+  ```rust
+    let config = SpiConfig::from(spi_mode).crc(0x07);
+    let mut spi = dp.SPI2.spi(pins, config, 1.MHz(), &clocks);
+    let mut frame = [0x01, 0x02, 0x03];
+    spi.transfer_with_crc(&mut frame)?;
+  ```
+
+  ## Transmit-only mode
+
+  Displays and LED strips like the APA102 only need SCK and MOSI. `SpiExt::spi_tx_only`
+  takes the `(sck, mosi)` pins and returns a `SpiTxOnly`, which disables the receiver,
+  so writes don't wait on or drain received words. The MISO pin is not claimed.
+
+  ```rust
+    let pins = (gpioa.pa5, gpioa.pa7);
+    let mut leds = dp.SPI1.spi_tx_only(pins, spi_mode, 4.MHz(), &clocks);
+    leds.write(&[0x00, 0x00, 0x00, 0x00])?;
   ```
 */
 
 mod hal_02;
 mod hal_1;
+mod half_duplex;
+pub mod sdcard;
+mod tx_only;
+
+pub use half_duplex::SpiHalfDuplex;
+pub use tx_only::SpiTxOnly;
 
 use core::ops::{Deref, DerefMut};
 use core::ptr;
@@ -80,6 +138,93 @@ pub struct Mode {
     pub phase: Phase,
 }
 
+/// SPI configuration
+///
+/// The data frame size is not part of the configuration, it follows the word type of
+/// the `Spi` (`u8` or `u16`), see `Spi::frame_size`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SpiConfig {
+    /// Clock polarity and phase
+    pub mode: Mode,
+    /// Order in which the bits of a frame are shifted out
+    pub bit_format: SpiBitFormat,
+    /// Polynomial of the hardware CRC, `None` disables the CRC
+    pub crc: Option<u16>,
+}
+
+impl SpiConfig {
+    pub fn mode(mut self, mode: Mode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn bit_format(mut self, bit_format: SpiBitFormat) -> Self {
+        self.bit_format = bit_format;
+        self
+    }
+
+    pub fn lsb_first(mut self) -> Self {
+        self.bit_format = SpiBitFormat::LsbFirst;
+        self
+    }
+
+    pub fn msb_first(mut self) -> Self {
+        self.bit_format = SpiBitFormat::MsbFirst;
+        self
+    }
+
+    /// Enables the hardware CRC with `polynomial`, e.g. `0x07` for CRC-8 or `0x1021`
+    /// for CRC-16-CCITT
+    pub fn crc(mut self, polynomial: u16) -> Self {
+        self.crc = Some(polynomial);
+        self
+    }
+}
+
+impl Default for SpiConfig {
+    fn default() -> Self {
+        Self {
+            mode: Mode {
+                polarity: Polarity::IdleLow,
+                phase: Phase::CaptureOnFirstTransition,
+            },
+            bit_format: SpiBitFormat::MsbFirst,
+            crc: None,
+        }
+    }
+}
+
+impl From<Mode> for SpiConfig {
+    fn from(mode: Mode) -> Self {
+        Self::default().mode(mode)
+    }
+}
+
+/// Word type of a data frame, selects `CR1.DFF`
+pub trait FrameSize: Copy + crate::Sealed {
+    #[doc(hidden)]
+    const DFF: bool;
+}
+
+impl crate::Sealed for u8 {}
+impl FrameSize for u8 {
+    const DFF: bool = false;
+}
+
+impl crate::Sealed for u16 {}
+impl FrameSize for u16 {
+    const DFF: bool = true;
+}
+
+/// Slave select (NSS) management in slave mode
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SlaveSelect {
+    /// Selected by `SpiSlave::select`, the NSS pin is free for other uses
+    Software,
+    /// Selected while the NSS pin is held low by the master
+    Hardware,
+}
+
 /// Interrupt event
 pub enum Event {
     /// New data has been received
@@ -91,7 +236,7 @@ pub enum Event {
 }
 
 /// SPI error
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[non_exhaustive]
 pub enum Error {
     /// Overrun occurred
@@ -100,10 +245,48 @@ pub enum Error {
     ModeFault,
     /// CRC error
     Crc,
+    /// Reading is not possible, the receiver of a `SpiTxOnly` is disabled
+    TxOnly,
 }
 
 use core::marker::PhantomData;
 
+/// Status register bits
+const SR_RXNE: u16 = 1 << 0;
+const SR_TXE: u16 = 1 << 1;
+const SR_CRCERR: u16 = 1 << 4;
+const SR_MODF: u16 = 1 << 5;
+const SR_OVR: u16 = 1 << 6;
+
+/// Decodes the status register for a non-blocking read, `Ok` if a word was received
+fn rx_status(sr: u16) -> nb::Result<(), Error> {
+    if sr & SR_OVR != 0 {
+        Err(Error::Overrun.into())
+    } else if sr & SR_MODF != 0 {
+        Err(Error::ModeFault.into())
+    } else if sr & SR_CRCERR != 0 {
+        Err(Error::Crc.into())
+    } else if sr & SR_RXNE != 0 {
+        Ok(())
+    } else {
+        Err(nb::Error::WouldBlock)
+    }
+}
+
+/// Decodes the status register for a non-blocking write, `Ok` if a word can be written
+fn tx_status(sr: u16) -> nb::Result<(), Error> {
+    // NOTE: Error::Overrun was deleted in #408. Need check
+    if sr & SR_MODF != 0 {
+        Err(Error::ModeFault.into())
+    } else if sr & SR_CRCERR != 0 {
+        Err(Error::Crc.into())
+    } else if sr & SR_TXE != 0 {
+        Ok(())
+    } else {
+        Err(nb::Error::WouldBlock)
+    }
+}
+
 pub trait InMode {}
 impl InMode for Floating {}
 impl InMode for PullUp {}
@@ -312,30 +495,39 @@ pub trait SpiExt: Sized + Instance {
     fn spi(
         self,
         pins: impl Into<MasterPins<Self::Sck, Self::Mi<Floating>, Self::Mo>>,
-        mode: Mode,
+        config: impl Into<SpiConfig>,
         freq: Hertz,
         clocks: &Clocks,
     ) -> Spi<Self, u8, Floating>;
     fn spi_u16(
         self,
         pins: impl Into<MasterPins<Self::Sck, Self::Mi<Floating>, Self::Mo>>,
-        mode: Mode,
+        config: impl Into<SpiConfig>,
         freq: Hertz,
         clocks: &Clocks,
     ) -> Spi<Self, u16> {
-        Self::spi(self, pins, mode, freq, clocks).frame_size_16bit()
+        Self::spi(self, pins, config, freq, clocks).frame_size_16bit()
+    }
+    fn spi_tx_only(
+        self,
+        pins: impl Into<MasterPins<Self::Sck, Self::Mi<Floating>, Self::Mo>>,
+        config: impl Into<SpiConfig>,
+        freq: Hertz,
+        clocks: &Clocks,
+    ) -> SpiTxOnly<Self, u8> {
+        Spi::new_tx_only(self, pins, config, freq, clocks)
     }
     fn spi_slave(
         self,
         pins: impl Into<SlavePins<Self::Sck, Self::So<PushPull>, Self::Si<Floating>>>,
-        mode: Mode,
+        config: impl Into<SpiConfig>,
     ) -> SpiSlave<Self, u8, PushPull, Floating>;
     fn spi_slave_u16(
         self,
         pins: impl Into<SlavePins<Self::Sck, Self::So<PushPull>, Self::Si<Floating>>>,
-        mode: Mode,
+        config: impl Into<SpiConfig>,
     ) -> SpiSlave<Self, u16, PushPull, Floating> {
-        Self::spi_slave(self, pins, mode).frame_size_16bit()
+        Self::spi_slave(self, pins, config).frame_size_16bit()
     }
 }
 
@@ -343,18 +535,18 @@ impl<SPI: Instance> SpiExt for SPI {
     fn spi(
         self,
         pins: impl Into<MasterPins<Self::Sck, Self::Mi<Floating>, Self::Mo>>,
-        mode: Mode,
+        config: impl Into<SpiConfig>,
         freq: Hertz,
         clocks: &Clocks,
     ) -> Spi<Self, u8, Floating> {
-        Spi::new(self, pins, mode, freq, clocks)
+        Spi::new(self, pins, config, freq, clocks)
     }
     fn spi_slave(
         self,
         pins: impl Into<SlavePins<Self::Sck, Self::So<PushPull>, Self::Si<Floating>>>,
-        mode: Mode,
+        config: impl Into<SpiConfig>,
     ) -> SpiSlave<Self, u8, PushPull, Floating> {
-        SpiSlave::new(self, pins, mode)
+        SpiSlave::new(self, pins, config)
     }
 }
 
@@ -379,6 +571,12 @@ pub struct Spi<SPI: Instance, W, PULL = Floating> {
 }
 
 /// Spi in Slave mode
+///
+/// The clock is driven by the master, so every frame clocked in also shifts out the word that was
+/// written before it started. Use `preload` for the first frame and keep `TXE` serviced with
+/// `write_nonblocking` afterwards. In SPI mode the peripheral has no underrun flag: if no new word
+/// was written in time, the previous one is sent again. A received word that was not read before
+/// the next frame completes is reported as `Error::Overrun` by `read_nonblocking`.
 pub struct SpiSlave<SPI: Instance, W, Otype = PushPull, PULL = Floating> {
     inner: SpiInner<SPI, W>,
     pins: (SPI::Sck, SPI::So<Otype>, SPI::Si<PULL>),
@@ -411,7 +609,7 @@ impl<SPI: Instance, W, Otype, PULL> DerefMut for SpiSlave<SPI, W, Otype, PULL> {
 }
 
 /// The bit format to send the data in
-#[derive(Debug, Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum SpiBitFormat {
     /// Least significant bit first
     LsbFirst,
@@ -452,6 +650,51 @@ impl Instance for pac::SPI3 {
     type Si<PULL> = spi3::Si<PULL>;
 }
 
+/// `CR1.BR` value for the highest frequency not above `freq`
+fn baud_rate_divider<SPI: Instance>(freq: Hertz, clocks: &Clocks) -> u8 {
+    match SPI::clock(clocks) / freq {
+        0 => unreachable!(),
+        1..=2 => 0b000,
+        3..=5 => 0b001,
+        6..=11 => 0b010,
+        12..=23 => 0b011,
+        24..=47 => 0b100,
+        48..=95 => 0b101,
+        96..=191 => 0b110,
+        _ => 0b111,
+    }
+}
+
+impl<SPI: Instance, W: Copy, PULL> Spi<SPI, W, PULL> {
+    /// Changes the clock frequency, e.g. to initialize a device at a low speed first.
+    ///
+    /// Waits until the SPI is idle, the frequency is rounded down like in `Spi::new`.
+    pub fn set_frequency(&mut self, freq: Hertz, clocks: &Clocks) {
+        let br = baud_rate_divider::<SPI>(freq, clocks);
+        while !self.is_tx_empty() {}
+        while self.is_busy() {}
+        self.spi.cr1().modify(|_, w| w.spe().clear_bit());
+        self.spi.cr1().modify(|_, w| w.br().set(br).spe().set_bit());
+    }
+
+    /// Changes clock polarity and phase, e.g. to talk to devices with different modes on
+    /// a shared bus.
+    ///
+    /// Waits until the SPI is idle and briefly disables it. Must only be called between
+    /// transfers while no device is selected, not while a DMA transfer is running.
+    pub fn set_mode(&mut self, mode: impl Into<Mode>) {
+        let mode = mode.into();
+        while !self.is_tx_empty() {}
+        while self.is_busy() {}
+        self.spi.cr1().modify(|_, w| w.spe().clear_bit());
+        self.spi.cr1().modify(|_, w| {
+            w.cpha().bit(mode.phase == Phase::CaptureOnSecondTransition);
+            w.cpol().bit(mode.polarity == Polarity::IdleHigh);
+            w.spe().set_bit()
+        });
+    }
+}
+
 impl<SPI: Instance, PULL> Spi<SPI, u8, PULL> {
     /**
       Constructs an SPI instance using SPI1 in 8bit dataframe mode.
@@ -463,10 +706,13 @@ impl<SPI: Instance, PULL> Spi<SPI, u8, PULL> {
     pub fn new(
         spi: SPI,
         pins: impl Into<MasterPins<SPI::Sck, SPI::Mi<PULL>, SPI::Mo>>,
-        mode: Mode,
+        config: impl Into<SpiConfig>,
         freq: Hertz,
         clocks: &Clocks,
     ) -> Self {
+        let config = config.into();
+        let mode = config.mode;
+
         // enable or reset SPI
         let rcc = unsafe { &(*RCC::ptr()) };
         SPI::enable(rcc);
@@ -475,17 +721,11 @@ impl<SPI: Instance, PULL> Spi<SPI, u8, PULL> {
         // disable SS output
         spi.cr2().write(|w| w.ssoe().clear_bit());
 
-        let br = match SPI::clock(clocks) / freq {
-            0 => unreachable!(),
-            1..=2 => 0b000,
-            3..=5 => 0b001,
-            6..=11 => 0b010,
-            12..=23 => 0b011,
-            24..=47 => 0b100,
-            48..=95 => 0b101,
-            96..=191 => 0b110,
-            _ => 0b111,
-        };
+        if let Some(polynomial) = config.crc {
+            spi.crcpr().write(|w| w.crcpoly().set(polynomial));
+        }
+
+        let br = baud_rate_divider::<SPI>(freq, clocks);
 
         let pins = pins.into();
 
@@ -498,8 +738,9 @@ impl<SPI: Instance, PULL> Spi<SPI, u8, PULL> {
             w.mstr().set_bit();
             // baudrate value
             w.br().set(br);
-            // lsbfirst: MSB first
-            w.lsbfirst().clear_bit();
+            // lsbfirst: bit order from config
+            w.lsbfirst()
+                .bit(config.bit_format == SpiBitFormat::LsbFirst);
             // ssm: enable software slave management (NSS pin free for other uses)
             w.ssm().set_bit();
             // ssi: set nss high = master mode
@@ -510,6 +751,8 @@ impl<SPI: Instance, PULL> Spi<SPI, u8, PULL> {
             w.bidimode().clear_bit();
             // both TX and RX are used
             w.rxonly().clear_bit();
+            // crcen: hardware CRC from config
+            w.crcen().bit(config.crc.is_some());
             // spe: enable the SPI bus
             w.spe().set_bit()
         });
@@ -537,8 +780,11 @@ impl<SPI: Instance, Otype, PULL> SpiSlave<SPI, u8, Otype, PULL> {
     pub fn new(
         spi: SPI,
         pins: impl Into<SlavePins<SPI::Sck, SPI::So<Otype>, SPI::Si<PULL>>>,
-        mode: Mode,
+        config: impl Into<SpiConfig>,
     ) -> Self {
+        let config = config.into();
+        let mode = config.mode;
+
         // enable or reset SPI
         let rcc = unsafe { &(*RCC::ptr()) };
         SPI::enable(rcc);
@@ -547,6 +793,10 @@ impl<SPI: Instance, Otype, PULL> SpiSlave<SPI, u8, Otype, PULL> {
         // disable SS output
         spi.cr2().write(|w| w.ssoe().clear_bit());
 
+        if let Some(polynomial) = config.crc {
+            spi.crcpr().write(|w| w.crcpoly().set(polynomial));
+        }
+
         let pins = pins.into();
 
         spi.cr1().write(|w| {
@@ -556,8 +806,9 @@ impl<SPI: Instance, Otype, PULL> SpiSlave<SPI, u8, Otype, PULL> {
             w.cpol().bit(mode.polarity == Polarity::IdleHigh);
             // mstr: slave configuration
             w.mstr().clear_bit();
-            // lsbfirst: MSB first
-            w.lsbfirst().clear_bit();
+            // lsbfirst: bit order from config
+            w.lsbfirst()
+                .bit(config.bit_format == SpiBitFormat::LsbFirst);
             // ssm: enable software slave management (NSS pin free for other uses)
             w.ssm().set_bit();
             // ssi: set nss low = slave mode
@@ -568,6 +819,8 @@ impl<SPI: Instance, Otype, PULL> SpiSlave<SPI, u8, Otype, PULL> {
             w.bidimode().clear_bit();
             // both TX and RX are used
             w.rxonly().clear_bit();
+            // crcen: hardware CRC from config
+            w.crcen().bit(config.crc.is_some());
             // spe: enable the SPI bus
             w.spe().set_bit()
         });
@@ -584,6 +837,43 @@ impl<SPI: Instance, Otype, PULL> SpiSlave<SPI, u8, Otype, PULL> {
     }
 }
 
+impl<SPI: Instance, W: Copy, Otype, PULL> SpiSlave<SPI, W, Otype, PULL> {
+    /// Selects how the slave select (NSS) input is managed.
+    ///
+    /// With `SlaveSelect::Hardware` the NSS pin of the peripheral (`PA4`/`PA15` for SPI1,
+    /// `PB12` for SPI2, `PA15`/`PA4` for SPI3) has to be configured as input.
+    pub fn slave_select(&mut self, nss: SlaveSelect) {
+        self.spi.cr1().modify(|_, w| match nss {
+            SlaveSelect::Software => w.ssm().set_bit(),
+            SlaveSelect::Hardware => w.ssm().clear_bit(),
+        });
+    }
+
+    /// Selects the slave when NSS is managed by software, the SPI then responds to the clock
+    pub fn select(&mut self) {
+        self.spi.cr1().modify(|_, w| w.ssi().clear_bit());
+    }
+
+    /// Deselects the slave when NSS is managed by software, the SPI then ignores the clock
+    pub fn deselect(&mut self) {
+        self.spi.cr1().modify(|_, w| w.ssi().set_bit());
+    }
+
+    /// Loads the word that is shifted out with the first frame clocked by the master.
+    ///
+    /// A slave can only answer a request with the frame following it, so the master always
+    /// receives this word while it sends its first frame. Without preloading, the first
+    /// received word is `0`. Stale received data and a pending overrun are discarded.
+    pub fn preload(&mut self, word: W) {
+        while self.is_rx_not_empty() {
+            let _ = self.read_data_reg();
+        }
+        let _ = self.spi.sr().read();
+        while !self.is_tx_empty() {}
+        self.write_data_reg(word);
+    }
+}
+
 pub trait SpiReadWrite<T> {
     fn read_data_reg(&mut self) -> T;
     fn write_data_reg(&mut self, data: T);
@@ -633,10 +923,15 @@ impl<SPI: Instance, W: Copy> SpiReadWrite<W> for SpiInner<SPI, W> {
 
 impl<SPI: Instance, W: Copy> SpiInner<SPI, W> {
     /// Select which frame format is used for data transfers
+    ///
+    /// `CR1.LSBFIRST` must not be changed during a transfer, so this waits until the SPI
+    /// is idle first.
     pub fn bit_format(&mut self, format: SpiBitFormat) {
+        while !self.is_tx_empty() {}
+        while self.is_busy() {}
         self.spi
             .cr1()
-            .modify(|_, w| w.lsbfirst().bit(matches!(format, SpiBitFormat::LsbFirst)));
+            .modify(|_, w| w.lsbfirst().bit(format == SpiBitFormat::LsbFirst));
     }
 
     /// Starts listening to the SPI by enabling the _Received data
@@ -686,12 +981,32 @@ impl<SPI: Instance, W: Copy> SpiInner<SPI, W> {
     }
 }
 
-impl<SPI: Instance, PULL> Spi<SPI, u8, PULL> {
-    /// Converts from 8bit dataframe to 16bit.
-    pub fn frame_size_16bit(self) -> Spi<SPI, u16, PULL> {
+impl<SPI: Instance, W> SpiInner<SPI, W> {
+    /// Switches the data frame format.
+    ///
+    /// `CR1.DFF` must only be written while the SPI is disabled, so this waits for the
+    /// current frame to finish, disables the peripheral, changes the frame size and
+    /// enables it again.
+    fn set_frame_size<W2: FrameSize>(&mut self) {
+        while !self.spi.sr().read().txe().bit_is_set() {}
+        while self.spi.sr().read().bsy().bit_is_set() {}
         self.spi.cr1().modify(|_, w| w.spe().clear_bit());
-        self.spi.cr1().modify(|_, w| w.dff().set_bit());
+        self.spi.cr1().modify(|_, w| w.dff().bit(W2::DFF));
+        // The CRC width follows DFF, so the calculation is restarted
+        if self.spi.cr1().read().crcen().bit_is_set() {
+            self.spi.cr1().modify(|_, w| w.crcen().clear_bit());
+            self.spi.cr1().modify(|_, w| w.crcen().set_bit());
+        }
         self.spi.cr1().modify(|_, w| w.spe().set_bit());
+    }
+}
+
+impl<SPI: Instance, W, PULL> Spi<SPI, W, PULL> {
+    /// Converts to the data frame size of the `W2` word type (`u8` or `u16`).
+    ///
+    /// The SPI is briefly disabled while the frame size is changed.
+    pub fn frame_size<W2: FrameSize>(mut self) -> Spi<SPI, W2, PULL> {
+        self.inner.set_frame_size::<W2>();
         Spi {
             inner: SpiInner::new(self.inner.spi),
             pins: self.pins,
@@ -699,12 +1014,12 @@ impl<SPI: Instance, PULL> Spi<SPI, u8, PULL> {
     }
 }
 
-impl<SPI: Instance, Otype, PULL> SpiSlave<SPI, u8, Otype, PULL> {
-    /// Converts from 8bit dataframe to 16bit.
-    pub fn frame_size_16bit(self) -> SpiSlave<SPI, u16, Otype, PULL> {
-        self.spi.cr1().modify(|_, w| w.spe().clear_bit());
-        self.spi.cr1().modify(|_, w| w.dff().set_bit());
-        self.spi.cr1().modify(|_, w| w.spe().set_bit());
+impl<SPI: Instance, W, Otype, PULL> SpiSlave<SPI, W, Otype, PULL> {
+    /// Converts to the data frame size of the `W2` word type (`u8` or `u16`).
+    ///
+    /// The SPI is briefly disabled while the frame size is changed.
+    pub fn frame_size<W2: FrameSize>(mut self) -> SpiSlave<SPI, W2, Otype, PULL> {
+        self.inner.set_frame_size::<W2>();
         SpiSlave {
             inner: SpiInner::new(self.inner.spi),
             pins: self.pins,
@@ -712,29 +1027,31 @@ impl<SPI: Instance, Otype, PULL> SpiSlave<SPI, u8, Otype, PULL> {
     }
 }
 
+impl<SPI: Instance, PULL> Spi<SPI, u8, PULL> {
+    /// Converts from 8bit dataframe to 16bit.
+    pub fn frame_size_16bit(self) -> Spi<SPI, u16, PULL> {
+        self.frame_size()
+    }
+}
+
+impl<SPI: Instance, Otype, PULL> SpiSlave<SPI, u8, Otype, PULL> {
+    /// Converts from 8bit dataframe to 16bit.
+    pub fn frame_size_16bit(self) -> SpiSlave<SPI, u16, Otype, PULL> {
+        self.frame_size()
+    }
+}
+
 impl<SPI: Instance, PULL> Spi<SPI, u16, PULL> {
     /// Converts from 16bit dataframe to 8bit.
-    pub fn frame_size_8bit(self) -> Spi<SPI, u16, PULL> {
-        self.spi.cr1().modify(|_, w| w.spe().clear_bit());
-        self.spi.cr1().modify(|_, w| w.dff().clear_bit());
-        self.spi.cr1().modify(|_, w| w.spe().set_bit());
-        Spi {
-            inner: SpiInner::new(self.inner.spi),
-            pins: self.pins,
-        }
+    pub fn frame_size_8bit(self) -> Spi<SPI, u8, PULL> {
+        self.frame_size()
     }
 }
 
 impl<SPI: Instance, Otype, PULL> SpiSlave<SPI, u16, Otype, PULL> {
     /// Converts from 16bit dataframe to 8bit.
     pub fn frame_size_8bit(self) -> SpiSlave<SPI, u8, Otype, PULL> {
-        self.spi.cr1().modify(|_, w| w.spe().clear_bit());
-        self.spi.cr1().modify(|_, w| w.dff().clear_bit());
-        self.spi.cr1().modify(|_, w| w.spe().set_bit());
-        SpiSlave {
-            inner: SpiInner::new(self.inner.spi),
-            pins: self.pins,
-        }
+        self.frame_size()
     }
 }
 
@@ -744,45 +1061,138 @@ where
     W: Copy,
 {
     pub fn read_nonblocking(&mut self) -> nb::Result<W, Error> {
-        let sr = self.spi.sr().read();
-
-        Err(if sr.ovr().bit_is_set() {
-            Error::Overrun.into()
-        } else if sr.modf().bit_is_set() {
-            Error::ModeFault.into()
-        } else if sr.crcerr().bit_is_set() {
-            Error::Crc.into()
-        } else if sr.rxne().bit_is_set() {
+        match rx_status(self.spi.sr().read().bits()) {
             // NOTE(read_volatile) read only 1 byte (the svd2rust API only allows
             // reading a half-word)
-            return Ok(self.read_data_reg());
-        } else {
-            nb::Error::WouldBlock
-        })
+            Ok(()) => Ok(self.read_data_reg()),
+            Err(nb::Error::Other(Error::Overrun)) => {
+                // Clear OVR by reading DR after SR, the data in DR is stale
+                let _ = self.read_data_reg();
+                Err(Error::Overrun.into())
+            }
+            Err(e) => Err(e),
+        }
     }
     pub fn write_nonblocking(&mut self, data: W) -> nb::Result<(), Error> {
-        let sr = self.spi.sr().read();
-
-        // NOTE: Error::Overrun was deleted in #408. Need check
-        Err(if sr.modf().bit_is_set() {
-            Error::ModeFault.into()
-        } else if sr.crcerr().bit_is_set() {
-            Error::Crc.into()
-        } else if sr.txe().bit_is_set() {
-            // NOTE(write_volatile) see note above
-            self.write_data_reg(data);
-            return Ok(());
-        } else {
-            nb::Error::WouldBlock
-        })
+        tx_status(self.spi.sr().read().bits())?;
+        // NOTE(write_volatile) see note above
+        self.write_data_reg(data);
+        Ok(())
     }
     pub fn write(&mut self, words: &[W]) -> Result<(), Error> {
         self.spi_write(words)
     }
+
+    /// Returns `true` if the hardware CRC is enabled by `SpiConfig::crc`
+    pub fn is_crc_enabled(&self) -> bool {
+        self.spi.cr1().read().crcen().bit_is_set()
+    }
+
+    /// CRC calculated over the transmitted words since the last restart
+    pub fn tx_crc(&self) -> u16 {
+        self.spi.txcrcr().read().tx_crc().bits()
+    }
+
+    /// CRC calculated over the received words since the last restart
+    pub fn rx_crc(&self) -> u16 {
+        self.spi.rxcrcr().read().rx_crc().bits()
+    }
+
+    /// Restarts the CRC calculation and clears a CRC error.
+    ///
+    /// Waits until the SPI is idle, the SPI is briefly disabled. Does nothing if the
+    /// CRC is disabled.
+    pub fn reset_crc(&mut self) {
+        if !self.is_crc_enabled() {
+            return;
+        }
+        while !self.is_tx_empty() {}
+        while self.is_busy() {}
+        self.spi.cr1().modify(|_, w| w.spe().clear_bit());
+        self.spi.cr1().modify(|_, w| w.crcen().clear_bit());
+        self.spi.cr1().modify(|_, w| w.crcen().set_bit());
+        self.spi.cr1().modify(|_, w| w.spe().set_bit());
+        self.spi.sr().modify(|_, w| w.crcerr().clear_bit());
+    }
+
+    /// Transfers `words` in place followed by the CRC.
+    ///
+    /// Returns `Error::Crc` if the CRC received after the words does not match the
+    /// calculated one. Panics if the CRC is disabled.
+    pub fn transfer_with_crc(&mut self, words: &mut [W]) -> Result<(), Error> {
+        assert!(self.is_crc_enabled(), "hardware CRC is disabled");
+        self.reset_crc();
+        let n = words.len();
+        for (i, word) in words.iter_mut().enumerate() {
+            *word = self.transfer_word_crc(*word, i + 1 == n)?;
+        }
+        if n > 0 && self.finish_crc() {
+            Err(Error::Crc)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Writes `words` followed by the CRC, the received data and CRC are ignored.
+    ///
+    /// Panics if the CRC is disabled.
+    pub fn write_with_crc(&mut self, words: &[W]) -> Result<(), Error> {
+        assert!(self.is_crc_enabled(), "hardware CRC is disabled");
+        self.reset_crc();
+        let n = words.len();
+        for (i, &word) in words.iter().enumerate() {
+            self.transfer_word_crc(word, i + 1 == n)?;
+        }
+        if n > 0 {
+            self.finish_crc();
+        }
+        Ok(())
+    }
+
+    /// Transfers a word, the CRC is sent after it if it is the `last` one
+    fn transfer_word_crc(&mut self, word: W, last: bool) -> Result<W, Error> {
+        nb::block!(self.write_nonblocking(word))?;
+        if last {
+            // Has to be set before the end of the last data frame
+            self.spi.cr1().modify(|_, w| w.crcnext().set_bit());
+        }
+        nb::block!(self.read_nonblocking())
+    }
+
+    /// Reads the received CRC, returns `true` on a CRC error
+    fn finish_crc(&mut self) -> bool {
+        while !self.is_rx_not_empty() {}
+        let _ = self.read_data_reg();
+        while self.is_busy() {}
+        self.spi.cr1().modify(|_, w| w.crcnext().clear_bit());
+        let crcerr = self.spi.sr().read().crcerr().bit_is_set();
+        self.spi.sr().modify(|_, w| w.crcerr().clear_bit());
+        crcerr
+    }
 }
 
 // DMA
 
+impl<SPI: Instance, RXCH, TXCH, PULL> SpiRxTxDma<SPI, RXCH, TXCH, PULL> {
+    /// Starts a full-duplex DMA transfer, sending `tx_buffer` while receiving into `rx_buffer`.
+    ///
+    /// The RX channel runs with a higher priority than the TX channel. If the buffers differ
+    /// in length, only as many frames as the shorter buffer holds are transferred and the rest
+    /// of the longer buffer is left untouched. Both buffers are given back by `Transfer::wait`.
+    pub fn dma_transfer<RXB, TXB>(
+        self,
+        tx_buffer: TXB,
+        rx_buffer: RXB,
+    ) -> Transfer<W, (RXB, TXB), Self>
+    where
+        Self: crate::dma::ReadWriteDma<RXB, TXB, u8> + TransferPayload,
+        RXB: WriteBuffer<Word = u8>,
+        TXB: ReadBuffer<Word = u8>,
+    {
+        crate::dma::ReadWriteDma::read_write(self, rx_buffer, tx_buffer)
+    }
+}
+
 pub type SpiTxDma<SPI, CHANNEL, PULL = Floating> = TxDma<Spi<SPI, u8, PULL>, CHANNEL>;
 pub type SpiRxDma<SPI, CHANNEL, PULL = Floating> = RxDma<Spi<SPI, u8, PULL>, CHANNEL>;
 pub type SpiRxTxDma<SPI, RXCHANNEL, TXCHANNEL, PULL = Floating> =
@@ -1010,30 +1420,31 @@ macro_rules! spi_dma {
                 let (rxptr, rxlen) = unsafe { rxbuffer.write_buffer() };
                 let (txptr, txlen) = unsafe { txbuffer.read_buffer() };
 
-                if rxlen != txlen {
-                    panic!("receive and send buffer lengths do not match!");
-                }
+                // Every transmitted frame clocks in one received frame, so only the
+                // common part of both buffers is transferred.
+                let len = rxlen.min(txlen);
 
                 self.rxchannel.set_peripheral_address(
                     unsafe { (*<$SPIi>::ptr()).dr().as_ptr() as u32 },
                     false,
                 );
                 self.rxchannel.set_memory_address(rxptr as u32, true);
-                self.rxchannel.set_transfer_length(rxlen);
+                self.rxchannel.set_transfer_length(len);
 
                 self.txchannel.set_peripheral_address(
                     unsafe { (*<$SPIi>::ptr()).dr().as_ptr() as u32 },
                     false,
                 );
                 self.txchannel.set_memory_address(txptr as u32, true);
-                self.txchannel.set_transfer_length(txlen);
+                self.txchannel.set_transfer_length(len);
 
                 atomic::compiler_fence(Ordering::Release);
                 self.rxchannel.ch().cr().modify(|_, w| {
                     // memory to memory mode disabled
                     w.mem2mem().clear_bit();
-                    // medium channel priority level
-                    w.pl().medium();
+                    // high channel priority level, so DR is read before the next
+                    // frame arrives and no overrun occurs
+                    w.pl().high();
                     // 8-bit memory size
                     w.msize().bits8();
                     // 8-bit peripheral size
@@ -1269,30 +1680,31 @@ macro_rules! spi_dma {
                 let (rxptr, rxlen) = unsafe { rxbuffer.write_buffer() };
                 let (txptr, txlen) = unsafe { txbuffer.read_buffer() };
 
-                if rxlen != txlen {
-                    panic!("receive and send buffer lengths do not match!");
-                }
+                // Every transmitted frame clocks in one received frame, so only the
+                // common part of both buffers is transferred.
+                let len = rxlen.min(txlen);
 
                 self.rxchannel.set_peripheral_address(
                     unsafe { (*<$SPIi>::ptr()).dr().as_ptr() as u32 },
                     false,
                 );
                 self.rxchannel.set_memory_address(rxptr as u32, true);
-                self.rxchannel.set_transfer_length(rxlen);
+                self.rxchannel.set_transfer_length(len);
 
                 self.txchannel.set_peripheral_address(
                     unsafe { (*<$SPIi>::ptr()).dr().as_ptr() as u32 },
                     false,
                 );
                 self.txchannel.set_memory_address(txptr as u32, true);
-                self.txchannel.set_transfer_length(txlen);
+                self.txchannel.set_transfer_length(len);
 
                 atomic::compiler_fence(Ordering::Release);
                 self.rxchannel.ch().cr().modify(|_, w| {
                     // memory to memory mode disabled
                     w.mem2mem().clear_bit();
-                    // medium channel priority level
-                    w.pl().medium();
+                    // high channel priority level, so DR is read before the next
+                    // frame arrives and no overrun occurs
+                    w.pl().high();
                     // 8-bit memory size
                     w.msize().bits8();
                     // 8-bit peripheral size
@@ -1358,3 +1770,59 @@ spi_dma!(
     SpiSlave3TxDma,
     SpiSlave3RxTxDma
 );
+
+#[test]
+fn nonblocking_interleaved_loopback() {
+    /// MOSI connected to MISO, every written word is received right away
+    struct Loopback {
+        sr: u16,
+        dr: u8,
+    }
+
+    impl Loopback {
+        fn write(&mut self, word: u8) -> nb::Result<(), Error> {
+            tx_status(self.sr)?;
+            if self.sr & SR_RXNE != 0 {
+                // The previous word was not read, the new one is lost
+                self.sr |= SR_OVR;
+            } else {
+                self.dr = word;
+                self.sr |= SR_RXNE;
+            }
+            Ok(())
+        }
+
+        fn read(&mut self) -> nb::Result<u8, Error> {
+            let status = rx_status(self.sr);
+            // Reading DR clears RXNE, after reading SR also OVR
+            self.sr &= !(SR_RXNE | SR_OVR);
+            status.map(|()| self.dr)
+        }
+    }
+
+    let mut spi = Loopback { sr: SR_TXE, dr: 0 };
+    assert_eq!(spi.read(), Err(nb::Error::WouldBlock));
+    for word in 0..=u8::MAX {
+        assert_eq!(spi.write(word), Ok(()));
+        assert_eq!(spi.read(), Ok(word));
+        assert_eq!(spi.read(), Err(nb::Error::WouldBlock));
+    }
+
+    // A lost word is reported once, then the transfer continues
+    spi.write(1).unwrap();
+    spi.write(2).unwrap();
+    assert_eq!(spi.read(), Err(nb::Error::Other(Error::Overrun)));
+    spi.write(3).unwrap();
+    assert_eq!(spi.read(), Ok(3));
+
+    // Errors take precedence over pending data, busy waits
+    assert_eq!(
+        rx_status(SR_RXNE | SR_MODF),
+        Err(nb::Error::Other(Error::ModeFault))
+    );
+    assert_eq!(
+        tx_status(SR_TXE | SR_CRCERR),
+        Err(nb::Error::Other(Error::Crc))
+    );
+    assert_eq!(tx_status(0), Err(nb::Error::WouldBlock));
+}
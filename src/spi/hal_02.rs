@@ -30,6 +30,12 @@ impl From<Mode> for super::Mode {
     }
 }
 
+impl From<Mode> for super::SpiConfig {
+    fn from(m: Mode) -> Self {
+        super::Mode::from(m).into()
+    }
+}
+
 impl<SPI, W, PULL> spi::FullDuplex<W> for Spi<SPI, W, PULL>
 where
     SPI: Instance,
@@ -46,6 +52,22 @@ where
     }
 }
 
+impl<SPI, W, Otype, PULL> spi::FullDuplex<W> for SpiSlave<SPI, W, Otype, PULL>
+where
+    SPI: Instance,
+    W: Copy,
+{
+    type Error = Error;
+
+    fn read(&mut self) -> nb::Result<W, Error> {
+        self.read_nonblocking()
+    }
+
+    fn send(&mut self, data: W) -> nb::Result<(), Error> {
+        self.write_nonblocking(data)
+    }
+}
+
 impl<SPI, W, PULL> blocking::transfer::Default<W> for Spi<SPI, W, PULL>
 where
     SPI: Instance,
@@ -68,3 +90,19 @@ impl<SPI: Instance, PULL> blocking::Write<u16> for Spi<SPI, u16, PULL> {
         self.deref_mut().write(words)
     }
 }
+
+impl<SPI: Instance, PULL> blocking::Write<u8> for SpiTxOnly<SPI, u8, PULL> {
+    type Error = Error;
+
+    fn write(&mut self, words: &[u8]) -> Result<(), Error> {
+        SpiTxOnly::write(self, words)
+    }
+}
+
+impl<SPI: Instance, PULL> blocking::Write<u16> for SpiTxOnly<SPI, u16, PULL> {
+    type Error = Error;
+
+    fn write(&mut self, words: &[u16]) -> Result<(), Error> {
+        SpiTxOnly::write(self, words)
+    }
+}
@@ -28,12 +28,18 @@ impl From<Mode> for super::Mode {
     }
 }
 
+impl From<Mode> for super::SpiConfig {
+    fn from(m: Mode) -> Self {
+        super::Mode::from(m).into()
+    }
+}
+
 impl embedded_hal::spi::Error for Error {
     fn kind(&self) -> ErrorKind {
         match self {
             Self::Overrun => ErrorKind::Overrun,
             Self::ModeFault => ErrorKind::ModeFault,
-            Self::Crc => ErrorKind::Other,
+            Self::Crc | Self::TxOnly => ErrorKind::Other,
         }
     }
 }
@@ -42,8 +48,16 @@ impl<SPI: Instance, W, PULL> ErrorType for Spi<SPI, W, PULL> {
     type Error = Error;
 }
 
+impl<SPI: Instance, W, Otype, PULL> ErrorType for SpiSlave<SPI, W, Otype, PULL> {
+    type Error = Error;
+}
+
+impl<SPI: Instance, W, PULL> ErrorType for SpiTxOnly<SPI, W, PULL> {
+    type Error = Error;
+}
+
 mod nb {
-    use super::{Error, Instance, Spi};
+    use super::{Error, Instance, Spi, SpiSlave};
     use embedded_hal_nb::spi::FullDuplex;
 
     impl<SPI, W, PULL> FullDuplex<W> for Spi<SPI, W, PULL>
@@ -59,35 +73,95 @@ mod nb {
             self.write_nonblocking(data)
         }
     }
+
+    impl<SPI, W, Otype, PULL> FullDuplex<W> for SpiSlave<SPI, W, Otype, PULL>
+    where
+        SPI: Instance,
+        W: Copy,
+    {
+        fn read(&mut self) -> nb::Result<W, Error> {
+            self.read_nonblocking()
+        }
+
+        fn write(&mut self, data: W) -> nb::Result<(), Error> {
+            self.write_nonblocking(data)
+        }
+    }
 }
 
 mod blocking {
-    use super::super::{Instance, Spi};
+    use super::super::{Error, Instance, Spi, SpiTxOnly};
     use core::ops::DerefMut;
     use embedded_hal::spi::SpiBus;
 
     impl<SPI: Instance, W, PULL> SpiBus<W> for Spi<SPI, W, PULL>
+    where
+        SPI: Instance,
+        W: Copy + Default + 'static,
+    {
+        fn transfer_in_place(&mut self, words: &mut [W]) -> Result<(), Self::Error> {
+            for word in words {
+                ::nb::block!(self.write_nonblocking(*word))?;
+                *word = ::nb::block!(self.read_nonblocking())?;
+            }
+            Ok(())
+        }
+
+        fn transfer(&mut self, buff: &mut [W], data: &[W]) -> Result<(), Self::Error> {
+            // The longer of both slices determines the length, missing words are sent as 0
+            for i in 0..buff.len().max(data.len()) {
+                let word = data.get(i).copied().unwrap_or_default();
+                ::nb::block!(self.write_nonblocking(word))?;
+                let word = ::nb::block!(self.read_nonblocking())?;
+                if let Some(b) = buff.get_mut(i) {
+                    *b = word;
+                }
+            }
+            Ok(())
+        }
+
+        fn read(&mut self, words: &mut [W]) -> Result<(), Self::Error> {
+            for word in words {
+                ::nb::block!(self.write_nonblocking(W::default()))?;
+                *word = ::nb::block!(self.read_nonblocking())?;
+            }
+            Ok(())
+        }
+
+        fn write(&mut self, words: &[W]) -> Result<(), Self::Error> {
+            self.deref_mut().write(words)
+        }
+
+        fn flush(&mut self) -> Result<(), Self::Error> {
+            while !self.is_tx_empty() {}
+            while self.is_busy() {}
+            Ok(())
+        }
+    }
+    /// Only writes are supported, reading returns [`Error::TxOnly`]
+    impl<SPI: Instance, W, PULL> SpiBus<W> for SpiTxOnly<SPI, W, PULL>
     where
         SPI: Instance,
         W: Copy + 'static,
     {
         fn transfer_in_place(&mut self, _words: &mut [W]) -> Result<(), Self::Error> {
-            todo!()
+            Err(Error::TxOnly)
         }
 
         fn transfer(&mut self, _buff: &mut [W], _data: &[W]) -> Result<(), Self::Error> {
-            todo!()
+            Err(Error::TxOnly)
         }
 
         fn read(&mut self, _words: &mut [W]) -> Result<(), Self::Error> {
-            todo!()
+            Err(Error::TxOnly)
         }
 
         fn write(&mut self, words: &[W]) -> Result<(), Self::Error> {
-            self.deref_mut().write(words)
+            SpiTxOnly::write(self, words)
         }
 
         fn flush(&mut self) -> Result<(), Self::Error> {
+            while self.is_busy() {}
             Ok(())
         }
     }
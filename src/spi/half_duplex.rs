@@ -0,0 +1,142 @@
+use super::*;
+
+impl<SPI: Instance, W: Copy, PULL> Spi<SPI, W, PULL> {
+    /// Switches to bidirectional (3-wire) half-duplex mode
+    ///
+    /// Data is transmitted and received on the MOSI pin, which has to be connected
+    /// to the single data line of the device. The MISO pin is not used.
+    ///
+    /// The returned interface starts in transmit direction.
+    pub fn into_half_duplex(self, clocks: &Clocks) -> SpiHalfDuplex<SPI, W, PULL> {
+        while !self.is_tx_empty() {}
+        while self.is_busy() {}
+        self.spi.cr1().modify(|_, w| w.spe().clear_bit());
+        let br = self.spi.cr1().read().br().bits();
+        // core clock cycles per SCK period
+        let sck_cycles = clocks.sysclk().raw() / SPI::clock(clocks).raw() * (2 << br);
+        self.spi.cr1().modify(|_, w| {
+            w.bidimode().set_bit();
+            w.bidioe().set_bit()
+        });
+        self.spi.cr1().modify(|_, w| w.spe().set_bit());
+        SpiHalfDuplex {
+            inner: self,
+            sck_cycles,
+        }
+    }
+}
+
+/// Spi in bidirectional (3-wire) half-duplex master mode
+///
+/// In receive direction the master generates the clock as long as the SPI is enabled,
+/// there is no transmission to pace it. To stop the clock after the last word, the SPI
+/// has to be disabled while that word is being received: one SCK period after `RXNE`
+/// of the second to last word (or after enabling, if only one word is read) and before
+/// the last word completes. [`read`](Self::read) does this in a critical section, the
+/// SPI is left disabled in receive direction until the next read or [`set_tx`](Self::set_tx).
+pub struct SpiHalfDuplex<SPI: Instance, W, PULL = Floating> {
+    inner: Spi<SPI, W, PULL>,
+    sck_cycles: u32,
+}
+
+impl<SPI: Instance, W, PULL> Deref for SpiHalfDuplex<SPI, W, PULL> {
+    type Target = SpiInner<SPI, W>;
+    fn deref(&self) -> &Self::Target {
+        &self.inner.inner
+    }
+}
+
+impl<SPI: Instance, W, PULL> DerefMut for SpiHalfDuplex<SPI, W, PULL> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.inner.inner
+    }
+}
+
+impl<SPI: Instance, W: Copy, PULL> SpiHalfDuplex<SPI, W, PULL> {
+    /// Switches the data line to transmission
+    ///
+    /// Waits for a pending reception to finish.
+    pub fn set_tx(&mut self) {
+        if !self.is_tx() {
+            while self.is_spi_enabled() && !self.is_rx_not_empty() {}
+            self.spi.cr1().modify(|_, w| w.spe().clear_bit());
+            let _ = self.read_data_reg();
+            let _ = self.spi.sr().read();
+            self.spi.cr1().modify(|_, w| w.bidioe().set_bit());
+            self.spi.cr1().modify(|_, w| w.spe().set_bit());
+        }
+    }
+
+    /// Switches the data line to reception
+    ///
+    /// Waits for a pending transmission to finish. The SPI stays disabled, so no clock
+    /// is generated until [`read`](Self::read) is called.
+    pub fn set_rx(&mut self) {
+        if self.is_tx() {
+            while !self.is_tx_empty() {}
+            while self.is_busy() {}
+            self.spi.cr1().modify(|_, w| w.spe().clear_bit());
+            self.spi.cr1().modify(|_, w| w.bidioe().clear_bit());
+        }
+    }
+
+    /// Returns true if the data line is switched to transmission
+    pub fn is_tx(&self) -> bool {
+        self.spi.cr1().read().bidioe().bit_is_set()
+    }
+
+    fn is_spi_enabled(&self) -> bool {
+        self.spi.cr1().read().spe().bit_is_set()
+    }
+
+    /// Switches to transmission and writes all words, blocking
+    pub fn write(&mut self, words: &[W]) -> Result<(), Error> {
+        self.set_tx();
+        self.spi_write(words)
+    }
+
+    /// Switches to reception and reads `words.len()` words, blocking
+    pub fn read(&mut self, words: &mut [W]) -> Result<(), Error> {
+        if words.is_empty() {
+            return Ok(());
+        }
+        self.set_rx();
+        // discard a word left from an earlier reception
+        let _ = self.read_data_reg();
+        let _ = self.spi.sr().read();
+
+        let last = words.len() - 1;
+        let sck_cycles = self.sck_cycles;
+        cortex_m::interrupt::free(|_| {
+            self.spi.cr1().modify(|_, w| w.spe().set_bit());
+            for (i, word) in words.iter_mut().enumerate() {
+                if i == last {
+                    // the last word is being received, stop the clock after it
+                    cortex_m::asm::delay(sck_cycles);
+                    self.spi.cr1().modify(|_, w| w.spe().clear_bit());
+                }
+                while !self.is_rx_not_empty() {}
+                *word = self.read_data_reg();
+            }
+        });
+        if self.is_overrun() {
+            let _ = self.spi.sr().read();
+            return Err(Error::Overrun);
+        }
+        Ok(())
+    }
+
+    /// Leaves half-duplex mode and returns the full-duplex `Spi`
+    pub fn release(mut self) -> Spi<SPI, W, PULL> {
+        self.set_tx();
+        while !self.is_tx_empty() {}
+        while self.is_busy() {}
+        self.spi.cr1().modify(|_, w| w.spe().clear_bit());
+        self.spi.cr1().modify(|_, w| {
+            w.bidimode().clear_bit();
+            w.bidioe().clear_bit()
+        });
+        self.spi.cr1().modify(|_, w| w.spe().set_bit());
+        self.inner
+    }
+}
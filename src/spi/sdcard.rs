@@ -0,0 +1,505 @@
+//! SD card block device over SPI
+//!
+//! Gives boards without an SDIO peripheral access to the 512 byte blocks of an SD or SDHC
+//! card, the base for a FAT filesystem. The card is connected to an `Spi` in mode 0 and
+//! a chip select output pin.
+//!
+//! The card is initialized at 400 kHz and then switched to the working frequency, at most
+//! 25 MHz. All commands and data blocks are protected by CRC.
+//!
+//! ```rust
+//! let mut card = SdCard::new(spi, cs);
+//! card.init(4.MHz(), &clocks).unwrap();
+//! let mut block = [0; BLOCK_SIZE];
+//! card.read_block(0, &mut block).unwrap();
+//! ```
+//!
+//! With the `embedded-sdmmc` feature, `SdCard` implements the `BlockDevice` trait of
+//! the `embedded-sdmmc` crate, which provides a FAT filesystem on top of it.
+
+use core::cell::RefCell;
+
+use embedded_hal::digital::OutputPin;
+
+use super::{Error as SpiError, Instance, Spi};
+use crate::rcc::Clocks;
+use crate::time::{Hertz, Hz};
+
+/// Size of a data block in bytes
+pub const BLOCK_SIZE: usize = 512;
+
+const CMD0: u8 = 0;
+const CMD8: u8 = 8;
+const CMD9: u8 = 9;
+const CMD13: u8 = 13;
+const CMD16: u8 = 16;
+const CMD17: u8 = 17;
+const CMD24: u8 = 24;
+const CMD55: u8 = 55;
+const CMD58: u8 = 58;
+const CMD59: u8 = 59;
+const ACMD41: u8 = 41;
+
+const R1_IDLE: u8 = 0x01;
+const R1_ILLEGAL_COMMAND: u8 = 0x04;
+const DATA_START_TOKEN: u8 = 0xFE;
+
+// Number of ACMD41 tries, about 1 s at 400 kHz
+const INIT_RETRIES: u32 = 1_500;
+// Number of bytes to wait for a data token or the end of a write
+const BUSY_RETRIES: u32 = 100_000;
+
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// SPI transfer failed
+    Spi(SpiError),
+    /// The chip select pin could not be set
+    ChipSelect,
+    /// The card did not answer in time
+    Timeout,
+    /// The card rejected a command with the given R1 response
+    Command { command: u8, response: u8 },
+    /// The card is not a supported SD card
+    UnsupportedCard,
+    /// A data block or command was corrupted
+    Crc,
+    /// The card did not accept written data, with the data response token
+    Write(u8),
+    /// The card sent an error token instead of a data block
+    Read(u8),
+    /// The card was accessed before [`SdCard::init`] succeeded
+    NotInitialized,
+}
+
+impl From<SpiError> for Error {
+    fn from(e: SpiError) -> Self {
+        Self::Spi(e)
+    }
+}
+
+/// Type of an initialized card
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CardType {
+    /// SD version 1, byte addressed
+    Sd1,
+    /// SD version 2 standard capacity, byte addressed
+    Sd2,
+    /// SDHC or SDXC, block addressed
+    Sdhc,
+}
+
+/// SD card on an SPI bus
+pub struct SdCard<SPI: Instance, PULL, CS> {
+    // The `BlockDevice` trait accesses the card through a shared reference
+    inner: RefCell<Inner<SPI, PULL, CS>>,
+}
+
+struct Inner<SPI: Instance, PULL, CS> {
+    spi: Spi<SPI, u8, PULL>,
+    cs: CS,
+    card_type: Option<CardType>,
+}
+
+impl<SPI: Instance, PULL, CS: OutputPin> SdCard<SPI, PULL, CS> {
+    /// The card has to be initialized by [`init`](Self::init) before use
+    pub fn new(spi: Spi<SPI, u8, PULL>, cs: CS) -> Self {
+        Self {
+            inner: RefCell::new(Inner {
+                spi,
+                cs,
+                card_type: None,
+            }),
+        }
+    }
+
+    /// Initializes the card and switches the SPI to `freq` afterwards
+    pub fn init(&mut self, freq: Hertz, clocks: &Clocks) -> Result<CardType, Error> {
+        self.inner.get_mut().init(freq, clocks)
+    }
+
+    /// Type of the card, `None` before the initialization
+    pub fn card_type(&self) -> Option<CardType> {
+        self.inner.borrow().card_type
+    }
+
+    /// Reads the block with index `block`
+    ///
+    /// Returns [`Error::NotInitialized`] before the initialization.
+    pub fn read_block(&mut self, block: u32, buf: &mut [u8; BLOCK_SIZE]) -> Result<(), Error> {
+        self.inner.get_mut().read_block(block, buf)
+    }
+
+    /// Writes the block with index `block` and waits until the card has programmed it
+    ///
+    /// Returns [`Error::NotInitialized`] before the initialization.
+    pub fn write_block(&mut self, block: u32, buf: &[u8; BLOCK_SIZE]) -> Result<(), Error> {
+        self.inner.get_mut().write_block(block, buf)
+    }
+
+    /// Number of blocks of the card, read from the CSD register
+    pub fn num_blocks(&mut self) -> Result<u32, Error> {
+        self.inner.get_mut().num_blocks()
+    }
+
+    /// Releases the SPI and the chip select pin
+    pub fn release(self) -> (Spi<SPI, u8, PULL>, CS) {
+        let inner = self.inner.into_inner();
+        (inner.spi, inner.cs)
+    }
+}
+
+#[cfg(feature = "embedded-sdmmc")]
+impl<SPI: Instance, PULL, CS: OutputPin> embedded_sdmmc::BlockDevice for SdCard<SPI, PULL, CS> {
+    type Error = Error;
+
+    fn read(
+        &self,
+        blocks: &mut [embedded_sdmmc::Block],
+        start_block_idx: embedded_sdmmc::BlockIdx,
+    ) -> Result<(), Error> {
+        let mut inner = self.inner.borrow_mut();
+        for (block, idx) in blocks.iter_mut().zip(start_block_idx.0..) {
+            inner.read_block(idx, &mut block.contents)?;
+        }
+        Ok(())
+    }
+
+    fn write(
+        &self,
+        blocks: &[embedded_sdmmc::Block],
+        start_block_idx: embedded_sdmmc::BlockIdx,
+    ) -> Result<(), Error> {
+        let mut inner = self.inner.borrow_mut();
+        for (block, idx) in blocks.iter().zip(start_block_idx.0..) {
+            inner.write_block(idx, &block.contents)?;
+        }
+        Ok(())
+    }
+
+    fn num_blocks(&self) -> Result<embedded_sdmmc::BlockCount, Error> {
+        self.inner
+            .borrow_mut()
+            .num_blocks()
+            .map(embedded_sdmmc::BlockCount)
+    }
+}
+
+impl<SPI: Instance, PULL, CS: OutputPin> Inner<SPI, PULL, CS> {
+    fn init(&mut self, freq: Hertz, clocks: &Clocks) -> Result<CardType, Error> {
+        self.card_type = None;
+        self.spi.set_frequency(Hz(400_000), clocks);
+
+        // At least 74 clocks with CS and MOSI high to enter the native mode
+        self.deselect()?;
+        for _ in 0..10 {
+            self.transfer(0xFF)?;
+        }
+
+        let result = self.select().and_then(|_| self.init_card());
+        self.deselect()?;
+        let card_type = result?;
+
+        self.spi.set_frequency(freq, clocks);
+        self.card_type = Some(card_type);
+        Ok(card_type)
+    }
+
+    fn init_card(&mut self) -> Result<CardType, Error> {
+        // Enter SPI mode
+        let mut retries = 10;
+        while self.command(CMD0, 0)? != R1_IDLE {
+            retries -= 1;
+            if retries == 0 {
+                return Err(Error::Timeout);
+            }
+        }
+        self.expect(CMD59, 1, R1_IDLE)?;
+
+        // Check the voltage range and the version
+        let r1 = self.command(CMD8, 0x1AA)?;
+        let version2 = if r1 & R1_ILLEGAL_COMMAND != 0 {
+            false
+        } else if r1 == R1_IDLE {
+            let mut r7 = [0; 4];
+            self.read_bytes(&mut r7)?;
+            if r7[2] & 0x0F != 0x01 || r7[3] != 0xAA {
+                return Err(Error::UnsupportedCard);
+            }
+            true
+        } else {
+            return Err(Error::Command {
+                command: CMD8,
+                response: r1,
+            });
+        };
+
+        // Start the initialization, announce high capacity support for version 2
+        let arg = if version2 { 1 << 30 } else { 0 };
+        let mut retries = INIT_RETRIES;
+        loop {
+            self.command(CMD55, 0)?;
+            match self.command(ACMD41, arg)? {
+                0 => break,
+                R1_IDLE => {}
+                response => {
+                    return Err(Error::Command {
+                        command: ACMD41,
+                        response,
+                    })
+                }
+            }
+            retries -= 1;
+            if retries == 0 {
+                return Err(Error::Timeout);
+            }
+        }
+
+        if !version2 {
+            self.expect(CMD16, BLOCK_SIZE as u32, 0)?;
+            return Ok(CardType::Sd1);
+        }
+
+        // The card capacity status is bit 30 of the OCR
+        self.expect(CMD58, 0, 0)?;
+        let mut ocr = [0; 4];
+        self.read_bytes(&mut ocr)?;
+        if ocr[0] & 0x40 != 0 {
+            Ok(CardType::Sdhc)
+        } else {
+            self.expect(CMD16, BLOCK_SIZE as u32, 0)?;
+            Ok(CardType::Sd2)
+        }
+    }
+
+    fn read_block(&mut self, block: u32, buf: &mut [u8; BLOCK_SIZE]) -> Result<(), Error> {
+        let address = self.address(block)?;
+        self.with_selected(|card| {
+            card.expect(CMD17, address, 0)?;
+            card.read_data(buf)
+        })
+    }
+
+    fn write_block(&mut self, block: u32, buf: &[u8; BLOCK_SIZE]) -> Result<(), Error> {
+        let address = self.address(block)?;
+        self.with_selected(|card| {
+            card.expect(CMD24, address, 0)?;
+            card.transfer(0xFF)?;
+            card.transfer(DATA_START_TOKEN)?;
+            for &byte in buf.iter() {
+                card.transfer(byte)?;
+            }
+            let crc = crc16(buf);
+            card.transfer((crc >> 8) as u8)?;
+            card.transfer(crc as u8)?;
+
+            match card.transfer(0xFF)? & 0x1F {
+                0x05 => {}
+                0x0B => return Err(Error::Crc),
+                token => return Err(Error::Write(token)),
+            }
+            card.wait_ready()?;
+
+            // Check that programming succeeded
+            let r1 = card.command(CMD13, 0)?;
+            let r2 = card.transfer(0xFF)?;
+            if r1 != 0 || r2 != 0 {
+                return Err(Error::Write(r2));
+            }
+            Ok(())
+        })
+    }
+
+    fn num_blocks(&mut self) -> Result<u32, Error> {
+        let mut csd = [0; 16];
+        self.with_selected(|card| {
+            card.expect(CMD9, 0, 0)?;
+            card.read_data(&mut csd)
+        })?;
+        csd_num_blocks(&csd).ok_or(Error::UnsupportedCard)
+    }
+
+    fn address(&self, block: u32) -> Result<u32, Error> {
+        match self.card_type {
+            Some(CardType::Sdhc) => Ok(block),
+            Some(_) => Ok(block * BLOCK_SIZE as u32),
+            None => Err(Error::NotInitialized),
+        }
+    }
+
+    fn with_selected<T>(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> Result<T, Error>,
+    ) -> Result<T, Error> {
+        self.select()?;
+        let result = f(self);
+        self.deselect()?;
+        result
+    }
+
+    fn select(&mut self) -> Result<(), Error> {
+        self.cs.set_low().map_err(|_| Error::ChipSelect)
+    }
+
+    fn deselect(&mut self) -> Result<(), Error> {
+        self.cs.set_high().map_err(|_| Error::ChipSelect)?;
+        // The card releases MISO with the next clock
+        self.transfer(0xFF)?;
+        Ok(())
+    }
+
+    fn transfer(&mut self, byte: u8) -> Result<u8, Error> {
+        nb::block!(self.spi.write_nonblocking(byte))?;
+        Ok(nb::block!(self.spi.read_nonblocking())?)
+    }
+
+    fn read_bytes(&mut self, buf: &mut [u8]) -> Result<(), Error> {
+        for byte in buf.iter_mut() {
+            *byte = self.transfer(0xFF)?;
+        }
+        Ok(())
+    }
+
+    fn wait_ready(&mut self) -> Result<(), Error> {
+        for _ in 0..BUSY_RETRIES {
+            if self.transfer(0xFF)? == 0xFF {
+                return Ok(());
+            }
+        }
+        Err(Error::Timeout)
+    }
+
+    /// Sends a command and returns the R1 response
+    fn command(&mut self, command: u8, arg: u32) -> Result<u8, Error> {
+        if command != CMD0 {
+            self.wait_ready()?;
+        }
+        let mut frame = [0x40 | command, 0, 0, 0, 0, 0];
+        frame[1..5].copy_from_slice(&arg.to_be_bytes());
+        frame[5] = crc7(&frame[..5]) << 1 | 1;
+        for byte in frame {
+            self.transfer(byte)?;
+        }
+
+        // The response follows after up to 8 bytes
+        for _ in 0..8 {
+            let r1 = self.transfer(0xFF)?;
+            if r1 & 0x80 == 0 {
+                if r1 & 0x08 != 0 {
+                    return Err(Error::Crc);
+                }
+                return Ok(r1);
+            }
+        }
+        Err(Error::Timeout)
+    }
+
+    fn expect(&mut self, command: u8, arg: u32, expected: u8) -> Result<(), Error> {
+        let response = self.command(command, arg)?;
+        if response == expected {
+            Ok(())
+        } else {
+            Err(Error::Command { command, response })
+        }
+    }
+
+    fn read_data(&mut self, buf: &mut [u8]) -> Result<(), Error> {
+        let mut token = 0xFF;
+        for _ in 0..BUSY_RETRIES {
+            token = self.transfer(0xFF)?;
+            if token != 0xFF {
+                break;
+            }
+        }
+        match token {
+            DATA_START_TOKEN => {}
+            0xFF => return Err(Error::Timeout),
+            token => return Err(Error::Read(token)),
+        }
+
+        self.read_bytes(buf)?;
+        let mut crc = [0; 2];
+        self.read_bytes(&mut crc)?;
+        if u16::from_be_bytes(crc) != crc16(buf) {
+            return Err(Error::Crc);
+        }
+        Ok(())
+    }
+}
+
+/// CRC7 of commands, polynomial x^7 + x^3 + 1
+fn crc7(data: &[u8]) -> u8 {
+    let mut crc = 0u8;
+    for &byte in data {
+        for bit in (0..8).rev() {
+            let feedback = ((byte >> bit) ^ (crc >> 6)) & 1;
+            crc = (crc << 1) & 0x7F;
+            if feedback != 0 {
+                crc ^= 0x09;
+            }
+        }
+    }
+    crc
+}
+
+/// CRC16 of data blocks, CRC-16/XMODEM
+fn crc16(data: &[u8]) -> u16 {
+    let mut crc = 0u16;
+    for &byte in data {
+        crc ^= (byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                crc << 1 ^ 0x1021
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+/// Number of 512 byte blocks from the CSD register
+fn csd_num_blocks(csd: &[u8; 16]) -> Option<u32> {
+    match csd[0] >> 6 {
+        // CSD version 1: (C_SIZE + 1) * 2^(C_SIZE_MULT + 2) blocks of 2^READ_BL_LEN bytes
+        0 => {
+            let read_bl_len = (csd[5] & 0x0F) as u32;
+            let c_size =
+                ((csd[6] & 0x03) as u32) << 10 | (csd[7] as u32) << 2 | (csd[8] >> 6) as u32;
+            let c_size_mult = ((csd[9] & 0x03) << 1 | csd[10] >> 7) as u32;
+            Some((((c_size + 1) as u64) << (c_size_mult + 2 + read_bl_len) >> 9) as u32)
+        }
+        // CSD version 2: (C_SIZE + 1) * 512 KiB
+        1 => {
+            let c_size = ((csd[7] & 0x3F) as u32) << 16 | (csd[8] as u32) << 8 | csd[9] as u32;
+            Some((c_size + 1) * 1024)
+        }
+        _ => None,
+    }
+}
+
+#[test]
+fn sdcard_crc() {
+    // CMD0 and CMD8 with their well known CRC bytes
+    assert_eq!(crc7(&[0x40, 0, 0, 0, 0]) << 1 | 1, 0x95);
+    assert_eq!(crc7(&[0x48, 0, 0, 0x01, 0xAA]) << 1 | 1, 0x87);
+    // 512 bytes of 0xFF from the SD specification
+    assert_eq!(crc16(&[0xFF; BLOCK_SIZE]), 0x7FA1);
+    assert_eq!(crc16(b"123456789"), 0x31C3);
+}
+
+#[test]
+fn sdcard_csd() {
+    // 8 GB SDHC card with C_SIZE = 15159
+    let csd = [
+        0x40, 0x0E, 0x00, 0x32, 0x5B, 0x59, 0x00, 0x00, 0x3B, 0x37, 0x7F, 0x80, 0x0A, 0x40, 0x00,
+        0x8B,
+    ];
+    assert_eq!(csd_num_blocks(&csd), Some(15_160 * 1024));
+    // 1 GB SD card, C_SIZE = 3837, C_SIZE_MULT = 7, READ_BL_LEN = 9
+    let csd = [
+        0x00, 0x26, 0x00, 0x32, 0x5F, 0x59, 0x83, 0xBF, 0x76, 0xDB, 0xFF, 0x80, 0x16, 0x80, 0x00,
+        0x00,
+    ];
+    assert_eq!(csd_num_blocks(&csd), Some(3_838 * 512));
+}
@@ -0,0 +1,94 @@
+use super::*;
+
+impl<SPI: Instance> Spi<SPI, u8, Floating> {
+    /**
+      Constructs a transmit-only SPI instance in 8bit dataframe mode.
+
+      Only SCK and MOSI are needed, e.g. `(PA5, PA7)`, the MISO pin stays free for other uses.
+      See [`into_tx_only`](Spi::into_tx_only).
+    */
+    pub fn new_tx_only(
+        spi: SPI,
+        pins: impl Into<MasterPins<SPI::Sck, SPI::Mi<Floating>, SPI::Mo>>,
+        config: impl Into<SpiConfig>,
+        freq: Hertz,
+        clocks: &Clocks,
+    ) -> SpiTxOnly<SPI, u8> {
+        Self::new(spi, pins, config, freq, clocks).into_tx_only()
+    }
+}
+
+impl<SPI: Instance, W: Copy, PULL> Spi<SPI, W, PULL> {
+    /// Switches to transmit-only mode
+    ///
+    /// Uses the bidirectional mode with the output enabled, so the receiver is disabled:
+    /// no words are received, `RXNE` and `OVR` are never set and writes don't have to
+    /// drain the receive buffer. A MISO pin passed to `Spi::new` is not used.
+    pub fn into_tx_only(self) -> SpiTxOnly<SPI, W, PULL> {
+        while !self.is_tx_empty() {}
+        while self.is_busy() {}
+        self.spi.cr1().modify(|_, w| w.spe().clear_bit());
+        // clear a word and OVR left from full-duplex mode
+        let _ = self.spi.dr().read();
+        let _ = self.spi.sr().read();
+        self.spi.cr1().modify(|_, w| {
+            w.bidimode().set_bit();
+            w.bidioe().set_bit()
+        });
+        self.spi.cr1().modify(|_, w| w.spe().set_bit());
+        SpiTxOnly { inner: self }
+    }
+}
+
+/// Spi in transmit-only master mode, e.g. for displays and LED strips
+///
+/// Use `write` or `write_nonblocking`, nothing is received in this mode.
+pub struct SpiTxOnly<SPI: Instance, W, PULL = Floating> {
+    inner: Spi<SPI, W, PULL>,
+}
+
+impl<SPI: Instance, W: Copy, PULL> SpiTxOnly<SPI, W, PULL> {
+    /// Writes `words` and waits until they were sent
+    pub fn write(&mut self, words: &[W]) -> Result<(), Error> {
+        self.inner.write(words)
+    }
+
+    /// Writes a word once the transmit buffer is empty
+    pub fn write_nonblocking(&mut self, data: W) -> nb::Result<(), Error> {
+        self.inner.write_nonblocking(data)
+    }
+
+    /// Returns true if the transfer is in progress
+    pub fn is_busy(&self) -> bool {
+        self.inner.is_busy()
+    }
+
+    /// Select which frame format is used for data transfers, see [`SpiInner::bit_format`]
+    pub fn bit_format(&mut self, format: SpiBitFormat) {
+        self.inner.bit_format(format)
+    }
+
+    /// Changes the clock frequency, see [`Spi::set_frequency`]
+    pub fn set_frequency(&mut self, freq: Hertz, clocks: &Clocks) {
+        self.inner.set_frequency(freq, clocks)
+    }
+
+    /// Changes clock polarity and phase, see [`Spi::set_mode`]
+    pub fn set_mode(&mut self, mode: impl Into<Mode>) {
+        self.inner.set_mode(mode)
+    }
+
+    /// Leaves transmit-only mode and returns the full-duplex `Spi`
+    pub fn release(self) -> Spi<SPI, W, PULL> {
+        while !self.inner.is_tx_empty() {}
+        while self.inner.is_busy() {}
+        let spi = &self.inner.spi;
+        spi.cr1().modify(|_, w| w.spe().clear_bit());
+        spi.cr1().modify(|_, w| {
+            w.bidimode().clear_bit();
+            w.bidioe().clear_bit()
+        });
+        spi.cr1().modify(|_, w| w.spe().set_bit());
+        self.inner
+    }
+}
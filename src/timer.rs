@@ -17,6 +17,15 @@
   | CH3 |     PA10    |       PE13    |
   | CH4 |     PA11    |       PE14    |
 
+  Complementary outputs and break input:
+
+  | Channel | Tim1NoRemap | Tim1PartialRemap | Tim1FullRemap |
+  |:----:|:-----------:|:----------------:|:-------------:|
+  | BKIN |     PB12    |        PA6       |      PE15     |
+  | CH1N |     PB13    |        PA7       |      PE8      |
+  | CH2N |     PB14    |        PB0       |      PE10     |
+  | CH3N |     PB15    |        PB1       |      PE12     |
+
   ### TIM2
 
   | Channel | Tim2NoRemap | Tim2PartialRemap1 | Tim2PartialRemap2 | Tim2FullRemap |
@@ -58,10 +67,10 @@ use cortex_m::peripheral::SYST;
 
 use crate::time::Hertz;
 
-#[cfg(feature = "rtic")]
 pub mod monotonic;
-#[cfg(feature = "rtic")]
 pub use monotonic::*;
+pub mod capture;
+pub mod cascade;
 pub(crate) mod pins;
 pub mod pwm_input;
 pub use pins::*;
@@ -71,6 +80,8 @@ pub mod counter;
 pub use counter::*;
 pub mod pwm;
 pub use pwm::*;
+pub mod one_pulse;
+pub use one_pulse::*;
 
 mod hal_02;
 mod hal_1;
@@ -164,6 +175,129 @@ impl<TIM: Instance> TimerExt for TIM {
     }
 }
 
+/// Creates timers of several peripherals without repeating the clock configuration
+///
+/// The peripherals can be recovered with the `release` functions of the created types
+/// and passed to the group again to use them in a different mode, creating a timer
+/// resets the peripheral.
+///
+/// ```rust
+/// let timers = clocks.timers();
+/// let mut delay = timers.delay_us(dp.TIM2);
+/// let mut pwm = timers.pwm_hz(dp.TIM3, pins, &mut afio.mapr, 1.kHz());
+///
+/// // Later use TIM3 as counter
+/// let mut counter = timers.counter_hz(pwm.release().release());
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct TimerGroup {
+    clocks: Clocks,
+}
+
+impl TimerGroup {
+    pub fn new(clocks: &Clocks) -> Self {
+        Self { clocks: *clocks }
+    }
+
+    /// Clock configuration of the timers
+    pub fn clocks(&self) -> &Clocks {
+        &self.clocks
+    }
+
+    /// [Timer] with dynamic precision, e.g. for PWM input or input capture
+    pub fn timer<TIM: Instance>(&self, tim: TIM) -> Timer<TIM> {
+        Timer::new(tim, &self.clocks)
+    }
+
+    /// [FTimer] with custom fixed precision
+    pub fn ftimer<TIM: Instance, const FREQ: u32>(&self, tim: TIM) -> FTimer<TIM, FREQ> {
+        FTimer::new(tim, &self.clocks)
+    }
+
+    /// SysTick [Timer] clocked by the core clock
+    pub fn syst(&self, tim: SYST) -> Timer<SYST> {
+        Timer::syst(tim, &self.clocks)
+    }
+
+    /// Non-blocking [Counter] with custom fixed precision
+    pub fn counter<TIM: Instance, const FREQ: u32>(&self, tim: TIM) -> Counter<TIM, FREQ> {
+        tim.counter(&self.clocks)
+    }
+
+    /// Non-blocking [Counter] with fixed precision of 1 ms (1 kHz sampling)
+    pub fn counter_ms<TIM: Instance>(&self, tim: TIM) -> CounterMs<TIM> {
+        tim.counter_ms(&self.clocks)
+    }
+
+    /// Non-blocking [Counter] with fixed precision of 1 μs (1 MHz sampling)
+    pub fn counter_us<TIM: Instance>(&self, tim: TIM) -> CounterUs<TIM> {
+        tim.counter_us(&self.clocks)
+    }
+
+    /// Non-blocking [Counter] with dynamic precision which uses `Hertz` as Duration units
+    pub fn counter_hz<TIM: Instance>(&self, tim: TIM) -> CounterHz<TIM> {
+        tim.counter_hz(&self.clocks)
+    }
+
+    /// Blocking [Delay] with custom fixed precision
+    pub fn delay<TIM: Instance, const FREQ: u32>(&self, tim: TIM) -> Delay<TIM, FREQ> {
+        tim.delay(&self.clocks)
+    }
+
+    /// Blocking [Delay] with fixed precision of 1 ms (1 kHz sampling)
+    pub fn delay_ms<TIM: Instance>(&self, tim: TIM) -> DelayMs<TIM> {
+        tim.delay_ms(&self.clocks)
+    }
+
+    /// Blocking [Delay] with fixed precision of 1 μs (1 MHz sampling)
+    pub fn delay_us<TIM: Instance>(&self, tim: TIM) -> DelayUs<TIM> {
+        tim.delay_us(&self.clocks)
+    }
+
+    /// [Pwm] with custom fixed precision
+    pub fn pwm<TIM, REMAP, P, PINS, const FREQ: u32>(
+        &self,
+        tim: TIM,
+        pins: PINS,
+        mapr: &mut crate::afio::MAPR,
+        time: fugit::TimerDurationU32<FREQ>,
+    ) -> Pwm<TIM, REMAP, P, PINS, FREQ>
+    where
+        TIM: PwmExt,
+        REMAP: pins::sealed::Remap<Periph = TIM>,
+        PINS: pwm::Pins<REMAP, P>,
+    {
+        tim.pwm(pins, mapr, time, &self.clocks)
+    }
+
+    /// [PwmHz] with dynamic precision
+    pub fn pwm_hz<TIM, REMAP, P, PINS>(
+        &self,
+        tim: TIM,
+        pins: PINS,
+        mapr: &mut crate::afio::MAPR,
+        freq: Hertz,
+    ) -> PwmHz<TIM, REMAP, P, PINS>
+    where
+        TIM: PwmExt,
+        REMAP: pins::sealed::Remap<Periph = TIM>,
+        PINS: pwm::Pins<REMAP, P>,
+    {
+        tim.pwm_hz(pins, mapr, freq, &self.clocks)
+    }
+}
+
+/// Creates a [TimerGroup] from the clock configuration
+pub trait TimerGroupExt {
+    fn timers(&self) -> TimerGroup;
+}
+
+impl TimerGroupExt for Clocks {
+    fn timers(&self) -> TimerGroup {
+        TimerGroup::new(self)
+    }
+}
+
 pub trait SysTimerExt: Sized {
     /// Creates timer which takes [Hertz] as Duration
     fn counter_hz(self, clocks: &Clocks) -> SysCounterHz;
@@ -281,6 +415,7 @@ mod sealed {
         fn start_one_pulse(&mut self);
         fn cr1_reset(&mut self);
         fn stop_in_debug(&mut self, dbg: &mut DBG, state: bool);
+        unsafe fn steal() -> Self;
     }
 
     pub trait WithPwm: General {
@@ -290,6 +425,28 @@ mod sealed {
         fn preload_output_channel_in_mode(&mut self, channel: Channel, mode: Ocm);
         fn start_pwm(&mut self);
         fn enable_channel(channel: u8, b: bool);
+        /// Sets CCxP, the output is active low if set
+        fn set_channel_polarity(channel: u8, active_low: bool);
+        /// Sets MOE on advanced timers
+        fn enable_main_output(&mut self) {}
+    }
+
+    pub trait Advanced: WithPwm {
+        fn enable_nchannel(channel: u8, b: bool);
+        /// Sets CCxNP, the complementary output is active low if set
+        fn set_nchannel_polarity(channel: u8, active_low: bool);
+        /// Sets OISx or OISxN, the output level while MOE is cleared, and OSSI
+        fn set_idle_state(channel: u8, complementary: bool, high: bool);
+        fn set_dtg_value(&mut self, value: u8);
+        fn read_dtg_value(&self) -> u8;
+        fn enable_break(&mut self, active_high: bool);
+        fn disable_break(&mut self);
+        fn set_main_output(&mut self, b: bool);
+        fn is_main_output_enabled(&self) -> bool;
+        /// Sets BIE, the break interrupt enable
+        fn listen_break(&mut self, b: bool);
+        fn is_break_pending(&self) -> bool;
+        fn clear_break_flag(&mut self);
     }
 
     pub trait MasterTimer: General {
@@ -297,7 +454,7 @@ mod sealed {
         fn master_mode(&mut self, mode: Self::Mms);
     }
 }
-pub(crate) use sealed::{General, MasterTimer, WithPwm};
+pub(crate) use sealed::{Advanced, General, MasterTimer, WithPwm};
 
 pub trait Instance:
     crate::Sealed + rcc::Enable + rcc::Reset + rcc::BusTimerClock + General
@@ -412,6 +569,10 @@ macro_rules! hal {
                 fn stop_in_debug(&mut self, dbg: &mut DBG, state: bool) {
                     dbg.cr().modify(|_, w| w.$dbg_timX_stop().bit(state));
                 }
+                #[inline(always)]
+                unsafe fn steal() -> Self {
+                    <$TIM>::steal()
+                }
             }
             $(with_pwm!($TIM: $cnum $(, $aoe)?);)?
 
@@ -472,6 +633,14 @@ macro_rules! with_pwm {
                     unsafe { bb::write(tim.ccer(), c*4, b); }
                 }
             }
+
+            #[inline(always)]
+            fn set_channel_polarity(c: u8, active_low: bool) {
+                let tim = unsafe { &*<$TIM>::ptr() };
+                if c < Self::CH_NUMBER {
+                    unsafe { bb::write(tim.ccer(), c*4 + 1, active_low); }
+                }
+            }
         }
     };
     ($TIM:ty: CH2) => {
@@ -524,6 +693,14 @@ macro_rules! with_pwm {
                     unsafe { bb::write(tim.ccer(), c*4, b); }
                 }
             }
+
+            #[inline(always)]
+            fn set_channel_polarity(c: u8, active_low: bool) {
+                let tim = unsafe { &*<$TIM>::ptr() };
+                if c < Self::CH_NUMBER {
+                    unsafe { bb::write(tim.ccer(), c*4 + 1, active_low); }
+                }
+            }
         }
     };
     ($TIM:ty: CH4 $(, $aoe:ident)?) => {
@@ -570,6 +747,11 @@ macro_rules! with_pwm {
                 self.cr1().modify(|_, w| w.cen().set_bit());
             }
 
+            #[inline(always)]
+            fn enable_main_output(&mut self) {
+                $(let $aoe = self.bdtr().modify(|_, w| w.moe().set_bit());)?
+            }
+
             #[inline(always)]
             fn enable_channel(c: u8, b: bool) {
                 let tim = unsafe { &*<$TIM>::ptr() };
@@ -577,10 +759,97 @@ macro_rules! with_pwm {
                     unsafe { bb::write(tim.ccer(), c*4, b); }
                 }
             }
+
+            #[inline(always)]
+            fn set_channel_polarity(c: u8, active_low: bool) {
+                let tim = unsafe { &*<$TIM>::ptr() };
+                if c < Self::CH_NUMBER {
+                    unsafe { bb::write(tim.ccer(), c*4 + 1, active_low); }
+                }
+            }
         }
     }
 }
 
+#[cfg(any(feature = "stm32f100", feature = "stm32f103", feature = "connectivity"))]
+macro_rules! advanced {
+    ($($TIM:ty,)+) => {
+        $(
+            impl Advanced for $TIM {
+                #[inline(always)]
+                fn enable_nchannel(c: u8, b: bool) {
+                    let tim = unsafe { &*<$TIM>::ptr() };
+                    // CH4 has no complementary output
+                    if c < Self::CH_NUMBER - 1 {
+                        unsafe { bb::write(tim.ccer(), c*4 + 2, b); }
+                    }
+                }
+                #[inline(always)]
+                fn set_nchannel_polarity(c: u8, active_low: bool) {
+                    let tim = unsafe { &*<$TIM>::ptr() };
+                    if c < Self::CH_NUMBER - 1 {
+                        unsafe { bb::write(tim.ccer(), c*4 + 3, active_low); }
+                    }
+                }
+                #[inline(always)]
+                fn set_idle_state(c: u8, complementary: bool, high: bool) {
+                    let tim = unsafe { &*<$TIM>::ptr() };
+                    // CH4 has no complementary output
+                    if c < Self::CH_NUMBER && !(complementary && c == Self::CH_NUMBER - 1) {
+                        unsafe { bb::write(tim.cr2(), 8 + c*2 + complementary as u8, high); }
+                    }
+                    // Drive the outputs to their idle level while MOE is cleared
+                    tim.bdtr().modify(|_, w| w.ossi().set_bit());
+                }
+                #[inline(always)]
+                fn set_dtg_value(&mut self, value: u8) {
+                    self.bdtr().modify(|_, w| w.dtg().set(value));
+                }
+                #[inline(always)]
+                fn read_dtg_value(&self) -> u8 {
+                    self.bdtr().read().dtg().bits()
+                }
+                #[inline(always)]
+                fn enable_break(&mut self, active_high: bool) {
+                    // Keep MOE cleared after a break until it is set again by software
+                    // and drive the outputs to their idle level while MOE is cleared
+                    self.bdtr().modify(|_, w| {
+                        w.bke().set_bit()
+                            .bkp().bit(active_high)
+                            .aoe().clear_bit()
+                            .ossi().set_bit()
+                    });
+                }
+                #[inline(always)]
+                fn disable_break(&mut self) {
+                    // AOE stays cleared, so outputs disabled by software stay off
+                    self.bdtr().modify(|_, w| w.bke().clear_bit());
+                }
+                #[inline(always)]
+                fn set_main_output(&mut self, b: bool) {
+                    self.bdtr().modify(|_, w| w.moe().bit(b));
+                }
+                #[inline(always)]
+                fn is_main_output_enabled(&self) -> bool {
+                    self.bdtr().read().moe().bit_is_set()
+                }
+                #[inline(always)]
+                fn listen_break(&mut self, b: bool) {
+                    self.dier().modify(|_, w| w.bie().bit(b));
+                }
+                #[inline(always)]
+                fn is_break_pending(&self) -> bool {
+                    self.sr().read().bif().bit_is_set()
+                }
+                #[inline(always)]
+                fn clear_break_flag(&mut self) {
+                    self.sr().write(|w| unsafe { w.bits(0xffff & !(1 << 7)) });
+                }
+            }
+        )+
+    };
+}
+
 impl<TIM: Instance> Timer<TIM> {
     /// Initialize timer
     pub fn new(tim: TIM, clocks: &Clocks) -> Self {
@@ -639,6 +908,47 @@ impl<TIM: Instance> Timer<TIM> {
     pub fn stop_in_debug(&mut self, dbg: &mut DBG, state: bool) {
         self.tim.stop_in_debug(dbg, state);
     }
+
+    /// Starts the timer with an update interrupt at `freq`, e.g. for periodic tasks.
+    ///
+    /// The interrupt of the timer still has to be unmasked in the NVIC and the handler has
+    /// to clear the flag with `clear_interrupt(Event::Update)`. The update event loading
+    /// the prescaler does not raise the interrupt and a pending flag is cleared, so the
+    /// first interrupt occurs after a full period.
+    ///
+    /// Fails with `Error::WrongAutoReload` if `freq` is 0 or too high or too low for the
+    /// timer clock.
+    pub fn start_periodic(&mut self, freq: Hertz) -> Result<(), Error> {
+        let ticks = self
+            .clk
+            .raw()
+            .checked_div(freq.raw())
+            .ok_or(Error::WrongAutoReload)? as u64;
+        let (psc, arr) =
+            duration_arr_presc(ticks, TIM::max_auto_reload()).ok_or(Error::WrongAutoReload)?;
+
+        self.tim.disable_counter();
+        self.tim.set_prescaler(psc);
+        self.tim.set_auto_reload(arr)?;
+        // Loads the prescaler and resets the counter
+        self.tim.trigger_update();
+        self.tim.clear_interrupt_flag(Event::Update);
+        self.tim.listen_interrupt(Event::Update, true);
+        self.tim.enable_counter();
+        Ok(())
+    }
+
+    /// Stops the timer started by `start_periodic` and disables the update interrupt
+    pub fn stop_periodic(&mut self) {
+        self.tim.disable_counter();
+        self.tim.listen_interrupt(Event::Update, false);
+        self.tim.clear_interrupt_flag(Event::Update);
+    }
+
+    /// Returns `true` if an update interrupt is pending
+    pub fn is_pending(&self) -> bool {
+        self.tim.get_interrupt_flag().contains(Event::Update)
+    }
 }
 
 impl<TIM: Instance + MasterTimer> Timer<TIM> {
@@ -746,6 +1056,24 @@ const fn compute_arr_presc(freq: u32, clock: u32) -> (u16, u32) {
     (psc as u16, arr)
 }
 
+/// Prescaler and auto-reload value for a period of `ticks` timer clock cycles with the
+/// smallest prescaler, `None` if the period does not fit the timer
+const fn duration_arr_presc(ticks: u64, max_arr: u32) -> Option<(u16, u32)> {
+    if ticks < 2 {
+        return None;
+    }
+    let psc = (ticks - 1) / (max_arr as u64 + 1);
+    if psc > u16::MAX as u64 {
+        return None;
+    }
+    // Round to the nearest tick of the prescaled clock
+    let arr = (ticks + (psc + 1) / 2) / (psc + 1) - 1;
+    if arr == 0 {
+        return None;
+    }
+    Some((psc as u16, arr as u32))
+}
+
 hal!(
     pac::TIM2: [Timer2, u16, dbg_tim2_stop, c: (CH4), m: tim2,],
     pac::TIM3: [Timer3, u16, dbg_tim3_stop, c: (CH4), m: tim2,],
@@ -756,6 +1084,9 @@ hal!(
     pac::TIM1: [Timer1, u16, dbg_tim1_stop, c: (CH4, _aoe), m: tim1,],
 );
 
+#[cfg(any(feature = "stm32f100", feature = "stm32f103", feature = "connectivity"))]
+advanced!(pac::TIM1,);
+
 #[cfg(any(feature = "stm32f100", feature = "high", feature = "connectivity"))]
 hal! {
     pac::TIM6: [Timer6, u16, dbg_tim6_stop, m: tim6,],
@@ -791,6 +1122,9 @@ hal! {
     pac::TIM8: [Timer8, u16, dbg_tim8_stop, c: (CH4, _aoe), m: tim1,],
 }
 
+#[cfg(all(feature = "stm32f103", feature = "high"))]
+advanced!(pac::TIM8,);
+
 //TODO: restore these timers once stm32-rs has been updated
 /*
  *   dbg_tim(12-13)_stop fields missing from 103 xl in stm32-rs
@@ -813,3 +1147,13 @@ hal! {
     TIM11: (tim11, dbg_tim11_stop),
 }
 */
+
+#[test]
+fn timer_duration_arr_presc() {
+    assert_eq!(duration_arr_presc(36_000, 0xffff), Some((0, 35_999)));
+    assert_eq!(duration_arr_presc(72_000, 0xffff), Some((1, 35_999)));
+    assert_eq!(duration_arr_presc(72_000_000, 0xffff), Some((1098, 65_513)));
+    assert_eq!(duration_arr_presc(1 << 32, 0xffff), Some((0xffff, 0xffff)));
+    assert_eq!(duration_arr_presc((1 << 32) + 1, 0xffff), None);
+    assert_eq!(duration_arr_presc(1, 0xffff), None);
+}
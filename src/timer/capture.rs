@@ -0,0 +1,291 @@
+//! Input capture on a single timer channel.
+//!
+//! The timer counts freely at a selectable frequency and the counter value is
+//! latched on an edge of the input signal. Together with the number of counter
+//! overflows between two captures, arbitrary long periods can be measured,
+//! e.g. the pulses of a tachometer or the echo of an ultrasonic sensor.
+//!
+//! [`InputCapture::read`] has to be called at least once per counter period
+//! (65536 ticks) to keep track of the overflows, either by polling it or from the
+//! timer interrupt after [`listen_capture`](InputCapture::listen_capture) and
+//! [`listen_overflow`](InputCapture::listen_overflow).
+
+use core::marker::PhantomData;
+
+use crate::pac;
+
+use crate::afio::MAPR;
+use crate::gpio::{self, Input};
+use crate::time::Hertz;
+use crate::timer::{Event, Timer};
+
+use super::pins::{sealed::Remap, CPin};
+
+/// Signal edge on which the counter is captured
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Edge {
+    Rising,
+    Falling,
+}
+
+/// Number of edges needed for one capture
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CapturePrescaler {
+    Div1 = 0,
+    Div2 = 1,
+    Div4 = 2,
+    Div8 = 3,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CaptureConfig {
+    pub edge: Edge,
+    pub prescaler: CapturePrescaler,
+    /// Input filter `ICxF`, `0` disables the filter
+    pub filter: u8,
+}
+
+impl CaptureConfig {
+    pub fn edge(mut self, edge: Edge) -> Self {
+        self.edge = edge;
+        self
+    }
+
+    pub fn prescaler(mut self, prescaler: CapturePrescaler) -> Self {
+        self.prescaler = prescaler;
+        self
+    }
+
+    /// Sets the input filter, see the `ICxF` field in the reference manual for the values
+    ///
+    /// # Panics
+    ///
+    /// Panics if `filter` doesn't fit the 4 bit field.
+    pub fn filter(mut self, filter: u8) -> Self {
+        assert!(filter < 16);
+        self.filter = filter;
+        self
+    }
+}
+
+impl Default for CaptureConfig {
+    fn default() -> Self {
+        Self {
+            edge: Edge::Rising,
+            prescaler: CapturePrescaler::Div1,
+            filter: 0,
+        }
+    }
+}
+
+impl From<Edge> for CaptureConfig {
+    fn from(edge: Edge) -> Self {
+        Self::default().edge(edge)
+    }
+}
+
+/// A captured counter value
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Capture {
+    /// Counter value at the edge
+    pub value: u16,
+    /// Counter overflows since the previous capture
+    pub overflows: u32,
+}
+
+impl Capture {
+    /// Timer ticks between the `previous` capture and this one
+    pub fn ticks_since(&self, previous: &Capture) -> u64 {
+        (((self.overflows as u64) << 16) + self.value as u64).wrapping_sub(previous.value as u64)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// A capture was overwritten before it has been read
+    Overcapture,
+}
+
+/// Input capture on channel `C` of a timer
+pub struct InputCapture<TIM, REMAP, PIN, const C: u8> {
+    tim: TIM,
+    pin: PIN,
+    freq: Hertz,
+    overflows: u32,
+    _remap: PhantomData<REMAP>,
+}
+
+macro_rules! hal {
+    ($($TIMX:ty,)+) => {
+        $(
+            impl Timer<$TIMX> {
+                /// Captures the counter counting at `freq` on edges of `pin`
+                ///
+                /// # Panics
+                ///
+                /// Panics if `freq` is higher than the timer clock or lower than
+                /// 1/65536 of it, which the 16 bit prescaler can't divide to.
+                pub fn input_capture<REMAP, PIN, MODE, const C: u8>(
+                    self,
+                    pin: PIN,
+                    mapr: &mut MAPR,
+                    freq: Hertz,
+                    config: impl Into<CaptureConfig>,
+                ) -> InputCapture<$TIMX, REMAP, PIN, C>
+                where
+                    REMAP: Remap<Periph = $TIMX>,
+                    PIN: CPin<REMAP, C> + gpio::PinExt<Mode = Input<MODE>>,
+                {
+                    REMAP::remap(mapr);
+                    let Self { tim, clk } = self;
+
+                    let psc = clk.raw().checked_div(freq.raw()).unwrap_or(0);
+                    assert!(
+                        (1..=1 << 16).contains(&psc),
+                        "capture frequency out of the prescaler range"
+                    );
+                    tim.psc().write(|w| w.psc().set((psc - 1) as u16));
+                    tim.arr().write(|w| w.arr().set(u16::MAX));
+                    // Load the prescaler without raising an update interrupt
+                    tim.cr1().modify(|_, w| w.urs().set_bit());
+                    tim.egr().write(|w| w.ug().set_bit());
+                    tim.cr1().modify(|_, w| w.urs().clear_bit());
+
+                    let mut capture = InputCapture {
+                        tim,
+                        pin,
+                        freq: clk / psc,
+                        overflows: 0,
+                        _remap: PhantomData,
+                    };
+                    capture.configure(config.into());
+                    capture.tim.sr().write(|w| unsafe { w.bits(0) });
+                    capture.tim.cr1().modify(|_, w| w.cen().set_bit());
+                    capture
+                }
+            }
+
+            impl<REMAP, PIN, const C: u8> InputCapture<$TIMX, REMAP, PIN, C> {
+                /// Changes the edge, prescaler and filter of the capture
+                pub fn configure(&mut self, config: CaptureConfig) {
+                    let c = C as u32;
+                    let shift = (c % 2) * 8;
+                    let ccmr = (config.filter as u32) << 4 | (config.prescaler as u32) << 2 | 0b01;
+
+                    // The channel has to be disabled to change CCxS
+                    self.tim
+                        .ccer()
+                        .modify(|r, w| unsafe { w.bits(r.bits() & !(1 << (c * 4))) });
+                    if c < 2 {
+                        self.tim.ccmr1_input().modify(|r, w| unsafe {
+                            w.bits(r.bits() & !(0xff << shift) | ccmr << shift)
+                        });
+                    } else {
+                        self.tim.ccmr2_input().modify(|r, w| unsafe {
+                            w.bits(r.bits() & !(0xff << shift) | ccmr << shift)
+                        });
+                    }
+                    self.set_edge(config.edge);
+                    self.tim
+                        .ccer()
+                        .modify(|r, w| unsafe { w.bits(r.bits() | 1 << (c * 4)) });
+                }
+
+                /// Changes the captured edge, e.g. to measure a pulse width
+                pub fn set_edge(&mut self, edge: Edge) {
+                    let ccxp = 1 << (C as u32 * 4 + 1);
+                    self.tim.ccer().modify(|r, w| unsafe {
+                        w.bits(match edge {
+                            Edge::Rising => r.bits() & !ccxp,
+                            Edge::Falling => r.bits() | ccxp,
+                        })
+                    });
+                }
+
+                /// Frequency of the timer ticks
+                pub fn tick_frequency(&self) -> Hertz {
+                    self.freq
+                }
+
+                /// Returns the next capture and the overflows since the previous one.
+                ///
+                /// Also counts a pending overflow if there is no capture.
+                pub fn read(&mut self) -> nb::Result<Capture, Error> {
+                    let sr = self.tim.sr().read().bits();
+                    let uif = sr & Event::Update.bits() != 0;
+                    if sr & (1 << (C + 1)) == 0 {
+                        if uif {
+                            self.clear_flags(Event::Update.bits());
+                            self.overflows += 1;
+                        }
+                        return Err(nb::Error::WouldBlock);
+                    }
+
+                    // Reading the captured value clears CCxIF
+                    let value = self.tim.ccr(C as usize).read().bits() as u16;
+                    // A pending overflow belongs to this capture if the counter
+                    // wrapped before the edge, otherwise it is left for the next one
+                    if uif && value < 0x8000 {
+                        self.clear_flags(Event::Update.bits());
+                        self.overflows += 1;
+                    }
+                    let capture = Capture {
+                        value,
+                        overflows: self.overflows,
+                    };
+                    self.overflows = 0;
+
+                    let ccxof = 1 << (C + 9);
+                    if sr & ccxof != 0 {
+                        self.clear_flags(ccxof);
+                        Err(nb::Error::Other(Error::Overcapture))
+                    } else {
+                        Ok(capture)
+                    }
+                }
+
+                /// Enables the capture interrupt
+                pub fn listen_capture(&mut self) {
+                    self.tim
+                        .dier()
+                        .modify(|r, w| unsafe { w.bits(r.bits() | 1 << (C + 1)) });
+                }
+
+                pub fn unlisten_capture(&mut self) {
+                    self.tim
+                        .dier()
+                        .modify(|r, w| unsafe { w.bits(r.bits() & !(1 << (C + 1))) });
+                }
+
+                /// Enables the update interrupt on counter overflow
+                pub fn listen_overflow(&mut self) {
+                    self.tim.dier().modify(|_, w| w.uie().set_bit());
+                }
+
+                pub fn unlisten_overflow(&mut self) {
+                    self.tim.dier().modify(|_, w| w.uie().clear_bit());
+                }
+
+                /// Stops the timer and returns the peripheral and the pin
+                pub fn release(self) -> ($TIMX, PIN) {
+                    self.tim.cr1().modify(|_, w| w.cen().clear_bit());
+                    self.tim.ccer().reset();
+                    self.tim.dier().reset();
+                    (self.tim, self.pin)
+                }
+
+                fn clear_flags(&mut self, mask: u32) {
+                    self.tim.sr().write(|w| unsafe { w.bits(0xffff & !mask) });
+                }
+            }
+        )+
+    };
+}
+
+#[cfg(any(feature = "stm32f100", feature = "stm32f103", feature = "connectivity"))]
+hal!(pac::TIM1,);
+
+hal!(pac::TIM2, pac::TIM3,);
+
+#[cfg(feature = "medium")]
+hal!(pac::TIM4,);
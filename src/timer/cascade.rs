@@ -0,0 +1,407 @@
+//! 32 bit counter from two cascaded 16 bit timers.
+//!
+//! The master timer counts at the selected frequency and sends its update event
+//! on every overflow to the trigger output (TRGO). The slave timer counts these
+//! overflows in external clock mode 1, clocked by the internal trigger input
+//! (ITRx) connected to the master.
+//!
+//! ## Internal trigger connections
+//!
+//! | Slave | ITR0 | ITR1 | ITR2 | ITR3 |
+//! |:-----:|:----:|:----:|:----:|:----:|
+//! | TIM1  | TIM5 | TIM2 | TIM3 | TIM4 |
+//! | TIM2  | TIM1 | TIM8 | TIM3 | TIM4 |
+//! | TIM3  | TIM1 | TIM2 | TIM5 | TIM4 |
+//! | TIM4  | TIM1 | TIM2 | TIM3 | TIM8 |
+//! | TIM5  | TIM2 | TIM3 | TIM4 | TIM8 |
+//! | TIM8  | TIM1 | TIM2 | TIM4 | TIM5 |
+//!
+//! On connectivity line devices ITR1 of TIM2 can be remapped to the Ethernet PTP
+//! or USB OTG SOF output, only the default connection to TIM8 is used here.
+//!
+//! ```rust
+//! let mut counter = Timer::new(dp.TIM2, &clocks).cascade(Timer::new(dp.TIM3, &clocks), 1.MHz());
+//! let us = counter.count();
+//! ```
+//!
+//! [`Cascade::input_capture`] latches the 32 bit counter on edges of a master timer channel.
+//! Only the master has a capture register, so the slave part is taken when the capture is
+//! read. [`CascadeCapture::read`] has to be called within one master period (65536 ticks)
+//! after the edge.
+
+use core::marker::PhantomData;
+
+use crate::afio::MAPR;
+use crate::gpio::{self, Input};
+use crate::pac;
+use crate::time::Hertz;
+
+use super::capture::{CaptureConfig, Edge, Error};
+use super::pins::{sealed::Remap, CPin};
+use super::{Instance, Timer};
+
+pub(crate) mod sealed {
+    use crate::timer::capture::CaptureConfig;
+
+    pub trait Cascadable {
+        /// Sends the update event to TRGO
+        fn set_trgo_update(&mut self);
+        /// Clocks the counter by the internal trigger input `itr`
+        fn set_external_clock(&mut self, itr: u8);
+        fn reset_slave_mode(&mut self);
+        /// Sets channel `c` to input capture on TIx
+        fn configure_capture(&mut self, c: u8, config: CaptureConfig);
+        fn disable_capture(&mut self, c: u8);
+        fn set_capture_interrupt(&mut self, c: u8, enable: bool);
+        fn status(&self) -> u32;
+        fn clear_flags(&mut self, mask: u32);
+        /// Reads the captured value, clearing CCxIF
+        fn read_capture(&self, c: u8) -> u16;
+    }
+
+    /// The slave timer is connected to `MASTER` by the internal trigger input `ITR`
+    pub trait Itr<MASTER> {
+        const ITR: u8;
+    }
+}
+use sealed::{Cascadable, Itr};
+
+/// Master and slave timer forming a 32 bit counter
+pub struct Cascade<MASTER: Instance, SLAVE: Instance> {
+    master: Timer<MASTER>,
+    slave: Timer<SLAVE>,
+    freq: Hertz,
+}
+
+impl<MASTER: Instance + Cascadable> Timer<MASTER> {
+    /// Links this timer as master counting at `freq` to `slave`, counting the overflows
+    pub fn cascade<SLAVE>(self, mut slave: Timer<SLAVE>, freq: Hertz) -> Cascade<MASTER, SLAVE>
+    where
+        SLAVE: Instance + Cascadable + Itr<MASTER>,
+    {
+        let mut master = self;
+        let psc = master.clk.raw() / freq.raw();
+        assert!((1..=1 << 16).contains(&psc));
+
+        master.tim.cr1_reset();
+        slave.tim.cr1_reset();
+
+        master.tim.set_prescaler((psc - 1) as u16);
+        master.tim.set_auto_reload(0xffff).unwrap();
+        master.tim.trigger_update();
+        master.tim.set_trgo_update();
+
+        slave.tim.set_prescaler(0);
+        slave.tim.set_auto_reload(0xffff).unwrap();
+        slave.tim.trigger_update();
+        slave.tim.set_external_clock(SLAVE::ITR);
+
+        slave.tim.enable_counter();
+        master.tim.enable_counter();
+
+        let freq = master.clk / psc;
+        Cascade {
+            master,
+            slave,
+            freq,
+        }
+    }
+}
+
+impl<MASTER, SLAVE> Cascade<MASTER, SLAVE>
+where
+    MASTER: Instance + Cascadable,
+    SLAVE: Instance + Cascadable,
+{
+    /// Current value of the 32 bit counter
+    pub fn count(&self) -> u32 {
+        loop {
+            let high: u32 = self.slave.tim.read_count().into();
+            let low: u32 = self.master.tim.read_count().into();
+            // Retry if the master overflowed in between
+            if high == self.slave.tim.read_count().into() {
+                return high << 16 | low;
+            }
+        }
+    }
+
+    /// Frequency of the counter ticks
+    pub fn frequency(&self) -> Hertz {
+        self.freq
+    }
+
+    /// Sets the counter to 0
+    pub fn reset(&mut self) {
+        self.master.tim.disable_counter();
+        self.master.tim.reset_counter();
+        self.slave.tim.reset_counter();
+        self.master.tim.enable_counter();
+    }
+
+    /// Stops and unlinks the timers
+    pub fn release(mut self) -> (Timer<MASTER>, Timer<SLAVE>) {
+        self.master.tim.cr1_reset();
+        self.slave.tim.cr1_reset();
+        self.slave.tim.reset_slave_mode();
+        (self.master, self.slave)
+    }
+}
+
+/// Input capture of the 32 bit counter on channel `C` of the master timer
+pub struct CascadeCapture<MASTER: Instance, SLAVE: Instance, REMAP, PIN, const C: u8> {
+    cascade: Cascade<MASTER, SLAVE>,
+    pin: PIN,
+    _remap: PhantomData<REMAP>,
+}
+
+impl<MASTER, SLAVE> Cascade<MASTER, SLAVE>
+where
+    MASTER: Instance + Cascadable,
+    SLAVE: Instance + Cascadable,
+{
+    /// Captures the 32 bit counter on edges of `pin`, connected to channel `C` of the master
+    pub fn input_capture<REMAP, PIN, MODE, const C: u8>(
+        mut self,
+        pin: PIN,
+        mapr: &mut MAPR,
+        config: impl Into<CaptureConfig>,
+    ) -> CascadeCapture<MASTER, SLAVE, REMAP, PIN, C>
+    where
+        REMAP: Remap<Periph = MASTER>,
+        PIN: CPin<REMAP, C> + gpio::PinExt<Mode = Input<MODE>>,
+    {
+        REMAP::remap(mapr);
+        self.master.tim.configure_capture(C, config.into());
+        self.master.tim.clear_flags(1 << (C + 1) | 1 << (C + 9));
+        CascadeCapture {
+            cascade: self,
+            pin,
+            _remap: PhantomData,
+        }
+    }
+}
+
+impl<MASTER, SLAVE, REMAP, PIN, const C: u8> CascadeCapture<MASTER, SLAVE, REMAP, PIN, C>
+where
+    MASTER: Instance + Cascadable,
+    SLAVE: Instance + Cascadable,
+{
+    /// Changes the edge, prescaler and filter of the capture
+    pub fn configure(&mut self, config: CaptureConfig) {
+        self.cascade.master.tim.configure_capture(C, config);
+    }
+
+    /// Returns the 32 bit counter value at the last edge
+    ///
+    /// The result is wrong if the master counter overflowed twice since the edge, i.e. if
+    /// more than 65536 ticks passed between the edge and the call.
+    pub fn read(&mut self) -> nb::Result<u32, Error> {
+        let master = &mut self.cascade.master.tim;
+        let sr = master.status();
+        if sr & (1 << (C + 1)) == 0 {
+            return Err(nb::Error::WouldBlock);
+        }
+
+        let low = master.read_capture(C);
+        let value = capture_value(low, self.cascade.count());
+
+        let ccxof = 1 << (C + 9);
+        if sr & ccxof != 0 {
+            self.cascade.master.tim.clear_flags(ccxof);
+            Err(nb::Error::Other(Error::Overcapture))
+        } else {
+            Ok(value)
+        }
+    }
+
+    /// Current value of the 32 bit counter
+    pub fn count(&self) -> u32 {
+        self.cascade.count()
+    }
+
+    /// Frequency of the counter ticks
+    pub fn frequency(&self) -> Hertz {
+        self.cascade.freq
+    }
+
+    /// Enables the capture interrupt of the master timer
+    pub fn listen_capture(&mut self) {
+        self.cascade.master.tim.set_capture_interrupt(C, true);
+    }
+
+    pub fn unlisten_capture(&mut self) {
+        self.cascade.master.tim.set_capture_interrupt(C, false);
+    }
+
+    /// Disables the capture and returns the counter and the pin
+    pub fn release(mut self) -> (Cascade<MASTER, SLAVE>, PIN) {
+        self.cascade.master.tim.set_capture_interrupt(C, false);
+        self.cascade.master.tim.disable_capture(C);
+        (self.cascade, self.pin)
+    }
+}
+
+/// Combines the captured master count `low` with the slave part of the counter value `now`
+/// read after the edge
+fn capture_value(low: u16, now: u32) -> u32 {
+    // The slave has counted one overflow too many if the master wrapped after the edge
+    let high = if (now as u16) < low {
+        (now >> 16).wrapping_sub(1)
+    } else {
+        now >> 16
+    };
+    high << 16 | low as u32
+}
+
+macro_rules! cascadable {
+    ($($TIM:ty,)+) => {
+        $(
+            impl Cascadable for $TIM {
+                fn set_trgo_update(&mut self) {
+                    // MMS = 010: update
+                    self.cr2().modify(|r, w| unsafe {
+                        w.bits(r.bits() & !(0b111 << 4) | 0b010 << 4)
+                    });
+                }
+                fn set_external_clock(&mut self, itr: u8) {
+                    // TS = ITRx, SMS = 111: external clock mode 1
+                    self.smcr().modify(|r, w| unsafe {
+                        w.bits(r.bits() & !0b111_0111 | (itr as u32) << 4 | 0b111)
+                    });
+                }
+                fn reset_slave_mode(&mut self) {
+                    self.smcr()
+                        .modify(|r, w| unsafe { w.bits(r.bits() & !0b111_0111) });
+                }
+                fn configure_capture(&mut self, c: u8, config: CaptureConfig) {
+                    let c = c as u32;
+                    let shift = (c % 2) * 8;
+                    // CCxS = 01: ICx is mapped on TIx
+                    let ccmr = (config.filter as u32) << 4 | (config.prescaler as u32) << 2 | 0b01;
+                    let ccxp = match config.edge {
+                        Edge::Rising => 0,
+                        Edge::Falling => 1 << (c * 4 + 1),
+                    };
+
+                    // The channel has to be disabled to change CCxS
+                    self.disable_capture(c as u8);
+                    if c < 2 {
+                        self.ccmr1_input().modify(|r, w| unsafe {
+                            w.bits(r.bits() & !(0xff << shift) | ccmr << shift)
+                        });
+                    } else {
+                        self.ccmr2_input().modify(|r, w| unsafe {
+                            w.bits(r.bits() & !(0xff << shift) | ccmr << shift)
+                        });
+                    }
+                    self.ccer().modify(|r, w| unsafe {
+                        w.bits(r.bits() & !(0b11 << (c * 4)) | ccxp | 1 << (c * 4))
+                    });
+                }
+                fn disable_capture(&mut self, c: u8) {
+                    self.ccer()
+                        .modify(|r, w| unsafe { w.bits(r.bits() & !(1 << (c * 4))) });
+                }
+                fn set_capture_interrupt(&mut self, c: u8, enable: bool) {
+                    self.dier().modify(|r, w| unsafe {
+                        w.bits(if enable {
+                            r.bits() | 1 << (c + 1)
+                        } else {
+                            r.bits() & !(1 << (c + 1))
+                        })
+                    });
+                }
+                fn status(&self) -> u32 {
+                    self.sr().read().bits()
+                }
+                fn clear_flags(&mut self, mask: u32) {
+                    self.sr().write(|w| unsafe { w.bits(0xffff & !mask) });
+                }
+                fn read_capture(&self, c: u8) -> u16 {
+                    self.ccr(c as usize).read().bits() as u16
+                }
+            }
+        )+
+    };
+}
+
+macro_rules! itr {
+    ($($SLAVE:ty: $MASTER:ty => $itr:literal,)+) => {
+        $(
+            impl Itr<$MASTER> for $SLAVE {
+                const ITR: u8 = $itr;
+            }
+        )+
+    };
+}
+
+cascadable!(pac::TIM2, pac::TIM3,);
+itr!(
+    pac::TIM2: pac::TIM3 => 2,
+    pac::TIM3: pac::TIM2 => 1,
+);
+
+#[cfg(any(feature = "stm32f100", feature = "stm32f103", feature = "connectivity"))]
+cascadable!(pac::TIM1,);
+#[cfg(any(feature = "stm32f100", feature = "stm32f103", feature = "connectivity"))]
+itr!(
+    pac::TIM1: pac::TIM2 => 1,
+    pac::TIM1: pac::TIM3 => 2,
+    pac::TIM2: pac::TIM1 => 0,
+    pac::TIM3: pac::TIM1 => 0,
+);
+
+#[cfg(feature = "medium")]
+cascadable!(pac::TIM4,);
+#[cfg(feature = "medium")]
+itr!(
+    pac::TIM2: pac::TIM4 => 3,
+    pac::TIM3: pac::TIM4 => 3,
+    pac::TIM4: pac::TIM2 => 1,
+    pac::TIM4: pac::TIM3 => 2,
+);
+#[cfg(all(
+    feature = "medium",
+    any(feature = "stm32f100", feature = "stm32f103", feature = "connectivity")
+))]
+itr!(
+    pac::TIM1: pac::TIM4 => 3,
+    pac::TIM4: pac::TIM1 => 0,
+);
+
+#[cfg(any(feature = "high", feature = "connectivity"))]
+cascadable!(pac::TIM5,);
+#[cfg(any(feature = "high", feature = "connectivity"))]
+itr!(
+    pac::TIM3: pac::TIM5 => 2,
+    pac::TIM5: pac::TIM2 => 0,
+    pac::TIM5: pac::TIM3 => 1,
+    pac::TIM5: pac::TIM4 => 2,
+);
+#[cfg(all(
+    any(feature = "high", feature = "connectivity"),
+    any(feature = "stm32f100", feature = "stm32f103", feature = "connectivity")
+))]
+itr!(
+    pac::TIM1: pac::TIM5 => 0,
+);
+
+#[cfg(all(feature = "stm32f103", feature = "high"))]
+cascadable!(pac::TIM8,);
+#[cfg(all(feature = "stm32f103", feature = "high"))]
+itr!(
+    pac::TIM2: pac::TIM8 => 1,
+    pac::TIM4: pac::TIM8 => 3,
+    pac::TIM5: pac::TIM8 => 3,
+    pac::TIM8: pac::TIM1 => 0,
+    pac::TIM8: pac::TIM2 => 1,
+    pac::TIM8: pac::TIM4 => 2,
+    pac::TIM8: pac::TIM5 => 3,
+);
+
+#[test]
+fn cascade_capture_value() {
+    assert_eq!(capture_value(0x1234, 0x0005_2000), 0x0005_1234);
+    assert_eq!(capture_value(0xfff0, 0x0006_0010), 0x0005_fff0);
+    assert_eq!(capture_value(0xfff0, 0x0000_0010), 0xffff_fff0);
+}
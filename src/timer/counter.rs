@@ -1,8 +1,12 @@
-use super::{compute_arr_presc, Error, Event, FTimer, Instance, SysEvent, Timer};
+use super::{
+    compute_arr_presc, duration_arr_presc, Error, Event, FTimer, Instance, SysEvent, Timer,
+};
 use crate::pac::SYST;
 use core::convert::TryFrom;
 use core::ops::{Deref, DerefMut};
-use fugit::{HertzU32 as Hertz, MicrosDurationU32, TimerDurationU32, TimerInstantU32};
+use fugit::{
+    HertzU32 as Hertz, MicrosDurationU32, NanosDurationU32, TimerDurationU32, TimerInstantU32,
+};
 
 /// Hardware timers
 pub struct CounterHz<TIM>(pub(super) Timer<TIM>);
@@ -52,6 +56,35 @@ impl<TIM: Instance> CounterHz<TIM> {
         Ok(())
     }
 
+    /// Starts the timer with a period of `timeout`, e.g. for a software timeout.
+    ///
+    /// The smallest prescaler for which the period fits the counter is selected, which
+    /// gives the highest resolution. The period is rounded to whole timer ticks, the
+    /// length of a tick is returned.
+    ///
+    /// Fails with `Error::WrongAutoReload` if `timeout` is shorter than 2 ticks of the timer
+    /// clock or longer than 2^32 ticks (59.6 s at 72 MHz).
+    pub fn start_duration(
+        &mut self,
+        timeout: MicrosDurationU32,
+    ) -> Result<NanosDurationU32, Error> {
+        let ticks = timeout.ticks() as u64 * self.clk.raw() as u64 / 1_000_000;
+        let (psc, arr) =
+            duration_arr_presc(ticks, TIM::max_auto_reload()).ok_or(Error::WrongAutoReload)?;
+
+        self.tim.disable_counter();
+        self.tim.clear_interrupt_flag(Event::Update);
+        self.tim.reset_counter();
+
+        self.tim.set_prescaler(psc);
+        self.tim.set_auto_reload(arr)?;
+        self.tim.trigger_update();
+        self.tim.enable_counter();
+
+        let resolution = (psc as u64 + 1) * 1_000_000_000 / self.clk.raw() as u64;
+        Ok(NanosDurationU32::from_ticks(resolution as u32))
+    }
+
     pub fn wait(&mut self) -> nb::Result<(), Error> {
         if self.tim.get_interrupt_flag().contains(Event::Update) {
             self.tim.clear_interrupt_flag(Event::Update);
@@ -35,30 +35,48 @@ impl Timer<SYST> {
 }
 
 impl SysDelay {
+    /// Blocks for at least `us`
     pub fn delay(&mut self, us: MicrosDurationU32) {
+        self.delay_cycles(us.ticks() as u64 * (self.clk.raw() / 1_000_000) as u64);
+    }
+
+    /// Blocks for at least `cycles` SysTick clock cycles.
+    ///
+    /// Delays longer than the 24 bit reload value are split into several SysTick periods.
+    /// Each period adds a few cycles of overhead, so the delay is accurate to about 1 µs
+    /// at 8 MHz and to less than 100 ns at 72 MHz, plus the time of interrupts.
+    pub fn delay_cycles(&mut self, mut cycles: u64) {
         // The SysTick Reload Value register supports values between 1 and 0x00FFFFFF.
         const MAX_RVR: u32 = 0x00FF_FFFF;
 
-        let mut total_rvr = us.ticks() * (self.clk.raw() / 1_000_000);
-
-        while total_rvr != 0 {
-            let current_rvr = total_rvr.min(MAX_RVR);
+        while cycles != 0 {
+            let current_rvr = cycles.min(MAX_RVR as u64) as u32;
 
             self.tim.set_reload(current_rvr);
             self.tim.clear_current();
             self.tim.enable_counter();
 
             // Update the tracking variable while we are waiting...
-            total_rvr -= current_rvr;
+            cycles -= current_rvr as u64;
 
             while !self.tim.has_wrapped() {}
 
             self.tim.disable_counter();
         }
     }
+
+    /// Number of SysTick clock cycles in `time` of `unit` per second, rounded up
+    pub(crate) fn cycles(&self, time: u32, unit: u32) -> u64 {
+        let clk = self.clk.raw() as u64;
+        (time as u64 * clk + unit as u64 - 1) / unit as u64
+    }
 }
 
-/// Periodic non-blocking timer that imlements [embedded_hal_02::blocking::delay] traits
+/// Blocking delay on a general purpose or advanced timer.
+///
+/// Implements the [embedded_hal_02::blocking::delay] traits and `DelayNs`, leaving
+/// SysTick free for the tick of RTIC or an RTOS. Delays are rounded up to whole `FREQ` ticks
+/// and can be longer than the timer period.
 pub struct Delay<TIM, const FREQ: u32>(pub(super) FTimer<TIM, FREQ>);
 
 impl<T, const FREQ: u32> Deref for Delay<T, FREQ> {
@@ -85,13 +103,20 @@ pub type DelayMs<TIM> = Delay<TIM, 1_000>;
 impl<TIM: Instance, const FREQ: u32> Delay<TIM, FREQ> {
     /// Sleep for given time
     pub fn delay(&mut self, time: TimerDurationU32<FREQ>) {
-        let mut ticks = time.ticks().max(1) - 1;
+        self.delay_ticks(time.ticks() as u64);
+    }
+
+    /// Sleeps for at least `ticks` of `FREQ`.
+    ///
+    /// Delays longer than the counter period are split into several one-pulse periods.
+    pub(crate) fn delay_ticks(&mut self, mut ticks: u64) {
         while ticks != 0 {
-            let reload = ticks.min(TIM::max_auto_reload());
+            // A period takes ARR + 1 ticks, the counter does not run with ARR = 0
+            let period = ticks.clamp(2, TIM::max_auto_reload() as u64 + 1);
 
             // Write Auto-Reload Register (ARR)
             unsafe {
-                self.tim.set_auto_reload_unchecked(reload);
+                self.tim.set_auto_reload_unchecked((period - 1) as u32);
             }
 
             // Trigger update event (UEV) in the event generation register (EGR)
@@ -103,12 +128,17 @@ impl<TIM: Instance, const FREQ: u32> Delay<TIM, FREQ> {
             self.tim.start_one_pulse();
 
             // Update the tracking variable while we are waiting...
-            ticks -= reload;
+            ticks = ticks.saturating_sub(period);
             // Wait for CEN bit to clear
             while self.tim.is_counter_enabled() { /* wait */ }
         }
     }
 
+    /// Number of `FREQ` ticks in `time` of `unit` per second, rounded up
+    pub(crate) fn ticks(time: u32, unit: u32) -> u64 {
+        (time as u64 * FREQ as u64 + unit as u64 - 1) / unit as u64
+    }
+
     pub fn max_delay(&self) -> TimerDurationU32<FREQ> {
         TimerDurationU32::from_ticks(TIM::max_auto_reload())
     }
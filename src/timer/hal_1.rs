@@ -7,29 +7,34 @@ use core::convert::Infallible;
 use embedded_hal::delay::DelayNs;
 
 use super::{Delay, Instance, PwmChannel, SysDelay, WithPwm};
-use fugit::ExtU32Ceil;
 
+/// Delays with the resolution of the SysTick clock, see [`SysDelay::delay_cycles`]
 impl DelayNs for SysDelay {
     fn delay_ns(&mut self, ns: u32) {
-        self.delay(ns.nanos_at_least());
+        self.delay_cycles(self.cycles(ns, 1_000_000_000));
+    }
+
+    fn delay_us(&mut self, us: u32) {
+        self.delay_cycles(self.cycles(us, 1_000_000));
     }
 
     fn delay_ms(&mut self, ms: u32) {
-        self.delay(ms.millis_at_least());
+        self.delay_cycles(self.cycles(ms, 1_000));
     }
 }
 
+/// Delays rounded up to whole ticks of `FREQ`
 impl<TIM: Instance, const FREQ: u32> DelayNs for Delay<TIM, FREQ> {
     fn delay_ns(&mut self, ns: u32) {
-        self.delay(ns.micros_at_least());
+        self.delay_ticks(Self::ticks(ns, 1_000_000_000));
     }
 
     fn delay_us(&mut self, us: u32) {
-        self.delay(us.micros_at_least());
+        self.delay_ticks(Self::ticks(us, 1_000_000));
     }
 
     fn delay_ms(&mut self, ms: u32) {
-        self.delay(ms.millis_at_least());
+        self.delay_ticks(Self::ticks(ms, 1_000));
     }
 }
 
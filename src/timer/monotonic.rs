@@ -1,14 +1,23 @@
-//! RTIC Monotonic implementation
+//! Free running timestamps and RTIC Monotonic implementation
+//!
+//! [`MonoTimer::now`] extends the 16 bit counter to 64 bits with the number of
+//! counter overflows. They are counted by [`MonoTimer::handle_overflow`], which
+//! has to be called at least once per counter period (65536 ticks), usually from
+//! the timer interrupt after `listen(Event::Update)`.
+//!
+//! With the `rtic` feature `MonoTimer` implements `rtic_monotonic::Monotonic`,
+//! RTIC then takes care of the overflows.
 
 use super::{FTimer, Instance};
 use crate::rcc::Clocks;
 use core::ops::{Deref, DerefMut};
 pub use fugit::{self, ExtU32};
+#[cfg(feature = "rtic")]
 use rtic_monotonic::Monotonic;
 
 pub struct MonoTimer<TIM, const FREQ: u32> {
     timer: FTimer<TIM, FREQ>,
-    ovf: u32,
+    ovf: u64,
 }
 
 impl<TIM, const FREQ: u32> Deref for MonoTimer<TIM, FREQ> {
@@ -80,6 +89,38 @@ macro_rules! mono {
             }
         }
 
+        impl<const FREQ: u32> MonoTimer<$TIM, FREQ> {
+            /// Time since the timer has been started
+            pub fn now(&self) -> fugit::TimerInstantU64<FREQ> {
+                let (cnt, pending) = loop {
+                    let pending = self.tim.sr().read().uif().bit_is_set();
+                    let cnt = self.tim.cnt().read().cnt().bits() as u64;
+                    if pending == self.tim.sr().read().uif().bit_is_set() {
+                        break (cnt, pending);
+                    }
+                };
+
+                // If the overflow bit is set, `handle_overflow` has not yet been called
+                // and we need to compensate here.
+                let ovf = if pending { 0x10000 } else { 0 };
+
+                fugit::TimerInstantU64::from_ticks(self.ovf + ovf + cnt)
+            }
+
+            /// Counts a pending counter overflow, returns `true` if there was one
+            pub fn handle_overflow(&mut self) -> bool {
+                if self.tim.sr().read().uif().bit_is_set() {
+                    self.tim.sr().modify(|_, w| w.uif().clear_bit());
+
+                    self.ovf += 0x10000;
+                    true
+                } else {
+                    false
+                }
+            }
+        }
+
+        #[cfg(feature = "rtic")]
         impl<const FREQ: u32> Monotonic for MonoTimer<$TIM, FREQ> {
             type Instant = fugit::TimerInstantU32<FREQ>;
             type Duration = fugit::TimerDurationU32<FREQ>;
@@ -90,21 +131,11 @@ macro_rules! mono {
 
             #[inline(always)]
             fn now(&mut self) -> Self::Instant {
-                let cnt = self.tim.cnt().read().cnt().bits() as u32;
-
-                // If the overflow bit is set, we add this to the timer value. It means the `on_interrupt`
-                // has not yet happened, and we need to compensate here.
-                let ovf = if self.tim.sr().read().uif().bit_is_set() {
-                    0x10000
-                } else {
-                    0
-                };
-
-                Self::Instant::from_ticks(cnt.wrapping_add(ovf).wrapping_add(self.ovf))
+                Self::Instant::from_ticks(MonoTimer::<$TIM, FREQ>::now(self).ticks() as u32)
             }
 
             fn set_compare(&mut self, instant: Self::Instant) {
-                let now = self.now();
+                let now = Monotonic::now(self);
                 let cnt = self.tim.cnt().read().cnt().bits();
 
                 // Since the timer may or may not overflow based on the requested compare val, we check
@@ -124,11 +155,7 @@ macro_rules! mono {
 
             fn on_interrupt(&mut self) {
                 // If there was an overflow, increment the overflow counter.
-                if self.tim.sr().read().uif().bit_is_set() {
-                    self.tim.sr().modify(|_, w| w.uif().clear_bit());
-
-                    self.ovf += 0x10000;
-                }
+                self.handle_overflow();
             }
 
             #[inline(always)]
@@ -0,0 +1,230 @@
+//! One-pulse mode.
+//!
+//! Generates a single pulse on channel 1 after a delay, e.g. for a camera
+//! trigger or an ultrasonic ping. The counter starts on a software trigger or on
+//! an edge of the channel 2 input and stops by itself at the end of the pulse.
+//!
+//! In this mode `CCR1` holds the delay and `ARR + 1` the total time of delay and
+//! pulse, both in timer ticks. The output is inactive while the counter is below
+//! `CCR1` and active from `CCR1` up to `ARR` (PWM mode 2), so the pulse is
+//! `ARR + 1 - CCR1` ticks wide. The update event at the end clears `CEN` and
+//! resets the counter, which puts the output back into its inactive state.
+
+use core::convert::Infallible;
+use core::marker::PhantomData;
+use core::ops::{Deref, DerefMut};
+
+use crate::afio::MAPR;
+use crate::gpio::{self, Alternate};
+use crate::pac;
+use fugit::MicrosDurationU32;
+
+use super::capture::Edge;
+use super::pins::{sealed::Remap, CPin};
+use super::{Channel, Error, Event, Instance, Ocm, Timer, WithPwm};
+
+pub(crate) mod sealed {
+    pub trait TriggerInput {
+        fn set_ti2_trigger(&mut self, edge: Option<super::Edge>);
+    }
+}
+use sealed::TriggerInput;
+
+/// Single pulse output on channel 1
+pub struct OnePulse<TIM: Instance, REMAP, PIN> {
+    timer: Timer<TIM>,
+    pin: PIN,
+    _remap: PhantomData<REMAP>,
+}
+
+impl<TIM: Instance + WithPwm> Timer<TIM> {
+    /// Configures a pulse of `width` on `pin` starting `delay` after a trigger.
+    ///
+    /// The delay is at least one timer tick.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the pulse is out of range, see [`OnePulse::set_pulse`].
+    pub fn one_pulse<REMAP, PIN, Otype>(
+        mut self,
+        pin: PIN,
+        mapr: &mut MAPR,
+        delay: MicrosDurationU32,
+        width: MicrosDurationU32,
+    ) -> OnePulse<TIM, REMAP, PIN>
+    where
+        REMAP: Remap<Periph = TIM>,
+        PIN: CPin<REMAP, 0> + gpio::PinExt<Mode = Alternate<Otype>>,
+    {
+        REMAP::remap(mapr);
+
+        self.tim
+            .preload_output_channel_in_mode(Channel::C1, Ocm::PwmMode2);
+        self.tim.enable_preload(true);
+
+        let mut pulse = OnePulse {
+            timer: self,
+            pin,
+            _remap: PhantomData,
+        };
+        pulse.set_pulse(delay, width).expect("pulse out of range");
+        TIM::enable_channel(Channel::C1 as u8, true);
+        pulse.tim.enable_main_output();
+        pulse
+    }
+}
+
+impl<TIM: Instance + WithPwm, REMAP, PIN> OnePulse<TIM, REMAP, PIN> {
+    /// Changes delay and width of the next pulse
+    ///
+    /// Returns [`Error::WrongAutoReload`] if the pulse is shorter than one timer tick or
+    /// the total time exceeds the range of the 16 bit prescaler and counter.
+    pub fn set_pulse(
+        &mut self,
+        delay: MicrosDurationU32,
+        width: MicrosDurationU32,
+    ) -> Result<(), Error> {
+        let (psc, arr, ccr) = pulse_registers(self.clk.raw(), delay.ticks(), width.ticks())?;
+        self.tim.set_prescaler(psc);
+        self.tim.set_auto_reload(arr)?;
+        TIM::set_cc_value(Channel::C1 as u8, ccr);
+        // Load the registers without setting the completion flag
+        self.tim.trigger_update();
+        Ok(())
+    }
+
+    /// Starts the pulse by software
+    pub fn trigger(&mut self) {
+        self.tim.start_one_pulse();
+    }
+
+    /// Returns `true` while waiting for the delay or generating the pulse
+    pub fn is_running(&self) -> bool {
+        self.tim.is_counter_enabled()
+    }
+
+    /// Waits for the end of the pulse, the completion flag is cleared
+    pub fn wait(&mut self) -> nb::Result<(), Infallible> {
+        if self.tim.get_interrupt_flag().contains(Event::Update) {
+            self.tim.clear_interrupt_flag(Event::Update);
+            Ok(())
+        } else {
+            Err(nb::Error::WouldBlock)
+        }
+    }
+
+    /// Stops the timer and returns it with the pin
+    pub fn release(mut self) -> (Timer<TIM>, PIN) {
+        self.tim.cr1_reset();
+        TIM::enable_channel(Channel::C1 as u8, false);
+        (self.timer, self.pin)
+    }
+}
+
+/// Returns prescaler, auto reload and compare value for a pulse of `width_us` after
+/// `delay_us` at the timer clock `clk`
+fn pulse_registers(clk: u32, delay_us: u32, width_us: u32) -> Result<(u16, u32, u32), Error> {
+    let clk = clk as u64;
+    let total_us = delay_us as u64 + width_us as u64;
+    let psc = (total_us * clk / 1_000_000).saturating_sub(1) / (1 << 16);
+    let psc = u16::try_from(psc).map_err(|_| Error::WrongAutoReload)?;
+    let tick = clk / (psc as u64 + 1);
+
+    let ccr = (delay_us as u64 * tick / 1_000_000).max(1);
+    let ticks = total_us * tick / 1_000_000;
+    // The pulse lasts from CCR1 to ARR, at least one tick
+    if ticks <= ccr {
+        return Err(Error::WrongAutoReload);
+    }
+    Ok((psc, (ticks - 1) as u32, ccr as u32))
+}
+
+impl<TIM: Instance + WithPwm + TriggerInput, REMAP, PIN> OnePulse<TIM, REMAP, PIN> {
+    /// Starts the pulse on `edge` of the channel 2 input (TI2),
+    /// in addition to [`trigger`](Self::trigger)
+    pub fn enable_external_trigger(&mut self, edge: Edge) {
+        self.tim.set_ti2_trigger(Some(edge));
+    }
+
+    pub fn disable_external_trigger(&mut self) {
+        self.tim.set_ti2_trigger(None);
+    }
+}
+
+impl<TIM: Instance, REMAP, PIN> Deref for OnePulse<TIM, REMAP, PIN> {
+    type Target = Timer<TIM>;
+    fn deref(&self) -> &Self::Target {
+        &self.timer
+    }
+}
+
+impl<TIM: Instance, REMAP, PIN> DerefMut for OnePulse<TIM, REMAP, PIN> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.timer
+    }
+}
+
+macro_rules! trigger_input {
+    ($($TIM:ty,)+) => {
+        $(
+            impl TriggerInput for $TIM {
+                fn set_ti2_trigger(&mut self, edge: Option<Edge>) {
+                    match edge {
+                        Some(edge) => {
+                            // CC2S = 01: IC2 mapped on TI2
+                            self.ccmr1_input().modify(|r, w| unsafe {
+                                w.bits(r.bits() & !(0b11 << 8) | 0b01 << 8)
+                            });
+                            self.ccer()
+                                .modify(|_, w| w.cc2p().bit(edge == Edge::Falling));
+                            self.cr1().modify(|_, w| w.opm().set_bit());
+                            // TS = 110: TI2FP2, SMS = 110: trigger mode
+                            self.smcr().modify(|r, w| unsafe {
+                                w.bits(r.bits() & !0b111_0111 | 0b110_0110)
+                            });
+                        }
+                        None => {
+                            self.smcr()
+                                .modify(|r, w| unsafe { w.bits(r.bits() & !0b111_0111) });
+                        }
+                    }
+                }
+            }
+        )+
+    };
+}
+
+#[cfg(any(feature = "stm32f100", feature = "stm32f103", feature = "connectivity"))]
+trigger_input!(pac::TIM1,);
+
+trigger_input!(pac::TIM2, pac::TIM3,);
+
+#[cfg(feature = "medium")]
+trigger_input!(pac::TIM4,);
+
+#[cfg(any(feature = "high", feature = "connectivity"))]
+trigger_input!(pac::TIM5,);
+
+#[cfg(all(feature = "stm32f103", feature = "high"))]
+trigger_input!(pac::TIM8,);
+
+#[test]
+fn one_pulse_registers() {
+    // 8 MHz: 100 µs delay, 50 µs pulse without prescaler
+    assert_eq!(pulse_registers(8_000_000, 100, 50), Ok((0, 1199, 800)));
+    // 72 MHz: 1 s in total needs a prescaler
+    let (psc, arr, ccr) = pulse_registers(72_000_000, 500_000, 500_000).unwrap();
+    assert_eq!(psc, 1098);
+    assert!(arr <= 0xffff && ccr < arr);
+    // the delay is at least one tick
+    assert_eq!(pulse_registers(8_000_000, 0, 1), Ok((0, 7, 1)));
+    assert_eq!(
+        pulse_registers(8_000_000, 0, 0),
+        Err(Error::WrongAutoReload)
+    );
+    // beyond 2^32 ticks
+    assert_eq!(
+        pulse_registers(72_000_000, u32::MAX, u32::MAX),
+        Err(Error::WrongAutoReload)
+    );
+}
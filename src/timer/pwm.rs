@@ -51,17 +51,73 @@
     // PWM outputs are disabled by default
     c0.enable()
   ```
+
+  ## Complementary outputs
+
+  The advanced timers (`TIM1`, `TIM8`) also drive the complementary outputs
+  CH1N-CH3N (see [Alternate function remapping](super) for the pins), which are
+  enabled with `enable_complementary`. A dead-time is inserted between a channel
+  and its complementary output with `set_dead_time`, as needed for half-bridges.
+
+  Optionally the break input (BKIN) can be enabled with `enable_break`. A break
+  clears the main output enable (MOE) bit in hardware, which puts all outputs
+  in their inactive state until `enable_outputs` is called.
+
+  ## Polarity and idle state
+
+  `set_polarity` selects the active level of a channel output, e.g. `Polarity::ActiveLow`
+  for an LED connected to VDD, whose brightness then follows the duty cycle. The
+  complementary output CHxN has its own polarity, set with `set_complementary_polarity`,
+  and is active while the channel is inactive (apart from the dead-time). Inverting only
+  one of them makes both outputs active at the same time, so change both for a half-bridge.
+
+  The level of each output while MOE is cleared, after a break or `disable_outputs`, is
+  set with `set_idle_state` and `set_complementary_idle_state`. The idle state is the
+  output level, independent of the polarity.
+
+  ## Waveform generation
+
+  Most F1 devices have no DAC, an analog signal is made by PWM and an RC low-pass filter
+  instead. `WaveformGenerator` streams a table of duty cycles to one channel by DMA, one
+  entry per PWM period, e.g. a sine or audio samples. The DMA request is the update event
+  of the timer, which is fixed to one DMA channel:
+
+  | Timer | DMA channel |
+  |:-----:|:-----------:|
+  | TIM1  | DMA1 C5     |
+  | TIM2  | DMA1 C2     |
+  | TIM3  | DMA1 C3     |
+  | TIM4  | DMA1 C7     |
+  | TIM5  | DMA2 C2     |
+  | TIM8  | DMA2 C1     |
+
+  ```rust
+    static SINE: [u16; 32] = [..];
+    let c1 = Timer::new(dp.TIM2, &clocks)
+        .pwm_hz::<Tim2NoRemap, _, _>(pa0, &mut afio.mapr, 32.kHz())
+        .split();
+    let dma1 = dp.DMA1.split();
+    let mut wave = WaveformGenerator::new(c1, dma1.2, &SINE, &clocks);
+    wave.start();
+  ```
 */
 
 use crate::afio::MAPR;
 use crate::gpio::{self, Alternate};
 
-use super::{compute_arr_presc, Channel, FTimer, Instance, Ocm, Timer, WithPwm};
+use super::{compute_arr_presc, Channel, Error, FTimer, Instance, Ocm, Timer, WithPwm};
 use crate::rcc::Clocks;
 use core::marker::PhantomData;
 use core::ops::{Deref, DerefMut};
 use fugit::{HertzU32 as Hertz, TimerDurationU32};
 
+mod complementary;
+pub use complementary::{BreakPolarity, MAX_DEAD_TIME};
+mod servo;
+pub use servo::{Servo, ServoConfig};
+mod waveform;
+pub use waveform::WaveformGenerator;
+
 pub trait Pins<REMAP, P> {
     const C1: bool = false;
     const C2: bool = false;
@@ -86,6 +142,13 @@ pub trait Pins<REMAP, P> {
 
 pub use super::{pins::sealed::Remap, CPin, Ch, C1, C2, C3, C4};
 
+/// Active level of a PWM output
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Polarity {
+    ActiveHigh,
+    ActiveLow,
+}
+
 pub struct PwmChannel<TIM, const C: u8> {
     pub(super) _tim: PhantomData<TIM>,
 }
@@ -202,6 +265,80 @@ where
     }
 }
 
+/// Computes the compare value for a duty cycle `fraction` of the current period
+#[inline]
+fn duty_from_fraction<TIM: WithPwm>(fraction: f32) -> u32 {
+    let max_duty = TIM::read_auto_reload() + 1;
+    let duty = (max_duty as f32 * fraction.clamp(0.0, 1.0) + 0.5) as u32;
+    duty.min(TIM::max_auto_reload())
+}
+
+/// Computes the compare value for a duty cycle in `percent` of the current period
+#[inline]
+fn duty_from_percent<TIM: WithPwm>(percent: u8) -> u32 {
+    let max_duty = TIM::read_auto_reload() + 1;
+    let duty = max_duty * percent.min(100) as u32 / 100;
+    duty.min(TIM::max_auto_reload())
+}
+
+/// Loads a new period and rescales the compare values of the used channels,
+/// so that their duty cycles stay the same.
+///
+/// All values are preloaded and applied together by an update event,
+/// which restarts the current period.
+fn update_period<TIM, REMAP, P, PINS>(
+    tim: &mut TIM,
+    psc: Option<u16>,
+    arr: u32,
+) -> Result<(), Error>
+where
+    TIM: WithPwm,
+    PINS: Pins<REMAP, P>,
+{
+    if arr == 0 || arr > TIM::max_auto_reload() {
+        return Err(Error::WrongAutoReload);
+    }
+    let old_max = TIM::read_auto_reload() as u64 + 1;
+    let new_max = arr as u64 + 1;
+    for (c, used) in [PINS::C1, PINS::C2, PINS::C3, PINS::C4]
+        .into_iter()
+        .enumerate()
+    {
+        if used && (c as u8) < TIM::CH_NUMBER {
+            let duty = TIM::read_cc_value(c as u8) as u64 * new_max / old_max;
+            TIM::set_cc_value(c as u8, (duty as u32).min(TIM::max_auto_reload()));
+        }
+    }
+    if let Some(psc) = psc {
+        tim.set_prescaler(psc);
+    }
+    tim.set_auto_reload(arr)?;
+    tim.trigger_update();
+    Ok(())
+}
+
+/// Waits until the counter wrapped around, if it is running
+///
+/// The polarity bits are not preloaded, changing them inverts the output at once.
+/// Changing them right at the start of a period keeps the current period intact.
+fn wait_for_period_start<TIM: Instance>() {
+    // NOTE(unsafe) only reads the counter
+    let tim = unsafe { TIM::steal() };
+    let mut last: u32 = tim.read_count().into();
+    while tim.is_counter_enabled() {
+        let count: u32 = tim.read_count().into();
+        if count < last {
+            break;
+        }
+        last = count;
+    }
+}
+
+fn set_polarity<TIM: Instance + WithPwm>(c: u8, polarity: Polarity) {
+    wait_for_period_start::<TIM>();
+    TIM::set_channel_polarity(c, polarity == Polarity::ActiveLow);
+}
+
 impl<TIM: Instance + WithPwm, const C: u8> PwmChannel<TIM, C> {
     pub(crate) fn new() -> Self {
         Self {
@@ -236,6 +373,27 @@ impl<TIM: Instance + WithPwm, const C: u8> PwmChannel<TIM, C> {
     pub fn set_duty(&mut self, duty: u16) {
         TIM::set_cc_value(C, duty as u32)
     }
+
+    /// Sets the duty cycle as a fraction of the period, clamped to `0.0..=1.0`
+    #[inline]
+    pub fn set_duty_fraction(&mut self, fraction: f32) {
+        TIM::set_cc_value(C, duty_from_fraction::<TIM>(fraction))
+    }
+
+    /// Sets the duty cycle in percent of the period, clamped to 100 %
+    #[inline]
+    pub fn set_duty_percent(&mut self, percent: u8) {
+        TIM::set_cc_value(C, duty_from_percent::<TIM>(percent))
+    }
+
+    /// Sets the active level of the output.
+    ///
+    /// If the timer is running, this waits for the start of the next period, so the
+    /// current period is not cut short.
+    #[inline]
+    pub fn set_polarity(&mut self, polarity: Polarity) {
+        set_polarity::<TIM>(C, polarity)
+    }
 }
 
 pub struct PwmHz<TIM, REMAP, P, PINS>
@@ -361,6 +519,30 @@ where
         TIM::set_cc_value(PINS::check_used(channel) as u8, duty as u32)
     }
 
+    /// Sets the duty cycle of `channel` as a fraction of the period, clamped to `0.0..=1.0`
+    pub fn set_duty_fraction(&mut self, channel: Channel, fraction: f32) {
+        TIM::set_cc_value(
+            PINS::check_used(channel) as u8,
+            duty_from_fraction::<TIM>(fraction),
+        )
+    }
+
+    /// Sets the duty cycle of `channel` in percent of the period, clamped to 100 %
+    pub fn set_duty_percent(&mut self, channel: Channel, percent: u8) {
+        TIM::set_cc_value(
+            PINS::check_used(channel) as u8,
+            duty_from_percent::<TIM>(percent),
+        )
+    }
+
+    /// Sets the active level of the output of `channel`.
+    ///
+    /// If the timer is running, this waits for the start of the next period, so the
+    /// current period is not cut short.
+    pub fn set_polarity(&mut self, channel: Channel, polarity: Polarity) {
+        set_polarity::<TIM>(PINS::check_used(channel) as u8, polarity)
+    }
+
     /// If `0` returned means max_duty is 2^16
     pub fn get_max_duty(&self) -> u16 {
         (TIM::read_auto_reload() as u16).wrapping_add(1)
@@ -375,12 +557,26 @@ where
         clk / ((psc + 1) * (arr + 1))
     }
 
-    pub fn set_period(&mut self, period: Hertz) {
+    /// Changes the PWM frequency, the prescaler and auto reload value are
+    /// recomputed from the timer clock.
+    ///
+    /// The compare values of the used channels are rescaled, so their duty
+    /// cycles are kept. The new period starts immediately.
+    pub fn set_period(&mut self, period: impl Into<Hertz>) {
         let clk = self.clk;
 
-        let (psc, arr) = compute_arr_presc(period.raw(), clk.raw());
-        self.tim.set_prescaler(psc);
-        self.tim.set_auto_reload(arr).unwrap();
+        let (psc, arr) = compute_arr_presc(period.into().raw(), clk.raw());
+        update_period::<TIM, REMAP, P, PINS>(&mut self.tim, Some(psc), arr).unwrap();
+    }
+
+    /// Changes the period to `ticks` counter ticks keeping the current prescaler.
+    ///
+    /// The duty cycles are kept like in [`set_period`](Self::set_period).
+    /// Returns [`Error::WrongAutoReload`] if `ticks` is less than 2 or exceeds the
+    /// counter range, 65536 for 16 bit timers.
+    pub fn set_period_ticks(&mut self, ticks: u32) -> Result<(), Error> {
+        let arr = ticks.checked_sub(1).ok_or(Error::WrongAutoReload)?;
+        update_period::<TIM, REMAP, P, PINS>(&mut self.tim, None, arr)
     }
 }
 
@@ -505,6 +701,30 @@ where
         TIM::set_cc_value(PINS::check_used(channel) as u8, duty.into())
     }
 
+    /// Sets the duty cycle of `channel` as a fraction of the period, clamped to `0.0..=1.0`
+    pub fn set_duty_fraction(&mut self, channel: Channel, fraction: f32) {
+        TIM::set_cc_value(
+            PINS::check_used(channel) as u8,
+            duty_from_fraction::<TIM>(fraction),
+        )
+    }
+
+    /// Sets the duty cycle of `channel` in percent of the period, clamped to 100 %
+    pub fn set_duty_percent(&mut self, channel: Channel, percent: u8) {
+        TIM::set_cc_value(
+            PINS::check_used(channel) as u8,
+            duty_from_percent::<TIM>(percent),
+        )
+    }
+
+    /// Sets the active level of the output of `channel`.
+    ///
+    /// If the timer is running, this waits for the start of the next period, so the
+    /// current period is not cut short.
+    pub fn set_polarity(&mut self, channel: Channel, polarity: Polarity) {
+        set_polarity::<TIM>(PINS::check_used(channel) as u8, polarity)
+    }
+
     /// If `0` returned means max_duty is 2^16
     pub fn get_max_duty(&self) -> u16 {
         (TIM::read_auto_reload() as u16).wrapping_add(1)
@@ -514,7 +734,23 @@ where
         TimerDurationU32::from_ticks(TIM::read_auto_reload() + 1)
     }
 
+    /// Changes the period, the compare values of the used channels are rescaled,
+    /// so their duty cycles are kept. The new period starts immediately.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `period` is shorter than 2 ticks or longer than the counter range,
+    /// see [`set_period_ticks`](Self::set_period_ticks) for a fallible version.
     pub fn set_period(&mut self, period: TimerDurationU32<FREQ>) {
-        self.tim.set_auto_reload(period.ticks() - 1).unwrap();
+        self.set_period_ticks(period.ticks()).unwrap();
+    }
+
+    /// Changes the period to `ticks` ticks of `FREQ`, see [`set_period`](Self::set_period)
+    ///
+    /// Returns [`Error::WrongAutoReload`] if `ticks` is less than 2 or exceeds the
+    /// counter range, 65536 for 16 bit timers.
+    pub fn set_period_ticks(&mut self, ticks: u32) -> Result<(), Error> {
+        let arr = ticks.checked_sub(1).ok_or(Error::WrongAutoReload)?;
+        update_period::<TIM, REMAP, P, PINS>(&mut self.tim, None, arr)
     }
 }
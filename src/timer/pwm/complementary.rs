@@ -0,0 +1,359 @@
+//! Complementary outputs, dead-time and break input of the advanced timers
+
+use super::*;
+use crate::gpio::PinState;
+use crate::timer::Advanced;
+
+/// Active level of the break input (BKIN)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BreakPolarity {
+    ActiveLow,
+    ActiveHigh,
+}
+
+/// Maximal dead-time in timer clock cycles
+pub const MAX_DEAD_TIME: u16 = 1008;
+
+/// Encodes a dead-time in timer clock cycles as `BDTR.DTG` value.
+///
+/// The dead-time is rounded up to the next value that can be represented
+/// and saturates at [`MAX_DEAD_TIME`].
+const fn dead_time_to_dtg(ticks: u16) -> u8 {
+    match ticks {
+        0..=127 => ticks as u8,
+        128..=254 => 0b1000_0000 | ((ticks + 1) / 2 - 64) as u8,
+        255..=504 => 0b1100_0000 | ((ticks + 7) / 8 - 32) as u8,
+        505..=MAX_DEAD_TIME => 0b1110_0000 | ((ticks + 15) / 16 - 32) as u8,
+        _ => 0xff,
+    }
+}
+
+/// Decodes a `BDTR.DTG` value into timer clock cycles
+const fn dtg_to_dead_time(dtg: u8) -> u16 {
+    let dtg = dtg as u16;
+    if dtg & 0b1000_0000 == 0 {
+        dtg
+    } else if dtg & 0b1100_0000 == 0b1000_0000 {
+        (64 + (dtg & 0x3f)) * 2
+    } else if dtg & 0b1110_0000 == 0b1100_0000 {
+        (32 + (dtg & 0x1f)) * 8
+    } else {
+        (32 + (dtg & 0x1f)) * 16
+    }
+}
+
+fn set_complementary_polarity<TIM: Instance + Advanced>(c: u8, polarity: Polarity) {
+    wait_for_period_start::<TIM>();
+    TIM::set_nchannel_polarity(c, polarity == Polarity::ActiveLow);
+}
+
+#[inline]
+fn check_complementary(channel: Channel) -> u8 {
+    assert!(channel != Channel::C4, "CH4 has no complementary output");
+    channel as u8
+}
+
+impl<TIM: Instance + Advanced, const C: u8> PwmChannel<TIM, C> {
+    /// Enables the complementary output (CHxN) of this channel.
+    ///
+    /// Has no effect on CH4.
+    #[inline]
+    pub fn enable_complementary(&mut self) {
+        TIM::enable_nchannel(C, true);
+    }
+
+    #[inline]
+    pub fn disable_complementary(&mut self) {
+        TIM::enable_nchannel(C, false);
+    }
+
+    /// Sets the active level of the complementary output (CHxN).
+    ///
+    /// Has no effect on CH4. Waits for the start of the next period like
+    /// [`set_polarity`](Self::set_polarity).
+    #[inline]
+    pub fn set_complementary_polarity(&mut self, polarity: Polarity) {
+        set_complementary_polarity::<TIM>(C, polarity)
+    }
+
+    /// Sets the level of the output while the main output enable (MOE) bit is cleared
+    #[inline]
+    pub fn set_idle_state(&mut self, state: PinState) {
+        TIM::set_idle_state(C, false, state == PinState::High);
+    }
+
+    /// Sets the level of the complementary output while MOE is cleared.
+    ///
+    /// Has no effect on CH4.
+    #[inline]
+    pub fn set_complementary_idle_state(&mut self, state: PinState) {
+        TIM::set_idle_state(C, true, state == PinState::High);
+    }
+}
+
+impl<TIM, REMAP, P, PINS> PwmHz<TIM, REMAP, P, PINS>
+where
+    TIM: Instance + Advanced,
+    REMAP: Remap<Periph = TIM>,
+    PINS: Pins<REMAP, P>,
+{
+    /// Enables the complementary output (CHxN) of `channel`.
+    ///
+    /// Panics for `Channel::C4` which has no complementary output.
+    pub fn enable_complementary(&mut self, channel: Channel) {
+        let c = check_complementary(PINS::check_used(channel));
+        TIM::enable_nchannel(c, true)
+    }
+
+    pub fn disable_complementary(&mut self, channel: Channel) {
+        let c = check_complementary(PINS::check_used(channel));
+        TIM::enable_nchannel(c, false)
+    }
+
+    /// Sets the active level of the complementary output (CHxN) of `channel`.
+    ///
+    /// Waits for the start of the next period like [`set_polarity`](Self::set_polarity).
+    /// Panics for `Channel::C4` which has no complementary output.
+    pub fn set_complementary_polarity(&mut self, channel: Channel, polarity: Polarity) {
+        let c = check_complementary(PINS::check_used(channel));
+        set_complementary_polarity::<TIM>(c, polarity)
+    }
+
+    /// Sets the level of the output of `channel` while the main output enable (MOE)
+    /// bit is cleared
+    pub fn set_idle_state(&mut self, channel: Channel, state: PinState) {
+        TIM::set_idle_state(
+            PINS::check_used(channel) as u8,
+            false,
+            state == PinState::High,
+        )
+    }
+
+    /// Sets the level of the complementary output of `channel` while MOE is cleared.
+    ///
+    /// Don't set both outputs of a half-bridge high, they stay on together.
+    /// Panics for `Channel::C4` which has no complementary output.
+    pub fn set_complementary_idle_state(&mut self, channel: Channel, state: PinState) {
+        let c = check_complementary(PINS::check_used(channel));
+        TIM::set_idle_state(c, true, state == PinState::High)
+    }
+
+    /// Sets the dead-time inserted between a channel and its complementary
+    /// output in timer clock cycles, rounded up and limited to [`MAX_DEAD_TIME`].
+    pub fn set_dead_time(&mut self, ticks: u16) {
+        self.tim.set_dtg_value(dead_time_to_dtg(ticks));
+    }
+
+    /// Sets the dead-time in nanoseconds, rounded up to the next timer clock cycle
+    pub fn set_dead_time_ns(&mut self, ns: u32) {
+        let ticks = (ns as u64 * self.clk.raw() as u64 + 999_999_999) / 1_000_000_000;
+        self.set_dead_time(ticks.min(MAX_DEAD_TIME as u64) as u16);
+    }
+
+    /// Returns the dead-time in timer clock cycles
+    pub fn get_dead_time(&self) -> u16 {
+        dtg_to_dead_time(self.tim.read_dtg_value())
+    }
+
+    /// Enables the break input (BKIN).
+    ///
+    /// On break the hardware clears MOE and the outputs go to their idle
+    /// (inactive) level. They stay off until [`enable_outputs`](Self::enable_outputs)
+    /// is called again.
+    pub fn enable_break(&mut self, polarity: BreakPolarity) {
+        self.tim.enable_break(polarity == BreakPolarity::ActiveHigh);
+    }
+
+    /// Disables the break input
+    ///
+    /// MOE is not set automatically anymore, call [`enable_outputs`](Self::enable_outputs)
+    /// after a break or [`disable_outputs`](Self::disable_outputs).
+    pub fn disable_break(&mut self) {
+        self.tim.disable_break();
+    }
+
+    /// Sets the main output enable (MOE) bit
+    pub fn enable_outputs(&mut self) {
+        self.tim.set_main_output(true);
+    }
+
+    /// Clears the main output enable (MOE) bit, all outputs go to their idle level
+    pub fn disable_outputs(&mut self) {
+        self.tim.set_main_output(false);
+    }
+
+    /// Returns `false` after a break until the outputs are enabled again
+    pub fn outputs_enabled(&self) -> bool {
+        self.tim.is_main_output_enabled()
+    }
+
+    /// Enables the break interrupt
+    pub fn listen_break(&mut self) {
+        self.tim.listen_break(true);
+    }
+
+    /// Disables the break interrupt
+    pub fn unlisten_break(&mut self) {
+        self.tim.listen_break(false);
+    }
+
+    /// Returns `true` if a break occurred since the flag was cleared
+    pub fn is_break_pending(&self) -> bool {
+        self.tim.is_break_pending()
+    }
+
+    /// Clears the break flag
+    pub fn clear_break(&mut self) {
+        self.tim.clear_break_flag();
+    }
+}
+
+impl<TIM, REMAP, P, PINS, const FREQ: u32> Pwm<TIM, REMAP, P, PINS, FREQ>
+where
+    TIM: Instance + Advanced,
+    REMAP: Remap<Periph = TIM>,
+    PINS: Pins<REMAP, P>,
+{
+    /// Enables the complementary output (CHxN) of `channel`.
+    ///
+    /// Panics for `Channel::C4` which has no complementary output.
+    pub fn enable_complementary(&mut self, channel: Channel) {
+        let c = check_complementary(PINS::check_used(channel));
+        TIM::enable_nchannel(c, true)
+    }
+
+    pub fn disable_complementary(&mut self, channel: Channel) {
+        let c = check_complementary(PINS::check_used(channel));
+        TIM::enable_nchannel(c, false)
+    }
+
+    /// Sets the active level of the complementary output (CHxN) of `channel`.
+    ///
+    /// Waits for the start of the next period like [`set_polarity`](Self::set_polarity).
+    /// Panics for `Channel::C4` which has no complementary output.
+    pub fn set_complementary_polarity(&mut self, channel: Channel, polarity: Polarity) {
+        let c = check_complementary(PINS::check_used(channel));
+        set_complementary_polarity::<TIM>(c, polarity)
+    }
+
+    /// Sets the level of the output of `channel` while the main output enable (MOE)
+    /// bit is cleared
+    pub fn set_idle_state(&mut self, channel: Channel, state: PinState) {
+        TIM::set_idle_state(
+            PINS::check_used(channel) as u8,
+            false,
+            state == PinState::High,
+        )
+    }
+
+    /// Sets the level of the complementary output of `channel` while MOE is cleared.
+    ///
+    /// Don't set both outputs of a half-bridge high, they stay on together.
+    /// Panics for `Channel::C4` which has no complementary output.
+    pub fn set_complementary_idle_state(&mut self, channel: Channel, state: PinState) {
+        let c = check_complementary(PINS::check_used(channel));
+        TIM::set_idle_state(c, true, state == PinState::High)
+    }
+
+    /// Sets the dead-time inserted between a channel and its complementary
+    /// output in timer clock cycles (not in `FREQ` ticks), rounded up and
+    /// limited to [`MAX_DEAD_TIME`].
+    pub fn set_dead_time(&mut self, ticks: u16) {
+        self.tim.set_dtg_value(dead_time_to_dtg(ticks));
+    }
+
+    /// Returns the dead-time in timer clock cycles
+    pub fn get_dead_time(&self) -> u16 {
+        dtg_to_dead_time(self.tim.read_dtg_value())
+    }
+
+    /// Enables the break input (BKIN).
+    ///
+    /// On break the hardware clears MOE and the outputs go to their idle
+    /// (inactive) level. They stay off until [`enable_outputs`](Self::enable_outputs)
+    /// is called again.
+    pub fn enable_break(&mut self, polarity: BreakPolarity) {
+        self.tim.enable_break(polarity == BreakPolarity::ActiveHigh);
+    }
+
+    /// Disables the break input
+    ///
+    /// MOE is not set automatically anymore, call [`enable_outputs`](Self::enable_outputs)
+    /// after a break or [`disable_outputs`](Self::disable_outputs).
+    pub fn disable_break(&mut self) {
+        self.tim.disable_break();
+    }
+
+    /// Sets the main output enable (MOE) bit
+    pub fn enable_outputs(&mut self) {
+        self.tim.set_main_output(true);
+    }
+
+    /// Clears the main output enable (MOE) bit, all outputs go to their idle level
+    pub fn disable_outputs(&mut self) {
+        self.tim.set_main_output(false);
+    }
+
+    /// Returns `false` after a break until the outputs are enabled again
+    pub fn outputs_enabled(&self) -> bool {
+        self.tim.is_main_output_enabled()
+    }
+
+    /// Enables the break interrupt
+    pub fn listen_break(&mut self) {
+        self.tim.listen_break(true);
+    }
+
+    /// Disables the break interrupt
+    pub fn unlisten_break(&mut self) {
+        self.tim.listen_break(false);
+    }
+
+    /// Returns `true` if a break occurred since the flag was cleared
+    pub fn is_break_pending(&self) -> bool {
+        self.tim.is_break_pending()
+    }
+
+    /// Clears the break flag
+    pub fn clear_break(&mut self) {
+        self.tim.clear_break_flag();
+    }
+}
+
+#[test]
+fn dead_time_dtg() {
+    // exact values of each of the four ranges
+    for ticks in [
+        0,
+        1,
+        127,
+        128,
+        130,
+        254,
+        256,
+        264,
+        504,
+        512,
+        528,
+        MAX_DEAD_TIME,
+    ] {
+        assert_eq!(dtg_to_dead_time(dead_time_to_dtg(ticks)), ticks);
+    }
+    assert_eq!(dead_time_to_dtg(127), 0x7f);
+    assert_eq!(dead_time_to_dtg(128), 0x80);
+    assert_eq!(dead_time_to_dtg(256), 0xc0);
+    assert_eq!(dead_time_to_dtg(512), 0xe0);
+    assert_eq!(dead_time_to_dtg(MAX_DEAD_TIME), 0xff);
+
+    // other values are rounded up to the next one that can be represented
+    assert_eq!(dtg_to_dead_time(dead_time_to_dtg(129)), 130);
+    assert_eq!(dtg_to_dead_time(dead_time_to_dtg(255)), 256);
+    assert_eq!(dtg_to_dead_time(dead_time_to_dtg(505)), 512);
+    assert_eq!(dead_time_to_dtg(MAX_DEAD_TIME + 1), 0xff);
+    assert_eq!(dead_time_to_dtg(u16::MAX), 0xff);
+
+    // every DTG value maps back to itself
+    for dtg in 0..=u8::MAX {
+        assert_eq!(dead_time_to_dtg(dtg_to_dead_time(dtg)), dtg);
+    }
+}
@@ -0,0 +1,140 @@
+//! Hobby servo control on top of a PWM channel
+
+use super::*;
+
+/// Pulse range and calibration of a [`Servo`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ServoConfig {
+    /// Shortest pulse sent to the servo in µs, also used for 0°
+    pub min_pulse_us: u16,
+    /// Longest pulse sent to the servo in µs, also used for `max_angle`
+    pub max_pulse_us: u16,
+    /// Angle reached with `max_pulse_us`
+    pub max_angle: u8,
+    /// Calibration offset added to every pulse before clamping
+    pub offset_us: i16,
+}
+
+impl ServoConfig {
+    pub fn pulse_range(mut self, min_pulse_us: u16, max_pulse_us: u16) -> Self {
+        assert!(min_pulse_us <= max_pulse_us);
+        self.min_pulse_us = min_pulse_us;
+        self.max_pulse_us = max_pulse_us;
+        self
+    }
+
+    pub fn max_angle(mut self, max_angle: u8) -> Self {
+        self.max_angle = max_angle;
+        self
+    }
+
+    pub fn offset_us(mut self, offset_us: i16) -> Self {
+        self.offset_us = offset_us;
+        self
+    }
+}
+
+impl Default for ServoConfig {
+    fn default() -> Self {
+        Self {
+            min_pulse_us: 1000,
+            max_pulse_us: 2000,
+            max_angle: 180,
+            offset_us: 0,
+        }
+    }
+}
+
+/// Servo driven by a PWM channel at 50 Hz
+///
+/// The timer has to run at 50 Hz, e.g. configured by `pwm_hz(.., 50.Hz(), ..)`
+/// before it is split into channels. Pulse widths are converted to compare values
+/// with its 20 ms period. The pulse is always clamped to the range of the [`ServoConfig`].
+pub struct Servo<TIM, const C: u8> {
+    channel: PwmChannel<TIM, C>,
+    config: ServoConfig,
+}
+
+// Period of the 50 Hz servo signal
+const PERIOD_US: u64 = 20_000;
+
+impl<TIM: Instance + WithPwm, const C: u8> Servo<TIM, C> {
+    /// Servo with the default 1.0 ms - 2.0 ms pulse range for 0° - 180°
+    pub fn new(channel: PwmChannel<TIM, C>) -> Self {
+        Self::with_config(channel, ServoConfig::default())
+    }
+
+    /// # Panics
+    ///
+    /// Panics if `min_pulse_us` of `config` is larger than `max_pulse_us`.
+    pub fn with_config(channel: PwmChannel<TIM, C>, config: ServoConfig) -> Self {
+        assert!(config.min_pulse_us <= config.max_pulse_us);
+        let mut servo = Self { channel, config };
+        servo.set_pulse_us(config.min_pulse_us);
+        servo
+    }
+
+    pub fn config(&self) -> ServoConfig {
+        self.config
+    }
+
+    /// Changes the pulse range and calibration, the current pulse is clamped to the new range
+    ///
+    /// # Panics
+    ///
+    /// Panics if `min_pulse_us` of `config` is larger than `max_pulse_us`.
+    pub fn set_config(&mut self, config: ServoConfig) {
+        assert!(config.min_pulse_us <= config.max_pulse_us);
+        let pulse = self.get_pulse_us() as i32 - self.config.offset_us as i32;
+        self.config = config;
+        self.set_pulse_us(pulse.clamp(0, u16::MAX as i32) as u16);
+    }
+
+    /// Sets the pulse width in µs, the calibration offset is added and the
+    /// result is clamped to the configured pulse range
+    pub fn set_pulse_us(&mut self, pulse_us: u16) {
+        let pulse = (pulse_us as i32 + self.config.offset_us as i32).clamp(
+            self.config.min_pulse_us as i32,
+            self.config.max_pulse_us as i32,
+        ) as u64;
+        self.channel
+            .set_duty((pulse * Self::period_ticks() / PERIOD_US) as u16);
+    }
+
+    /// Returns the pulse width in µs sent to the servo, including the calibration offset
+    pub fn get_pulse_us(&self) -> u16 {
+        (self.channel.get_duty() as u64 * PERIOD_US / Self::period_ticks()) as u16
+    }
+
+    fn period_ticks() -> u64 {
+        TIM::read_auto_reload() as u64 + 1
+    }
+
+    /// Moves the servo to `deg` degrees, limited to `max_angle`
+    pub fn set_angle(&mut self, deg: u8) {
+        let ServoConfig {
+            min_pulse_us,
+            max_pulse_us,
+            max_angle,
+            ..
+        } = self.config;
+        let deg = deg.min(max_angle) as u32;
+        let range = (max_pulse_us - min_pulse_us) as u32;
+        let pulse = min_pulse_us as u32 + range * deg / (max_angle as u32).max(1);
+        self.set_pulse_us(pulse as u16);
+    }
+
+    #[inline]
+    pub fn enable(&mut self) {
+        self.channel.enable();
+    }
+
+    #[inline]
+    pub fn disable(&mut self) {
+        self.channel.disable();
+    }
+
+    pub fn release(self) -> PwmChannel<TIM, C> {
+        self.channel
+    }
+}
@@ -0,0 +1,165 @@
+//! Arbitrary waveforms from a table of duty cycles streamed by DMA
+
+use super::*;
+use crate::dma::{dma1, Ch, DmaExt};
+use crate::pac;
+use core::sync::atomic::{compiler_fence, Ordering};
+use embedded_dma::ReadBuffer;
+
+pub(crate) mod sealed {
+    /// The update DMA request of the timer is connected to the DMA channel `CH`
+    pub trait UpdateDma<CH> {
+        /// Address of the capture/compare register of `channel`
+        fn ccr_address(channel: u8) -> u32;
+        /// Sets UDE, a DMA request on every update event
+        fn enable_update_dma(b: bool);
+    }
+}
+use sealed::UpdateDma;
+
+/// PWM channel whose duty cycle is loaded from a table on every update event
+///
+/// The DMA channel of the timer update request copies the next table entry to the
+/// capture/compare register of the channel at the PWM frequency, which is the sample
+/// rate, and restarts at the beginning after the last entry. With an RC low-pass filter
+/// on the output this gives an analog signal like from a DAC, e.g. a sine wave from a
+/// table of `table.len()` samples has the frequency `sample_rate / table.len()`.
+///
+/// The entries are duty cycles in timer ticks like for `set_duty` and must not exceed
+/// `get_max_duty`. As the compare value is preloaded, an entry takes effect one period
+/// after it is written.
+pub struct WaveformGenerator<TIM, const C: u8, CH, B> {
+    channel: PwmChannel<TIM, C>,
+    dma: CH,
+    table: B,
+    len: usize,
+    sample_rate: Hertz,
+}
+
+impl<TIM, const C: u8, DMA, const D: u8, B> WaveformGenerator<TIM, C, Ch<DMA, D>, B>
+where
+    TIM: Instance + WithPwm + UpdateDma<Ch<DMA, D>>,
+    DMA: DmaExt,
+    B: ReadBuffer<Word = u16>,
+{
+    /// Prepares the DMA channel `dma` of the timer update request to play `table`
+    ///
+    /// The sample rate is the current PWM frequency of the timer. Panics if the table
+    /// is empty or has more than 65535 entries.
+    pub fn new(
+        channel: PwmChannel<TIM, C>,
+        mut dma: Ch<DMA, D>,
+        table: B,
+        clocks: &Clocks,
+    ) -> Self {
+        // NOTE(unsafe) the table is owned by the generator until it is released
+        let (ptr, len) = unsafe { table.read_buffer() };
+        assert!(len > 0, "empty waveform table");
+        dma.set_peripheral_address(TIM::ccr_address(C), false);
+        dma.set_memory_address(ptr as u32, true);
+        dma.set_transfer_length(len);
+        dma.ch().cr().modify(|_, w| {
+            w.mem2mem().clear_bit();
+            w.pl().high();
+            w.msize().bits16();
+            w.psize().bits16();
+            w.circ().set_bit();
+            w.dir().set_bit()
+        });
+
+        // NOTE(unsafe) only reads the prescaler of the running PWM
+        let psc = u32::from(unsafe { TIM::steal() }.read_prescaler()) + 1;
+        let arr = TIM::read_auto_reload() + 1;
+        let sample_rate = TIM::timer_clock(clocks) / psc / arr;
+
+        Self {
+            channel,
+            dma,
+            table,
+            len,
+            sample_rate,
+        }
+    }
+
+    /// Starts or resumes playing the table and enables the output
+    pub fn start(&mut self) {
+        compiler_fence(Ordering::Release);
+        self.dma.start();
+        TIM::enable_update_dma(true);
+        self.channel.enable();
+    }
+
+    /// Stops at the current entry, the output keeps its last duty cycle
+    pub fn stop(&mut self) {
+        TIM::enable_update_dma(false);
+        self.dma.stop();
+        compiler_fence(Ordering::Acquire);
+    }
+
+    /// Number of table entries per second
+    pub fn sample_rate(&self) -> Hertz {
+        self.sample_rate
+    }
+
+    /// Largest duty cycle, see `PwmChannel::get_max_duty`
+    pub fn get_max_duty(&self) -> u16 {
+        self.channel.get_max_duty()
+    }
+
+    /// Index of the next table entry
+    pub fn position(&self) -> usize {
+        (self.len - self.dma.remaining() as usize) % self.len
+    }
+
+    pub fn table(&self) -> &B {
+        &self.table
+    }
+
+    /// Stops playing and returns the PWM channel, the DMA channel and the table
+    pub fn release(mut self) -> (PwmChannel<TIM, C>, Ch<DMA, D>, B) {
+        self.stop();
+        (self.channel, self.dma, self.table)
+    }
+}
+
+macro_rules! update_dma {
+    ($($TIM:ty: $CH:ty,)+) => {
+        $(
+            impl UpdateDma<$CH> for $TIM {
+                fn ccr_address(channel: u8) -> u32 {
+                    // NOTE(unsafe) only the address is taken
+                    unsafe { (*<$TIM>::ptr()).ccr(channel as usize).as_ptr() as u32 }
+                }
+                fn enable_update_dma(b: bool) {
+                    // NOTE(unsafe) the bit is only used by the waveform generator
+                    unsafe { (*<$TIM>::ptr()).dier().modify(|_, w| w.ude().bit(b)) }
+                }
+            }
+        )+
+    };
+}
+
+update_dma!(
+    pac::TIM2: dma1::C2,
+    pac::TIM3: dma1::C3,
+);
+
+#[cfg(any(feature = "stm32f100", feature = "stm32f103", feature = "connectivity"))]
+update_dma!(
+    pac::TIM1: dma1::C5,
+);
+
+#[cfg(feature = "medium")]
+update_dma!(
+    pac::TIM4: dma1::C7,
+);
+
+#[cfg(any(feature = "high", feature = "connectivity"))]
+update_dma!(
+    pac::TIM5: crate::dma::dma2::C2,
+);
+
+#[cfg(all(feature = "stm32f103", feature = "high"))]
+update_dma!(
+    pac::TIM8: crate::dma::dma2::C1,
+);
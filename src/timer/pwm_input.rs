@@ -2,7 +2,6 @@
 //! In this mode, the timer sample a squared signal to find it's frequency and duty cycle.
 
 use core::marker::PhantomData;
-use core::mem;
 
 use crate::pac::{self, DBGMCU as DBG};
 
@@ -26,9 +25,9 @@ where
 
 /// PWM Input
 pub struct PwmInput<TIM, REMAP, PINS> {
-    _timer: PhantomData<TIM>,
+    timer: Timer<TIM>,
+    pins: PINS,
     _remap: PhantomData<REMAP>,
-    _pins: PhantomData<PINS>,
 }
 
 /// How the data is read from the timer
@@ -89,8 +88,7 @@ impl Timer<pac::TIM1> {
     {
         REMAP::remap(mapr);
         self.stop_in_debug(dbg, false);
-        let Self { tim, clk } = self;
-        tim1(tim, pins, clk, mode)
+        tim1(self, pins, mode)
     }
 }
 
@@ -108,8 +106,7 @@ impl Timer<pac::TIM2> {
     {
         REMAP::remap(mapr);
         self.stop_in_debug(dbg, false);
-        let Self { tim, clk } = self;
-        tim2(tim, pins, clk, mode)
+        tim2(self, pins, mode)
     }
 }
 
@@ -127,8 +124,7 @@ impl Timer<pac::TIM3> {
     {
         REMAP::remap(mapr);
         self.stop_in_debug(dbg, false);
-        let Self { tim, clk } = self;
-        tim3(tim, pins, clk, mode)
+        tim3(self, pins, mode)
     }
 }
 
@@ -147,8 +143,7 @@ impl Timer<pac::TIM4> {
     {
         REMAP::remap(mapr);
         self.stop_in_debug(dbg, false);
-        let Self { tim, clk } = self;
-        tim4(tim, pins, clk, mode)
+        tim4(self, pins, mode)
     }
 }
 
@@ -164,9 +159,8 @@ fn compute_arr_presc(freq: u32, clock: u32) -> (u16, u16) {
 macro_rules! hal {
     ($TIMX:ty: $timX:ident) => {
         fn $timX<REMAP, PINS>(
-            tim: $TIMX,
-            _pins: PINS,
-            clk: Hertz,
+            timer: Timer<$TIMX>,
+            pins: PINS,
             mode: Configuration,
         ) -> PwmInput<$TIMX, REMAP, PINS>
         where
@@ -174,6 +168,7 @@ macro_rules! hal {
             PINS: Pins<REMAP>,
         {
             use Configuration::*;
+            let (tim, clk) = (&timer.tim, timer.clk);
             // Disable capture on both channels during setting
             // (for Channel X bit is CCXE)
             tim.ccer().modify(|_, w| {
@@ -235,7 +230,11 @@ macro_rules! hal {
                 .modify(|_, w| w.cc1e().set_bit().cc2e().set_bit());
 
             tim.cr1().modify(|_, w| w.cen().set_bit());
-            unsafe { mem::MaybeUninit::uninit().assume_init() }
+            PwmInput {
+                timer,
+                pins,
+                _remap: PhantomData,
+            }
         }
 
         impl<REMAP, PINS> PwmInput<$TIMX, REMAP, PINS>
@@ -291,6 +290,17 @@ macro_rules! hal {
                 }
             }
 
+            /// Stops the timer and returns it with the pins, e.g. to use it in a different mode
+            pub fn release(self) -> (Timer<$TIMX>, PINS) {
+                let tim = &self.timer.tim;
+                tim.cr1().modify(|_, w| w.cen().clear_bit());
+                tim.ccer().reset();
+                tim.ccmr1_input().reset();
+                tim.smcr().reset();
+                tim.dier().reset();
+                (self.timer, self.pins)
+            }
+
             /// Wait until the timer has captured a period
             fn wait_for_capture(&self) {
                 unsafe { &(*<$TIMX>::ptr()) }.sr().write(|w| {
@@ -3,9 +3,23 @@
 //! Requires the `stm32-usbd` feature.
 //! See [https://github.com/stm32-rs/stm32f1xx-hal/tree/master/examples]
 //! for usage examples.
+//!
+//! ## Suspend and resume
+//!
+//! When the host suspends the bus, `UsbDevice::poll` puts the peripheral into
+//! low-power mode and the device state becomes `UsbDeviceState::Suspend`. To stay
+//! below the 2.5 mA allowed in suspend, the application can then stop the PLL and
+//! the HSE, e.g. by entering Stop mode. Bus activity raises the USB_WAKEUP
+//! interrupt (EXTI line 18) and the USB_LP interrupt. Before the next `poll` the
+//! clocks have to be restored, because the USB peripheral needs the 48 MHz derived
+//! from the PLL.
+//!
+//! If the host has enabled it (`UsbDevice::remote_wakeup_enabled`), the device may
+//! wake up the host by [`remote_wakeup`] after it has been suspended for at least 5 ms.
 
 use crate::pac::{RCC, USB};
-use crate::rcc::{Enable, Reset};
+use crate::rcc::{Clocks, Enable, Reset};
+use embedded_hal::delay::DelayNs;
 use stm32_usbd::UsbPeripheral;
 
 use crate::gpio::gpioa::{PA11, PA12};
@@ -13,9 +27,35 @@ use crate::gpio::{Floating, Input};
 pub use stm32_usbd::UsbBus;
 
 pub struct Peripheral {
-    pub usb: USB,
-    pub pin_dm: PA11<Input<Floating>>,
-    pub pin_dp: PA12<Input<Floating>>,
+    usb: USB,
+    pin_dm: PA11<Input<Floating>>,
+    pin_dp: PA12<Input<Floating>>,
+}
+
+impl Peripheral {
+    /// Panics if the clocks can not provide the 48 MHz needed by the USB peripheral
+    pub fn new(
+        usb: USB,
+        pin_dm: PA11<Input<Floating>>,
+        pin_dp: PA12<Input<Floating>>,
+        clocks: &Clocks,
+    ) -> Self {
+        assert!(
+            clocks.usbclk_valid(),
+            "USB needs a 48 MHz clock: use the HSE with the PLL at 48 MHz \
+             and USB prescaler 1 or at 72 MHz and USB prescaler 1.5"
+        );
+        Self {
+            usb,
+            pin_dm,
+            pin_dp,
+        }
+    }
+
+    /// Releases the USB peripheral and the D- and D+ pins
+    pub fn release(self) -> (USB, PA11<Input<Floating>>, PA12<Input<Floating>>) {
+        (self.usb, self.pin_dm, self.pin_dp)
+    }
 }
 
 unsafe impl Sync for Peripheral {}
@@ -46,3 +86,18 @@ unsafe impl UsbPeripheral for Peripheral {
 }
 
 pub type UsbBusType = UsbBus<Peripheral>;
+
+/// Signals resume to the host for 10 ms to wake it up.
+///
+/// The clocks have to be running, the device leaves the suspend state when the
+/// host continues the resume.
+pub fn remote_wakeup(_bus: &UsbBusType, delay: &mut impl DelayNs) {
+    // NOTE(unsafe) the bus owns the peripheral and only these bits are changed
+    let usb = unsafe { &*USB::ptr() };
+    usb.cntr()
+        .modify(|_, w| w.fsusp().clear_bit().lpmode().clear_bit());
+    usb.cntr().modify(|_, w| w.resume().set_bit());
+    // The resume signal has to last between 1 and 15 ms
+    delay.delay_ms(10);
+    usb.cntr().modify(|_, w| w.resume().clear_bit());
+}
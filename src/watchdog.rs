@@ -1,26 +1,42 @@
 //! Watchdog peripherals
 
 use crate::{
-    hal_02::watchdog::{Watchdog, WatchdogEnable},
-    pac::{DBGMCU as DBG, IWDG},
-    time::MilliSeconds,
+    hal_02::watchdog::Watchdog,
+    pac::{DBGMCU as DBG, IWDG, RCC, WWDG},
+    rcc::{Clocks, Enable},
+    time::{MicroSeconds, MilliSeconds},
 };
 use fugit::ExtU32;
 
 /// Wraps the Independent Watchdog (IWDG) peripheral
+///
+/// The watchdog is clocked by the LSI oscillator at nominal 40 kHz, which
+/// allows timeouts from 0.1 ms to about 26 s. The LSI varies between 30 and
+/// 60 kHz, so the real timeout may differ considerably from the requested one.
+///
+/// Once started, the watchdog can not be stopped except by a reset, that's why
+/// [`start`](Self::start) turns it into a [`StartedIndependentWatchdog`], which can
+/// not be released again.
 pub struct IndependentWatchdog {
     iwdg: IWDG,
 }
 
+/// Running Independent Watchdog (IWDG), returned by [`IndependentWatchdog::start`]
+///
+/// It has to be fed regularly and can only be stopped by a reset.
+pub struct StartedIndependentWatchdog {
+    iwdg: IWDG,
+}
+
 const LSI_KHZ: u32 = 40;
-const MAX_PR: u8 = 8;
+const MAX_PR: u8 = 0b110;
 const MAX_RL: u16 = 0xFFF;
 const KR_ACCESS: u16 = 0x5555;
 const KR_RELOAD: u16 = 0xAAAA;
 const KR_START: u16 = 0xCCCC;
 
 impl IndependentWatchdog {
-    /// Wrap and start the watchdog
+    /// Wrap the watchdog
     pub fn new(iwdg: IWDG) -> Self {
         IndependentWatchdog { iwdg }
     }
@@ -36,9 +52,10 @@ impl IndependentWatchdog {
             pr += 1;
         }
 
-        let max_period = Self::timeout_period(pr, MAX_RL);
-        let max_rl = u32::from(MAX_RL);
-        let rl = (timeout_ms * max_rl / max_period).min(max_rl) as u16;
+        // Round to the nearest reload value
+        let divider = Self::divider(pr);
+        let ticks = (timeout_ms.saturating_mul(LSI_KHZ) + divider / 2) / divider;
+        let rl = (ticks.clamp(1, u32::from(MAX_RL) + 1) - 1) as u16;
 
         self.access_registers(|iwdg| {
             iwdg.pr().modify(|_, w| unsafe { w.pr().bits(pr) });
@@ -46,16 +63,15 @@ impl IndependentWatchdog {
         });
     }
 
-    fn is_pr_updating(&self) -> bool {
-        self.iwdg.sr().read().pvu().bit()
-    }
-
-    /// Returns the interval in ms
-    pub fn interval(&self) -> MilliSeconds {
-        while self.is_pr_updating() {}
+    fn interval(iwdg: &IWDG) -> MilliSeconds {
+        // Wait until the prescaler and reload values are updated
+        while {
+            let sr = iwdg.sr().read();
+            sr.pvu().bit() || sr.rvu().bit()
+        } {}
 
-        let pr = self.iwdg.pr().read().pr().bits();
-        let rl = self.iwdg.rlr().read().rl().bits();
+        let pr = iwdg.pr().read().pr().bits();
+        let rl = iwdg.rlr().read().rl().bits();
         let ms = Self::timeout_period(pr, rl);
         ms.millis()
     }
@@ -64,7 +80,11 @@ impl IndependentWatchdog {
     ///
     /// Returns ms
     fn timeout_period(pr: u8, rl: u16) -> u32 {
-        let divider: u32 = match pr {
+        (u32::from(rl) + 1) * Self::divider(pr) / LSI_KHZ
+    }
+
+    fn divider(pr: u8) -> u32 {
+        match pr {
             0b000 => 4,
             0b001 => 8,
             0b010 => 16,
@@ -74,8 +94,7 @@ impl IndependentWatchdog {
             0b110 => 256,
             0b111 => 256,
             _ => panic!("Invalid IWDG prescaler divider"),
-        };
-        (u32::from(rl) + 1) * divider / LSI_KHZ
+        }
     }
 
     fn access_registers<A, F: FnMut(&IWDG) -> A>(&self, mut f: F) -> A {
@@ -88,27 +107,124 @@ impl IndependentWatchdog {
         a
     }
 
-    pub fn start(&mut self, period: MilliSeconds) {
+    /// Starts the watchdog with the representable timeout nearest to `period`
+    ///
+    /// The actual timeout is returned by [`StartedIndependentWatchdog::interval`].
+    pub fn start(self, period: MilliSeconds) -> StartedIndependentWatchdog {
         self.setup(period.ticks());
-
         self.iwdg.kr().write(|w| unsafe { w.key().bits(KR_START) });
+        StartedIndependentWatchdog { iwdg: self.iwdg }
+    }
+}
+
+impl StartedIndependentWatchdog {
+    /// Returns the actual timeout
+    pub fn interval(&self) -> MilliSeconds {
+        IndependentWatchdog::interval(&self.iwdg)
     }
 
+    /// Reloads the counter, which has to happen before the timeout elapses
     pub fn feed(&mut self) {
         self.iwdg.kr().write(|w| unsafe { w.key().bits(KR_RELOAD) });
     }
 }
 
-impl WatchdogEnable for IndependentWatchdog {
-    type Time = MilliSeconds;
-
-    fn start<T: Into<Self::Time>>(&mut self, period: T) {
-        self.start(period.into());
+impl Watchdog for StartedIndependentWatchdog {
+    fn feed(&mut self) {
+        self.feed();
     }
 }
 
-impl Watchdog for IndependentWatchdog {
-    fn feed(&mut self) {
-        self.feed();
+/// Wraps the Window Watchdog (WWDG) peripheral
+///
+/// The 7 bit down counter runs at PCLK1 / 4096 / 2^WDGTB and resets the MCU when it
+/// falls below 0x40. Feeding reloads the counter, but only inside the window: feeding
+/// before `window` has passed since the last feed resets the MCU as well. This detects
+/// both hanging and running away too fast. At 36 MHz PCLK1 the timeout ranges from
+/// 114 µs to 58 ms.
+///
+/// The early wakeup interrupt (WWDG) is raised one tick before the reset, e.g. to save
+/// state. Once started, the watchdog can only be stopped by a reset.
+pub struct WindowWatchdog {
+    wwdg: WWDG,
+    counter: u8,
+}
+
+/// Counter value at which the MCU is reset
+const WWDG_RESET: u8 = 0x3F;
+const WWDG_MAX: u8 = 0x7F;
+
+impl WindowWatchdog {
+    /// Wrap the watchdog and enable its clock
+    pub fn new(wwdg: WWDG) -> Self {
+        let rcc = unsafe { &(*RCC::ptr()) };
+        WWDG::enable(rcc);
+        WindowWatchdog { wwdg, counter: 0 }
+    }
+
+    /// Debug window watchdog stopped when core is halted
+    pub fn stop_on_debug(&self, dbg: &DBG, stop: bool) {
+        dbg.cr().modify(|_, w| w.dbg_wwdg_stop().bit(stop));
+    }
+
+    /// Starts the watchdog, which resets the MCU `timeout` after the last feed.
+    ///
+    /// Feeding before `window` has passed since the last feed resets the MCU too, a
+    /// `window` of zero allows feeding at any time. Both are rounded to the nearest
+    /// counter tick, the actual timeout is returned.
+    ///
+    /// Panics if the timeout is out of range or `window` is not shorter than `timeout`.
+    pub fn start(
+        &mut self,
+        clocks: &Clocks,
+        window: MicroSeconds,
+        timeout: MicroSeconds,
+    ) -> MicroSeconds {
+        assert!(window < timeout, "window must be shorter than the timeout");
+        let pclk1 = clocks.pclk1().raw() as u64;
+        let max_ticks = (WWDG_MAX - WWDG_RESET) as u64;
+
+        // Smallest prescaler which reaches the timeout for the best resolution
+        let tick_ns = |tb: u8| (4096 << tb) * 1_000_000_000 / pclk1;
+        let tb = (0..=3)
+            .find(|&tb| max_ticks * tick_ns(tb) >= timeout.ticks() as u64 * 1000)
+            .expect("timeout too long");
+        let tick_ns = tick_ns(tb);
+        let ticks = |us: u32| (us as u64 * 1000 + tick_ns / 2) / tick_ns;
+
+        let timeout_ticks = ticks(timeout.ticks()).clamp(1, max_ticks) as u8;
+        let window_ticks = (ticks(window.ticks()) as u8).min(timeout_ticks - 1);
+        let counter = WWDG_RESET + timeout_ticks;
+        let w = counter - window_ticks;
+
+        self.wwdg
+            .cfr()
+            .modify(|_, wr| wr.w().set(w).wdgtb().set(tb));
+        self.counter = counter;
+        self.wwdg
+            .cr()
+            .write(|wr| wr.t().set(counter).wdga().set_bit());
+
+        MicroSeconds::from_ticks((timeout_ticks as u64 * tick_ns / 1000) as u32)
+    }
+
+    /// Reloads the counter, which resets the MCU when called too early inside the window
+    pub fn feed(&mut self) {
+        self.wwdg.cr().write(|w| w.t().set(self.counter));
+    }
+
+    /// Enables the early wakeup interrupt, which can only be disabled by a reset
+    pub fn listen(&mut self) {
+        self.wwdg.cfr().modify(|_, w| w.ewi().set_bit());
+    }
+
+    /// Returns `true` if the counter has reached the early wakeup value 0x40
+    pub fn is_early_wakeup(&self) -> bool {
+        self.wwdg.sr().read().ewif().bit_is_set()
+    }
+
+    /// Clears the early wakeup interrupt flag
+    pub fn clear_interrupt(&mut self) {
+        self.wwdg.sr().write(|w| w.ewif().clear_bit());
     }
 }